@@ -5,6 +5,9 @@ use std::ops::{Range, RangeInclusive};
 
 use typed_index_collections::TiVec;
 
+#[cfg(test)]
+mod tests;
+
 // TODO add tiny idx?
 
 /// Backing storge used for `Idx<T>`
@@ -162,6 +165,20 @@ impl<T> IdxRange<T> {
     pub fn is_empty(&self) -> bool {
         self.range.is_empty()
     }
+
+    /// Returns whether `idx` is part of the index range.
+    pub fn contains(&self, idx: Idx<T>) -> bool {
+        self.range.contains(&idx.raw)
+    }
+
+    /// Returns the indices that are part of both ranges. The result
+    /// is empty if the ranges don't overlap.
+    #[must_use]
+    pub fn intersect(&self, other: &Self) -> Self {
+        let start = self.range.start.max(other.range.start);
+        let end = self.range.end.min(other.range.end).max(start);
+        Self { range: start..end, _p: PhantomData }
+    }
 }
 
 impl<T> Iterator for IdxRange<T> {
@@ -0,0 +1,39 @@
+use crate::{Idx, IdxRange};
+
+fn idx(raw: usize) -> Idx<()> {
+    Idx::from(raw)
+}
+
+fn range(start: usize, end: usize) -> IdxRange<()> {
+    IdxRange::new(idx(start)..idx(end))
+}
+
+fn indices(range: IdxRange<()>) -> Vec<usize> {
+    range.map(usize::from).collect()
+}
+
+#[test]
+fn contains() {
+    let range_ = range(1, 3);
+    assert!(!range_.contains(idx(0)));
+    assert!(range_.contains(idx(1)));
+    assert!(range_.contains(idx(2)));
+    assert!(!range_.contains(idx(3)));
+
+    let inclusive = IdxRange::new_inclusive(idx(1)..=idx(3));
+    assert!(inclusive.contains(idx(3)));
+    assert!(!range(1, 1).contains(idx(1)));
+}
+
+#[test]
+fn intersect() {
+    // overlapping
+    assert_eq!(indices(range(0, 3).intersect(&range(2, 5))), [2]);
+    assert_eq!(indices(range(2, 5).intersect(&range(0, 3))), [2]);
+    // nested
+    assert_eq!(indices(range(0, 5).intersect(&range(1, 3))), [1, 2]);
+    // adjacent and disjoint
+    assert!(range(0, 2).intersect(&range(2, 4)).is_empty());
+    assert!(range(0, 1).intersect(&range(3, 5)).is_empty());
+    assert!(range(3, 5).intersect(&range(0, 1)).is_empty());
+}
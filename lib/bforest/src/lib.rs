@@ -8,7 +8,8 @@
 //!
 //! The tradeoffs are different:
 //!
-//! - Keys and values are expected to be small and copyable. We optimize for 32-bit types.
+//! - Keys and values are expected to be small and copyable. We optimize for 32-bit types,
+//!   64-bit keys are supported with larger nodes (see [`MapForest64`] and [`SetForest64`]).
 //! - A comparator object is used to compare keys, allowing smaller "context free" keys.
 //! - Empty trees have a very small 32-bit footprint.
 //! - All the trees in a forest can be cleared in constant time.
@@ -29,11 +30,12 @@ mod set;
 #[cfg(test)]
 mod tests;
 
-pub use self::map::{Map, MapCursor, MapForest, MapIter};
+pub use self::map::{Map, MapCursor, MapForest, MapForest64, MapIter, MapIterRef, MapLeaves};
 use self::node::NodeData;
 use self::path::Path;
 use self::pool::NodePool;
-pub use self::set::{RevSetIter, Set, SetCursor, SetForest, SetIter};
+pub use self::pool::{NodeRemap, PoolStats};
+pub use self::set::{RevSetIter, Set, SetCursor, SetForest, SetForest64, SetIter, SetLeaves};
 
 /// The maximum branching factor of an inner node in a B+-tree.
 /// The minimum number of outgoing edges is `INNER_SIZE/2`.
@@ -42,6 +44,7 @@ const INNER_SIZE: usize = 8;
 /// Given the worst case branching factor of `INNER_SIZE/2` = 4, this is the
 /// worst case path length from the root node to a leaf node in a tree with 2^32
 /// entries. We would run out of node references before we hit `MAX_PATH`.
+/// Node references are 32 bits for any key type, so this also holds for 64-bit keys.
 const MAX_PATH: usize = 16;
 
 /// Key comparator.
@@ -1,8 +1,9 @@
 //! Forest of maps.
 
+use core::cmp::Ordering;
 use core::marker::PhantomData;
 
-use super::{Comparator, Forest, Node, NodeData, NodePool, Path, INNER_SIZE};
+use super::{Comparator, Forest, Node, NodeData, NodePool, NodeRemap, Path, PoolStats, INNER_SIZE};
 use crate::packed_option::PackedOption;
 
 #[cfg(test)]
@@ -40,6 +41,12 @@ where
     nodes: NodePool<MapTypes<K, V>>,
 }
 
+/// A [`MapForest`] with 64-bit keys.
+///
+/// The leaf nodes hold as many entries as with 32-bit keys, so the trees have the same shape but
+/// the nodes are larger than a cache line.
+pub type MapForest64<V> = MapForest<u64, V>;
+
 impl<K, V> MapForest<K, V>
 where
     K: Copy,
@@ -56,6 +63,118 @@ where
     pub fn clear(&mut self) {
         self.nodes.clear();
     }
+
+    /// Move the nodes of `maps` to the start of the pool and release all other nodes
+    /// (including the free list), so that the pool is as small as possible.
+    ///
+    /// The roots of `maps` are updated in place. Every other map of this forest is invalidated,
+    /// unless it is a copy of one of `maps` and updated with the returned [`NodeRemap`].
+    pub fn compact<'a>(&mut self, maps: impl IntoIterator<Item = &'a mut Map<K, V>>) -> NodeRemap
+    where
+        K: 'a,
+        V: 'a,
+    {
+        let maps: Vec<_> = maps.into_iter().collect();
+        let remap = self.nodes.compact(maps.iter().filter_map(|map| map.root.expand()));
+        for map in maps {
+            map.remap(&remap);
+        }
+        remap
+    }
+
+    /// Rebuild `map` with freshly allocated nodes that are laid out in traversal order and
+    /// filled evenly, so that iterating and searching the map is cache friendly. The old
+    /// nodes are moved to the free list, use [`compact`](Self::compact) to release them.
+    ///
+    /// Copies of `map` are invalidated and must be replaced by the returned map.
+    pub fn rebuild(&mut self, map: Map<K, V>) -> Map<K, V> {
+        let root = map.root.expand().map(|root| self.nodes.rebuild(root));
+        Map { root: root.into(), unused: PhantomData }
+    }
+
+    /// Memory usage of the node pool shared by all maps in the forest.
+    ///
+    /// Nodes freed by removing elements (or clearing individual maps) are kept on a free
+    /// list for reuse. Only [`clear`](Self::clear) (which keeps the capacity) and
+    /// [`compact`](Self::compact) release them.
+    pub fn stats(&self) -> PoolStats {
+        self.nodes.stats()
+    }
+
+    /// Creates a new map that contains the entries of both `a` and `b`. The values of keys
+    /// that are present in both maps are combined with `combine(a_val, b_val)`.
+    ///
+    /// Both maps are traversed in lockstep and are not modified, so this is `O(|a| + |b|)`
+    /// (plus the cost of building the new map).
+    pub fn merge_with<C: Comparator<K>>(
+        &mut self,
+        a: &Map<K, V>,
+        b: &Map<K, V>,
+        comp: &C,
+        combine: impl Fn(V, V) -> V,
+    ) -> Map<K, V> {
+        let mut a_path = Path::default();
+        let mut b_path = Path::default();
+        let mut a_next = a.root.expand().map(|root| a_path.first(root, &self.nodes));
+        let mut b_next = b.root.expand().map(|root| b_path.first(root, &self.nodes));
+
+        let mut res = Map::new();
+        res.insert_sorted(
+            |forest| {
+                let entry = match (a_next, b_next) {
+                    (Some((a_key, a_val)), Some((b_key, b_val))) => match comp.cmp(a_key, b_key) {
+                        Ordering::Less => {
+                            a_next = a_path.next(&forest.nodes);
+                            (a_key, a_val)
+                        }
+                        Ordering::Greater => {
+                            b_next = b_path.next(&forest.nodes);
+                            (b_key, b_val)
+                        }
+                        Ordering::Equal => {
+                            a_next = a_path.next(&forest.nodes);
+                            b_next = b_path.next(&forest.nodes);
+                            (a_key, combine(a_val, b_val))
+                        }
+                    },
+                    (Some(entry), None) => {
+                        a_next = a_path.next(&forest.nodes);
+                        entry
+                    }
+                    (None, Some(entry)) => {
+                        b_next = b_path.next(&forest.nodes);
+                        entry
+                    }
+                    (None, None) => return None,
+                };
+                Some(entry)
+            },
+            self,
+            comp,
+            |_, val| val,
+        );
+        res
+    }
+
+    /// Do the maps `a` and `b` contain the same keys with equal values?
+    ///
+    /// Both maps are traversed in lockstep until the first difference, so this is
+    /// `O(min(|a|, |b|))` and does not allocate.
+    pub fn trees_equal<C: Comparator<K>>(&self, a: &Map<K, V>, b: &Map<K, V>, comp: &C) -> bool
+    where
+        V: PartialEq,
+    {
+        let mut a_iter = a.iter(self);
+        let mut b_iter = b.iter(self);
+        loop {
+            match (a_iter.next(), b_iter.next()) {
+                (Some((a_key, a_val)), Some((b_key, b_val)))
+                    if comp.cmp(a_key, b_key) == Ordering::Equal && a_val == b_val => {}
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
 }
 
 impl<K, V> Default for MapForest<K, V>
@@ -97,6 +216,13 @@ where
         Self { root: None.into(), unused: PhantomData }
     }
 
+    /// Update the root of this map after its forest was compacted.
+    ///
+    /// This is only required for copies of a map that were not passed to `compact` themselves.
+    pub fn remap(&mut self, remap: &NodeRemap) {
+        self.root = self.root.map(|root| remap.get(root)).into();
+    }
+
     /// Is this an empty map?
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -320,7 +446,51 @@ where
 
     /// Create an iterator traversing this map. The iterator type is `(K, V)`.
     pub fn iter<'a>(&self, forest: &'a MapForest<K, V>) -> MapIter<'a, K, V> {
-        MapIter { root: self.root, pool: &forest.nodes, path: Path::default() }
+        MapIter { root: self.root, pool: &forest.nodes, path: Path::default(), start: None }
+    }
+
+    /// Create an iterator that starts at the first entry with a key greater than or equal to
+    /// `key` and traverses the remainder of the map. If all keys are smaller than `key` the
+    /// iterator is empty.
+    pub fn iter_from<'a, C: Comparator<K>>(
+        &self,
+        forest: &'a MapForest<K, V>,
+        key: K,
+        comp: &C,
+    ) -> MapIter<'a, K, V> {
+        let mut path = Path::default();
+        let start = self.root.expand().and_then(|root| {
+            if let Some(val) = path.find(key, root, &forest.nodes, comp) {
+                return Some((key, val));
+            }
+            // `find` leaves the path at the insert position which may be one beyond the last
+            // entry of a leaf, in that case the entry we are looking for is in the next leaf
+            let (node, entry) = path.leaf_pos()?;
+            let (keys, vals) = forest.nodes[node].unwrap_leaf();
+            if entry < keys.len() {
+                Some((keys[entry], vals[entry]))
+            } else {
+                path.next(&forest.nodes)
+            }
+        });
+        MapIter { root: None.into(), pool: &forest.nodes, path, start }
+    }
+
+    /// Create an iterator over the leaf nodes of this map. Each item contains the keys and
+    /// values stored in one leaf as two slices of the same length, which allows processing a
+    /// whole leaf in a tight loop instead of calling [`MapIter::next`] for every entry. The
+    /// slices are sorted by key and contiguous: concatenating them yields the same entries in
+    /// the same order as [`Map::iter`].
+    pub fn leaves<'a>(&self, forest: &'a MapForest<K, V>) -> MapLeaves<'a, K, V> {
+        MapLeaves { root: self.root, pool: &forest.nodes, path: Path::default() }
+    }
+
+    /// Create an iterator traversing this map that yields references to the values stored in
+    /// the leaf nodes instead of copying them. The iterator type is `(K, &V)`. This avoids
+    /// copying large values, the forest is borrowed for the lifetime of the iterator so the map
+    /// can not be modified while iterating.
+    pub fn iter_ref<'a>(&self, forest: &'a MapForest<K, V>) -> MapIterRef<'a, K, V> {
+        MapIterRef { leaves: self.leaves(forest), keys: &[], vals: &[] }
     }
 }
 
@@ -557,6 +727,73 @@ where
     root: PackedOption<Node>,
     pool: &'a NodePool<MapTypes<K, V>>,
     path: Path<MapTypes<K, V>>,
+    /// The first entry returned by an iterator created with [`Map::iter_from`]. The `path`
+    /// points at this entry.
+    start: Option<(K, V)>,
+}
+
+/// An iterator visiting the leaf nodes of a `Map`, see [`Map::leaves`].
+#[derive(Clone, Copy)]
+pub struct MapLeaves<'a, K, V>
+where
+    K: 'a + Copy,
+    V: 'a + Copy,
+{
+    root: PackedOption<Node>,
+    pool: &'a NodePool<MapTypes<K, V>>,
+    path: Path<MapTypes<K, V>>,
+}
+
+/// An iterator visiting the key-value pairs of a `Map` by reference, see [`Map::iter_ref`].
+#[derive(Clone, Copy)]
+pub struct MapIterRef<'a, K, V>
+where
+    K: 'a + Copy,
+    V: 'a + Copy,
+{
+    leaves: MapLeaves<'a, K, V>,
+    /// The remaining entries of the current leaf.
+    keys: &'a [K],
+    vals: &'a [V],
+}
+
+impl<'a, K, V> Iterator for MapLeaves<'a, K, V>
+where
+    K: 'a + Copy,
+    V: 'a + Copy,
+{
+    type Item = (&'a [K], &'a [V]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Same approach as `MapIter`: `self.root` is only set before the first leaf.
+        let leaf = match self.root.take() {
+            Some(root) => {
+                self.path.first(root, self.pool);
+                self.path.leaf_pos()?.0
+            }
+            None => self.path.next_leaf(self.pool)?,
+        };
+        Some(self.pool[leaf].unwrap_leaf())
+    }
+}
+
+impl<'a, K, V> Iterator for MapIterRef<'a, K, V>
+where
+    K: 'a + Copy,
+    V: 'a + Copy,
+{
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.keys.is_empty() {
+            (self.keys, self.vals) = self.leaves.next()?;
+        }
+        let key = self.keys[0];
+        let val = &self.vals[0];
+        self.keys = &self.keys[1..];
+        self.vals = &self.vals[1..];
+        Some((key, val))
+    }
 }
 
 impl<'a, K, V> Iterator for MapIter<'a, K, V>
@@ -570,6 +807,9 @@ where
         // We use `self.root` to indicate if we need to go to the first element. Reset to `None`
         // once we've returned the first element. This also works for an empty tree since the
         // `path.next()` call returns `None` when the path is empty. This also fuses the iterator.
+        if let Some(start) = self.start.take() {
+            return Some(start);
+        }
         match self.root.take() {
             Some(root) => Some(self.path.first(root, self.pool)),
             None => self.path.next(self.pool),
@@ -1,6 +1,7 @@
 use std::{fmt, mem};
 
 use super::*;
+use crate::tests::{wide_key, TestKey};
 
 impl<K, V> Map<K, V>
 where
@@ -52,6 +53,9 @@ fn node_size() {
     // check that nodes are cache line sized when keys and values are 32 bits.
     type F = MapTypes<u32, u32>;
     assert_eq!(mem::size_of::<NodeData<F>>(), 64);
+
+    // 64-bit keys keep the same number of entries per node, so the nodes grow instead
+    assert_eq!(mem::size_of::<NodeData<MapTypes<u64, u32>>>(), 96);
 }
 
 #[test]
@@ -82,72 +86,89 @@ fn empty() {
 
 #[test]
 fn inserting() {
-    let f = &mut MapForest::<u32, f32>::new();
-    let mut m = Map::<u32, f32>::new();
+    inserting_with(|n| n)
+}
+
+#[test]
+fn inserting_u64() {
+    inserting_with(wide_key)
+}
+
+fn inserting_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let mut m = Map::<K, f32>::new();
 
     // The first seven values stay in a single leaf node.
-    assert_eq!(m.insert(50, 5.0, f, &()), None);
-    assert_eq!(m.insert(50, 5.5, f, &()), Some(5.0));
-    assert_eq!(m.insert(20, 2.0, f, &()), None);
-    assert_eq!(m.insert(80, 8.0, f, &()), None);
-    assert_eq!(m.insert(40, 4.0, f, &()), None);
-    assert_eq!(m.insert(60, 6.0, f, &()), None);
-    assert_eq!(m.insert(90, 9.0, f, &()), None);
-    assert_eq!(m.insert(200, 20.0, f, &()), None);
+    assert_eq!(m.insert(k(50), 5.0, f, &()), None);
+    assert_eq!(m.insert(k(50), 5.5, f, &()), Some(5.0));
+    assert_eq!(m.insert(k(20), 2.0, f, &()), None);
+    assert_eq!(m.insert(k(80), 8.0, f, &()), None);
+    assert_eq!(m.insert(k(40), 4.0, f, &()), None);
+    assert_eq!(m.insert(k(60), 6.0, f, &()), None);
+    assert_eq!(m.insert(k(90), 9.0, f, &()), None);
+    assert_eq!(m.insert(k(200), 20.0, f, &()), None);
 
     // m.verify(f, &());
 
     assert_eq!(
         m.iter(f).collect::<Vec<_>>(),
-        [(20, 2.0), (40, 4.0), (50, 5.5), (60, 6.0), (80, 8.0), (90, 9.0), (200, 20.0),]
+        [
+            (k(20), 2.0),
+            (k(40), 4.0),
+            (k(50), 5.5),
+            (k(60), 6.0),
+            (k(80), 8.0),
+            (k(90), 9.0),
+            (k(200), 20.0),
+        ]
     );
 
-    assert_eq!(m.get(0, f, &()), None);
-    assert_eq!(m.get(20, f, &()), Some(2.0));
-    assert_eq!(m.get(30, f, &()), None);
-    assert_eq!(m.get(40, f, &()), Some(4.0));
-    assert_eq!(m.get(50, f, &()), Some(5.5));
-    assert_eq!(m.get(60, f, &()), Some(6.0));
-    assert_eq!(m.get(70, f, &()), None);
-    assert_eq!(m.get(80, f, &()), Some(8.0));
-    assert_eq!(m.get(100, f, &()), None);
-
-    assert_eq!(m.get_or_less(0, f, &()), None);
-    assert_eq!(m.get_or_less(20, f, &()), Some((20, 2.0)));
-    assert_eq!(m.get_or_less(30, f, &()), Some((20, 2.0)));
-    assert_eq!(m.get_or_less(40, f, &()), Some((40, 4.0)));
-    assert_eq!(m.get_or_less(200, f, &()), Some((200, 20.0)));
-    assert_eq!(m.get_or_less(201, f, &()), Some((200, 20.0)));
+    assert_eq!(m.get(k(0), f, &()), None);
+    assert_eq!(m.get(k(20), f, &()), Some(2.0));
+    assert_eq!(m.get(k(30), f, &()), None);
+    assert_eq!(m.get(k(40), f, &()), Some(4.0));
+    assert_eq!(m.get(k(50), f, &()), Some(5.5));
+    assert_eq!(m.get(k(60), f, &()), Some(6.0));
+    assert_eq!(m.get(k(70), f, &()), None);
+    assert_eq!(m.get(k(80), f, &()), Some(8.0));
+    assert_eq!(m.get(k(100), f, &()), None);
+
+    assert_eq!(m.get_or_less(k(0), f, &()), None);
+    assert_eq!(m.get_or_less(k(20), f, &()), Some((k(20), 2.0)));
+    assert_eq!(m.get_or_less(k(30), f, &()), Some((k(20), 2.0)));
+    assert_eq!(m.get_or_less(k(40), f, &()), Some((k(40), 4.0)));
+    assert_eq!(m.get_or_less(k(200), f, &()), Some((k(200), 20.0)));
+    assert_eq!(m.get_or_less(k(201), f, &()), Some((k(200), 20.0)));
 
     {
         let mut c = m.cursor(f, &());
-        assert_eq!(c.prev(), Some((200, 20.0)));
-        assert_eq!(c.prev(), Some((90, 9.0)));
-        assert_eq!(c.prev(), Some((80, 8.0)));
-        assert_eq!(c.prev(), Some((60, 6.0)));
-        assert_eq!(c.prev(), Some((50, 5.5)));
-        assert_eq!(c.prev(), Some((40, 4.0)));
-        assert_eq!(c.prev(), Some((20, 2.0)));
+        assert_eq!(c.prev(), Some((k(200), 20.0)));
+        assert_eq!(c.prev(), Some((k(90), 9.0)));
+        assert_eq!(c.prev(), Some((k(80), 8.0)));
+        assert_eq!(c.prev(), Some((k(60), 6.0)));
+        assert_eq!(c.prev(), Some((k(50), 5.5)));
+        assert_eq!(c.prev(), Some((k(40), 4.0)));
+        assert_eq!(c.prev(), Some((k(20), 2.0)));
         assert_eq!(c.prev(), None);
     }
 
     // Test some removals where the node stays healthy.
-    assert_eq!(m.tpath(50, f, &()), "node0[2]");
-    assert_eq!(m.tpath(80, f, &()), "node0[4]");
-    assert_eq!(m.tpath(200, f, &()), "node0[6]");
+    assert_eq!(m.tpath(k(50), f, &()), "node0[2]");
+    assert_eq!(m.tpath(k(80), f, &()), "node0[4]");
+    assert_eq!(m.tpath(k(200), f, &()), "node0[6]");
 
-    assert_eq!(m.remove(80, f, &()), Some(8.0));
-    assert_eq!(m.tpath(50, f, &()), "node0[2]");
-    assert_eq!(m.tpath(80, f, &()), "node0[4]");
-    assert_eq!(m.tpath(200, f, &()), "node0[5]");
-    assert_eq!(m.remove(80, f, &()), None);
+    assert_eq!(m.remove(k(80), f, &()), Some(8.0));
+    assert_eq!(m.tpath(k(50), f, &()), "node0[2]");
+    assert_eq!(m.tpath(k(80), f, &()), "node0[4]");
+    assert_eq!(m.tpath(k(200), f, &()), "node0[5]");
+    assert_eq!(m.remove(k(80), f, &()), None);
     // m.verify(f, &());
 
-    assert_eq!(m.remove(20, f, &()), Some(2.0));
-    assert_eq!(m.tpath(50, f, &()), "node0[1]");
-    assert_eq!(m.tpath(80, f, &()), "node0[3]");
-    assert_eq!(m.tpath(200, f, &()), "node0[4]");
-    assert_eq!(m.remove(20, f, &()), None);
+    assert_eq!(m.remove(k(20), f, &()), Some(2.0));
+    assert_eq!(m.tpath(k(50), f, &()), "node0[1]");
+    assert_eq!(m.tpath(k(80), f, &()), "node0[3]");
+    assert_eq!(m.tpath(k(200), f, &()), "node0[4]");
+    assert_eq!(m.remove(k(20), f, &()), None);
     // m.verify(f, &());
 
     // [ 40 50 60 90 200 ]
@@ -155,25 +176,25 @@ fn inserting() {
     {
         let mut c = m.cursor(f, &());
         assert_eq!(c.goto_first(), Some(4.0));
-        assert_eq!(c.key(), Some(40));
+        assert_eq!(c.key(), Some(k(40)));
         assert_eq!(c.value(), Some(4.0));
-        assert_eq!(c.next(), Some((50, 5.5)));
-        assert_eq!(c.next(), Some((60, 6.0)));
-        assert_eq!(c.next(), Some((90, 9.0)));
-        assert_eq!(c.next(), Some((200, 20.0)));
+        assert_eq!(c.next(), Some((k(50), 5.5)));
+        assert_eq!(c.next(), Some((k(60), 6.0)));
+        assert_eq!(c.next(), Some((k(90), 9.0)));
+        assert_eq!(c.next(), Some((k(200), 20.0)));
         c.verify();
         assert_eq!(c.next(), None);
         c.verify();
     }
 
     // Removals from the root leaf node beyond underflow.
-    assert_eq!(m.remove(200, f, &()), Some(20.0));
-    assert_eq!(m.remove(40, f, &()), Some(4.0));
-    assert_eq!(m.remove(60, f, &()), Some(6.0));
+    assert_eq!(m.remove(k(200), f, &()), Some(20.0));
+    assert_eq!(m.remove(k(40), f, &()), Some(4.0));
+    assert_eq!(m.remove(k(60), f, &()), Some(6.0));
     // m.verify(f, &());
-    assert_eq!(m.remove(50, f, &()), Some(5.5));
+    assert_eq!(m.remove(k(50), f, &()), Some(5.5));
     // m.verify(f, &());
-    assert_eq!(m.remove(90, f, &()), Some(9.0));
+    assert_eq!(m.remove(k(90), f, &()), Some(9.0));
     // m.verify(f, &());
     assert!(m.is_empty());
 }
@@ -357,152 +378,218 @@ fn split_level1_leaf() {
 
 // Make a tree with two barely healthy leaf nodes:
 // [ 10 20 30 40 ] [ 50 60 70 80 ]
-fn two_leaf(f: &mut MapForest<u32, f32>) -> Map<u32, f32> {
+fn two_leaf<K: TestKey>(f: &mut MapForest<K, f32>, k: fn(u32) -> K) -> Map<K, f32> {
     f.clear();
     let mut m = Map::new();
     for n in 1..9 {
-        m.insert(n * 10, n as f32, f, &());
+        m.insert(k(n * 10), n as f32, f, &());
     }
     m
 }
 
 #[test]
 fn remove_level1() {
-    let f = &mut MapForest::<u32, f32>::new();
-    let mut m = two_leaf(f);
+    remove_level1_with(|n| n)
+}
+
+#[test]
+fn remove_level1_u64() {
+    remove_level1_with(wide_key)
+}
+
+fn remove_level1_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let mut m = two_leaf(f, k);
 
     // Verify geometry.
     // m.verify(f, &());
-    assert_eq!(m.tpath(10, f, &()), "node2[0]--node0[0]");
-    assert_eq!(m.tpath(40, f, &()), "node2[0]--node0[3]");
-    assert_eq!(m.tpath(49, f, &()), "node2[0]--node0[4]");
-    assert_eq!(m.tpath(50, f, &()), "node2[1]--node1[0]");
-    assert_eq!(m.tpath(80, f, &()), "node2[1]--node1[3]");
+    assert_eq!(m.tpath(k(10), f, &()), "node2[0]--node0[0]");
+    assert_eq!(m.tpath(k(40), f, &()), "node2[0]--node0[3]");
+    assert_eq!(m.tpath(k(49), f, &()), "node2[0]--node0[4]");
+    assert_eq!(m.tpath(k(50), f, &()), "node2[1]--node1[0]");
+    assert_eq!(m.tpath(k(80), f, &()), "node2[1]--node1[3]");
 
     // Remove the front entry from a node that stays healthy.
-    assert_eq!(m.insert(55, 5.5, f, &()), None);
-    assert_eq!(m.remove(50, f, &()), Some(5.0));
+    assert_eq!(m.insert(k(55), 5.5, f, &()), None);
+    assert_eq!(m.remove(k(50), f, &()), Some(5.0));
     // m.verify(f, &());
-    assert_eq!(m.tpath(49, f, &()), "node2[0]--node0[4]");
-    assert_eq!(m.tpath(50, f, &()), "node2[0]--node0[4]");
-    assert_eq!(m.tpath(55, f, &()), "node2[1]--node1[0]");
+    assert_eq!(m.tpath(k(49), f, &()), "node2[0]--node0[4]");
+    assert_eq!(m.tpath(k(50), f, &()), "node2[0]--node0[4]");
+    assert_eq!(m.tpath(k(55), f, &()), "node2[1]--node1[0]");
 
     // Remove the front entry from the first leaf node: No critical key to update.
-    assert_eq!(m.insert(15, 1.5, f, &()), None);
-    assert_eq!(m.remove(10, f, &()), Some(1.0));
+    assert_eq!(m.insert(k(15), 1.5, f, &()), None);
+    assert_eq!(m.remove(k(10), f, &()), Some(1.0));
     // m.verify(f, &());
 
     // [ 15 20 30 40 ] [ 55 60 70 80 ]
 
     // Remove the front entry from a right-most node that underflows.
     // No rebalancing for the right-most node. Still need critical key update.
-    assert_eq!(m.remove(55, f, &()), Some(5.5));
+    assert_eq!(m.remove(k(55), f, &()), Some(5.5));
     // m.verify(f, &());
-    assert_eq!(m.tpath(55, f, &()), "node2[0]--node0[4]");
-    assert_eq!(m.tpath(60, f, &()), "node2[1]--node1[0]");
+    assert_eq!(m.tpath(k(55), f, &()), "node2[0]--node0[4]");
+    assert_eq!(m.tpath(k(60), f, &()), "node2[1]--node1[0]");
 
     // [ 15 20 30 40 ] [ 60 70 80 ]
 
     // Replenish the right leaf.
-    assert_eq!(m.insert(90, 9.0, f, &()), None);
-    assert_eq!(m.insert(100, 10.0, f, &()), None);
+    assert_eq!(m.insert(k(90), 9.0, f, &()), None);
+    assert_eq!(m.insert(k(100), 10.0, f, &()), None);
     // m.verify(f, &());
-    assert_eq!(m.tpath(55, f, &()), "node2[0]--node0[4]");
-    assert_eq!(m.tpath(60, f, &()), "node2[1]--node1[0]");
+    assert_eq!(m.tpath(k(55), f, &()), "node2[0]--node0[4]");
+    assert_eq!(m.tpath(k(60), f, &()), "node2[1]--node1[0]");
 
     // [ 15 20 30 40 ] [ 60 70 80 90 100 ]
 
     // Removing one entry from the left leaf should trigger a rebalancing from the right
     // sibling.
-    assert_eq!(m.remove(20, f, &()), Some(2.0));
+    assert_eq!(m.remove(k(20), f, &()), Some(2.0));
     // m.verify(f, &());
 
     // [ 15 30 40 60 ] [ 70 80 90 100 ]
     // Check that the critical key was updated correctly.
-    assert_eq!(m.tpath(50, f, &()), "node2[0]--node0[3]");
-    assert_eq!(m.tpath(60, f, &()), "node2[0]--node0[3]");
-    assert_eq!(m.tpath(70, f, &()), "node2[1]--node1[0]");
+    assert_eq!(m.tpath(k(50), f, &()), "node2[0]--node0[3]");
+    assert_eq!(m.tpath(k(60), f, &()), "node2[0]--node0[3]");
+    assert_eq!(m.tpath(k(70), f, &()), "node2[1]--node1[0]");
 
     // Remove front entry from the left-most leaf node, underflowing.
     // This should cause two leaf nodes to be merged and the root node to go away.
-    assert_eq!(m.remove(15, f, &()), Some(1.5));
+    assert_eq!(m.remove(k(15), f, &()), Some(1.5));
     // m.verify(f, &());
 }
 
 #[test]
 fn remove_level1_rightmost() {
-    let f = &mut MapForest::<u32, f32>::new();
-    let mut m = two_leaf(f);
+    remove_level1_rightmost_with(|n| n)
+}
+
+#[test]
+fn remove_level1_rightmost_u64() {
+    remove_level1_rightmost_with(wide_key)
+}
+
+fn remove_level1_rightmost_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let mut m = two_leaf(f, k);
 
     // [ 10 20 30 40 ] [ 50 60 70 80 ]
 
     // Remove entries from the right leaf. This doesn't trigger a rebalancing.
-    assert_eq!(m.remove(60, f, &()), Some(6.0));
-    assert_eq!(m.remove(80, f, &()), Some(8.0));
-    assert_eq!(m.remove(50, f, &()), Some(5.0));
+    assert_eq!(m.remove(k(60), f, &()), Some(6.0));
+    assert_eq!(m.remove(k(80), f, &()), Some(8.0));
+    assert_eq!(m.remove(k(50), f, &()), Some(5.0));
     // m.verify(f, &());
 
     // [ 10 20 30 40 ] [ 70 ]
-    assert_eq!(m.tpath(50, f, &()), "node2[0]--node0[4]");
-    assert_eq!(m.tpath(70, f, &()), "node2[1]--node1[0]");
+    assert_eq!(m.tpath(k(50), f, &()), "node2[0]--node0[4]");
+    assert_eq!(m.tpath(k(70), f, &()), "node2[1]--node1[0]");
 
     // Removing the last entry from the right leaf should cause a collapse.
-    assert_eq!(m.remove(70, f, &()), Some(7.0));
+    assert_eq!(m.remove(k(70), f, &()), Some(7.0));
     // m.verify(f, &());
 }
 
 // Make a 3-level tree with barely healthy nodes.
 // 1 root, 8 inner nodes, 7*4+5=33 leaf nodes, 4 entries each.
-fn level3_sparse(f: &mut MapForest<u32, f32>) -> Map<u32, f32> {
+fn level3_sparse<K: TestKey>(f: &mut MapForest<K, f32>, k: fn(u32) -> K) -> Map<K, f32> {
     f.clear();
     let mut m = Map::new();
     for n in 1..133 {
-        m.insert(n * 10, n as f32, f, &());
+        m.insert(k(n * 10), n as f32, f, &());
     }
     m
 }
 
 #[test]
 fn level3_removes() {
-    let f = &mut MapForest::<u32, f32>::new();
-    let mut m = level3_sparse(f);
+    level3_removes_with(|n| n)
+}
+
+#[test]
+fn level3_removes_u64() {
+    level3_removes_with(wide_key)
+}
+
+fn level3_removes_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let mut m = level3_sparse(f, k);
     // m.verify(f, &());
 
     // Check geometry.
     // Root: node11
     // [ node2 170 node10 330 node16 490 node21 650 node26 810 node31 970 node36 1130 node41 ]
     // L1: node11
-    assert_eq!(m.tpath(0, f, &()), "node11[0]--node2[0]--node0[0]");
-    assert_eq!(m.tpath(10000, f, &()), "node11[7]--node41[4]--node40[4]");
+    assert_eq!(m.tpath(k(0), f, &()), "node11[0]--node2[0]--node0[0]");
+    assert_eq!(m.tpath(k(10000), f, &()), "node11[7]--node41[4]--node40[4]");
 
     // 650 is a critical key in the middle of the root.
-    assert_eq!(m.tpath(640, f, &()), "node11[3]--node21[3]--node19[3]");
-    assert_eq!(m.tpath(650, f, &()), "node11[4]--node26[0]--node20[0]");
+    assert_eq!(m.tpath(k(640), f, &()), "node11[3]--node21[3]--node19[3]");
+    assert_eq!(m.tpath(k(650), f, &()), "node11[4]--node26[0]--node20[0]");
 
     // Deleting 640 triggers a rebalance from node19 to node 20, cascading to n21 -> n26.
-    assert_eq!(m.remove(640, f, &()), Some(64.0));
+    assert_eq!(m.remove(k(640), f, &()), Some(64.0));
     // m.verify(f, &());
-    assert_eq!(m.tpath(650, f, &()), "node11[3]--node26[3]--node20[3]");
+    assert_eq!(m.tpath(k(650), f, &()), "node11[3]--node26[3]--node20[3]");
 
     // 1130 is in the first leaf of the last L1 node. Deleting it triggers a rebalance node35
     // -> node37, but no rebalance above where there is no right sibling.
-    assert_eq!(m.tpath(1130, f, &()), "node11[6]--node41[0]--node35[0]");
-    assert_eq!(m.tpath(1140, f, &()), "node11[6]--node41[0]--node35[1]");
-    assert_eq!(m.remove(1130, f, &()), Some(113.0));
+    assert_eq!(m.tpath(k(1130), f, &()), "node11[6]--node41[0]--node35[0]");
+    assert_eq!(m.tpath(k(1140), f, &()), "node11[6]--node41[0]--node35[1]");
+    assert_eq!(m.remove(k(1130), f, &()), Some(113.0));
     // m.verify(f, &());
-    assert_eq!(m.tpath(1140, f, &()), "node11[6]--node41[0]--node37[0]");
+    assert_eq!(m.tpath(k(1140), f, &()), "node11[6]--node41[0]--node37[0]");
+}
+
+#[test]
+fn iter_from() {
+    iter_from_with(|n| n)
+}
+
+#[test]
+fn iter_from_u64() {
+    iter_from_with(wide_key)
+}
+
+fn iter_from_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let m = level3_sparse(f, k);
+    let suffix = |start: u32| (start..133).map(move |n| (k(n * 10), n as f32));
+
+    // exact keys, including the first key of a leaf and of an inner node
+    assert!(m.iter_from(f, k(10), &()).eq(suffix(1)));
+    assert!(m.iter_from(f, k(650), &()).eq(suffix(65)));
+    assert!(m.iter_from(f, k(1320), &()).eq(suffix(132)));
+
+    // keys between stored keys, including the gaps between leaves
+    assert!(m.iter_from(f, k(0), &()).eq(suffix(1)));
+    assert!(m.iter_from(f, k(645), &()).eq(suffix(65)));
+    assert!(m.iter_from(f, k(1135), &()).eq(suffix(114)));
+
+    // keys past the end
+    assert_eq!(m.iter_from(f, k(1321), &()).next(), None);
+    assert_eq!(Map::<K, f32>::new().iter_from(f, k(0), &()).next(), None);
 }
 
 #[test]
 fn insert_many() {
-    let f = &mut MapForest::<u32, f32>::new();
-    let mut m = Map::<u32, f32>::new();
+    insert_many_with(|n| n)
+}
+
+#[test]
+fn insert_many_u64() {
+    insert_many_with(wide_key)
+}
+
+fn insert_many_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let mut m = Map::<K, f32>::new();
 
     let mm = 4096;
     let mut x = 0;
 
     for n in 0..mm {
-        assert_eq!(m.insert(x, n as f32, f, &()), None);
+        assert_eq!(m.insert(k(x), n as f32, f, &()), None);
         // m.verify(f, &());
 
         x = (x + n + 1) % mm;
@@ -510,13 +597,13 @@ fn insert_many() {
 
     x = 0;
     for n in 0..mm {
-        assert_eq!(m.get(x, f, &()), Some(n as f32));
+        assert_eq!(m.get(k(x), f, &()), Some(n as f32));
         x = (x + n + 1) % mm;
     }
 
     x = 0;
     for n in 0..mm {
-        assert_eq!(m.remove(x, f, &()), Some(n as f32));
+        assert_eq!(m.remove(k(x), f, &()), Some(n as f32));
         // m.verify(f, &());
 
         x = (x + n + 1) % mm;
@@ -527,27 +614,36 @@ fn insert_many() {
 
 #[test]
 fn insert_sorted() {
-    let f = &mut MapForest::<u32, f32>::new();
-    let mut m = Map::<u32, f32>::new();
+    insert_sorted_with(|n| n)
+}
+
+#[test]
+fn insert_sorted_u64() {
+    insert_sorted_with(wide_key)
+}
+
+fn insert_sorted_with<K: TestKey>(k: fn(u32) -> K) {
+    let f = &mut MapForest::<K, f32>::new();
+    let mut m = Map::<K, f32>::new();
 
     let mm = 4096;
 
     for n in 0..mm {
-        assert_eq!(m.insert(2 * n, (2 * n) as f32, f, &()), None);
+        assert_eq!(m.insert(k(2 * n), (2 * n) as f32, f, &()), None);
     }
 
-    m.insert_sorted_iter((0..mm).map(|n| (2 * n + 1, (2 * n + 1) as f32)), f, &(), |a, b| {
+    m.insert_sorted_iter((0..mm).map(|n| (k(2 * n + 1), (2 * n + 1) as f32)), f, &(), |a, b| {
         assert_eq!(a, None);
         b
     });
 
-    assert!(m.iter(f).eq((0..2 * mm).map(|n| (n, n as f32))));
+    assert!(m.iter(f).eq((0..2 * mm).map(|n| (k(n), n as f32))));
 
-    let mut m = Map::<u32, f32>::new();
+    let mut m = Map::<K, f32>::new();
 
     let mm = 4096;
 
-    let iter = (0..mm).map(|n| (2 * n + 1, (2 * n + 1) as f32));
+    let iter = (0..mm).map(|n| (k(2 * n + 1), (2 * n + 1) as f32));
 
     m.insert_sorted_iter(iter.clone(), f, &(), |a, b| {
         assert_eq!(a, None);
@@ -560,10 +656,162 @@ fn insert_sorted() {
 
     assert!(m.iter(f).eq(iter));
 
-    let iter = (0..mm).map(|n| (2 * n + 1, (4 * n + 1) as f32));
+    let iter = (0..mm).map(|n| (k(2 * n + 1), (4 * n + 1) as f32));
 
     m.insert_sorted_iter(iter, f, &(), |old, new| new + old.unwrap());
 
-    let iter = (0..mm).map(|n| (2 * n + 1, (6 * n + 2) as f32));
+    let iter = (0..mm).map(|n| (k(2 * n + 1), (6 * n + 2) as f32));
     assert!(m.iter(f).eq(iter));
 }
+
+#[test]
+fn merge_with() {
+    let f = &mut MapForest::<u32, f32>::new();
+    let mut a = Map::<u32, f32>::new();
+    let mut b = Map::<u32, f32>::new();
+
+    // large enough that both maps span multiple levels
+    let mm = 1000;
+    for n in 0..mm {
+        a.insert(2 * n, 1.0, f, &());
+        b.insert(3 * n, 2.0, f, &());
+    }
+
+    let merged = f.merge_with(&a, &b, &(), |a, b| a + b);
+    let expected = (0..3 * mm).filter_map(|n| match (n % 2 == 0 && n < 2 * mm, n % 3 == 0) {
+        (true, true) => Some((n, 3.0)),
+        (true, false) => Some((n, 1.0)),
+        (false, true) => Some((n, 2.0)),
+        (false, false) => None,
+    });
+    assert!(merged.iter(f).eq(expected));
+
+    // the inputs are unchanged
+    assert!(a.iter(f).eq((0..mm).map(|n| (2 * n, 1.0))));
+    assert!(b.iter(f).eq((0..mm).map(|n| (3 * n, 2.0))));
+
+    let empty = Map::new();
+    assert!(f.merge_with(&empty, &empty, &(), |_, _| unreachable!()).is_empty());
+    let merged = f.merge_with(&a, &empty, &(), |_, _| unreachable!());
+    assert!(merged.iter(f).eq(a.iter(f)));
+}
+
+#[test]
+fn trees_equal() {
+    let f = &mut MapForest::<u32, f32>::new();
+    let mut a = Map::<u32, f32>::new();
+    let mut b = Map::<u32, f32>::new();
+    for n in 0..500 {
+        a.insert(n, n as f32, f, &());
+        b.insert(499 - n, (499 - n) as f32, f, &());
+    }
+    assert!(f.trees_equal(&a, &b, &()));
+
+    // same keys but a different value
+    b.insert(250, 0.5, f, &());
+    assert!(!f.trees_equal(&a, &b, &()));
+    b.remove(250, f, &());
+    assert!(!f.trees_equal(&a, &b, &()));
+    assert!(!f.trees_equal(&b, &a, &()));
+}
+
+#[test]
+fn pool_stats() {
+    let f = &mut MapForest::<u32, f32>::new();
+    let mut m = Map::<u32, f32>::new();
+    for i in 0..100 {
+        m.insert(i, i as f32, f, &());
+    }
+    let allocated = f.stats().allocated;
+    assert_eq!(f.stats().free, 0);
+
+    m.retain(f, |_, _| false);
+    assert!(m.is_empty());
+    assert_eq!(f.stats().free, allocated);
+}
+
+#[test]
+fn compact() {
+    let f = &mut MapForest::<u32, u32>::new();
+    let mut a = Map::new();
+    let mut b = Map::new();
+    for k in 0..1000 {
+        a.insert(k, k, f, &());
+        b.insert(k, 2 * k, f, &());
+    }
+    a.retain(f, |k, _| k % 50 == 0);
+    b.clear(f);
+    b.insert(7, 14, f, &());
+    let expected: Vec<_> = a.iter(f).collect();
+    let before = f.stats();
+
+    f.compact([&mut a, &mut b]);
+    assert_eq!(f.stats().free, 0);
+    assert_eq!(f.stats().allocated, before.live());
+    assert_eq!(a.iter(f).collect::<Vec<_>>(), expected);
+    assert_eq!(b.iter(f).collect::<Vec<_>>(), [(7, 14)]);
+}
+
+#[test]
+fn rebuild() {
+    let f = &mut MapForest::<u32, u32>::new();
+    let mut m = Map::new();
+    for i in 0..1000u32 {
+        let k = i.wrapping_mul(2_654_435_761) % 4093;
+        m.insert(k, i, f, &());
+    }
+    let expected: Vec<_> = m.iter(f).collect();
+    let before = f.stats();
+
+    let mut m = f.rebuild(m);
+    assert_eq!(m.iter(f).collect::<Vec<_>>(), expected);
+    assert_eq!(f.stats().free, before.live());
+    // the new nodes are as full as possible
+    let mut level = expected.len().div_ceil(7);
+    let mut nodes = level;
+    while level > 1 {
+        level = level.div_ceil(8);
+        nodes += level;
+    }
+    assert_eq!(f.stats().live(), nodes);
+
+    m.insert(5000, 1, f, &());
+    assert_eq!(m.get(5000, f, &()), Some(1));
+    assert_eq!(m.iter(f).count(), expected.len() + 1);
+}
+
+#[test]
+fn leaves() {
+    let mut f = MapForest::<u32, f32>::new();
+    let mut m = Map::<u32, f32>::new();
+    assert_eq!(m.leaves(&f).count(), 0);
+
+    for i in 0..1000u32 {
+        let key = i.wrapping_mul(2_654_435_761) % 10007;
+        m.insert(key, key as f32 * 0.5, &mut f, &());
+    }
+    let mut entries = Vec::new();
+    let mut num_leaves = 0;
+    for (keys, vals) in m.leaves(&f) {
+        assert_eq!(keys.len(), vals.len());
+        entries.extend(keys.iter().copied().zip(vals.iter().copied()));
+        num_leaves += 1;
+    }
+    assert!(num_leaves > 1);
+    assert_eq!(entries, m.iter(&f).collect::<Vec<_>>());
+}
+
+#[test]
+fn iter_ref() {
+    let mut f = MapForest::<u32, [f64; 4]>::new();
+    let mut m = Map::<u32, [f64; 4]>::new();
+    assert_eq!(m.iter_ref(&f).count(), 0);
+
+    for i in 0..1000u32 {
+        let key = i.wrapping_mul(2_654_435_761) % 10007;
+        m.insert(key, [key as f64; 4], &mut f, &());
+    }
+    let entries: Vec<_> = m.iter_ref(&f).map(|(key, val)| (key, *val)).collect();
+    assert!(m.leaves(&f).count() > 1);
+    assert_eq!(entries, m.iter(&f).collect::<Vec<_>>());
+}
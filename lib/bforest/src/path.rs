@@ -202,6 +202,16 @@ impl<F: Forest> Path<F> {
         })
     }
 
+    /// Move this path to the first entry of the next leaf node and return the leaf node.
+    ///
+    /// Reset the path to `size = 0` and return `None` if there is no next leaf node.
+    pub fn next_leaf(&mut self, pool: &NodePool<F>) -> Option<Node> {
+        if self.size == 0 {
+            return None;
+        }
+        self.next_node(self.size - 1, pool)
+    }
+
     /// Move this path to the previous key-value pair and return it.
     ///
     /// If the path is at the off-the-end position, go to the last key-value pair.
@@ -4,32 +4,86 @@
 // use super::Comparator;
 // #[cfg(test)]
 // use core::fmt;
+use core::borrow::{Borrow, BorrowMut};
+use core::mem::size_of;
 use core::ops::{Index, IndexMut};
 
-use super::{Forest, Node, NodeData};
+use super::{Forest, Node, NodeData, INNER_SIZE};
 
 /// A pool of nodes, including a free list.
 pub(super) struct NodePool<F: Forest> {
     nodes: Vec<NodeData<F>>,
     freelist: Option<Node>,
+    num_free: usize,
+}
+
+/// Memory usage of the node pool backing a forest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Number of nodes allocated from the pool (including nodes on the free list).
+    pub allocated: usize,
+    /// Number of allocated nodes on the free list that can be reused.
+    pub free: usize,
+    /// Number of nodes the pool can hold without reallocating.
+    pub capacity: usize,
+    /// Number of bytes reserved for nodes (`capacity` times the size of a node).
+    pub bytes: usize,
+}
+
+impl PoolStats {
+    /// Number of nodes that are currently used by a tree in the forest.
+    pub fn live(&self) -> usize {
+        self.allocated - self.free
+    }
+}
+
+/// The new location of every node moved by the `compact` method of a forest.
+///
+/// Sets and maps are `Copy`, so there may be copies of a tree that were not passed
+/// to `compact`. These must be updated with the remapping before they are used again.
+#[derive(Clone, Debug, Default)]
+pub struct NodeRemap {
+    nodes: Vec<Option<Node>>,
+}
+
+impl NodeRemap {
+    /// The new location of `node`.
+    pub(super) fn get(&self, node: Node) -> Node {
+        self.nodes
+            .get(usize::from(node))
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| panic!("{} was not moved during compaction", node))
+    }
 }
 
 impl<F: Forest> Clone for NodePool<F> {
     fn clone(&self) -> Self {
-        Self { nodes: self.nodes.clone(), freelist: self.freelist }
+        Self { nodes: self.nodes.clone(), freelist: self.freelist, num_free: self.num_free }
     }
 }
 
 impl<F: Forest> NodePool<F> {
     /// Allocate a new empty pool of nodes.
     pub fn new() -> Self {
-        Self { nodes: Vec::new(), freelist: None }
+        Self { nodes: Vec::new(), freelist: None, num_free: 0 }
     }
 
     /// Free all nodes.
     pub fn clear(&mut self) {
         self.nodes.clear();
         self.freelist = None;
+        self.num_free = 0;
+    }
+
+    /// Memory usage statistics of this pool.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            allocated: self.nodes.len(),
+            free: self.num_free,
+            capacity: self.nodes.capacity(),
+            bytes: self.nodes.capacity() * size_of::<NodeData<F>>(),
+        }
     }
 
     /// Allocate a new node containing `data`.
@@ -42,6 +96,7 @@ impl<F: Forest> NodePool<F> {
                     NodeData::Free { next } => self.freelist = next,
                     _ => panic!("Invalid {} on free list", node),
                 }
+                self.num_free -= 1;
                 self.nodes[usize::from(node)] = data;
                 node
             }
@@ -54,12 +109,120 @@ impl<F: Forest> NodePool<F> {
         }
     }
 
+    /// Move the nodes of the trees rooted at `roots` to a contiguous prefix of the pool and
+    /// release all other nodes (including the free list). Trees that are passed multiple times
+    /// are only moved once. The root nodes must be updated with the returned remapping.
+    pub fn compact(&mut self, roots: impl IntoIterator<Item = Node>) -> NodeRemap {
+        let mut remap = NodeRemap { nodes: vec![None; self.nodes.len()] };
+        let mut nodes = Vec::new();
+        let mut todo = Vec::new();
+        for root in roots {
+            todo.push(root);
+            while let Some(node) = todo.pop() {
+                let new = &mut remap.nodes[usize::from(node)];
+                if new.is_some() {
+                    continue;
+                }
+                *new = Some(nodes.len().into());
+                let data = self[node];
+                if let NodeData::Inner { size, tree, .. } = data {
+                    todo.extend_from_slice(&tree[..usize::from(size) + 1]);
+                }
+                nodes.push(data);
+            }
+        }
+
+        for data in &mut nodes {
+            if let NodeData::Inner { size, tree, .. } = data {
+                for child in &mut tree[..usize::from(*size) + 1] {
+                    *child = remap.get(*child);
+                }
+            }
+        }
+
+        nodes.shrink_to_fit();
+        self.nodes = nodes;
+        self.freelist = None;
+        self.num_free = 0;
+        remap
+    }
+
+    /// Rebuild the tree rooted at `root` from its entries. The nodes of the new tree are appended
+    /// to the pool in the order in which they are traversed (all leaves from left to right,
+    /// followed by the inner nodes one level at a time), so that iterating the tree walks through
+    /// memory sequentially. Nodes are filled as evenly as possible. The nodes of the old tree are
+    /// moved to the free list. Returns the root of the new tree.
+    pub fn rebuild(&mut self, root: Node) -> Node {
+        let mut entries = Vec::new();
+        self.collect_entries(root, &mut entries);
+        self.free_tree(root);
+
+        let (key, _) = entries[0];
+        let leaf_size = F::splat_key(key).borrow().len();
+        let mut level: Vec<(Node, F::Key)> = balanced_chunks(&entries, leaf_size)
+            .map(|chunk| {
+                let (crit_key, val) = chunk[0];
+                let mut keys = F::splat_key(crit_key);
+                let mut vals = F::splat_value(val);
+                for (i, &(key, val)) in chunk.iter().enumerate() {
+                    keys.borrow_mut()[i] = key;
+                    vals.borrow_mut()[i] = val;
+                }
+                let leaf = NodeData::Leaf { size: chunk.len() as u8, keys, vals };
+                (self.push_node(leaf), crit_key)
+            })
+            .collect();
+
+        while level.len() > 1 {
+            level = balanced_chunks(&level, INNER_SIZE)
+                .map(|chunk| {
+                    let (first, crit_key) = chunk[0];
+                    let mut keys = [crit_key; INNER_SIZE - 1];
+                    let mut tree = [first; INNER_SIZE];
+                    for (i, &(node, key)) in chunk.iter().enumerate() {
+                        tree[i] = node;
+                        if i != 0 {
+                            keys[i - 1] = key;
+                        }
+                    }
+                    let inner = NodeData::Inner { size: (chunk.len() - 1) as u8, keys, tree };
+                    (self.push_node(inner), crit_key)
+                })
+                .collect();
+        }
+        level[0].0
+    }
+
+    /// Append the key-value pairs of the tree rooted at `node` to `entries` in order.
+    fn collect_entries(&self, node: Node, entries: &mut Vec<(F::Key, F::Value)>) {
+        match self[node] {
+            NodeData::Inner { size, tree, .. } => {
+                for &child in &tree[..usize::from(size) + 1] {
+                    // The recursion is bounded by `MAX_PATH` just like in `free_tree`.
+                    self.collect_entries(child, entries);
+                }
+            }
+            ref leaf => {
+                let (keys, vals) = leaf.unwrap_leaf();
+                entries.extend(keys.iter().copied().zip(vals.iter().copied()));
+            }
+        }
+    }
+
+    /// Allocate a new node at the end of the pool, ignoring the free list.
+    fn push_node(&mut self, data: NodeData<F>) -> Node {
+        let node = self.nodes.len().into();
+        self.nodes.push(data);
+        node
+    }
+
     /// Free a node.
     pub fn free_node(&mut self, node: Node) {
         // Quick check for a double free.
         debug_assert!(!self.nodes[usize::from(node)].is_free(), "{} is already free", node);
         self.nodes[usize::from(node)] = NodeData::Free { next: self.freelist };
         self.freelist = Some(node);
+        self.num_free += 1;
     }
 
     /// Free the entire tree rooted at `node`.
@@ -78,6 +241,19 @@ impl<F: Forest> NodePool<F> {
     }
 }
 
+/// Split `items` into the smallest number of chunks with at most `max` items each. The sizes of
+/// the chunks differ by at most one, so every chunk is at least half full if there are multiple.
+fn balanced_chunks<T>(items: &[T], max: usize) -> impl Iterator<Item = &[T]> {
+    let num = items.len().div_ceil(max);
+    let (len, extra) = (items.len() / num, items.len() % num);
+    let mut rest = items;
+    (0..num).map(move |i| {
+        let (chunk, tail) = rest.split_at(len + usize::from(i < extra));
+        rest = tail;
+        chunk
+    })
+}
+
 // #[cfg(test)]
 // impl<F: Forest> NodePool<F> {
 //     /// Verify the consistency of the tree rooted at `node`.
@@ -2,9 +2,12 @@
 #[cfg(test)]
 mod tests;
 
+use core::cmp::Ordering;
 use core::marker::PhantomData;
 
-use super::{Comparator, Forest, Node, NodeData, NodePool, Path, SetValue, INNER_SIZE};
+use super::{
+    Comparator, Forest, Node, NodeData, NodePool, NodeRemap, Path, PoolStats, SetValue, INNER_SIZE,
+};
 use crate::packed_option::PackedOption;
 
 /// Tag type defining forest types for a set.
@@ -36,6 +39,12 @@ where
     nodes: NodePool<SetTypes<K>>,
 }
 
+/// A [`SetForest`] with 64-bit keys.
+///
+/// The leaf nodes hold as many keys as with 32-bit keys, so the trees have the same shape but the
+/// nodes are larger than a cache line.
+pub type SetForest64 = SetForest<u64>;
+
 impl<K: Copy> Clone for SetForest<K> {
     fn clone(&self) -> Self {
         Self { nodes: self.nodes.clone() }
@@ -57,6 +66,130 @@ where
     pub fn clear(&mut self) {
         self.nodes.clear();
     }
+
+    /// Move the nodes of `sets` to the start of the pool and release all other nodes
+    /// (including the free list), so that the pool is as small as possible.
+    ///
+    /// The roots of `sets` are updated in place. Every other set of this forest is invalidated,
+    /// unless it is a copy of one of `sets` and updated with the returned [`NodeRemap`].
+    pub fn compact<'a>(&mut self, sets: impl IntoIterator<Item = &'a mut Set<K>>) -> NodeRemap
+    where
+        K: 'a,
+    {
+        let sets: Vec<_> = sets.into_iter().collect();
+        let remap = self.nodes.compact(sets.iter().filter_map(|set| set.root.expand()));
+        for set in sets {
+            set.remap(&remap);
+        }
+        remap
+    }
+
+    /// Rebuild `set` with freshly allocated nodes that are laid out in traversal order and
+    /// filled evenly, so that iterating and searching the set is cache friendly. The old
+    /// nodes are moved to the free list, use [`compact`](Self::compact) to release them.
+    ///
+    /// Copies of `set` are invalidated and must be replaced by the returned set.
+    pub fn rebuild(&mut self, set: Set<K>) -> Set<K> {
+        let root = set.root.expand().map(|root| self.nodes.rebuild(root));
+        Set { root: root.into(), unused: PhantomData }
+    }
+
+    /// Memory usage of the node pool shared by all sets in the forest.
+    ///
+    /// Nodes freed by removing elements (or clearing individual sets) are kept on a free
+    /// list for reuse. Only [`clear`](Self::clear) (which keeps the capacity) and
+    /// [`compact`](Self::compact) release them.
+    pub fn stats(&self) -> PoolStats {
+        self.nodes.stats()
+    }
+
+    /// Is every element of `a` also an element of `b`?
+    ///
+    /// Both sets are traversed in lockstep, so this is `O(|a| + |b|)` and does not allocate.
+    pub fn is_subset<C: Comparator<K>>(&self, a: &Set<K>, b: &Set<K>, comp: &C) -> bool {
+        let mut b_iter = b.iter(self);
+        'outer: for x in a.iter(self) {
+            for y in &mut b_iter {
+                match comp.cmp(x, y) {
+                    Ordering::Less => return false,
+                    Ordering::Equal => continue 'outer,
+                    Ordering::Greater => (),
+                }
+            }
+            // `b` is exhausted but `a` still has elements left
+            return false;
+        }
+        true
+    }
+
+    /// Do the sets `a` and `b` have no elements in common?
+    ///
+    /// Both sets are traversed in lockstep, so this is `O(|a| + |b|)` and does not allocate.
+    pub fn is_disjoint<C: Comparator<K>>(&self, a: &Set<K>, b: &Set<K>, comp: &C) -> bool {
+        let mut a_iter = a.iter(self);
+        let mut b_iter = b.iter(self);
+        let (mut x, mut y) = match (a_iter.next(), b_iter.next()) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return true,
+        };
+        loop {
+            match comp.cmp(x, y) {
+                Ordering::Less => match a_iter.next() {
+                    Some(next) => x = next,
+                    None => return true,
+                },
+                Ordering::Greater => match b_iter.next() {
+                    Some(next) => y = next,
+                    None => return true,
+                },
+                Ordering::Equal => return false,
+            }
+        }
+    }
+
+    /// Do the sets `a` and `b` contain the same elements?
+    ///
+    /// Both sets are traversed in lockstep until the first difference, so this is
+    /// `O(min(|a|, |b|))` and does not allocate.
+    pub fn trees_equal<C: Comparator<K>>(&self, a: &Set<K>, b: &Set<K>, comp: &C) -> bool {
+        let mut a_iter = a.iter(self);
+        let mut b_iter = b.iter(self);
+        loop {
+            match (a_iter.next(), b_iter.next()) {
+                (Some(x), Some(y)) if comp.cmp(x, y) == Ordering::Equal => (),
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
+
+    /// Split `set` at `k`: all elements `>= k` are moved into a new set which is returned, while
+    /// all elements `< k` remain in `set`. Both resulting sets stay balanced.
+    ///
+    /// The elements are moved one at a time, so this is `O(m log n)` for `m` moved elements. If
+    /// `k` is smaller than (or equal to) every element of `set`, the whole tree is moved to the
+    /// returned set without touching any nodes. If `k` is larger than every element, no nodes
+    /// are touched either.
+    pub fn split_off<C: Comparator<K>>(&mut self, set: &mut Set<K>, k: K, comp: &C) -> Set<K> {
+        let first =
+            if let Some(first) = set.iter(self).next() { first } else { return Set::new() };
+        if comp.cmp(k, first) != Ordering::Greater {
+            return core::mem::take(set);
+        }
+        let last = set.iter_rev(self).next().unwrap();
+        if comp.cmp(k, last) == Ordering::Greater {
+            return Set::new();
+        }
+
+        let mut res = Set::new();
+        loop {
+            let mut cursor = SetCursor::new(set, self, comp);
+            cursor.goto(k);
+            let elem = if let Some(elem) = cursor.remove() { elem } else { break };
+            res.insert(elem, self, comp);
+        }
+        res
+    }
 }
 
 impl<K> Default for SetForest<K>
@@ -94,6 +227,13 @@ where
         Self { root: None.into(), unused: PhantomData }
     }
 
+    /// Update the root of this set after its forest was compacted.
+    ///
+    /// This is only required for copies of a set that were not passed to `compact` themselves.
+    pub fn remap(&mut self, remap: &NodeRemap) {
+        self.root = self.root.map(|root| remap.get(root)).into();
+    }
+
     /// Is this an empty set?
     pub fn is_empty(&self) -> bool {
         self.root.is_none()
@@ -107,6 +247,18 @@ where
             .is_some()
     }
 
+    /// Does the set contain every element of `other`?
+    ///
+    /// See [`SetForest::is_subset`].
+    pub fn contains_all<C: Comparator<K>>(
+        &self,
+        other: &Set<K>,
+        forest: &SetForest<K>,
+        comp: &C,
+    ) -> bool {
+        forest.is_subset(other, self, comp)
+    }
+
     /// Try to insert `key` into the set.
     ///
     /// If the set did not contain `key`, insert it and return true.
@@ -186,6 +338,14 @@ where
         RevSetIter { root: self.root, pool: &forest.nodes, path: Path::default() }
     }
 
+    /// Create an iterator over the leaf nodes of this set. Each item is the slice of keys
+    /// stored in one leaf, which allows processing a whole leaf in a tight loop instead of
+    /// calling [`SetIter::next`] for every element. The slices are sorted and contiguous:
+    /// concatenating them yields the same elements in the same order as [`Set::iter`].
+    pub fn leaves<'a>(&'a self, forest: &'a SetForest<K>) -> SetLeaves<'a, K> {
+        SetLeaves { root: self.root, pool: &forest.nodes, path: Path::default() }
+    }
+
     /// create a `SetPos` which allows streaming iteration of the set
     pub fn read_cursor(self) -> SetPos<K> {
         SetPos { root: self.root, path: Path::default() }
@@ -366,6 +526,36 @@ where
     }
 }
 
+/// An iterator visiting the leaf nodes of a `Set`, see [`Set::leaves`].
+#[derive(Clone, Copy)]
+pub struct SetLeaves<'a, K>
+where
+    K: 'a + Copy,
+{
+    root: PackedOption<Node>,
+    pool: &'a NodePool<SetTypes<K>>,
+    path: Path<SetTypes<K>>,
+}
+
+impl<'a, K> Iterator for SetLeaves<'a, K>
+where
+    K: 'a + Copy,
+{
+    type Item = &'a [K];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Same approach as `SetIter`: `self.root` is only set before the first leaf.
+        let leaf = match self.root.take() {
+            Some(root) => {
+                self.path.first(root, self.pool);
+                self.path.leaf_pos()?.0
+            }
+            None => self.path.next_leaf(self.pool)?,
+        };
+        Some(self.pool[leaf].unwrap_leaf().0)
+    }
+}
+
 /// An iterator visiting the elements of a `Set`.
 #[derive(Clone, Copy)]
 pub struct SetPos<K>
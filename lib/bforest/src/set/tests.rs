@@ -1,6 +1,7 @@
 use std::{fmt, mem};
 
 use super::*;
+use crate::tests::{wide_key, TestKey};
 use crate::NodeData;
 
 impl<'a, K, C> SetCursor<'a, K, C>
@@ -24,6 +25,9 @@ fn node_size() {
     // check that nodes are cache line sized when keys are 32 bits.
     type F = SetTypes<u32>;
     assert_eq!(mem::size_of::<NodeData<F>>(), 64);
+
+    // 64-bit keys keep the same number of entries per node, so the nodes grow instead
+    assert_eq!(mem::size_of::<NodeData<SetTypes<u64>>>(), 128);
 }
 
 #[test]
@@ -51,40 +55,49 @@ fn empty() {
 
 #[test]
 fn simple_cursor() {
-    let mut f = SetForest::<u32>::new();
-    let mut s = Set::<u32>::new();
+    simple_cursor_with(|n| n)
+}
+
+#[test]
+fn simple_cursor_u64() {
+    simple_cursor_with(wide_key)
+}
+
+fn simple_cursor_with<K: TestKey>(k: fn(u32) -> K) {
+    let mut f = SetForest::<K>::new();
+    let mut s = Set::<K>::new();
     let mut c = SetCursor::new(&mut s, &mut f, &());
 
-    assert!(c.insert(50));
+    assert!(c.insert(k(50)));
     c.verify();
-    assert_eq!(c.elem(), Some(50));
+    assert_eq!(c.elem(), Some(k(50)));
 
-    assert!(c.insert(100));
+    assert!(c.insert(k(100)));
     c.verify();
-    assert_eq!(c.elem(), Some(100));
+    assert_eq!(c.elem(), Some(k(100)));
 
-    assert!(c.insert(10));
+    assert!(c.insert(k(10)));
     c.verify();
-    assert_eq!(c.elem(), Some(10));
+    assert_eq!(c.elem(), Some(k(10)));
 
     // Basic movement.
-    assert_eq!(c.next(), Some(50));
-    assert_eq!(c.next(), Some(100));
+    assert_eq!(c.next(), Some(k(50)));
+    assert_eq!(c.next(), Some(k(100)));
     assert_eq!(c.next(), None);
     assert_eq!(c.next(), None);
-    assert_eq!(c.prev(), Some(100));
-    assert_eq!(c.prev(), Some(50));
-    assert_eq!(c.prev(), Some(10));
+    assert_eq!(c.prev(), Some(k(100)));
+    assert_eq!(c.prev(), Some(k(50)));
+    assert_eq!(c.prev(), Some(k(10)));
     assert_eq!(c.prev(), None);
     assert_eq!(c.prev(), None);
 
-    assert!(c.goto(50));
-    assert_eq!(c.elem(), Some(50));
-    assert_eq!(c.remove(), Some(50));
+    assert!(c.goto(k(50)));
+    assert_eq!(c.elem(), Some(k(50)));
+    assert_eq!(c.remove(), Some(k(50)));
     c.verify();
 
-    assert_eq!(c.elem(), Some(100));
-    assert_eq!(c.remove(), Some(100));
+    assert_eq!(c.elem(), Some(k(100)));
+    assert_eq!(c.remove(), Some(k(100)));
     c.verify();
     assert_eq!(c.elem(), None);
     assert_eq!(c.remove(), None);
@@ -93,35 +106,44 @@ fn simple_cursor() {
 
 #[test]
 fn two_level_sparse_tree() {
-    let mut f = SetForest::<u32>::new();
-    let mut s = Set::<u32>::new();
+    two_level_sparse_tree_with(|n| n)
+}
+
+#[test]
+fn two_level_sparse_tree_u64() {
+    two_level_sparse_tree_with(wide_key)
+}
+
+fn two_level_sparse_tree_with<K: TestKey>(k: fn(u32) -> K) {
+    let mut f = SetForest::<K>::new();
+    let mut s = Set::<K>::new();
     let mut c = SetCursor::new(&mut s, &mut f, &());
 
     // Insert enough elements that we get a two-level tree.
     // Each leaf node holds 8 elements
     assert!(c.is_empty());
     for i in 0..50 {
-        assert!(c.insert(i));
-        assert_eq!(c.elem(), Some(i));
+        assert!(c.insert(k(i)));
+        assert_eq!(c.elem(), Some(k(i)));
     }
     assert!(!c.is_empty());
 
-    assert_eq!(c.goto_first(), Some(0));
+    assert_eq!(c.goto_first(), Some(k(0)));
     assert_eq!(c.tpath(), "node2[0]--node0[0]");
 
     assert_eq!(c.prev(), None);
     for i in 1..50 {
-        assert_eq!(c.next(), Some(i));
+        assert_eq!(c.next(), Some(k(i)));
     }
     assert_eq!(c.next(), None);
     for i in (0..50).rev() {
-        assert_eq!(c.prev(), Some(i));
+        assert_eq!(c.prev(), Some(k(i)));
     }
     assert_eq!(c.prev(), None);
 
-    assert!(c.goto(25));
+    assert!(c.goto(k(25)));
     for i in 25..50 {
-        assert_eq!(c.remove(), Some(i));
+        assert_eq!(c.remove(), Some(k(i)));
         assert!(!c.is_empty());
         c.verify();
     }
@@ -129,8 +151,8 @@ fn two_level_sparse_tree() {
     for i in (0..25).rev() {
         assert!(!c.is_empty());
         assert_eq!(c.elem(), None);
-        assert_eq!(c.prev(), Some(i));
-        assert_eq!(c.remove(), Some(i));
+        assert_eq!(c.prev(), Some(k(i)));
+        assert_eq!(c.remove(), Some(k(i)));
         c.verify();
     }
     assert_eq!(c.elem(), None);
@@ -139,8 +161,17 @@ fn two_level_sparse_tree() {
 
 #[test]
 fn three_level_sparse_tree() {
-    let mut f = SetForest::<u32>::new();
-    let mut s = Set::<u32>::new();
+    three_level_sparse_tree_with(|n| n)
+}
+
+#[test]
+fn three_level_sparse_tree_u64() {
+    three_level_sparse_tree_with(wide_key)
+}
+
+fn three_level_sparse_tree_with<K: TestKey>(k: fn(u32) -> K) {
+    let mut f = SetForest::<K>::new();
+    let mut s = Set::<K>::new();
     let mut c = SetCursor::new(&mut s, &mut f, &());
 
     // Insert enough elements that we get a 3-level tree.
@@ -148,27 +179,27 @@ fn three_level_sparse_tree() {
     // Inner nodes hold 8 node pointers.
     assert!(c.is_empty());
     for i in 0..150 {
-        assert!(c.insert(i));
-        assert_eq!(c.elem(), Some(i));
+        assert!(c.insert(k(i)));
+        assert_eq!(c.elem(), Some(k(i)));
     }
     assert!(!c.is_empty());
 
-    assert!(c.goto(0));
+    assert!(c.goto(k(0)));
     assert_eq!(c.tpath(), "node11[0]--node2[0]--node0[0]");
 
     assert_eq!(c.prev(), None);
     for i in 1..150 {
-        assert_eq!(c.next(), Some(i));
+        assert_eq!(c.next(), Some(k(i)));
     }
     assert_eq!(c.next(), None);
     for i in (0..150).rev() {
-        assert_eq!(c.prev(), Some(i));
+        assert_eq!(c.prev(), Some(k(i)));
     }
     assert_eq!(c.prev(), None);
 
-    assert!(c.goto(125));
+    assert!(c.goto(k(125)));
     for i in 125..150 {
-        assert_eq!(c.remove(), Some(i));
+        assert_eq!(c.remove(), Some(k(i)));
         assert!(!c.is_empty());
         c.verify();
     }
@@ -176,8 +207,8 @@ fn three_level_sparse_tree() {
     for i in (0..125).rev() {
         assert!(!c.is_empty());
         assert_eq!(c.elem(), None);
-        assert_eq!(c.prev(), Some(i));
-        assert_eq!(c.remove(), Some(i));
+        assert_eq!(c.prev(), Some(k(i)));
+        assert_eq!(c.remove(), Some(k(i)));
         c.verify();
     }
     assert_eq!(c.elem(), None);
@@ -192,25 +223,34 @@ fn three_level_sparse_tree() {
 // Level 4: 512 leafs, up to 7680 elements
 //
 // A 3-level tree can hold at most 960 elements.
-fn dense4l(f: &mut SetForest<i32>) -> Set<i32> {
+fn dense4l<K: TestKey>(f: &mut SetForest<K>, k: fn(u32) -> K) -> Set<K> {
     f.clear();
     let mut s = Set::new();
 
     // Insert 400 elements in 7 passes over the range to avoid the half-full leaf node pattern
     // that comes from sequential insertion. This will generate a normal leaf layer.
     for n in 0..4000 {
-        assert!(s.insert((n * 7) % 4000, f, &()));
+        assert!(s.insert(k((n * 7) % 4000), f, &()));
     }
     s
 }
 
 #[test]
 fn four_level() {
-    let mut f = SetForest::<i32>::new();
-    let mut s = dense4l(&mut f);
+    four_level_with(|n| n as i32)
+}
+
+#[test]
+fn four_level_u64() {
+    four_level_with(wide_key)
+}
+
+fn four_level_with<K: TestKey>(k: fn(u32) -> K) {
+    let mut f = SetForest::<K>::new();
+    let mut s = dense4l(&mut f, k);
 
     let fwd_iter = s.iter(&f).collect::<Vec<_>>();
-    assert_eq!(fwd_iter[0..10], [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert!(fwd_iter[0..10].iter().copied().eq((0..10).map(k)));
     let mut rev_iter = s.iter_rev(&f).collect::<Vec<_>>();
     rev_iter.reverse();
     assert_eq!(fwd_iter, rev_iter);
@@ -221,28 +261,28 @@ fn four_level() {
 
     // Peel off a whole sub-tree of the root by deleting from the front.
     // The 900 element is near the front of the second sub-tree.
-    assert!(c.goto(900));
+    assert!(c.goto(k(900)));
     assert_eq!(c.tpath(), "node48[1]--node47[0]--node26[0]--node20[4]");
-    assert!(c.goto(0));
+    assert!(c.goto(k(0)));
     for i in 0..900 {
         assert!(!c.is_empty());
-        assert_eq!(c.remove(), Some(i));
+        assert_eq!(c.remove(), Some(k(i)));
     }
     c.verify();
-    assert_eq!(c.elem(), Some(900));
+    assert_eq!(c.elem(), Some(k(900)));
 
     // Delete backwards from somewhere in the middle.
-    assert!(c.goto(3000));
+    assert!(c.goto(k(3000)));
     for i in (2000..3000).rev() {
-        assert_eq!(c.prev(), Some(i));
-        assert_eq!(c.remove(), Some(i));
-        assert_eq!(c.elem(), Some(3000));
+        assert_eq!(c.prev(), Some(k(i)));
+        assert_eq!(c.remove(), Some(k(i)));
+        assert_eq!(c.elem(), Some(k(3000)));
     }
     c.verify();
 
     // Remove everything in a scattered manner, triggering many collapsing patterns.
     for i in 0..4000 {
-        if c.goto((i * 7) % 4000) {
+        if c.goto(k((i * 7) % 4000)) {
             c.remove();
         }
     }
@@ -252,6 +292,294 @@ fn four_level() {
 #[test]
 fn four_level_clear() {
     let mut f = SetForest::<i32>::new();
-    let mut s = dense4l(&mut f);
+    let mut s = dense4l(&mut f, |n| n as i32);
     s.clear(&mut f);
 }
+
+fn set_from(f: &mut SetForest<u32>, elems: impl IntoIterator<Item = u32>) -> Set<u32> {
+    let mut s = Set::new();
+    for elem in elems {
+        s.insert(elem, f, &());
+    }
+    s
+}
+
+#[test]
+fn subset() {
+    let mut f = SetForest::<u32>::new();
+    let empty = Set::<u32>::new();
+    let small = set_from(&mut f, [3, 50, 99]);
+    let large = set_from(&mut f, 0..100);
+
+    assert!(f.is_subset(&small, &large, &()));
+    assert!(!f.is_subset(&large, &small, &()));
+    assert!(large.contains_all(&small, &f, &()));
+    assert!(!small.contains_all(&large, &f, &()));
+
+    assert!(f.is_subset(&empty, &small, &()));
+    assert!(f.is_subset(&empty, &empty, &()));
+    assert!(!f.is_subset(&small, &empty, &()));
+
+    // an element past the end of `large`
+    let tail = set_from(&mut f, [50, 100]);
+    assert!(!f.is_subset(&tail, &large, &()));
+}
+
+#[test]
+fn subset_equal() {
+    let mut f = SetForest::<u32>::new();
+    let a = set_from(&mut f, (0..200).map(|i| i * 3));
+    let b = set_from(&mut f, (0..200).rev().map(|i| i * 3));
+
+    assert!(f.is_subset(&a, &b, &()));
+    assert!(f.is_subset(&b, &a, &()));
+    assert!(!f.is_disjoint(&a, &b, &()));
+}
+
+#[test]
+fn trees_equal() {
+    let mut f = SetForest::<u32>::new();
+    let empty = Set::<u32>::new();
+    // different insertion orders result in differently shaped trees
+    let a = set_from(&mut f, (0..500).map(|i| i * 3));
+    let b = set_from(&mut f, (0..500).rev().map(|i| i * 3));
+    let c = set_from(&mut f, (0..500).map(|i| (i * 7) % 500 * 3));
+
+    assert!(f.trees_equal(&a, &b, &()));
+    assert!(f.trees_equal(&b, &c, &()));
+    assert!(f.trees_equal(&a, &a, &()));
+    assert!(f.trees_equal(&empty, &empty, &()));
+    assert!(!f.trees_equal(&a, &empty, &()));
+    assert!(!f.trees_equal(&empty, &a, &()));
+}
+
+#[test]
+fn trees_equal_one_difference() {
+    let mut f = SetForest::<u32>::new();
+    let a = set_from(&mut f, 0..300);
+    let mut b = set_from(&mut f, 0..300);
+
+    // an element missing from the middle
+    b.remove(150, &mut f, &());
+    assert!(!f.trees_equal(&a, &b, &()));
+    assert!(!f.trees_equal(&b, &a, &()));
+
+    // an element replaced by a different one
+    b.insert(1000, &mut f, &());
+    assert!(!f.trees_equal(&a, &b, &()));
+
+    // an additional element at the end
+    b.remove(1000, &mut f, &());
+    b.insert(150, &mut f, &());
+    assert!(f.trees_equal(&a, &b, &()));
+    b.insert(300, &mut f, &());
+    assert!(!f.trees_equal(&a, &b, &()));
+    assert!(!f.trees_equal(&b, &a, &()));
+}
+
+#[test]
+fn overlapping() {
+    let mut f = SetForest::<u32>::new();
+    let a = set_from(&mut f, 0..60);
+    let b = set_from(&mut f, 40..100);
+
+    assert!(!f.is_subset(&a, &b, &()));
+    assert!(!f.is_subset(&b, &a, &()));
+    assert!(!f.is_disjoint(&a, &b, &()));
+    assert!(!f.is_disjoint(&b, &a, &()));
+}
+
+#[test]
+fn disjoint() {
+    let mut f = SetForest::<u32>::new();
+    let empty = Set::<u32>::new();
+    let even = set_from(&mut f, (0..100).map(|i| 2 * i));
+    let odd = set_from(&mut f, (0..100).map(|i| 2 * i + 1));
+
+    assert!(f.is_disjoint(&even, &odd, &()));
+    assert!(f.is_disjoint(&odd, &even, &()));
+    assert!(!f.is_subset(&even, &odd, &()));
+    assert!(f.is_disjoint(&even, &empty, &()));
+    assert!(f.is_disjoint(&empty, &empty, &()));
+}
+
+fn reassemble(f: &mut SetForest<u32>, lo: &Set<u32>, hi: &Set<u32>) -> Vec<u32> {
+    let mut res = *lo;
+    let elems: Vec<_> = hi.iter(f).collect();
+    for elem in elems {
+        assert!(res.insert(elem, f, &()));
+    }
+    res.iter(f).collect()
+}
+
+#[test]
+fn split_off() {
+    let mut f = SetForest::<u32>::new();
+    let elems: Vec<_> = (0..500).map(|i| 2 * i).collect();
+    let mut lo = set_from(&mut f, elems.iter().copied());
+
+    // split at an element that is not in the set
+    let hi = f.split_off(&mut lo, 301, &());
+    assert_eq!(lo.iter(&f).last(), Some(300));
+    assert_eq!(hi.iter(&f).next(), Some(302));
+    assert_eq!(hi.iter(&f).count(), 349);
+    assert_eq!(reassemble(&mut f, &lo, &hi), elems);
+
+    // split at an element of the set
+    let mut lo = set_from(&mut f, elems.iter().copied());
+    let mut hi = f.split_off(&mut lo, 500, &());
+    assert!(lo.iter(&f).all(|elem| elem < 500));
+    assert!(hi.iter(&f).all(|elem| elem >= 500));
+    assert!(hi.contains(500, &f, &()));
+    lo.cursor(&mut f, &()).verify();
+    hi.cursor(&mut f, &()).verify();
+    assert_eq!(reassemble(&mut f, &lo, &hi), elems);
+}
+
+#[test]
+fn split_off_bounds() {
+    let mut f = SetForest::<u32>::new();
+    let elems = [10, 20, 30];
+
+    // before all elements: everything is moved
+    let mut lo = set_from(&mut f, elems);
+    let hi = f.split_off(&mut lo, 5, &());
+    assert!(lo.is_empty());
+    assert_eq!(hi.iter(&f).collect::<Vec<_>>(), elems);
+
+    // after all elements: nothing is moved
+    let mut lo = set_from(&mut f, elems);
+    let hi = f.split_off(&mut lo, 31, &());
+    assert!(hi.is_empty());
+    assert_eq!(lo.iter(&f).collect::<Vec<_>>(), elems);
+
+    let mut empty = Set::new();
+    assert!(f.split_off(&mut empty, 0, &()).is_empty());
+}
+
+#[test]
+fn pool_stats() {
+    let mut f = SetForest::<u32>::new();
+    assert_eq!(f.stats(), PoolStats::default());
+
+    let mut s = Set::<u32>::new();
+    for i in 0..100 {
+        s.insert(i * 10, &mut f, &());
+    }
+    let stats = f.stats();
+    assert!(stats.allocated > 1);
+    assert_eq!(stats.free, 0);
+    assert!(stats.capacity >= stats.allocated);
+    assert_eq!(stats.bytes, stats.capacity * mem::size_of::<NodeData<SetTypes<u32>>>());
+
+    // removing all elements moves every node to the free list
+    s.clear(&mut f);
+    assert_eq!(f.stats().free, stats.allocated);
+    assert_eq!(f.stats().live(), 0);
+
+    // freed nodes are reused before the pool grows
+    for i in 0..100 {
+        s.insert(i * 10, &mut f, &());
+    }
+    assert_eq!(f.stats().allocated, stats.allocated);
+    s.retain(&mut f, |k| k < 10);
+    assert_eq!(f.stats().live(), 1);
+
+    f.clear();
+    assert_eq!(f.stats().allocated, 0);
+    assert_eq!(f.stats().free, 0);
+}
+
+#[test]
+fn compact() {
+    let mut f = SetForest::<u32>::new();
+    let mut sets: Vec<_> =
+        (0..10).map(|i| set_from(&mut f, (0..1000).map(|k| k * 10 + i))).collect();
+
+    // interleaved removals leave most of the pool on the free list
+    for (i, set) in sets.iter_mut().enumerate() {
+        set.retain(&mut f, |k| k % 97 == i as u32);
+    }
+    let expected: Vec<Vec<u32>> = sets.iter().map(|s| s.iter(&f).collect()).collect();
+    let mut alias = sets[3];
+    let before = f.stats();
+    assert!(before.free > 0);
+
+    let remap = f.compact(&mut sets);
+    alias.remap(&remap);
+    let after = f.stats();
+    assert_eq!(after.free, 0);
+    assert_eq!(after.allocated, before.live());
+    assert!(after.capacity < before.capacity);
+
+    let contents: Vec<Vec<u32>> = sets.iter().map(|s| s.iter(&f).collect()).collect();
+    assert_eq!(contents, expected);
+    assert_eq!(alias.iter(&f).collect::<Vec<_>>(), expected[3]);
+
+    // the compacted trees are still fully usable
+    for set in &mut sets {
+        set.insert(5, &mut f, &());
+    }
+    assert!(sets.iter().all(|s| s.contains(5, &f, &())));
+}
+
+#[test]
+fn rebuild() {
+    let mut f = SetForest::<u32>::new();
+    let mut s = Set::<u32>::new();
+    // pseudo random insertion order scatters the nodes through the pool
+    for i in 0..2000u32 {
+        s.insert(i.wrapping_mul(2_654_435_761) % 10007, &mut f, &());
+    }
+    let expected: Vec<u32> = s.iter(&f).collect();
+    let before = f.stats();
+
+    let mut s = f.rebuild(s);
+    assert_eq!(s.iter(&f).collect::<Vec<_>>(), expected);
+    assert_eq!(f.stats().free, before.live());
+
+    // the leaves are visited in the order in which they are stored in the pool
+    let mut c = s.cursor(&mut f, &());
+    c.goto_first();
+    c.verify();
+    let mut leaves = vec![c.path.leaf_pos().unwrap().0];
+    while c.next().is_some() {
+        let leaf = c.path.leaf_pos().unwrap().0;
+        if *leaves.last().unwrap() != leaf {
+            leaves.push(leaf);
+        }
+    }
+    assert!(leaves.windows(2).all(|w| w[1].0 == w[0].0 + 1), "{leaves:?}");
+
+    // the rebuilt tree is still fully usable
+    s.retain(&mut f, |k| k % 3 != 0);
+    for i in 0..100 {
+        s.insert(i * 3, &mut f, &());
+    }
+    let mut expected: Vec<u32> = expected.into_iter().filter(|k| k % 3 != 0).collect();
+    expected.extend((0..100).map(|i| i * 3));
+    expected.sort_unstable();
+    assert_eq!(s.iter(&f).collect::<Vec<_>>(), expected);
+
+    assert!(f.rebuild(Set::new()).is_empty());
+}
+
+#[test]
+fn leaves() {
+    let mut f = SetForest::<u32>::new();
+    let mut s = Set::<u32>::new();
+    assert_eq!(s.leaves(&f).count(), 0);
+
+    for i in 0..1000u32 {
+        s.insert(i.wrapping_mul(2_654_435_761) % 10007, &mut f, &());
+    }
+    let mut elems = Vec::new();
+    let mut num_leaves = 0;
+    for leaf in s.leaves(&f) {
+        assert!(!leaf.is_empty());
+        elems.extend_from_slice(leaf);
+        num_leaves += 1;
+    }
+    assert!(num_leaves > 1);
+    assert_eq!(elems, s.iter(&f).collect::<Vec<_>>());
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use super::*;
 
 /// An opaque reference to a basic block in a function.
@@ -5,6 +7,17 @@ use super::*;
 pub struct Block(u32);
 impl_idx_from!(Block(u32));
 
+/// Key types the insert/remove/iterate tests of the map and set forests are instantiated with.
+pub trait TestKey: Copy + Ord + fmt::Debug + fmt::Display {}
+impl<K: Copy + Ord + fmt::Debug + fmt::Display> TestKey for K {}
+
+/// Maps `n` to a 64-bit key. The lower 32 bits are the same for every key, so keys that are
+/// truncated to 32 bits anywhere collide. The mapping preserves the order of `n` and
+/// therefore produces the same tree geometry as using `n` directly.
+pub fn wide_key(n: u32) -> u64 {
+    (u64::from(n) << 32) | 0x5555_5555
+}
+
 #[test]
 fn comparator() {
     let block1 = Block::from(1u32);
@@ -12,14 +12,14 @@ use openvaf::{
     AbsPathBuf, CompilationDestination, CompilationTermination, LLVMCodeGenOptLevel, LintLevel,
     Target,
 };
-pub(crate) use osdi_0_4::{
+pub(crate) use osdi_0_5::{
     ANALYSIS_AC, ANALYSIS_DC, ANALYSIS_IC, ANALYSIS_NOISE, ANALYSIS_STATIC, ANALYSIS_TRAN,
     CALC_NOISE, CALC_REACT_JACOBIAN, CALC_REACT_RESIDUAL, CALC_RESIST_JACOBIAN,
     CALC_RESIST_RESIDUAL,
 };
 
 use crate::devices::DeviceImpl;
-use crate::veriloga::osdi_0_4::{
+use crate::veriloga::osdi_0_5::{
     OsdiDescriptor, LOG_FMT_ERR, LOG_LVL_DEBUG, LOG_LVL_DISPLAY, LOG_LVL_ERR, LOG_LVL_FATAL,
     LOG_LVL_INFO, LOG_LVL_MASK, LOG_LVL_WARN,
 };
@@ -27,7 +27,7 @@ use crate::veriloga::osdi_device::OsdiDevice;
 
 // autogenerated
 #[allow(warnings)]
-mod osdi_0_4;
+mod osdi_0_5;
 mod osdi_device;
 
 #[derive(Default)]
@@ -70,6 +70,7 @@ pub fn compile_va(path: &Utf8Path, opts: &Opts) -> Result<Vec<Box<dyn DeviceImpl
         output: CompilationDestination::Cache { cache_dir },
         include: opts.include.clone(),
         opt_lvl: opts.opt_lvl.unwrap_or(LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive),
+        module_opt_lvls: Vec::new(),
         target: Target::host_target()
             .context("openvaf does currently not support this hardware/os")?,
         target_cpu: "native".to_owned(),
@@ -3,7 +3,7 @@
 use std::os::raw::{c_char, c_void};
 
 pub const OSDI_VERSION_MAJOR_CURR: u32 = 0;
-pub const OSDI_VERSION_MINOR_CURR: u32 = 4;
+pub const OSDI_VERSION_MINOR_CURR: u32 = 5;
 pub const PARA_TY_MASK: u32 = 3;
 pub const PARA_TY_REAL: u32 = 0;
 pub const PARA_TY_INT: u32 = 1;
@@ -12,6 +12,11 @@ pub const PARA_KIND_MASK: u32 = (3 << 30);
 pub const PARA_KIND_MODEL: u32 = (0 << 30);
 pub const PARA_KIND_INST: u32 = (1 << 30);
 pub const PARA_KIND_OPVAR: u32 = (2 << 30);
+pub const PARA_DEFAULT_STATIC: u32 = 0;
+pub const PARA_DEFAULT_COMPUTED: u32 = 1;
+pub const NODE_ABSTOL_NONE: u32 = 0;
+pub const NODE_ABSTOL_STATIC: u32 = 1;
+pub const NODE_ABSTOL_COMPUTED: u32 = 2;
 pub const ACCESS_FLAG_READ: u32 = 0;
 pub const ACCESS_FLAG_SET: u32 = 1;
 pub const ACCESS_FLAG_INSTANCE: u32 = 4;
@@ -110,6 +115,12 @@ pub struct OsdiNode {
     pub is_flow: bool,
 }
 #[repr(C)]
+pub struct OsdiNodeAbstol {
+    pub flags: u32,
+    pub model_off: u32,
+    pub val: f64,
+}
+#[repr(C)]
 pub struct OsdiParamOpvar {
     pub name: *mut *mut c_char,
     pub num_alias: u32,
@@ -119,6 +130,13 @@ pub struct OsdiParamOpvar {
     pub len: u32,
 }
 #[repr(C)]
+pub struct OsdiParamDefault {
+    pub flags: u32,
+    pub int_val: i32,
+    pub real_val: f64,
+    pub str_val: *mut c_char,
+}
+#[repr(C)]
 pub struct OsdiNoiseSource {
     pub name: *mut c_char,
     pub nodes: OsdiNodePair,
@@ -173,6 +191,14 @@ pub struct OsdiDescriptor {
     pub inputs: *mut OsdiNodePair,
     pub load_jacobian_with_offset_resist: fn(*mut c_void, *mut c_void, usize),
     pub load_jacobian_with_offset_react: fn(*mut c_void, *mut c_void, usize),
+    pub param_defaults: *mut OsdiParamDefault,
+    pub eval_residual: Option<fn(*mut c_void, *mut c_void, *mut c_void, *mut OsdiSimInfo) -> u32>,
+    pub num_gmin_nodes: u32,
+    pub gmin_nodes: *mut u32,
+    pub num_sensitivity_params: u32,
+    pub sensitivity_params: *mut u32,
+    pub load_sensitivities: Option<fn(*mut c_void, *mut c_void, *mut f64, *mut f64)>,
+    pub node_abstol: *mut OsdiNodeAbstol,
 }
 impl OsdiDescriptor {
     pub fn access(
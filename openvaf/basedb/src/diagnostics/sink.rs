@@ -82,6 +82,8 @@ impl<'a> Files<'_> for FileSrc<'a> {
 pub struct ConsoleSink<'a> {
     warning_cnt: usize,
     error_cnt: usize,
+    /// warnings that were already reported by a previous call to `summary`
+    summarized_warnings: usize,
     config: Config,
     db: &'a dyn BaseDB,
     dst: Box<dyn WriteColor + 'a>,
@@ -98,9 +100,10 @@ impl<'a> ConsoleSink<'a> {
     }
 
     pub fn summary(&mut self, target_name: &impl Display) -> bool {
+        let warning_cnt = self.warning_cnt - self.summarized_warnings;
         if self.error_cnt != 0 {
-            let warn = if self.warning_cnt != 0 {
-                format!("; {} warning emitted", self.warning_cnt)
+            let warn = if warning_cnt != 0 {
+                format!("; {} warning emitted", warning_cnt)
             } else {
                 String::new()
             };
@@ -113,15 +116,25 @@ impl<'a> ConsoleSink<'a> {
             return true;
         }
 
-        if self.warning_cnt != 0 {
-            let message = format!("`{}` generated {} warning", target_name, self.warning_cnt);
+        if warning_cnt != 0 {
+            let message = format!("`{}` generated {} warning", target_name, warning_cnt);
             self.print_simple_message(Severity::Warning, message);
-            self.warning_cnt = 0;
+            self.summarized_warnings = self.warning_cnt;
         }
 
         false
     }
 
+    /// The total number of warnings emitted to this sink.
+    pub fn warning_count(&self) -> usize {
+        self.warning_cnt
+    }
+
+    /// The total number of errors emitted to this sink.
+    pub fn error_count(&self) -> usize {
+        self.error_cnt
+    }
+
     pub fn print_simple_message(&mut self, severity: Severity, msg: String) {
         emit(
             &mut self.dst,
@@ -150,7 +163,15 @@ impl<'a> ConsoleSink<'a> {
         config.styles.primary_label_warning.set_bold(true);
         config.styles.secondary_label.set_bold(true);
 
-        ConsoleSink { warning_cnt: 0, error_cnt: 0, config, db, dst, anon_paths: false }
+        ConsoleSink {
+            warning_cnt: 0,
+            error_cnt: 0,
+            summarized_warnings: 0,
+            config,
+            db,
+            dst,
+            anon_paths: false,
+        }
     }
 
     /// only print the filename instead of the full path, this is useful for UI tests where we do not want to expose the full path
@@ -179,5 +179,13 @@ pub mod builtin {
         pub const variant_const_simparam = LintData{default_lvl: Warn, documentation_id: 15};
         pub const port_without_direction = LintData{default_lvl: Deny, documentation_id: 16};
         pub const trivial_probe = LintData{default_lvl: Warn, documentation_id: 17};
+        pub const shadowed_builtin = LintData{default_lvl: Warn, documentation_id: 18};
+        pub const uninitialized_output_arg = LintData{default_lvl: Warn, documentation_id: 19};
+        pub const file_io_every_iteration = LintData{default_lvl: Warn, documentation_id: 20};
+        pub const integer_division = LintData{default_lvl: Warn, documentation_id: 21};
+        pub const display_every_iteration = LintData{default_lvl: Warn, documentation_id: 22};
+        pub const random_analog_operator_arg = LintData{default_lvl: Warn, documentation_id: 23};
+        pub const mixed_branch_contribution = LintData{default_lvl: Warn, documentation_id: 24};
+        pub const no_contributions = LintData{default_lvl: Warn, documentation_id: 25};
     }
 }
@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
 use hir_def::db::HirDefDB;
-pub use hir_def::expr::Event;
+pub use hir_def::expr::{Event, GlobalEvent};
 use hir_def::DefWithBodyId;
 pub use hir_def::{/*expr::CaseCond,*/ BuiltIn, Case, ExprId, Literal, ParamSysFun, StmtId, Type,};
 use hir_ty::db::HirTyDB;
 use hir_ty::inference;
 use hir_ty::types::{Signature, Ty};
+use stdx::Ieee64;
 pub use syntax::ast::{BinaryOp, UnaryOp};
 
 use crate::{
@@ -79,6 +80,11 @@ impl<'a> BodyRef<'a> {
         self.infere.resolved_signatures.get(&expr).copied().unwrap_or(Signature(u32::MAX))
     }
 
+    /// Returns the (frequency, power) pairs of a call to `noise_table`/`noise_table_log`.
+    pub fn noise_table(&self, call: ExprId) -> &'a [(Ieee64, Ieee64)] {
+        &self.infere.noise_tables[&call]
+    }
+
     pub fn as_literal(&self, expr: ExprId) -> Option<&'a Literal> {
         match &self.body.exprs[expr] {
             hir_def::Expr::Literal(lit) => Some(lit),
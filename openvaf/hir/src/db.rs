@@ -64,6 +64,12 @@ impl CompilationDB {
         )
     }
 
+    /// Adds a virtual file next to the root file of [`new_virtual`](Self::new_virtual)
+    /// (for example a noise table read by the model)
+    pub fn add_virtual_file(&self, path: &str, contents: &str) {
+        self.vfs.write().add_virt_file(path, contents.to_owned().into());
+    }
+
     pub fn compilation_unit(&self) -> CompilationUnit {
         CompilationUnit { root_file: self.root_file }
     }
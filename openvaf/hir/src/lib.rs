@@ -21,23 +21,26 @@ pub use hir_def::expr::CaseCond;
 pub use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::{DefMap, LocalScopeId, ScopeDefItem};
 use hir_def::{
-    AliasParamId, BlockId, BlockLoc, BranchId, DefWithBodyId, DisciplineId, FunctionId,
-    LocalFunctionArgId, Lookup, ModuleId, ModuleLoc, NatureAttrId, NatureId, NodeId, ParamId,
-    VarId,
+    AliasParamId, BlockId, BlockLoc, BranchId, DefWithBodyId, DisciplineId, FunctionId, Intern,
+    LocalFunctionArgId, Lookup, ModuleId, ModuleLoc, NatureAttrId, NatureAttrLoc, NatureId, NodeId,
+    ParamId, VarId,
 };
 pub use hir_def::{BuiltIn, Case, Literal, ParamSysFun, Path, Type};
 pub use hir_ty::builtin;
 use hir_ty::db::HirTyDB as HirDatabase;
 use hir_ty::inference;
+use hir_ty::NatureTy;
+pub use hir_ty::TimeOperator;
 pub use rec_declarations::RecDeclarations;
 use salsa::InternKey;
 use smol_str::SmolStr;
-use syntax::ast;
 pub use syntax::name::Name;
+use syntax::{ast, AstNode, SyntaxKind};
 
 pub use crate::attributes::AstCache;
 pub use crate::body::{
-    AssignmentLhs, Body, BodyRef, ContributeKind, Expr, ExprId, Ref, ResolvedFun, Stmt, StmtId,
+    AssignmentLhs, Body, BodyRef, ContributeKind, Event, Expr, ExprId, GlobalEvent, Ref,
+    ResolvedFun, Stmt, StmtId,
 };
 pub use crate::db::CompilationDB;
 
@@ -112,6 +115,36 @@ impl CompilationUnit {
     pub fn preprocess(&self, db: &CompilationDB) -> syntax::Preprocess {
         db.preprocess(self.root_file)
     }
+
+    /// Calls `f` with the text of every (non-trivia) token of the parsed source.
+    /// Literals in the default value of a parameter are replaced by a placeholder for
+    /// their kind. The kind is kept because it determines the type of parameters that are
+    /// declared without one (`parameter r = 1;`). Two compilation units that visit the same
+    /// sequence therefore only differ in the values of their parameter defaults.
+    pub fn visit_structure(&self, db: &CompilationDB, mut f: impl FnMut(&str)) {
+        let ast = db.parse(self.root_file).tree();
+        for token in ast.syntax().descendants_with_tokens().filter_map(|it| it.into_token()) {
+            if token.kind().is_trivia() {
+                continue;
+            }
+            let is_default = token.parent().map_or(false, |parent| {
+                ast::Literal::can_cast(parent.kind())
+                    && parent
+                        .ancestors()
+                        .take_while(|node| !ast::Constraint::can_cast(node.kind()))
+                        .any(|node| ast::Param::can_cast(node.kind()))
+            });
+            if is_default {
+                match token.kind() {
+                    SyntaxKind::INT_NUMBER => f("<integer>"),
+                    SyntaxKind::STR_LIT => f("<string>"),
+                    _ => f("<real>"),
+                }
+            } else {
+                f(token.text())
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -411,6 +444,17 @@ impl Node {
     pub fn is_gnd(self, db: &CompilationDB) -> bool {
         db.node_data(self.id).is_gnd
     }
+
+    /// The node this node refers to if it was aliased with `$analog_node_alias`.
+    /// All references to an alias are resolved to this node.
+    pub fn alias_target(self, db: &CompilationDB) -> Option<Node> {
+        let id = db.resolve_node_alias(self.id);
+        if id == self.id {
+            None
+        } else {
+            Some(Node { id })
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -456,6 +500,22 @@ impl Parameter {
         db.param_exprs(self.id).default
     }
 
+    /// The source code of the default value (with all macros expanded).
+    pub fn default_src(self, db: &CompilationDB) -> String {
+        let param = self.id.lookup(db).source(db);
+        param.default().map_or_else(String::new, |expr| expr.syntax().text().to_string())
+    }
+
+    /// The file and (one-based) line in which the parameter is declared.
+    pub fn decl_location(self, db: &CompilationDB) -> (String, u32) {
+        let loc = self.id.lookup(db);
+        let root_file = loc.scope.root_file;
+        let range = loc.source(db).syntax().text_range();
+        let span = db.parse(root_file).to_file_span(range, &db.sourcemap(root_file));
+        let line = db.line_index(span.file).line_col(span.range.start()).line + 1;
+        (db.file_path(span.file).to_string(), line)
+    }
+
     pub fn bounds(self, db: &CompilationDB) -> Arc<[ParamConstraint]> {
         db.param_exprs(self.id).bounds
     }
@@ -597,6 +657,28 @@ impl Nature {
     pub fn units(self, db: &CompilationDB) -> String {
         db.nature_data(self.id).units.clone().unwrap_or_default()
     }
+
+    /// The value of the `abstol` attribute if it is a literal.
+    pub fn abstol(self, db: &CompilationDB) -> Option<f64> {
+        let body = self.abstol_attr(db)?.value(db);
+        let body = body.borrow();
+        match *body.as_literal(body.get_entry_expr(0))? {
+            Literal::Float(val) => Some(val.into()),
+            Literal::Int(val) => Some(val.into()),
+            _ => None,
+        }
+    }
+
+    /// The `abstol` attribute of this nature (if it has one).
+    pub fn abstol_attr(self, db: &CompilationDB) -> Option<NatureAttribute> {
+        let id = db.nature_data(self.id).abstol?;
+        Some(NatureAttribute { id: NatureAttrLoc { nature: self.id, id }.intern(db) })
+    }
+
+    /// The nature of `ddt(x)`/`idt(x)` for a quantity `x` of this nature.
+    pub fn apply_time_operator(self, db: &CompilationDB, op: TimeOperator) -> Nature {
+        Nature { id: NatureTy::apply_time_operator(db, self.id, op) }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -608,6 +690,10 @@ impl NatureAttribute {
     pub fn value(&self, db: &CompilationDB) -> Body {
         Body::new(self.id.into(), db)
     }
+
+    pub fn ty(&self, db: &CompilationDB) -> Option<Type> {
+        db.nature_attr_ty(self.id)
+    }
 }
 
 impl NatureAttribute {
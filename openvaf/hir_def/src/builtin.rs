@@ -66,61 +66,62 @@ pub enum BuiltIn {
     error = 53u8,
     info = 54u8,
     abstime = 55u8,
-    dist_chi_square = 56u8,
-    dist_exponential = 57u8,
-    dist_poisson = 58u8,
-    dist_uniform = 59u8,
-    dist_erlang = 60u8,
-    dist_normal = 61u8,
-    dist_t = 62u8,
-    random = 63u8,
-    arandom = 64u8,
-    rdist_chi_square = 65u8,
-    rdist_exponential = 66u8,
-    rdist_poisson = 67u8,
-    rdist_uniform = 68u8,
-    rdist_erlang = 69u8,
-    rdist_normal = 70u8,
-    rdist_t = 71u8,
-    clog2 = 72u8,
-    log10 = 73u8,
-    temperature = 74u8,
-    vt = 75u8,
-    simparam = 76u8,
-    simparam_str = 77u8,
-    simprobe = 78u8,
-    discontinuity = 79u8,
-    param_given = 80u8,
-    port_connected = 81u8,
-    analog_node_alias = 82u8,
-    analog_port_alias = 83u8,
-    test_plusargs = 84u8,
-    value_plusargs = 85u8,
-    bound_step = 86u8,
-    analysis = 87u8,
-    ac_stim = 88u8,
-    noise_table = 89u8,
-    noise_table_log = 90u8,
-    white_noise = 91u8,
-    flicker_noise = 92u8,
-    limit = 93u8,
-    absdelay = 94u8,
-    ddt = 95u8,
-    idt = 96u8,
-    idtmod = 97u8,
-    ddx = 98u8,
-    zi_nd = 99u8,
-    zi_np = 100u8,
-    zi_zd = 101u8,
-    zi_zp = 102u8,
-    laplace_nd = 103u8,
-    laplace_np = 104u8,
-    laplace_zd = 105u8,
-    laplace_zp = 106u8,
-    limexp = 107u8,
-    last_crossing = 108u8,
-    slew = 109u8,
-    transition = 110u8,
+    realtime = 56u8,
+    dist_chi_square = 57u8,
+    dist_exponential = 58u8,
+    dist_poisson = 59u8,
+    dist_uniform = 60u8,
+    dist_erlang = 61u8,
+    dist_normal = 62u8,
+    dist_t = 63u8,
+    random = 64u8,
+    arandom = 65u8,
+    rdist_chi_square = 66u8,
+    rdist_exponential = 67u8,
+    rdist_poisson = 68u8,
+    rdist_uniform = 69u8,
+    rdist_erlang = 70u8,
+    rdist_normal = 71u8,
+    rdist_t = 72u8,
+    clog2 = 73u8,
+    log10 = 74u8,
+    temperature = 75u8,
+    vt = 76u8,
+    simparam = 77u8,
+    simparam_str = 78u8,
+    simprobe = 79u8,
+    discontinuity = 80u8,
+    param_given = 81u8,
+    port_connected = 82u8,
+    analog_node_alias = 83u8,
+    analog_port_alias = 84u8,
+    test_plusargs = 85u8,
+    value_plusargs = 86u8,
+    bound_step = 87u8,
+    analysis = 88u8,
+    ac_stim = 89u8,
+    noise_table = 90u8,
+    noise_table_log = 91u8,
+    white_noise = 92u8,
+    flicker_noise = 93u8,
+    limit = 94u8,
+    absdelay = 95u8,
+    ddt = 96u8,
+    idt = 97u8,
+    idtmod = 98u8,
+    ddx = 99u8,
+    zi_nd = 100u8,
+    zi_np = 101u8,
+    zi_zd = 102u8,
+    zi_zp = 103u8,
+    laplace_nd = 104u8,
+    laplace_np = 105u8,
+    laplace_zd = 106u8,
+    laplace_zp = 107u8,
+    limexp = 108u8,
+    last_crossing = 109u8,
+    slew = 110u8,
+    transition = 111u8,
 }
 #[derive(Eq, PartialEq, Copy, Clone, Hash, Debug)]
 #[allow(nonstandard_style, unreachable_pub)]
@@ -170,10 +171,31 @@ impl BuiltIn {
         }
     }
     #[allow(clippy::match_like_matches_macro)]
+    pub fn is_random(self) -> bool {
+        match self {
+            BuiltIn::dist_chi_square
+            | BuiltIn::dist_exponential
+            | BuiltIn::dist_poisson
+            | BuiltIn::dist_uniform
+            | BuiltIn::dist_erlang
+            | BuiltIn::dist_normal
+            | BuiltIn::dist_t
+            | BuiltIn::random
+            | BuiltIn::arandom
+            | BuiltIn::rdist_chi_square
+            | BuiltIn::rdist_exponential
+            | BuiltIn::rdist_poisson
+            | BuiltIn::rdist_uniform
+            | BuiltIn::rdist_erlang
+            | BuiltIn::rdist_normal
+            | BuiltIn::rdist_t => true,
+            _ => false,
+        }
+    }
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_unsupported(self) -> bool {
         match self {
             BuiltIn::simprobe
-            | BuiltIn::analog_node_alias
             | BuiltIn::analog_port_alias
             | BuiltIn::test_plusargs
             | BuiltIn::value_plusargs
@@ -188,10 +210,6 @@ impl BuiltIn {
             | BuiltIn::last_crossing
             | BuiltIn::slew
             | BuiltIn::transition
-            | BuiltIn::fclose
-            | BuiltIn::fopen
-            | BuiltIn::fdisplay
-            | BuiltIn::fwrite
             | BuiltIn::fstrobe
             | BuiltIn::fmonitor
             | BuiltIn::fgets
@@ -295,6 +313,7 @@ pub fn insert_builtin_scope(dst: &mut IndexMap<Name, ScopeDefItem, RandomState>)
     dst.insert(sysfun::error, BuiltIn::error.into());
     dst.insert(sysfun::info, BuiltIn::info.into());
     dst.insert(sysfun::abstime, BuiltIn::abstime.into());
+    dst.insert(sysfun::realtime, BuiltIn::realtime.into());
     dst.insert(sysfun::dist_chi_square, BuiltIn::dist_chi_square.into());
     dst.insert(sysfun::dist_exponential, BuiltIn::dist_exponential.into());
     dst.insert(sysfun::dist_poisson, BuiltIn::dist_poisson.into());
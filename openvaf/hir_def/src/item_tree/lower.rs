@@ -197,7 +197,7 @@ impl Ctx {
                         }
                     }
 
-                    kw::abs if abstol.is_none() => {
+                    kw::abstol if abstol.is_none() => {
                         abstol = Some(id.into());
                     }
                     _ => (),
@@ -61,6 +61,10 @@ impl Diagnostic for DefDiagnosticWrapped<'_> {
     fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
         match self.diag {
             DefDiagnostic::AlreadyDeclared { old, new, name } => {
+                // the compiled models are identified by the name of their module,
+                // so a duplicate module would collide in the simulator
+                let is_module =
+                    matches!((old, new), (ScopeDefItem::ModuleId(_), ScopeDefItem::ModuleId(_)));
                 let FileSpan { range, file } = self.parse.to_file_span(
                     new.text_range(self.db, self.ast_id_map, self.parse).unwrap(),
                     self.sm,
@@ -70,7 +74,11 @@ impl Diagnostic for DefDiagnosticWrapped<'_> {
                     style: LabelStyle::Primary,
                     file_id: file,
                     range: range.into(),
-                    message: "already declared in this scope".to_owned(),
+                    message: if is_module {
+                        "defined again here".to_owned()
+                    } else {
+                        "already declared in this scope".to_owned()
+                    },
                 }];
 
                 if let Some(def) = old.text_range(self.db, self.ast_id_map, self.parse) {
@@ -79,12 +87,26 @@ impl Diagnostic for DefDiagnosticWrapped<'_> {
                         style: LabelStyle::Secondary,
                         file_id: file,
                         range: range.into(),
-                        message: format!("help '{}' was first declared here", name),
+                        message: if is_module {
+                            format!("help: '{}' was first defined here", name)
+                        } else {
+                            format!("help '{}' was first declared here", name)
+                        },
                     })
                 }
-                Report::error()
-                    .with_message(format!("'{}' was already declared in this scope", name))
-                    .with_labels(labels)
+                if is_module {
+                    Report::error()
+                        .with_message(format!("module '{}' is defined multiple times", name))
+                        .with_labels(labels)
+                        .with_notes(vec![
+                            "help: simulators identify a compiled model by the name of its module"
+                                .to_owned(),
+                        ])
+                } else {
+                    Report::error()
+                        .with_message(format!("'{}' was already declared in this scope", name))
+                        .with_labels(labels)
+                }
             }
         }
     }
@@ -17,24 +17,64 @@ pub enum ParamInfoKind {
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum CallBackKind {
-    Print { kind: DisplayKind, arg_tys: Box<[FmtArg]> },
+    Print {
+        kind: DisplayKind,
+        arg_tys: Box<[FmtArg]>,
+    },
+    FileOpen,
+    FileWrite {
+        arg_tys: Box<[FmtArg]>,
+    },
+    FileClose,
     SimParam,
     SimParamOpt,
     SimParamStr,
     Derivative(Param),
     NodeDerivative(Node),
     ParamInfo(ParamInfoKind, Parameter),
+    /// The value of `param` (first argument) is outside of its only `from` range because it
+    /// violates the lower (or `upper`) bound of the range (second argument). Both arguments
+    /// are real numbers.
+    ParamBoundViolated {
+        param: Parameter,
+        upper: bool,
+        inclusive: bool,
+    },
     CollapseHint(Node, Option<Node>),
     LimDiscontinuity,
     Analysis,
-    BuiltinLimit { name: Spur, num_args: u32 },
+    BuiltinLimit {
+        name: Spur,
+        num_args: u32,
+    },
     StoreLimit(LimitState),
     TimeDerivative,
-    WhiteNoise { name: Spur, idx: u32 },
-    FlickerNoise { name: Spur, idx: u32 },
+    WhiteNoise {
+        name: Spur,
+        idx: u32,
+    },
+    FlickerNoise {
+        name: Spur,
+        idx: u32,
+    },
     NoiseTable(Box<NoiseTable>),
 }
 
+/// The placeholder name of the noise source with index `idx` if no name was specified in
+/// the source code. Backends replace it with a name that is meaningful to users.
+pub(crate) fn unnamed_noise_src(idx: u32) -> String {
+    format!("unnamed{idx}")
+}
+
+/// Whether `name` is the placeholder name of a noise source without a name
+/// (see [`unnamed_noise_src`]).
+pub fn is_unnamed_noise_src(name: &str) -> bool {
+    match name.strip_prefix("unnamed") {
+        Some(idx) => !idx.is_empty() && idx.bytes().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
 impl CallBackKind {
     pub fn signature(&self) -> FunctionSignature {
         match self {
@@ -74,6 +114,16 @@ impl CallBackKind {
                 returns: 0,
                 has_sideeffects: true,
             },
+            CallBackKind::ParamBoundViolated { param, upper, .. } => FunctionSignature {
+                name: format!(
+                    "{}_bound_violated({:?})",
+                    if *upper { "upper" } else { "lower" },
+                    param
+                ),
+                params: 2,
+                returns: 0,
+                has_sideeffects: true,
+            },
             CallBackKind::CollapseHint(hi, lo) => FunctionSignature {
                 name: format!("collapse_{:?}_{:?}", hi, lo),
                 params: 0,
@@ -86,6 +136,24 @@ impl CallBackKind {
                 returns: 0,
                 has_sideeffects: true,
             },
+            CallBackKind::FileOpen => FunctionSignature {
+                name: "$fopen".to_owned(),
+                params: 2,
+                returns: 1,
+                has_sideeffects: true,
+            },
+            CallBackKind::FileWrite { arg_tys } => FunctionSignature {
+                name: "$fwrite".to_owned(),
+                params: arg_tys.len() as u16 + 2,
+                returns: 0,
+                has_sideeffects: true,
+            },
+            CallBackKind::FileClose => FunctionSignature {
+                name: "$fclose".to_owned(),
+                params: 1,
+                returns: 0,
+                has_sideeffects: true,
+            },
             CallBackKind::BuiltinLimit { name, num_args } => FunctionSignature {
                 name: format!("$limit[{name:?}]"),
                 params: *num_args as u16,
@@ -135,7 +203,7 @@ impl CallBackKind {
                     table.name,
                     &table.vals
                 ),
-                params: 1,
+                params: 0,
                 returns: 1,
                 has_sideeffects: false,
             },
@@ -168,7 +236,13 @@ impl CallBackKind {
     }
 
     pub fn tracked(&self) -> bool {
-        !matches!(self, CallBackKind::Print { .. })
+        !matches!(
+            self,
+            CallBackKind::Print { .. }
+                | CallBackKind::FileOpen
+                | CallBackKind::FileWrite { .. }
+                | CallBackKind::FileClose
+        )
     }
 }
 
@@ -176,25 +250,25 @@ impl CallBackKind {
 pub struct NoiseTable {
     pub name: Spur,
     pub log: bool,
+    /// (frequency, power) pairs sorted by frequency. For `noise_table_log` these are the
+    /// logarithms (base 10) of frequency and power because the table is interpolated in
+    /// the log-log domain.
     pub vals: Box<[(Ieee64, Ieee64)]>,
     idx: u32,
 }
 
 impl NoiseTable {
-    // TODO: read from disk
     pub fn new(
         vals: impl IntoIterator<Item = (f64, f64)>,
         log: bool,
         name: Spur,
         idx: u32,
     ) -> Self {
-        let mut vals: Vec<(Ieee64, Ieee64)> = if log {
-            vals.into_iter().map(|(f, pwr)| (f.into(), pwr.into())).collect()
+        let vals = if log {
+            vals.into_iter().map(|(f, pwr)| (f.log10().into(), pwr.log10().into())).collect()
         } else {
-            vals.into_iter().map(|(f, pwr)| (f.log10().into(), pwr.into())).collect()
+            vals.into_iter().map(|(f, pwr)| (f.into(), pwr.into())).collect()
         };
-        vals.sort_unstable_by(|(f1, _), (f2, _)| f1.partial_cmp(f2).unwrap());
-        vals.dedup_by_key(|(f, _)| *f);
-        Self { name, log, vals: vals.into_boxed_slice(), idx }
+        Self { name, log, vals, idx }
     }
 }
@@ -25,6 +25,9 @@ pub struct LoweringCtx<'a, 'c> {
     /// but necessary to avoid accidental correlation/opimization.
     /// For example white_noise(x) - white_noise(x) is not zero.
     pub num_noise_sources: u32,
+    /// Linear resistors with a resistance below this threshold are collapsed
+    /// (see [`MirBuilder::with_collapse_resistance`](crate::MirBuilder::with_collapse_resistance)).
+    pub collapse_resistance: Option<f64>,
 }
 
 impl<'a, 'c> LoweringCtx<'a, 'c> {
@@ -43,6 +46,7 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
             inside_lim: false,
             intern,
             num_noise_sources: 0,
+            collapse_resistance: None,
         }
     }
 
@@ -77,7 +81,8 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
                 | PlaceKind::FunctionArg { .. }
                 | PlaceKind::Param(_)
                 | PlaceKind::ParamMin(_)
-                | PlaceKind::ParamMax(_) => return place,
+                | PlaceKind::ParamMax(_)
+                | PlaceKind::NatureAbstol(_) => return place,
 
                 PlaceKind::Var(var) => self.use_param(ParamKind::HiddenState(var)),
                 PlaceKind::ImplicitResidual { .. } | PlaceKind::Contribute { .. } => F_ZERO,
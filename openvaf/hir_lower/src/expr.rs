@@ -1,5 +1,6 @@
 use hir::builtin::{
-    FLICKER_NOISE_NAME, NOISE_TABLE_FILE_NAME, NOISE_TABLE_INLINE_NAME, WHITE_NOISE_NAME,
+    FLICKER_NOISE_NAME, FOPEN_MODE, FOPEN_NO_MODE, NOISE_TABLE_FILE_NAME, NOISE_TABLE_INLINE_NAME,
+    WHITE_NOISE_NAME,
 };
 use hir::signatures::{
     ABS_INT, ABS_REAL, BOOL_EQ, DDX_POT, IDTMOD_IC, IDTMOD_IC_MODULUS, IDTMOD_IC_MODULUS_OFFSET,
@@ -11,12 +12,13 @@ use hir::signatures::{
 };
 use hir::{Body, BuiltIn, Expr, ExprId, Literal, /*ParamSysFun,*/ Ref, ResolvedFun, Type};
 use mir::builder::InstBuilder;
-use mir::{Opcode, Value, FALSE, F_ZERO, GRAVESTONE, INFINITY, TRUE, ZERO};
+use mir::{Opcode, Value, FALSE, F_ZERO, GRAVESTONE, INFINITY, ONE, TRUE, ZERO};
 use mir_build::RetBuilder;
 use stdx::iter::zip;
 use syntax::ast::{BinaryOp, UnaryOp};
 
 use crate::body::BodyLoweringCtx;
+use crate::callbacks::unnamed_noise_src;
 use crate::fmt::DisplayKind;
 use crate::{
     CallBackKind, CurrentKind, IdtKind, ImplicitEquationKind, NoiseTable, ParamKind, PlaceKind,
@@ -443,6 +445,29 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 self.ctx.seal_block(unreachable_bb);
                 GRAVESTONE
             }
+
+            // the simulator manages the files, we just pass the descriptors around
+            BuiltIn::fopen => {
+                let name = self.lower_expr(args[0]);
+                let mode = match_signature! {signature:
+                    FOPEN_MODE => self.lower_expr(args[1]),
+                    FOPEN_NO_MODE => self.ctx.sconst("w")
+                };
+                self.ctx.call1(CallBackKind::FileOpen, &[name, mode])
+            }
+            BuiltIn::fwrite => {
+                self.ins_file_write(false, args);
+                GRAVESTONE
+            }
+            BuiltIn::fdisplay => {
+                self.ins_file_write(true, args);
+                GRAVESTONE
+            }
+            BuiltIn::fclose => {
+                let fd = self.lower_expr(args[0]);
+                self.ctx.call(CallBackKind::FileClose, &[fd]);
+                ZERO
+            }
             BuiltIn::analysis => {
                 let arg = self.lower_expr(args[0]);
                 self.ctx.call1(CallBackKind::Analysis, &[arg])
@@ -469,8 +494,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     let name = self.body.as_literal(args[1]).unwrap().unwrap_str();
                     self.ctx.func.interner.get_or_intern(name)
                 } else {
-                    let name = format!("unnamed{idx}");
-                    self.ctx.func.interner.get_or_intern(name)
+                    self.ctx.func.interner.get_or_intern(unnamed_noise_src(idx))
                 };
                 let pwr = self.lower_expr(args[0]);
                 self.ctx.call1(CallBackKind::WhiteNoise { name, idx }, &[pwr])
@@ -483,8 +507,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     let name = self.body.as_literal(args[2]).unwrap().unwrap_str();
                     self.ctx.func.interner.get_or_intern(name)
                 } else {
-                    let name = format!("unnamed{idx}");
-                    self.ctx.func.interner.get_or_intern(name)
+                    self.ctx.func.interner.get_or_intern(unnamed_noise_src(idx))
                 };
                 let pwr = self.lower_expr(args[0]);
                 let exp = self.lower_expr(args[1]);
@@ -498,15 +521,18 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     let name = self.body.as_literal(args[1]).unwrap().unwrap_str();
                     self.ctx.func.interner.get_or_intern(name)
                 } else {
-                    let name = format!("unnamed{idx}");
-                    self.ctx.func.interner.get_or_intern(name)
+                    self.ctx.func.interner.get_or_intern(unnamed_noise_src(idx))
                 };
                 let log = builtin == BuiltIn::noise_table_log;
-                let noise_table = NoiseTable::new([(0.0, 0.0)], log, name, idx);
+                let vals =
+                    self.body.noise_table(expr).iter().map(|&(f, pwr)| (f.into(), pwr.into()));
+                let noise_table = NoiseTable::new(vals, log, name, idx);
                 self.ctx.call1(CallBackKind::NoiseTable(Box::new(noise_table)), &[])
             }
 
-            BuiltIn::abstime => self.ctx.use_param(ParamKind::Abstime),
+            // `timescale` is not supported so `$realtime` is always measured in seconds
+            // and therefore equivalent to `$abstime`
+            BuiltIn::abstime | BuiltIn::realtime => self.ctx.use_param(ParamKind::Abstime),
 
             BuiltIn::ddt => {
                 if self.ctx.no_equations {
@@ -669,6 +695,8 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 GRAVESTONE
             }
             BuiltIn::finish | BuiltIn::stop => GRAVESTONE,
+            // node aliases are resolved during type checking, unresolved aliases are an error
+            BuiltIn::analog_node_alias => ONE,
 
             /* TODO: absdelay
             BuiltIn::absdelay => {
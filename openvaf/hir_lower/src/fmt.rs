@@ -1,5 +1,5 @@
 use hir::{ExprId, Literal, Type};
-use mir::GRAVESTONE;
+use mir::{Value, GRAVESTONE};
 
 use crate::body::BodyLoweringCtx;
 use crate::callbacks::CallBackKind;
@@ -36,8 +36,31 @@ pub struct FmtArg {
 
 impl BodyLoweringCtx<'_, '_, '_> {
     pub fn ins_display(&mut self, kind: DisplayKind, newline: bool, args: &[ExprId]) {
-        let mut fmt_lit = String::new();
         let mut call_args = vec![GRAVESTONE];
+        let arg_tys = self.lower_fmt(newline, args, &mut call_args);
+        self.ctx.call(CallBackKind::Print { kind, arg_tys }, &call_args);
+    }
+
+    /// Lowers `$fwrite`/`$fdisplay`, the first argument is the file descriptor
+    /// returned by `$fopen`.
+    pub fn ins_file_write(&mut self, newline: bool, args: &[ExprId]) {
+        let fd = self.lower_expr(args[0]);
+        let mut call_args = vec![fd, GRAVESTONE];
+        let arg_tys = self.lower_fmt(newline, &args[1..], &mut call_args);
+        self.ctx.call(CallBackKind::FileWrite { arg_tys }, &call_args);
+    }
+
+    /// Lowers the arguments of a display-like system task to a printf style format
+    /// literal. The literal is stored in the last element of `call_args` (a placeholder)
+    /// and the values that are formatted are appended to `call_args`.
+    fn lower_fmt(
+        &mut self,
+        newline: bool,
+        args: &[ExprId],
+        call_args: &mut Vec<Value>,
+    ) -> Box<[FmtArg]> {
+        let fmt_pos = call_args.len() - 1;
+        let mut fmt_lit = String::new();
         let mut arg_tys = Vec::new();
 
         let mut i = 0;
@@ -149,8 +172,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
             fmt_lit.push('\n');
         }
 
-        call_args[0] = self.ctx.sconst(&fmt_lit);
-        self.ctx
-            .call(CallBackKind::Print { kind, arg_tys: arg_tys.into_boxed_slice() }, &call_args);
+        call_args[fmt_pos] = self.ctx.sconst(&fmt_lit);
+        arg_tys.into_boxed_slice()
     }
 }
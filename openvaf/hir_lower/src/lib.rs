@@ -2,9 +2,10 @@ use std::iter::FilterMap;
 
 use ahash::{AHashMap, AHashSet};
 use bitset::HybridBitSet;
-pub use callbacks::{CallBackKind, NoiseTable, ParamInfoKind};
+pub use callbacks::{is_unnamed_noise_src, CallBackKind, NoiseTable, ParamInfoKind};
 use hir::{
-    Branch, BranchWrite, CompilationDB, Module, Node, ParamSysFun, Parameter, Type, Variable,
+    Branch, BranchWrite, CompilationDB, Module, Nature, Node, ParamSysFun, Parameter, Type,
+    Variable,
 };
 use indexmap::IndexMap;
 use lasso::Rodeo;
@@ -38,6 +39,9 @@ mod parameters;
 mod state;
 mod stmt;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ImplicitEquationKind {
     Ddt,
@@ -45,6 +49,18 @@ pub enum ImplicitEquationKind {
     Idt(IdtKind),
 }
 
+impl ImplicitEquationKind {
+    /// The analog operator (or noise source) that introduced the equation.
+    pub fn operator_name(self) -> &'static str {
+        match self {
+            ImplicitEquationKind::Ddt => "ddt",
+            ImplicitEquationKind::NoiseSrc => "noise",
+            ImplicitEquationKind::Idt(IdtKind::Modulus | IdtKind::ModulusOffset) => "idtmod",
+            ImplicitEquationKind::Idt(_) => "idt",
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum CurrentKind {
     Branch(Branch),
@@ -170,6 +186,8 @@ pub enum PlaceKind {
     ParamMin(Parameter),
     ParamMax(Parameter),
     BoundStep,
+    /// The `abstol` of a nature computed during model setup
+    NatureAbstol(Nature),
 }
 
 impl PlaceKind {
@@ -181,7 +199,8 @@ impl PlaceKind {
 
             PlaceKind::ImplicitResidual { .. }
             | PlaceKind::Contribute { .. }
-            | PlaceKind::BoundStep => Type::Real,
+            | PlaceKind::BoundStep
+            | PlaceKind::NatureAbstol(_) => Type::Real,
             PlaceKind::ParamMin(param) | PlaceKind::ParamMax(param) | PlaceKind::Param(param) => {
                 param.ty(db)
             }
@@ -237,6 +256,21 @@ pub type LiveParams<'a> = FilterMap<
 >;
 
 impl HirInterner {
+    /// A name for `equation` made up of the operator that introduced it and the number of
+    /// equations introduced by the same operator before it (like `idt1`). Equations are
+    /// created in the order their operators appear in the source, so the name (and the
+    /// order of the equations) is stable across recompiles.
+    pub fn implicit_equation_name(&self, equation: ImplicitEquation) -> String {
+        let operator = self.implicit_equations[equation].operator_name();
+        let pos = self
+            .implicit_equations
+            .iter()
+            .take(usize::from(equation))
+            .filter(|kind| kind.operator_name() == operator)
+            .count();
+        format!("{operator}{pos}")
+    }
+
     fn contains_ddx(
         ddx_calls: &mut AHashMap<FuncRef, (HybridBitSet<Unknown>, HybridBitSet<Unknown>)>,
         func: &Function,
@@ -405,6 +439,7 @@ pub struct MirBuilder<'a> {
     tag_writes: bool,
     ctx: Option<&'a mut FunctionBuilderContext>,
     lower_equations: bool,
+    collapse_resistance: Option<f64>,
 }
 
 impl<'a> MirBuilder<'a> {
@@ -423,6 +458,7 @@ impl<'a> MirBuilder<'a> {
             ctx: None,
             lower_equations: false,
             tag_writes: false,
+            collapse_resistance: None,
         }
     }
 
@@ -453,6 +489,14 @@ impl<'a> MirBuilder<'a> {
         self
     }
 
+    /// Branches that are linear resistors (`I(a, b) <+ V(a, b) / R` or `I(a, b) <+ G * V(a, b)`)
+    /// are collapsed if their resistance is below `threshold`. The resistance must only
+    /// depend on parameters.
+    pub fn with_collapse_resistance(mut self, threshold: Option<f64>) -> Self {
+        self.collapse_resistance = threshold;
+        self
+    }
+
     pub fn with_ctx(mut self, ctx: &'a mut FunctionBuilderContext) -> Self {
         self.ctx = Some(ctx);
         self
@@ -483,6 +527,7 @@ impl<'a> MirBuilder<'a> {
 
         let mut ctx = LoweringCtx::new(self.db, builder, !self.lower_equations, &mut interner)
             .with_tagged_vars(self.tagged_reads);
+        ctx.collapse_resistance = self.collapse_resistance;
         let mut body_ctx =
             BodyLoweringCtx { ctx: &mut ctx, body: analog_initial_body.borrow(), path: &path };
 
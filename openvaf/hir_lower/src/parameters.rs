@@ -1,7 +1,7 @@
 use std::f64::NEG_INFINITY;
 use std::mem::replace;
 
-use hir::{CompilationDB, ConstraintValue, ParamConstraint, Parameter, Type};
+use hir::{CompilationDB, ConstraintValue, Nature, ParamConstraint, Parameter, Type};
 use lasso::Rodeo;
 use mir::builder::InstBuilder;
 use mir::{Block, FuncRef, Function, Opcode, Value, FALSE, GRAVESTONE, INFINITY};
@@ -91,6 +91,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::From,
                             ops,
+                            param,
                             invalid,
                             exit,
                         );
@@ -100,6 +101,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::Exclude,
                             ops,
+                            param,
                             invalid,
                             exit,
                         );
@@ -117,6 +119,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::From,
                             ops,
+                            param,
                             invalid,
                             exit,
                         );
@@ -126,6 +129,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::Exclude,
                             ops,
+                            param,
                             invalid,
                             exit,
                         );
@@ -271,6 +275,9 @@ impl HirInterner {
                     _ => unreachable!(),
                 });
 
+                // the optbarriers ensure that constant bounds can be read after optimization
+                let min = ctx.ctx.ins().optbarrier(min);
+                let max = ctx.ctx.ins().optbarrier(max);
                 ctx.ctx.intern.outputs.insert(PlaceKind::ParamMin(param), min.into());
                 ctx.ctx.intern.outputs.insert(PlaceKind::ParamMax(param), max.into());
                 precomputed_vals
@@ -287,6 +294,7 @@ impl HirInterner {
                 &precomputed_vals,
                 ConstraintKind::From,
                 ops,
+                param,
                 invalid,
                 exit,
             );
@@ -296,6 +304,7 @@ impl HirInterner {
                 &precomputed_vals,
                 ConstraintKind::Exclude,
                 ops,
+                param,
                 invalid,
                 exit,
             );
@@ -313,6 +322,38 @@ impl HirInterner {
     }
 }
 
+impl HirInterner {
+    /// Lowers the `abstol` attribute of each nature in `natures` and stores the result in
+    /// [`PlaceKind::NatureAbstol`]. Parameters are read as [`ParamKind::Param`], so calling
+    /// [`insert_param_init`](Self::insert_param_init) afterwards makes the abstol use the
+    /// initialized parameter values.
+    pub fn insert_nature_abstol(
+        &mut self,
+        db: &CompilationDB,
+        func: &mut Function,
+        literals: &mut Rodeo,
+        natures: &[Nature],
+    ) {
+        let mut ctx = FunctionBuilderContext::default();
+        let (builder, term) = FunctionBuilder::edit(func, literals, &mut ctx, false);
+        let mut ctx = LoweringCtx::new(db, builder, true, self);
+
+        for &nature in natures {
+            let attr = nature.abstol_attr(db).expect("nature without abstol");
+            let mut val = ctx.lower_expr_body(attr.value(db).borrow(), 0);
+            if matches!(attr.ty(db), Some(Type::Integer)) {
+                val = ctx.insert_cast(val, &Type::Integer, &Type::Real);
+            }
+            // the optbarrier ensures that constant tolerances can be read after optimization
+            let val = ctx.ins().optbarrier(val);
+            ctx.def_output(PlaceKind::NatureAbstol(nature), val);
+        }
+
+        ctx.ensured_sealed();
+        ctx.func.func.layout.append_inst_to_bb(term, ctx.current_block());
+    }
+}
+
 impl BodyLoweringCtx<'_, '_, '_> {
     #[allow(clippy::too_many_arguments)]
     fn check_param(
@@ -322,10 +363,14 @@ impl BodyLoweringCtx<'_, '_, '_> {
         precomputed_vals: &[(Value, Value)],
         kind: ConstraintKind,
         ops: CmpOps,
+        param: Parameter,
         invalid: FuncRef,
         global_exit: Block,
     ) {
         let mut exit = None;
+        // if there is only a single range we can tell which of its bounds was violated
+        let num_bounds = bounds.iter().filter(|bound| bound.kind == kind).count();
+        let mut range_check = None;
 
         for (i, bound) in bounds.iter().enumerate() {
             if bound.kind != kind {
@@ -360,6 +405,9 @@ impl BodyLoweringCtx<'_, '_, '_> {
 
                     let op = ops.in_bound(range.start_inclusive);
                     let is_lo_ok = self.ctx.ins().binary1(op, start, param_val);
+                    if num_bounds == 1 {
+                        range_check = Some((is_lo_ok, start, end, range));
+                    }
 
                     let is_ok = self.ctx.make_select(is_lo_ok, |builder, is_ok| {
                         if is_ok {
@@ -381,6 +429,26 @@ impl BodyLoweringCtx<'_, '_, '_> {
             ConstraintKind::From => {
                 if let Some(exit) = exit {
                     // error on fallthrough
+                    if let Some((is_lo_ok, start, end, range)) = range_check {
+                        self.ctx.make_cond(is_lo_ok, |ctx, is_lo_ok| {
+                            let (bound, inclusive) = if is_lo_ok {
+                                (end, range.end_inclusive)
+                            } else {
+                                (start, range.start_inclusive)
+                            };
+                            let (val, bound) = if ops.eq == Opcode::Ieq {
+                                (ctx.ins().ifcast(param_val), ctx.ins().ifcast(bound))
+                            } else {
+                                (param_val, bound)
+                            };
+                            let kind = CallBackKind::ParamBoundViolated {
+                                param,
+                                upper: is_lo_ok,
+                                inclusive,
+                            };
+                            ctx.call(kind, &[val, bound]);
+                        });
+                    }
                     self.ctx.ins().call(invalid, &[]);
                     self.ctx.ins().jump(global_exit);
 
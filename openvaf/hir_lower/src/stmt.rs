@@ -1,6 +1,9 @@
-use hir::{BranchWrite, Case, CaseCond, ContributeKind, ExprId, Node, Stmt, StmtId, Type};
+use hir::{
+    BranchWrite, Case, CaseCond, ContributeKind, Event, ExprId, GlobalEvent, Node, Stmt, StmtId,
+    Type,
+};
 use mir::builder::InstBuilder;
-use mir::{Opcode, F_ZERO};
+use mir::{InstructionData, Opcode, Value, F_ZERO};
 
 use crate::body::BodyLoweringCtx;
 use crate::{CallBackKind, CurrentKind, ParamKind, PlaceKind};
@@ -17,8 +20,16 @@ impl BodyLoweringCtx<'_, '_, '_> {
             Stmt::Expr(expr) => {
                 self.lower_expr(expr);
             }
+            Stmt::EventControl {
+                event: Event::Global { kind: GlobalEvent::FinalStep, .. },
+                ..
+            } => {
+                // simulators provide no hook that is called at the end of a simulation.
+                // Lowering the body inline would execute it during every iteration instead
+            }
             Stmt::EventControl { body, .. } => {
-                // TODO handle porperly
+                // initial_step: the body is lowered inline. Any operating point independent
+                // computations are moved to the init function (and only run during setup)
                 self.lower_stmt(body);
             }
             Stmt::Assignment { lhs, rhs } => {
@@ -167,6 +178,9 @@ impl BodyLoweringCtx<'_, '_, '_> {
         if rhs == F_ZERO {
             return;
         }
+        if let (false, Some(threshold)) = (voltage_src, self.ctx.collapse_resistance) {
+            self.collapse_small_resistance(write, rhs, threshold)
+        }
 
         let place = PlaceKind::Contribute { dst: write, reactive: false, voltage_src };
         let old = self.ctx.use_place(place);
@@ -180,6 +194,62 @@ impl BodyLoweringCtx<'_, '_, '_> {
         self.ctx.def_place(place, new);
     }
 
+    /// Emits a collapse hint for a linear resistor (`I(a, b) <+ V(a, b) / R` or
+    /// `I(a, b) <+ G * V(a, b)`) that is taken if the resistance is below `threshold`.
+    fn collapse_small_resistance(&mut self, write: BranchWrite, rhs: Value, threshold: f64) {
+        let (hi, lo) = write.nodes(self.ctx.db);
+        let (hi, lo) = match (self.ctx.node(hi), lo.and_then(|lo| self.ctx.node(lo))) {
+            (Some(hi), lo) => (hi, lo),
+            (None, Some(lo)) => (lo, None),
+            (None, None) => return,
+        };
+        let voltages = [
+            Some(ParamKind::Voltage { hi, lo }),
+            lo.map(|lo| ParamKind::Voltage { hi: lo, lo: Some(hi) }),
+        ];
+        let params = &self.ctx.intern.params;
+        let is_voltage = |val| voltages.iter().flatten().any(|kind| params.get(kind) == Some(&val));
+
+        let inst = if let Some(inst) = self.ctx.dfg().value_def(rhs).inst() {
+            inst
+        } else {
+            return;
+        };
+        let (val, is_resistance) = match self.ctx.dfg().insts[inst] {
+            InstructionData::Binary { opcode: Opcode::Fdiv, args: [num, den] }
+                if is_voltage(num) =>
+            {
+                (den, true)
+            }
+            InstructionData::Binary { opcode: Opcode::Fmul, args: [lhs, rhs] }
+                if is_voltage(lhs) =>
+            {
+                (rhs, false)
+            }
+            InstructionData::Binary { opcode: Opcode::Fmul, args: [lhs, rhs] }
+                if is_voltage(rhs) =>
+            {
+                (lhs, false)
+            }
+            _ => return,
+        };
+
+        // the hint is removed later if the resistance depends on the operating point
+        let val = self.ctx.ins().fabs(val);
+        let collapse = if is_resistance {
+            let threshold = self.ctx.fconst(threshold);
+            self.ctx.ins().flt(val, threshold)
+        } else {
+            let threshold = self.ctx.fconst(threshold.recip());
+            self.ctx.ins().fgt(val, threshold)
+        };
+        self.ctx.make_cond(collapse, |ctx, collapse| {
+            if collapse {
+                ctx.call(CallBackKind::CollapseHint(hi, lo), &[]);
+            }
+        });
+    }
+
     fn lower_contribute_unnamed_branch(
         &mut self,
         negate: &mut bool,
@@ -0,0 +1,377 @@
+use hir::{CompilationDB, ScopeDef, TimeOperator};
+use lasso::Rodeo;
+use mir::{Const, Function, InstructionData, Opcode};
+
+use crate::{CallBackKind, HirInterner, MirBuilder, ParamKind, PlaceKind};
+
+#[test]
+fn file_io() {
+    let src = r#"
+        `include "disciplines.vams"
+        module file_io(inout a, inout c);
+            electrical a, c;
+            parameter real r = 1.0;
+            integer fd;
+            analog begin
+                @(initial_step) begin
+                    fd = $fopen("file_io.log", "w");
+                    $fwrite(fd, "r=%g\n", r);
+                end
+                I(a, c) <+ V(a, c) / r;
+            end
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let (func, intern) = MirBuilder::new(
+        &db,
+        module,
+        &|kind| matches!(kind, PlaceKind::Contribute { .. }),
+        &mut [].into_iter(),
+    )
+    .build(&mut Rodeo::new());
+
+    let fopen = intern.callbacks.index(&CallBackKind::FileOpen).expect("$fopen was not lowered");
+    let fwrite = intern
+        .callbacks
+        .indices()
+        .find(|&cb| matches!(intern.callbacks[cb], CallBackKind::FileWrite { .. }))
+        .expect("$fwrite was not lowered");
+
+    let mut fd = None;
+    let mut written_fd = None;
+    for bb in func.layout.blocks() {
+        for inst in func.layout.block_insts(bb) {
+            if let InstructionData::Call { func_ref, .. } = func.dfg.insts[inst] {
+                if func_ref == fopen {
+                    fd = Some(func.dfg.first_result(inst));
+                } else if func_ref == fwrite {
+                    // the format literal and the formatted value follow the descriptor
+                    assert_eq!(func.dfg.instr_args(inst).len(), 3);
+                    written_fd = Some(func.dfg.instr_args(inst)[0]);
+                }
+            }
+        }
+    }
+    assert!(fd.is_some());
+    assert_eq!(fd, written_fd);
+}
+
+#[test]
+fn time_operator_natures() {
+    let src = r#"
+        `include "disciplines.vams"
+        module charge(inout a, inout c);
+            electrical a, c;
+            analog I(a, c) <+ ddt(1e-12 * V(a, c));
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let discipline = module.ports(&db)[0].discipline(&db);
+    let current = discipline.flow(&db).unwrap();
+    let voltage = discipline.potential(&db).unwrap();
+
+    // the charge Q = idt(I) of a current I turns back into a current with ddt(Q)
+    let charge = current.apply_time_operator(&db, TimeOperator::Idt);
+    assert_eq!(charge.name(&db), "Charge");
+    assert_eq!(charge.units(&db), "coul");
+    assert_eq!(charge.apply_time_operator(&db, TimeOperator::Ddt), current);
+
+    let flux = voltage.apply_time_operator(&db, TimeOperator::Idt);
+    assert_eq!(flux.name(&db), "Flux");
+    assert_eq!(flux.apply_time_operator(&db, TimeOperator::Ddt), voltage);
+
+    // natures without a relationship are unchanged
+    assert_eq!(voltage.apply_time_operator(&db, TimeOperator::Ddt), voltage);
+}
+
+#[test]
+fn nature_abstol() {
+    let src = r#"
+        `include "disciplines.vams"
+        nature Energy
+            units = "J";
+            access = En;
+        endnature
+        discipline energy
+            potential Energy;
+        enddiscipline
+        module res(inout a, inout c, inout e);
+            electrical a, c;
+            energy e;
+            analog I(a, c) <+ V(a, c);
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let electrical = module.ports(&db)[0].discipline(&db);
+    assert_eq!(electrical.potential(&db).unwrap().abstol(&db), Some(1e-6));
+    assert_eq!(electrical.flow(&db).unwrap().abstol(&db), Some(1e-12));
+    let energy = module.ports(&db)[2].discipline(&db);
+    assert_eq!(energy.potential(&db).unwrap().abstol(&db), None);
+}
+
+#[test]
+fn implicit_equation_names() {
+    let src = r#"
+        `include "disciplines.vams"
+        module integrator(inout a, inout c);
+            electrical a, c;
+            analog begin
+                I(a, c) <+ idt(V(a, c), 0.0);
+                I(a, c) <+ 1e-3 * idt(V(a));
+            end
+        endmodule
+    "#;
+    let names = || {
+        let db = CompilationDB::new_virtual(src).unwrap();
+        assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+        let module = db.compilation_unit().modules(&db)[0];
+        let (_, intern) = MirBuilder::new(
+            &db,
+            module,
+            &|kind| matches!(kind, PlaceKind::Contribute { .. }),
+            &mut [].into_iter(),
+        )
+        .with_equations()
+        .build(&mut Rodeo::new());
+        intern
+            .implicit_equations
+            .keys()
+            .map(|eq| intern.implicit_equation_name(eq))
+            .collect::<Vec<_>>()
+    };
+
+    let first = names();
+    assert_eq!(first, ["idt0", "idt1"]);
+    assert_eq!(first, names());
+}
+
+#[test]
+fn param_bound_violation() {
+    let src = r#"
+        `include "disciplines.vams"
+        module mosfet(inout d, inout s);
+            electrical d, s;
+            parameter real L = 1.0 from [1.0:inf);
+            analog I(d, s) <+ V(d, s) / L;
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let params: Vec<_> = module
+        .rec_declarations(&db)
+        .filter_map(
+            |(_, def)| if let ScopeDef::Parameter(param) = def { Some(param) } else { None },
+        )
+        .collect();
+    let mut func = Function::default();
+    let mut intern = HirInterner::default();
+    intern.insert_param_init(&db, &mut func, &mut Rodeo::new(), false, true, &params);
+
+    // L can only fall below its lower bound (the upper bound is infinite but still checked)
+    let lower_bound = intern
+        .callbacks
+        .indices()
+        .find(|&cb| {
+            matches!(
+                intern.callbacks[cb],
+                CallBackKind::ParamBoundViolated { param, upper: false, inclusive: true }
+                    if param.name(&db) == "L"
+            )
+        })
+        .expect("no lower bound check was generated for L");
+
+    // the check is duplicated for the given and the default value of L
+    let mut bounds = Vec::new();
+    for bb in func.layout.blocks() {
+        for inst in func.layout.block_insts(bb) {
+            if let InstructionData::Call { func_ref, .. } = func.dfg.insts[inst] {
+                if func_ref == lower_bound {
+                    bounds.push(func.dfg.instr_args(inst)[1]);
+                }
+            }
+        }
+    }
+    assert!(!bounds.is_empty());
+    for bound in bounds {
+        assert_eq!(func.dfg.value_def(bound).as_const(), Some(Const::Float(1.0.into())));
+    }
+}
+
+#[test]
+fn param_default_references() {
+    let src = r#"
+        `include "disciplines.vams"
+        module res(inout a, inout c);
+            electrical a, c;
+            parameter real r = 1.0;
+            aliasparam r_alias = r;
+            parameter real g = r*2;
+            parameter real g_alias = r_alias*2;
+            analog I(a, c) <+ V(a, c) * (g + g_alias);
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let params: Vec<_> = module
+        .rec_declarations(&db)
+        .filter_map(
+            |(_, def)| if let ScopeDef::Parameter(param) = def { Some(param) } else { None },
+        )
+        .collect();
+    let mut func = Function::default();
+    let mut intern = HirInterner::default();
+    intern.insert_param_init(&db, &mut func, &mut Rodeo::new(), false, false, &params);
+
+    // the defaults of g and g_alias read the value of r after its own default was applied
+    let r = params.iter().find(|param| param.name(&db) == "r").unwrap();
+    let r = intern.outputs[&PlaceKind::Param(*r)].unwrap();
+    let mut reads = 0;
+    for bb in func.layout.blocks() {
+        for inst in func.layout.block_insts(bb) {
+            if let InstructionData::Binary { opcode: Opcode::Fmul, args: [lhs, _] } =
+                func.dfg.insts[inst]
+            {
+                assert_eq!(lhs, r);
+                reads += 1;
+            }
+        }
+    }
+    assert_eq!(reads, 2);
+
+    // parameters can only reference parameters that are declared before them
+    let src = r#"
+        `include "disciplines.vams"
+        module res(inout a, inout c);
+            electrical a, c;
+            parameter real g = r*2;
+            parameter real r = 1.0;
+            analog I(a, c) <+ V(a, c) * g;
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let diagnostics = db.compilation_unit().test_diagnostics(&db);
+    assert!(
+        diagnostics.contains("definition of 'g' references parameter 'r' defined afterwards"),
+        "{diagnostics}"
+    );
+}
+
+#[test]
+fn realtime_is_abstime() {
+    let src = r#"
+        `include "disciplines.vams"
+        module clock(inout a, inout c);
+            electrical a, c;
+            analog I(a, c) <+ $realtime - $abstime;
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let (func, intern) = MirBuilder::new(
+        &db,
+        module,
+        &|kind| matches!(kind, PlaceKind::Contribute { .. }),
+        &mut [].into_iter(),
+    )
+    .build(&mut Rodeo::new());
+
+    // without `timescale both are measured in seconds and share a single input
+    let abstime = *intern.params.get(&ParamKind::Abstime).expect("$abstime was not lowered");
+    let sub = func
+        .layout
+        .blocks()
+        .flat_map(|bb| func.layout.block_insts(bb))
+        .find(|&inst| func.dfg.insts[inst].opcode() == Opcode::Fsub)
+        .expect("subtraction was not lowered");
+    assert_eq!(func.dfg.instr_args(sub), [abstime, abstime]);
+}
+
+#[test]
+fn thermal_voltage() {
+    let src = r#"
+        `include "disciplines.vams"
+        module thermal_voltage(inout a, inout c);
+            electrical a, c;
+            real vt_sim, vt_arg;
+            analog begin
+                vt_sim = $vt();
+                vt_arg = $vt(300.0);
+                I(a, c) <+ V(a, c) / (vt_sim + vt_arg) + ddx(vt_sim, $temperature);
+            end
+        endmodule
+    "#;
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = db.compilation_unit().modules(&db)[0];
+    let (func, intern) = MirBuilder::new(
+        &db,
+        module,
+        &|kind| matches!(kind, PlaceKind::Contribute { .. }),
+        &mut [].into_iter(),
+    )
+    .build(&mut Rodeo::new());
+
+    // $vt(T) = k*T/q
+    let (temp_param, &temp) =
+        intern.params.index_and_val(&ParamKind::Temperature).expect("$vt() must use $temperature");
+    let mut temperatures = Vec::new();
+    for bb in func.layout.blocks() {
+        for inst in func.layout.block_insts(bb) {
+            if func.dfg.insts[inst].opcode() == Opcode::Fmul {
+                let args = func.dfg.instr_args(inst);
+                assert!((func.dfg.values.unwrap_f64(args[0]) - 8.617333e-5).abs() < 1e-10);
+                temperatures.push(args[1]);
+            }
+        }
+    }
+    assert_eq!(temperatures.len(), 2, "{temperatures:?}");
+    assert_eq!(temperatures[0], temp);
+    assert_eq!(func.dfg.values.unwrap_f64(temperatures[1]), 300.0);
+
+    // the derivative by the temperature is resolved by auto_diff
+    assert!(intern.callbacks.contains(&CallBackKind::Derivative(temp_param)));
+    assert!(intern.unknowns(&func, false).unknowns.contains(&temp));
+}
+
+#[test]
+fn self_assignment() {
+    let lower = |assignment: &str| {
+        let src = format!(
+            r#"
+            `include "disciplines.vams"
+            module copy(inout a, inout c);
+                electrical a, c;
+                real x;
+                analog begin
+                    x = V(a, c);
+                    {assignment}
+                    I(a, c) <+ x;
+                end
+            endmodule
+        "#
+        );
+        let db = CompilationDB::new_virtual(&src).unwrap();
+        assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+        let module = db.compilation_unit().modules(&db)[0];
+        let (func, _) = MirBuilder::new(
+            &db,
+            module,
+            &|kind| matches!(kind, PlaceKind::Contribute { .. }),
+            &mut [].into_iter(),
+        )
+        .build(&mut Rodeo::new());
+        func.to_debug_string()
+    };
+
+    // variables are lowered to SSA values so `x = x` must not produce any instructions
+    assert_eq!(lower("x = x;"), lower(""));
+}
@@ -485,6 +485,7 @@ copied_builtins! {
     STOP = FINISH
 
     ABSTIME = REAL_INFO
+    REALTIME = REAL_INFO
     TEMPERATURE = REAL_INFO
 
     RDIST_CHI_SQUARE = RDIST_1_ARG
@@ -4,7 +4,7 @@ use hir_def::BuiltIn;
 
 use crate::builtin::*;
 
-const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
+const BUILTIN_INFO: [BuiltinInfo; 112usize] = [
     ABS,
     ACOS,
     ACOSH,
@@ -61,6 +61,7 @@ const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
     ERROR,
     INFO,
     ABSTIME,
+    REALTIME,
     DIST_CHI_SQUARE,
     DIST_EXPONENTIAL,
     DIST_POISSON,
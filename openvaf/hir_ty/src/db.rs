@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
+use ahash::{AHashMap, AHashSet};
 use hir_def::db::HirDefDB;
 use hir_def::nameres::{ResolvedPath, ScopeDefItem};
 use hir_def::{
-    AliasParamId, BranchId, DefWithBodyId, DisciplineId, Lookup, NatureAttrId, NatureId, NodeId,
-    ParamId, ParamSysFun, Type,
+    AliasParamId, BranchId, BuiltIn, DefWithBodyId, DisciplineId, Expr, ExprId, Literal, Lookup,
+    ModuleId, NatureAttrId, NatureId, NodeId, ParamId, ParamSysFun, Path, Stmt, StmtId, Type,
 };
 use stdx::Upcast;
+use syntax::name::Name;
 
 use crate::inference::InferenceResult;
 use crate::lower::{BranchTy, DisciplineTy, NatureTy};
@@ -36,6 +38,11 @@ pub trait HirTyDB: HirDefDB + Upcast<dyn HirDefDB> {
     #[salsa::cycle(resolve_alias_recover)]
     fn resolve_alias(&self, id: AliasParamId) -> Option<Alias>;
 
+    fn node_aliases(&self, module: ModuleId) -> Arc<NodeAliases>;
+
+    #[salsa::transparent]
+    fn resolve_node_alias(&self, node: NodeId) -> NodeId;
+
     #[salsa::transparent]
     fn node_discipline(&self, node: NodeId) -> Option<DisciplineId>;
 
@@ -93,6 +100,96 @@ fn resolve_alias(db: &dyn HirTyDB, id: AliasParamId) -> Option<Alias> {
     }
 }
 
+/// The nodes of a module that were aliased to another node with `$analog_node_alias`.
+///
+/// Aliases are resolved statically so only calls that are executed unconditionally (not
+/// nested in a conditional statement or event) are considered. The alias must be an
+/// internal node and the hierarchical reference must be the name of another node of
+/// the same module.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct NodeAliases {
+    /// Maps each alias to the node it refers to.
+    pub nodes: AHashMap<NodeId, NodeId>,
+    /// The `$analog_node_alias` calls that were resolved (identified by whether they are
+    /// part of the analog initial block and their expression).
+    pub calls: AHashSet<(bool, ExprId)>,
+}
+
+fn node_aliases(db: &dyn HirTyDB, module: ModuleId) -> Arc<NodeAliases> {
+    let mut res = NodeAliases::default();
+    let module_data = db.module_data(module);
+    for initial in [true, false] {
+        let body = db.body(DefWithBodyId::ModuleId { initial, module });
+        let mut stmts: Vec<StmtId> = body.entry_stmts.iter().rev().copied().collect();
+        while let Some(stmt) = stmts.pop() {
+            let expr = match body.stmts[stmt] {
+                Stmt::Block { body: ref block } => {
+                    stmts.extend(block.iter().rev().copied());
+                    continue;
+                }
+                Stmt::Expr(expr) => expr,
+                _ => continue,
+            };
+            let (fun, args) = match &body.exprs[expr] {
+                Expr::Call { fun: Some(fun), args } if args.len() == 2 => (fun, args),
+                _ => continue,
+            };
+            let scope = body.stmt_scopes[stmt];
+            match scope.resolve_path(db.upcast(), fun) {
+                Ok(ResolvedPath::ScopeDefItem(ScopeDefItem::BuiltIn(
+                    BuiltIn::analog_node_alias,
+                ))) => (),
+                _ => continue,
+            }
+
+            let alias = match &body.exprs[args[0]] {
+                Expr::Path { path, port: false } => {
+                    scope.resolve_item_path::<NodeId>(db.upcast(), path)
+                }
+                _ => continue,
+            };
+            let node = match &body.exprs[args[1]] {
+                Expr::Literal(Literal::String(name)) => scope.resolve_item_path::<NodeId>(
+                    db.upcast(),
+                    &Path::new_ident(Name::resolve(name)),
+                ),
+                _ => continue,
+            };
+            let (alias, mut node) = match (alias, node) {
+                (Ok(alias), Ok(node)) => (alias, node),
+                _ => continue,
+            };
+            if module_data.ports.contains(&alias) || res.nodes.contains_key(&alias) {
+                continue;
+            }
+
+            // reject aliases that would form a cycle
+            while node != alias {
+                match res.nodes.get(&node) {
+                    Some(&dst) => node = dst,
+                    None => break,
+                }
+            }
+            if node == alias {
+                continue;
+            }
+
+            res.nodes.insert(alias, node);
+            res.calls.insert((initial, expr));
+        }
+    }
+    Arc::new(res)
+}
+
+fn resolve_node_alias(db: &dyn HirTyDB, node: NodeId) -> NodeId {
+    let aliases = db.node_aliases(node.lookup(db.upcast()).module);
+    let mut resolved = node;
+    while let Some(&dst) = aliases.nodes.get(&resolved) {
+        resolved = dst;
+    }
+    resolved
+}
+
 fn node_discipline(db: &dyn HirTyDB, node: NodeId) -> Option<DisciplineId> {
     let def_map = node.lookup(db.upcast()).module.lookup(db.upcast()).scope.def_map(db.upcast());
     let node = db.node_data(node);
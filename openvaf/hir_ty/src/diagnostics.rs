@@ -6,7 +6,7 @@ use basedb::diagnostics::{
 };
 use basedb::lints::builtin::non_standard_code;
 use basedb::lints::{Lint, LintSrc};
-use basedb::{BaseDB, FileId};
+use basedb::{BaseDB, FileId, FileReadError};
 use hir_def::body::BodySourceMap;
 use hir_def::{ExprId, FunctionId, Lookup, Type};
 use stdx::iter::zip;
@@ -18,7 +18,7 @@ use syntax::{Parse, SourceFile, TextSize};
 use typed_index_collections::TiSlice;
 
 use crate::db::HirTyDB;
-use crate::inference::InferenceDiagnostic;
+use crate::inference::{InferenceDiagnostic, NoiseTableError};
 use crate::types::{Signature, SignatureData, Ty, TyRequirement};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -292,6 +292,80 @@ impl Diagnostic for InferenceDiagnosticWrapped<'_> {
                         "help: expected one of the following\nbranch current access: I(branch), I(a,b)\nnode voltage: V(x)".to_owned(),
                     ])
             }
+            InferenceDiagnostic::RealParamBound { expr } => {
+                let src = self.parse.to_file_span(
+                    self.body_sm.expr_map_back[expr].as_ref().unwrap().range(),
+                    self.sm,
+                );
+
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: "expected integer value".to_owned(),
+                    }])
+                    .with_message("type mismatch: bound of an integer parameter is a real value")
+                    .with_notes(vec![
+                        "help: the bounds of a parameter must have the same type as the parameter"
+                            .to_owned(),
+                    ])
+            }
+            InferenceDiagnostic::InvalidNoiseTable { expr, ref err, from_file } => {
+                let expr = match *err {
+                    NoiseTableError::NonLiteral { expr } => expr,
+                    _ => expr,
+                };
+                let src = self.parse.to_file_span(
+                    self.body_sm.expr_map_back[expr].as_ref().unwrap().range(),
+                    self.sm,
+                );
+                let kind = if from_file { "line" } else { "pair" };
+
+                let (message, label) = match *err {
+                    NoiseTableError::NonLiteral { .. } => {
+                        ("noise table is not a literal".to_owned(), "expected a literal")
+                    }
+                    NoiseTableError::FileRead { err: FileReadError::Io(err) } => {
+                        (format!("failed to read noise table: {err}"), "failed to read file")
+                    }
+                    NoiseTableError::FileRead { err: FileReadError::InvalidTextFormat(_) } => (
+                        "failed to read noise table: file is not valid UTF-8".to_owned(),
+                        "failed to read file",
+                    ),
+                    NoiseTableError::InvalidPair { pos } => (
+                        format!("invalid noise table: {kind} {pos} is not a frequency-power pair"),
+                        "invalid noise table",
+                    ),
+                    NoiseTableError::Unsorted { pos } => (
+                        format!("invalid noise table: unsorted frequency at {kind} {pos}"),
+                        "invalid noise table",
+                    ),
+                    NoiseTableError::NonPositive { pos } => (
+                        format!("invalid noise table: non-positive value at {kind} {pos}"),
+                        "invalid noise table",
+                    ),
+                    NoiseTableError::Empty => {
+                        ("invalid noise table: table is empty".to_owned(), "invalid noise table")
+                    }
+                };
+
+                let mut res = Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: label.to_owned(),
+                    }])
+                    .with_message(message);
+                if matches!(err, NoiseTableError::NonPositive { .. }) {
+                    res = res.with_notes(vec![
+                        "help: noise_table_log interpolates the logarithm of frequency and power"
+                            .to_owned(),
+                    ])
+                }
+                res
+            }
             InferenceDiagnostic::ExpectedProbe { e } => {
                 let src = self
                     .parse
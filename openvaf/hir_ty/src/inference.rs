@@ -1,20 +1,22 @@
 use std::borrow::Cow;
+use std::io;
 use std::mem;
 use std::sync::Arc;
 
 use ahash::AHashMap;
 use arena::ArenaMap;
-use hir_def::body::Body;
+use basedb::{FileId, FileReadError};
+use hir_def::body::{Body, ConstraintValue};
 use hir_def::db::HirDefDB;
 use hir_def::expr::{CaseCond, Literal};
 use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::{NatureAccess, ResolvedPath, ScopeDefItem, ScopeDefItemKind};
 use hir_def::{
     BranchId, BuiltIn, DefWithBodyId, Expr, ExprId, FunctionArgLoc, FunctionId, LocalFunctionArgId,
-    Lookup, NatureId, NodeId, ParamSysFun, Path, Stmt, StmtId, Type, VarId,
+    Lookup, NatureId, NodeId, ParamId, ParamSysFun, Path, Stmt, StmtId, Type, VarId,
 };
-use stdx::impl_from;
 use stdx::iter::zip;
+use stdx::{impl_from, Ieee64};
 use syntax::ast::{self, BinaryOp, UnaryOp};
 use syntax::{TextRange, TextSize};
 use typed_index_collections::{TiSlice, TiVec};
@@ -22,14 +24,18 @@ use typed_index_collections::{TiSlice, TiVec};
 use crate::builtin::{
     DDX_FLOW, DDX_POT, DDX_POT_DIFF, DDX_TEMP, LIMIT_BUILTIN_FUNCTION, LIMIT_USER_FUNCTION,
     NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES, NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW,
+    NOISE_TABLE_FILE, NOISE_TABLE_FILE_NAME,
 };
 use crate::db::{Alias, HirTyDB};
 use crate::diagnostics::{ArrayTypeMismatch, SignatureMismatch, TypeMismatch};
 use crate::inference::fmt_parser::parse_real_fmt_spec;
+pub use crate::inference::noise_table::NoiseTableError;
+use crate::inference::noise_table::{check_noise_table, inline_noise_table, parse_noise_table};
 use crate::lower::{BranchTy, DisciplineAccess};
 use crate::types::{default_return_ty, BuiltinInfo, Signature, SignatureData, Ty, TyRequirement};
 
 mod fmt_parser;
+mod noise_table;
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum ResolvedFun {
@@ -72,6 +78,8 @@ pub struct InferenceResult {
     pub resolved_signatures: AHashMap<ExprId, Signature>,
     pub assignment_destination: AHashMap<StmtId, AssignDst>,
     pub casts: AHashMap<ExprId, Type>,
+    /// The (frequency, power) pairs of calls to `noise_table` and `noise_table_log`
+    pub noise_tables: AHashMap<ExprId, Box<[(Ieee64, Ieee64)]>>,
     pub diagnostics: Vec<InferenceDiagnostic>,
 }
 
@@ -83,7 +91,8 @@ impl InferenceResult {
             ..Default::default()
         };
 
-        let mut ctx = Ctx { result, body: &body, db, expr_stmt_ty: None };
+        let mut ctx =
+            Ctx { result, body: &body, db, root_file: id.file(db.upcast()), expr_stmt_ty: None };
         ctx.expr_stmt_ty = match id {
             DefWithBodyId::ParamId(param) => match &db.param_data(param).ty {
                 Some(ty) => Some(ty.clone()),
@@ -100,6 +109,10 @@ impl InferenceResult {
             ctx.infere_stmt(*stmt);
         }
 
+        if let DefWithBodyId::ParamId(param) = id {
+            ctx.check_param_bounds(param);
+        }
+
         Arc::new(ctx.result)
     }
 }
@@ -108,6 +121,8 @@ struct Ctx<'a> {
     result: InferenceResult,
     body: &'a Body,
     db: &'a dyn HirTyDB,
+    /// noise table files are resolved relative to this file
+    root_file: FileId,
     /// A Body that only represent expressions have expr stmts as entry_stmts.
     /// These need to be type checked properly.
     /// For behavioural (anlog body and function) and untype (nature attr)
@@ -155,6 +170,32 @@ impl Ctx<'_> {
         self.body.stmts[stmt].walk_child_stmts(|stmt| self.infere_stmt(stmt));
     }
 
+    /// Real bounds of integer parameters are implicitly cast (and therefore rounded) by
+    /// `infere_assignment` which changes the meaning of the constraint, so they are rejected.
+    /// Integral literals (like `1.0`) are exempt since casting them is lossless.
+    /// Bounds that can not be converted at all are already reported during inference.
+    fn check_param_bounds(&mut self, param: ParamId) {
+        if !matches!(self.expr_stmt_ty, Some(Type::Integer)) {
+            return;
+        }
+        for bound in self.db.param_exprs(param).bounds.iter() {
+            let exprs = match bound.val {
+                ConstraintValue::Value(val) => [Some(val), None],
+                ConstraintValue::Range(range) => [Some(range.start), Some(range.end)],
+            };
+            for expr in exprs.into_iter().flatten() {
+                if let Expr::Literal(Literal::Float(val)) = self.body.exprs[expr] {
+                    if f64::from(val).fract() == 0.0 {
+                        continue;
+                    }
+                }
+                if let Some(Type::Real) = self.result.expr_types[expr].to_value() {
+                    self.result.diagnostics.push(InferenceDiagnostic::RealParamBound { expr });
+                }
+            }
+        }
+    }
+
     fn infere_assignment(&mut self, stmt: StmtId, val: ExprId, dst_ty: Option<Type>) {
         if let Some(val_ty) = self.infere_expr(stmt, val) {
             if let Some(value_ty) = val_ty.to_value() {
@@ -287,7 +328,7 @@ impl Ctx<'_> {
                 ScopeDefItem::BlockId(_) | ScopeDefItem::ModuleId(_) => Ty::Scope,
                 ScopeDefItem::NatureId(nature) => Ty::Nature(nature),
                 ScopeDefItem::DisciplineId(discipline) => Ty::Discipline(discipline),
-                ScopeDefItem::NodeId(node) => Ty::Node(node),
+                ScopeDefItem::NodeId(node) => Ty::Node(self.db.resolve_node_alias(node)),
                 ScopeDefItem::VarId(var) => Ty::Var(self.db.var_data(var).ty.clone(), var),
                 ScopeDefItem::ParamId(param) => Ty::Param(self.db.param_ty(param), param),
                 ScopeDefItem::AliasParamId(param) => match self.db.resolve_alias(param)? {
@@ -635,6 +676,11 @@ impl Ctx<'_> {
             | BuiltIn::error
             | BuiltIn::info
             | BuiltIn::fatal => self.infere_display(stmt, args),
+            // the first argument is the file descriptor
+            BuiltIn::fwrite | BuiltIn::fdisplay => self.infere_display(stmt, &args[1..]),
+            BuiltIn::noise_table | BuiltIn::noise_table_log => {
+                self.infere_noise_table(expr, builtin == BuiltIn::noise_table_log, args[0])
+            }
 
             _ => (),
         }
@@ -642,6 +688,53 @@ impl Ctx<'_> {
         (Some(ty), valid)
     }
 
+    fn infere_noise_table(&mut self, expr: ExprId, log: bool, table: ExprId) {
+        let from_file = matches!(
+            self.result.resolved_signatures.get(&expr),
+            Some(&(NOISE_TABLE_FILE | NOISE_TABLE_FILE_NAME))
+        );
+        let res = if from_file {
+            self.read_noise_table_file(table)
+        } else if let Expr::Array(ref entries) = self.body.exprs[table] {
+            inline_noise_table(self.body, entries)
+        } else {
+            Err(NoiseTableError::NonLiteral { expr: table })
+        };
+
+        match res.and_then(|vals| check_noise_table(&vals, log).map(|_| vals)) {
+            Ok(vals) => {
+                let vals = vals.into_iter().map(|(_, freq, pwr)| (freq.into(), pwr.into()));
+                self.result.noise_tables.insert(expr, vals.collect());
+            }
+            Err(err) => self.result.diagnostics.push(InferenceDiagnostic::InvalidNoiseTable {
+                expr: table,
+                err,
+                from_file,
+            }),
+        }
+    }
+
+    fn read_noise_table_file(
+        &self,
+        file_name: ExprId,
+    ) -> Result<Vec<(u32, f64, f64)>, NoiseTableError> {
+        let file_name = if let Expr::Literal(Literal::String(ref name)) = self.body.exprs[file_name]
+        {
+            name
+        } else {
+            return Err(NoiseTableError::NonLiteral { expr: file_name });
+        };
+        let path = self.db.file_path(self.root_file).parent().and_then(|dir| dir.join(file_name));
+        let file = if let Some(path) = path {
+            self.db.file_id(path)
+        } else {
+            let err = FileReadError::Io(io::ErrorKind::NotFound);
+            return Err(NoiseTableError::FileRead { err });
+        };
+        let src = self.db.file_text(file).map_err(|err| NoiseTableError::FileRead { err })?;
+        parse_noise_table(&src)
+    }
+
     fn check_display_dynamic_arg(&mut self, fmt_expr: ExprId, arg: Option<ExprId>, off: TextSize) {
         let arg = if let Some(arg) = arg {
             arg
@@ -1265,6 +1358,14 @@ pub enum InferenceDiagnostic {
         e: ExprId,
         stmt: StmtId,
     },
+    RealParamBound {
+        expr: ExprId,
+    },
+    InvalidNoiseTable {
+        expr: ExprId,
+        err: NoiseTableError,
+        from_file: bool,
+    },
 }
 
 impl_from!(TypeMismatch,SignatureMismatch, ArrayTypeMismatch for InferenceDiagnostic);
@@ -0,0 +1,101 @@
+use basedb::FileReadError;
+use hir_def::body::Body;
+use hir_def::expr::Literal;
+use hir_def::{Expr, ExprId};
+use syntax::ast::UnaryOp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoiseTableError {
+    /// the file name or an entry of an inline table is not a literal
+    NonLiteral {
+        expr: ExprId,
+    },
+    FileRead {
+        err: FileReadError,
+    },
+    /// a line of a table file (or a pair of an inline table) is not a pair of numbers
+    InvalidPair {
+        pos: u32,
+    },
+    /// frequencies must be strictly ascending
+    Unsorted {
+        pos: u32,
+    },
+    /// the logarithm of zero and negative numbers is undefined
+    NonPositive {
+        pos: u32,
+    },
+    Empty,
+}
+
+/// Parses the contents of a noise table file. Every line contains a frequency and the
+/// noise power at that frequency separated by whitespace. Empty lines are ignored and
+/// everything after a `#` is a comment.
+/// The position of each pair is the line number so errors point to the right line.
+pub(super) fn parse_noise_table(src: &str) -> Result<Vec<(u32, f64, f64)>, NoiseTableError> {
+    let mut table = Vec::new();
+    for (line, text) in src.lines().enumerate() {
+        let pos = line as u32 + 1;
+        let text = match text.split_once('#') {
+            Some((text, _comment)) => text,
+            None => text,
+        };
+        let mut vals = text.split_whitespace();
+        match (vals.next(), vals.next(), vals.next()) {
+            (None, _, _) => continue,
+            (Some(freq), Some(pwr), None) => match (freq.parse(), pwr.parse()) {
+                (Ok(freq), Ok(pwr)) => table.push((pos, freq, pwr)),
+                _ => return Err(NoiseTableError::InvalidPair { pos }),
+            },
+            _ => return Err(NoiseTableError::InvalidPair { pos }),
+        }
+    }
+    Ok(table)
+}
+
+/// Collects the pairs of an inline noise table `{f1, pwr1, f2, pwr2, ...}`.
+/// Only (negated) literals are supported as entries.
+pub(super) fn inline_noise_table(
+    body: &Body,
+    entries: &[ExprId],
+) -> Result<Vec<(u32, f64, f64)>, NoiseTableError> {
+    let mut table = Vec::with_capacity(entries.len() / 2);
+    for (i, pair) in entries.chunks(2).enumerate() {
+        let pos = i as u32 + 1;
+        let (freq, pwr) = match *pair {
+            [freq, pwr] => (freq, pwr),
+            _ => return Err(NoiseTableError::InvalidPair { pos }),
+        };
+        table.push((pos, literal_val(body, freq)?, literal_val(body, pwr)?))
+    }
+    Ok(table)
+}
+
+fn literal_val(body: &Body, expr: ExprId) -> Result<f64, NoiseTableError> {
+    match body.exprs[expr] {
+        Expr::Literal(Literal::Int(val)) => Ok(val.into()),
+        Expr::Literal(Literal::Float(val)) => Ok(val.into()),
+        Expr::UnaryOp { expr: arg, op: UnaryOp::Neg } => literal_val(body, arg).map(|val| -val),
+        _ => Err(NoiseTableError::NonLiteral { expr }),
+    }
+}
+
+/// Checks that the frequencies of `table` are strictly ascending and (for
+/// `noise_table_log`) that all values have a logarithm.
+pub(super) fn check_noise_table(
+    table: &[(u32, f64, f64)],
+    log: bool,
+) -> Result<(), NoiseTableError> {
+    if table.is_empty() {
+        return Err(NoiseTableError::Empty);
+    }
+    for (i, &(pos, freq, pwr)) in table.iter().enumerate() {
+        if log && (freq <= 0.0 || pwr <= 0.0) {
+            return Err(NoiseTableError::NonPositive { pos });
+        }
+        if i != 0 && table[i - 1].1 >= freq {
+            return Err(NoiseTableError::Unsorted { pos });
+        }
+    }
+    Ok(())
+}
@@ -6,4 +6,4 @@ pub mod lower;
 pub mod types;
 pub mod validation;
 
-pub use lower::{BranchTy, DisciplineTy, NatureTy};
+pub use lower::{BranchTy, DisciplineTy, NatureTy, TimeOperator};
@@ -6,10 +6,34 @@ use hir_def::{
     BranchId, DisciplineId, Intern, Lookup, NatureAttrId, NatureAttrLoc, NatureId, NatureRef,
     NatureRefKind, NodeId,
 };
+use stdx::impl_display;
 use syntax::name::{kw, Name};
 
 use crate::db::HirTyDB;
 
+/// The analog operators that change the nature of their argument
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TimeOperator {
+    Ddt,
+    Idt,
+}
+
+impl TimeOperator {
+    pub fn inverse(self) -> TimeOperator {
+        match self {
+            TimeOperator::Ddt => TimeOperator::Idt,
+            TimeOperator::Idt => TimeOperator::Ddt,
+        }
+    }
+}
+
+impl_display! {
+    match TimeOperator{
+        TimeOperator::Ddt => "ddt";
+        TimeOperator::Idt => "idt";
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NatureTy {
     pub ddt_nature: NatureId,
@@ -82,6 +106,37 @@ impl NatureTy {
         nature1_info.base_nature == nature2_info.base_nature
     }
 
+    /// Returns the nature of `ddt(x)`/`idt(x)` for a quantity `x` of nature `nature`.
+    /// For example the time derivative of a `Charge` is a `Current`. Natures that
+    /// don't declare (or inherit) a `ddt_nature`/`idt_nature` are returned unchanged.
+    pub fn apply_time_operator(db: &dyn HirTyDB, nature: NatureId, op: TimeOperator) -> NatureId {
+        let info = db.nature_info(nature);
+        match op {
+            TimeOperator::Ddt => info.ddt_nature,
+            TimeOperator::Idt => info.idt_nature,
+        }
+    }
+
+    /// Checks that applying `op` and its inverse leads back to `nature`
+    /// (`idt(ddt(x))` must have the nature of `x`). Returns the nature that is reached
+    /// instead if the chain is broken. Natures without a relationship are always valid.
+    pub fn check_time_operator(
+        db: &dyn HirTyDB,
+        nature: NatureId,
+        op: TimeOperator,
+    ) -> Result<(), NatureId> {
+        let res = NatureTy::apply_time_operator(db, nature, op);
+        if res == nature {
+            return Ok(());
+        }
+        let reached = NatureTy::apply_time_operator(db, res, op.inverse());
+        if reached == res || NatureTy::related(db, reached, nature) {
+            Ok(())
+        } else {
+            Err(reached)
+        }
+    }
+
     pub fn lookup_attr(
         db: &dyn HirTyDB,
         nature: NatureId,
@@ -255,12 +310,13 @@ impl BranchTy {
                 BranchKind::PortFlow(scope.resolve_item_path(db.upcast(), port).ok()?)
             }
             hir_def::BranchKind::NodeGnd(node) => {
-                BranchKind::NodeGnd(scope.resolve_item_path(db.upcast(), node).ok()?)
+                let node = scope.resolve_item_path(db.upcast(), node).ok()?;
+                BranchKind::NodeGnd(db.resolve_node_alias(node))
             }
             hir_def::BranchKind::Nodes(node1, node2) => {
                 let node1 = scope.resolve_item_path(db.upcast(), node1).ok()?;
                 let node2 = scope.resolve_item_path(db.upcast(), node2).ok()?;
-                BranchKind::Nodes(node1, node2)
+                BranchKind::Nodes(db.resolve_node_alias(node1), db.resolve_node_alias(node2))
             }
             hir_def::BranchKind::Missing => return None,
         };
@@ -1,12 +1,17 @@
 use basedb::diagnostics::{Diagnostic, Label, LabelStyle, Report};
-use basedb::lints::builtin::{const_simparam, trivial_probe, variant_const_simparam};
+use basedb::lints::builtin::{
+    const_simparam, display_every_iteration, file_io_every_iteration, integer_division,
+    mixed_branch_contribution, no_contributions, random_analog_operator_arg, shadowed_builtin,
+    trivial_probe, uninitialized_output_arg, variant_const_simparam,
+};
 use basedb::lints::{self, Lint, LintSrc};
 use basedb::{AstIdMap, BaseDB, FileId};
 pub use body::BodyValidationDiagnostic;
 use hir_def::body::BodySourceMap;
+use hir_def::nameres::ScopeDefItem;
 use hir_def::{
-    DisciplineAttr, ExprId, ItemLoc, ItemTree, ItemTreeNode, Lookup, NatureAttr, NodeId,
-    NodeTypeDecl,
+    DisciplineAttr, ExprId, FunctionArgLoc, ItemLoc, ItemTree, ItemTreeNode, Lookup, NatureAttr,
+    NodeId, NodeTypeDecl,
 };
 use syntax::name::Name;
 use syntax::sourcemap::{FileSpan, SourceMap};
@@ -15,6 +20,7 @@ pub use types::TypeValidationDiagnostic;
 
 use crate::db::HirTyDB;
 use crate::inference::BranchWrite;
+use crate::lower::TimeOperator;
 use crate::validation::body::{BodyCtx, IllegalCtxAccess, IllegalCtxAccessKind};
 use crate::validation::types::DuplicateItem;
 
@@ -103,6 +109,22 @@ impl BodyValidationDiagnosticWrapped<'_> {
         let src = loc.ast_ptr(self.db.upcast()).range();
         (loc.name(self.db.upcast()), self.parse.to_file_span(src, self.sm))
     }
+
+    fn branch_name(&self, branch: BranchWrite) -> String {
+        let db = self.db.upcast();
+        match branch {
+            BranchWrite::Named(branch) => {
+                let branch = branch.lookup(db).name(db);
+                branch.to_string()
+            }
+            BranchWrite::Unnamed { hi, lo: Some(lo) } => {
+                format!("({}, {})", db.node_data(hi).name, db.node_data(lo).name)
+            }
+            BranchWrite::Unnamed { hi, lo: None } => {
+                format!("({})", db.node_data(hi).name)
+            }
+        }
+    }
 }
 
 impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
@@ -130,6 +152,34 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                 let src = self.body_sm.lint_src(stmt, trivial_probe);
                 Some((trivial_probe, src))
             }
+            BodyValidationDiagnostic::UninitializedOutputArg { fun, .. } => {
+                let decl = fun.lookup(self.db.upcast()).ast_id(self.db.upcast());
+                Some((uninitialized_output_arg, LintSrc::item(decl.into())))
+            }
+            BodyValidationDiagnostic::FileIoEveryIteration { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, file_io_every_iteration);
+                Some((file_io_every_iteration, src))
+            }
+            BodyValidationDiagnostic::IntegerDivision { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, integer_division);
+                Some((integer_division, src))
+            }
+            BodyValidationDiagnostic::DisplayEveryIteration { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, display_every_iteration);
+                Some((display_every_iteration, src))
+            }
+            BodyValidationDiagnostic::RandomAnalogOperatorArg { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, random_analog_operator_arg);
+                Some((random_analog_operator_arg, src))
+            }
+            BodyValidationDiagnostic::MixedBranchContribution { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, mixed_branch_contribution);
+                Some((mixed_branch_contribution, src))
+            }
+            BodyValidationDiagnostic::NoContributions { module } => {
+                let decl = module.lookup(self.db.upcast()).ast_id(self.db.upcast());
+                Some((no_contributions, LintSrc::item(decl.into())))
+            }
             _ => None,
         }
     }
@@ -213,18 +263,38 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                     self.sm,
                 );
 
-                Report::error()
-                    .with_message(format!("branch contributions are not allowed in {}", ctx))
-                    .with_labels(vec![Label {
-                        style: LabelStyle::Secondary,
-                        file_id: file,
-                        range: range.into(),
-                        message: "not allowed here".to_owned(),
-                    }])
-                    .with_notes(vec![
-                        "help: branch contributions are only allowed in module-level analog blocks"
-                            .to_owned(),
-                    ])
+                if ctx == BodyCtx::AnalogInitialBlock {
+                    Report::error()
+                        .with_message(
+                            "branch contributions are not allowed in analog initial blocks",
+                        )
+                        .with_labels(vec![Label {
+                            style: LabelStyle::Primary,
+                            file_id: file,
+                            range: range.into(),
+                            message: "only executed once before the simulation".to_owned(),
+                        }])
+                        .with_notes(vec![
+                            "info: analog initial blocks are not part of the simulated equations, \
+                             the contribution would have no effect"
+                                .to_owned(),
+                            "help: move the contribution into an analog block".to_owned(),
+                        ])
+                } else {
+                    Report::error()
+                        .with_message(format!("branch contributions are not allowed in {}", ctx))
+                        .with_labels(vec![Label {
+                            style: LabelStyle::Secondary,
+                            file_id: file,
+                            range: range.into(),
+                            message: "not allowed here".to_owned(),
+                        }])
+                        .with_notes(vec![
+                            "help: branch contributions are only allowed in module-level analog \
+                             blocks"
+                                .to_owned(),
+                        ])
+                }
             }
             BodyValidationDiagnostic::WriteToInputArg { expr, arg } => {
                 let FileSpan { range, file } = self.expr_src(expr);
@@ -248,6 +318,29 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                     }])
                     .with_notes(vec![format!("help: change direction of '{}' to inout", arg_name)])
             }
+            BodyValidationDiagnostic::UninitializedOutputArg { fun, arg } => {
+                let arg = FunctionArgLoc { fun, id: arg };
+                let arg_name = arg.name(self.db.upcast());
+                let fun_name = self.db.function_data(fun).name.clone();
+                let arg_src = arg.ast_ptr(self.db.upcast()).range();
+                let FileSpan { range, file } = self.parse.to_file_span(arg_src, self.sm);
+
+                Report::warning()
+                    .with_message(format!(
+                        "output argument '{}' of function '{}' is not assigned on all paths",
+                        arg_name, fun_name
+                    ))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "possibly uninitialized".to_owned(),
+                    }])
+                    .with_notes(vec![format!(
+                        "help: the caller receives an undefined value if '{}' is not assigned",
+                        arg_name
+                    )])
+            }
             BodyValidationDiagnostic::IllegalParamAccess { def, expr, param } => {
                 let FileSpan { range, file } = self.expr_src(expr);
                 let (def_name, def_src) = self.lookup(def);
@@ -397,6 +490,137 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
 
                 res
             }
+            BodyValidationDiagnostic::UnresolvedNodeAlias { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message("node alias could not be resolved".to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "unresolved node alias".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: OpenVAF resolves node aliases during compilation so the call must be executed unconditionally\nthe first argument must be an internal node and the second argument the name of another node in the same module".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::FileIoEveryIteration { expr, func, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message(format!("call to '${func:?}' is executed during every iteration"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "file access outside of an event".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: move the file access into an @(initial_step) block".to_owned()
+                    ])
+            }
+            BodyValidationDiagnostic::IntegerDivision { expr, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message("integer division truncates the result")
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "the truncated result is converted to real".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: convert an operand to real (for example 1.0/2) for real division"
+                            .to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::DisplayEveryIteration { expr, func, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message(format!("call to '${func:?}' is executed during every iteration"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "output outside of an event".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: move the call into an @(initial_step) or @(final_step) block"
+                            .to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::RandomAnalogOperatorArg {
+                arg,
+                func,
+                random: random_fn,
+                ..
+            } => {
+                let FileSpan { range, file } = self.expr_src(arg);
+                Report::warning()
+                    .with_message(format!("argument of '{func:?}' is a random value"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: format!("'${random_fn:?}' is not continuous"),
+                    }])
+                    .with_notes(vec![
+                        "help: analog operators expect an argument that is continuous in time"
+                            .to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::MixedBranchContribution { branch, first, second, .. } => {
+                let first = self.expr_src(first);
+                let second = self.expr_src(second);
+                let branch_name = self.branch_name(branch);
+                Report::warning()
+                    .with_message(format!(
+                        "unconditional contributions to both the potential and the flow of branch \
+                         {branch_name}"
+                    ))
+                    .with_labels(vec![
+                        Label {
+                            style: LabelStyle::Primary,
+                            file_id: second.file,
+                            range: second.range.into(),
+                            message: "contribution of the opposite nature".to_owned(),
+                        },
+                        Label {
+                            style: LabelStyle::Secondary,
+                            file_id: first.file,
+                            range: first.range.into(),
+                            message: "first contributed here".to_owned(),
+                        },
+                    ])
+                    .with_notes(vec![
+                        "info: a branch can't be a voltage and a current source at the same time"
+                            .to_owned(),
+                        "help: only the last contribution takes effect, use a condition to switch \
+                         between them"
+                            .to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::NoContributions { module } => {
+                let name = &self.db.module_data(module).name;
+                let range = ScopeDefItem::ModuleId(module)
+                    .text_range(self.db.upcast(), self.map, self.parse)
+                    .unwrap();
+                let FileSpan { range, file } = self.parse.to_file_span(range, self.sm);
+                Report::warning()
+                    .with_message(format!("module '{}' has no contributions", name))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "behaves like an open circuit".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "info: the analog block neither contributes to a branch nor introduces \
+                         an implicit equation"
+                            .to_owned(),
+                        "help: add a contribution (like I(a, c) <+ V(a, c) / r) to the analog block"
+                            .to_owned(),
+                    ])
+            }
             BodyValidationDiagnostic::IncompatibleNatureAccess {
                 ref candidates,
                 access_nature,
@@ -468,19 +692,7 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
             }
             BodyValidationDiagnostic::TrivialBranchAccess { branch, expr, .. } => {
                 let FileSpan { range, file } = self.expr_src(expr);
-                let db = self.db.upcast();
-                let branch_name = match branch {
-                    BranchWrite::Named(branch) => {
-                        let branch = branch.lookup(db).name(db);
-                        branch.to_string()
-                    }
-                    BranchWrite::Unnamed { hi, lo: Some(lo) } => {
-                        format!("({}, {})", db.node_data(hi).name, db.node_data(lo).name)
-                    }
-                    BranchWrite::Unnamed { hi, lo: None } => {
-                        format!("({})", db.node_data(hi).name)
-                    }
-                };
+                let branch_name = self.branch_name(branch);
                 let branch_probe = match branch {
                     BranchWrite::Named(_) => &branch_name,
                     BranchWrite::Unnamed { .. } => &branch_name[1..branch_name.len() - 1],
@@ -714,6 +926,63 @@ impl Diagnostic for TypeValidationDiagnosticWrapped<'_> {
                 }
                 .into_report(self.db, self.parse, self.map, self.sm)
             }
+            TypeValidationDiagnostic::ShadowedBuiltin { item, decl, ref name, builtin } => {
+                let range = item
+                    .text_range(self.db.upcast(), self.map, self.parse)
+                    .unwrap_or_else(|| self.map.get_syntax(decl).range());
+                let src = self.parse.to_file_span(range, self.sm);
+                let builtin_kind = builtin.item_kind();
+
+                Report::warning()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!("shadows {builtin_kind}"),
+                    }])
+                    .with_message(format!(
+                        "{} '{name}' shadows the {builtin_kind} '{name}'",
+                        item.item_kind()
+                    ))
+                    .with_notes(vec![format!(
+                        "help: rename '{name}' to avoid ambiguity with the {builtin_kind}"
+                    )])
+            }
+            TypeValidationDiagnostic::InconsistentTimeNature { nature, op, res, reached } => {
+                let item = &self.item_tree[nature.lookup(self.db.upcast()).id];
+                let attr = match op {
+                    TimeOperator::Ddt => &item.ddt_nature,
+                    TimeOperator::Idt => &item.idt_nature,
+                };
+                let attr = attr.as_ref().unwrap().1;
+                let id = u32::from(item.attrs.start()) + u32::from(attr);
+                let id = NatureAttr::lookup(self.item_tree, id.into()).ast_id();
+                let src = self.parse.to_file_span(self.map.get(id).range(), self.sm);
+
+                let name = |nature| self.db.nature_data(nature).name.clone();
+                let inv = op.inverse();
+                Report::warning()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!(
+                            "{op}_nature '{}' has {inv}_nature '{}'",
+                            name(res),
+                            name(reached)
+                        ),
+                    }])
+                    .with_message(format!(
+                        "inconsistent natures: {inv}({op}(x)) of nature '{}' has nature '{}'",
+                        name(nature),
+                        name(reached)
+                    ))
+                    .with_notes(vec![format!(
+                        "help: {inv}_nature of '{}' should be '{}'",
+                        name(res),
+                        name(nature)
+                    )])
+            }
         }
     }
 
@@ -722,6 +991,9 @@ impl Diagnostic for TypeValidationDiagnosticWrapped<'_> {
             TypeValidationDiagnostic::PortWithoutDirection { decl, .. } => {
                 Some((lints::builtin::port_without_direction, LintSrc::item(decl)))
             }
+            TypeValidationDiagnostic::ShadowedBuiltin { decl, .. } => {
+                Some((shadowed_builtin, LintSrc::item(decl)))
+            }
             _ => None,
         }
     }
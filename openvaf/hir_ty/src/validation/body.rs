@@ -2,12 +2,14 @@ use std::mem::replace;
 
 use ahash::{HashMap, HashSet};
 use hir_def::body::Body;
+use hir_def::expr::CaseCond;
 use hir_def::{
-    BranchId, BuiltIn, DefWithBodyId, DisciplineId, Expr, ExprId, FunctionArgLoc, Literal, Lookup,
-    NatureId, NodeId, ParamId, Path, Stmt, StmtId, VarId,
+    BranchId, BuiltIn, DefWithBodyId, DisciplineId, Expr, ExprId, FunctionArgLoc, FunctionId,
+    Literal, LocalFunctionArgId, Lookup, ModuleId, NatureId, NodeId, ParamId, Path, Stmt, StmtId,
+    Type, VarId,
 };
 use stdx::impl_display;
-use syntax::ast::AssignOp;
+use syntax::ast::{AssignOp, BinaryOp};
 use syntax::name::{AsIdent, Name};
 
 use crate::builtin::{
@@ -16,7 +18,7 @@ use crate::builtin::{
     TRANSITION_DELAY_RISET_FALLT_TOL,
 };
 use crate::db::HirTyDB;
-use crate::inference::{BranchWrite, InferenceResult, ResolvedFun};
+use crate::inference::{AssignDst, BranchWrite, InferenceResult, ResolvedFun};
 use crate::lower::BranchKind;
 use crate::types::{Signature, Ty};
 
@@ -60,6 +62,11 @@ pub enum BodyValidationDiagnostic {
         arg: FunctionArgLoc,
     },
 
+    UninitializedOutputArg {
+        fun: FunctionId,
+        arg: LocalFunctionArgId,
+    },
+
     IllegalParamAccess {
         def: ParamId,
         expr: ExprId,
@@ -79,6 +86,46 @@ pub enum BodyValidationDiagnostic {
         func: BuiltIn,
     },
 
+    UnresolvedNodeAlias {
+        expr: ExprId,
+    },
+
+    FileIoEveryIteration {
+        expr: ExprId,
+        stmt: StmtId,
+        func: BuiltIn,
+    },
+
+    IntegerDivision {
+        expr: ExprId,
+        stmt: StmtId,
+    },
+
+    DisplayEveryIteration {
+        expr: ExprId,
+        stmt: StmtId,
+        func: BuiltIn,
+    },
+
+    RandomAnalogOperatorArg {
+        arg: ExprId,
+        stmt: StmtId,
+        func: BuiltIn,
+        random: BuiltIn,
+    },
+
+    NoContributions {
+        module: ModuleId,
+    },
+
+    MixedBranchContribution {
+        branch: BranchWrite,
+        /// the earlier contribution (with the opposite nature)
+        first: ExprId,
+        second: ExprId,
+        stmt: StmtId,
+    },
+
     IncompatibleNatureAccess {
         candidates: [Option<(Name, Name)>; 2],
         access_nature: Option<NatureId>,
@@ -120,6 +167,9 @@ impl BodyValidationDiagnostic {
             non_const_dominator: Box::default(),
             non_trivial_branches: HashSet::default(),
             trivial_probes: HashMap::default(),
+            conditional: false,
+            unconditional_contributions: HashMap::default(),
+            contributes: false,
         };
 
         for stmt in &*body.entry_stmts {
@@ -136,10 +186,125 @@ impl BodyValidationDiagnostic {
             }
         }
 
+        // a module with ports but without any equations behaves like an open circuit,
+        // modules without ports (test benches) or without an analog block are not checked
+        if let DefWithBodyId::ModuleId { initial: false, module } = def {
+            if !validator.contributes
+                && !body.entry_stmts.is_empty()
+                && !db.module_data(module).ports.is_empty()
+            {
+                validator.diagnostics.push(BodyValidationDiagnostic::NoContributions { module })
+            }
+        }
+
+        if let DefWithBodyId::FunctionId(fun) = def {
+            let mut assigned = HashSet::default();
+            let visitor = OutputArgVisitor { db, body: &body, infer: &infere };
+            for stmt in &*body.entry_stmts {
+                visitor.visit_stmt(*stmt, &mut assigned)
+            }
+
+            for (arg, data) in db.function_data(fun).args.iter_enumerated() {
+                if data.is_output && !assigned.contains(&arg) {
+                    validator
+                        .diagnostics
+                        .push(BodyValidationDiagnostic::UninitializedOutputArg { fun, arg })
+                }
+            }
+        }
+
         validator.diagnostics
     }
 }
 
+/// Determines which output arguments of a function are assigned on all paths.
+struct OutputArgVisitor<'a> {
+    db: &'a dyn HirTyDB,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+}
+
+impl OutputArgVisitor<'_> {
+    fn visit_stmt(&self, stmt: StmtId, assigned: &mut HashSet<LocalFunctionArgId>) {
+        match self.body.stmts[stmt] {
+            Stmt::Missing | Stmt::Empty | Stmt::EventControl { .. } => (),
+            Stmt::Expr(expr) => self.visit_expr(expr, assigned),
+            Stmt::Assignment { val, .. } => {
+                self.visit_expr(val, assigned);
+                if let Some(AssignDst::FunVar { arg: Some(arg), .. }) =
+                    self.infer.assignment_destination.get(&stmt)
+                {
+                    assigned.insert(*arg);
+                }
+            }
+            Stmt::Block { ref body } => {
+                body.iter().for_each(|stmt| self.visit_stmt(*stmt, assigned));
+            }
+            Stmt::If { cond, then_branch, else_branch } => {
+                self.visit_expr(cond, assigned);
+                let mut then_assigned = assigned.clone();
+                self.visit_stmt(then_branch, &mut then_assigned);
+                self.visit_stmt(else_branch, assigned);
+                assigned.retain(|arg| then_assigned.contains(arg));
+            }
+            // the loop body may never execute
+            Stmt::ForLoop { init, cond, .. } => {
+                self.visit_stmt(init, assigned);
+                self.visit_expr(cond, assigned);
+            }
+            Stmt::WhileLoop { cond, .. } => self.visit_expr(cond, assigned),
+            Stmt::Case { discr, ref case_arms } => {
+                self.visit_expr(discr, assigned);
+                // without a default arm none of the arms may execute
+                if !case_arms.iter().any(|arm| arm.cond == CaseCond::Default) {
+                    return;
+                }
+
+                let mut all_arms: Option<HashSet<LocalFunctionArgId>> = None;
+                for arm in case_arms {
+                    let mut arm_assigned = assigned.clone();
+                    self.visit_stmt(arm.body, &mut arm_assigned);
+                    if let Some(all_arms) = &mut all_arms {
+                        all_arms.retain(|arg| arm_assigned.contains(arg));
+                    } else {
+                        all_arms = Some(arm_assigned);
+                    }
+                }
+                *assigned = all_arms.unwrap();
+            }
+        }
+    }
+
+    fn visit_expr(&self, expr: ExprId, assigned: &mut HashSet<LocalFunctionArgId>) {
+        match self.body.exprs[expr] {
+            // only the condition is evaluated unconditionally
+            Expr::Select { cond, .. }
+            | Expr::BinaryOp {
+                lhs: cond,
+                op: Some(BinaryOp::BooleanAnd | BinaryOp::BooleanOr),
+                ..
+            } => self.visit_expr(cond, assigned),
+            Expr::Call { ref args, .. } => {
+                args.iter().for_each(|arg| self.visit_expr(*arg, assigned));
+                if let Some(ResolvedFun::User { func, limit: false }) =
+                    self.infer.resolved_calls.get(&expr)
+                {
+                    let params = &self.db.function_data(*func).args;
+                    for (param, arg) in params.iter().zip(args) {
+                        if let Ty::FunctionVar { arg: Some(arg), .. } = self.infer.expr_types[*arg]
+                        {
+                            if param.is_output {
+                                assigned.insert(arg);
+                            }
+                        }
+                    }
+                }
+            }
+            ref expr => expr.walk_child_exprs(|expr| self.visit_expr(expr, assigned)),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum BodyCtx {
     AnalogBlock,
@@ -195,6 +360,13 @@ struct BodyValidator<'a> {
     non_const_dominator: Box<[ExprId]>,
     non_trivial_branches: HashSet<BranchWrite>,
     trivial_probes: HashMap<BranchWrite, Vec<(StmtId, ExprId)>>,
+    /// whether the current statement is (potentially) skipped by a condition, loop or event
+    conditional: bool,
+    /// the first unconditional contribution to the flow (0) and potential (1) of each branch
+    unconditional_contributions: HashMap<BranchWrite, [Option<ExprId>; 2]>,
+    /// whether the body contains a contribution or an operator that introduces an implicit
+    /// equation (`idt`/`idtmod`)
+    contributes: bool,
 }
 
 impl BodyValidator<'_> {
@@ -203,6 +375,9 @@ impl BodyValidator<'_> {
             Stmt::Assignment { dst, val, assignment_kind } => {
                 self.validate_expr(val, stmt);
 
+                if assignment_kind == AssignOp::Contribute {
+                    self.contributes = true;
+                }
                 if assignment_kind == AssignOp::Contribute && !self.ctx.allow_contribute() {
                     self.diagnostics
                         .push(BodyValidationDiagnostic::IllegalContribute { stmt, ctx: self.ctx })
@@ -210,13 +385,18 @@ impl BodyValidator<'_> {
                 // avoid duplicate errors
                 else if self.infer.assignment_destination.contains_key(&stmt) {
                     self.validate_assignment_dst(dst, stmt);
+                    if assignment_kind == AssignOp::Contribute && !self.conditional {
+                        self.check_mixed_contribution(dst, stmt);
+                    }
                 }
 
                 return;
             }
             Stmt::EventControl { body, .. } => {
                 let old = replace(&mut self.ctx, BodyCtx::EventControl);
+                let conditional = replace(&mut self.conditional, true);
                 self.validate_stmt(body);
+                self.conditional = conditional;
                 self.ctx = old;
                 return;
             }
@@ -239,10 +419,36 @@ impl BodyValidator<'_> {
         };
 
         self.validate_condition(cond, stmt, |s| {
-            s.body.stmts[stmt].walk_child_stmts(|stmt| s.validate_stmt(stmt))
+            let conditional = replace(&mut s.conditional, true);
+            s.body.stmts[stmt].walk_child_stmts(|stmt| s.validate_stmt(stmt));
+            s.conditional = conditional;
         });
     }
 
+    /// Contributing to both the potential and the flow of a branch only makes sense
+    /// if the contributions are conditional (switch branches).
+    fn check_mixed_contribution(&mut self, dst: ExprId, stmt: StmtId) {
+        let (branch, is_pot) = match self.infer.assignment_destination[&stmt] {
+            AssignDst::Flow(branch) => (branch, false),
+            AssignDst::Potential(branch) => (branch, true),
+            _ => return,
+        };
+
+        let contributions = self.unconditional_contributions.entry(branch).or_default();
+        if contributions[is_pot as usize].is_some() {
+            return;
+        }
+        contributions[is_pot as usize] = Some(dst);
+        if let Some(first) = contributions[!is_pot as usize] {
+            self.diagnostics.push(BodyValidationDiagnostic::MixedBranchContribution {
+                branch,
+                first,
+                second: dst,
+                stmt,
+            })
+        }
+    }
+
     fn validate_condition(
         &mut self,
         cond: ExprId,
@@ -533,6 +739,16 @@ impl ExprValidator<'_, '_> {
                 return;
             }
 
+            // integer division truncates which is usually not intended if the result is
+            // converted to a real number afterwards
+            Expr::BinaryOp { op: Some(BinaryOp::Division), .. }
+                if !matches!(self.parent.ctx, BodyCtx::Const | BodyCtx::ConstOrAnalysis)
+                    && self.parent.infer.expr_types[expr].to_value() == Some(Type::Integer)
+                    && self.parent.infer.casts.get(&expr) == Some(&Type::Real) =>
+            {
+                self.report(BodyValidationDiagnostic::IntegerDivision { expr, stmt: self.stmt })
+            }
+
             _ => (),
         }
 
@@ -547,11 +763,27 @@ impl ExprValidator<'_, '_> {
         call: BuiltIn,
         signature: Option<Signature>,
     ) {
+        if matches!(call, BuiltIn::idt | BuiltIn::idtmod) {
+            self.parent.contributes = true;
+        }
         match call {
             _ if call.is_unsupported() => self
                 .parent
                 .diagnostics
                 .push(BodyValidationDiagnostic::UnsupportedFunction { expr, func: call }),
+            BuiltIn::analog_node_alias => {
+                let resolved = match self.parent.owner {
+                    DefWithBodyId::ModuleId { initial, module } => {
+                        self.parent.db.node_aliases(module).calls.contains(&(initial, expr))
+                    }
+                    _ => false,
+                };
+                if !resolved {
+                    self.parent
+                        .diagnostics
+                        .push(BodyValidationDiagnostic::UnresolvedNodeAlias { expr })
+                }
+            }
             BuiltIn::potential | BuiltIn::flow => self.check_access(
                 |_| IllegalCtxAccessKind::NatureAccess,
                 expr,
@@ -588,6 +820,25 @@ impl ExprValidator<'_, '_> {
             _ => (),
         }
 
+        // analog operators expect an argument that is continuous in time, a random
+        // value is drawn anew during every evaluation (heuristic: only direct calls)
+        if call.is_analog_operator() {
+            if let Some(&arg) = args.first() {
+                if let Some(ResolvedFun::BuiltIn(random)) =
+                    self.parent.infer.resolved_calls.get(&arg)
+                {
+                    if random.is_random() {
+                        self.report(BodyValidationDiagnostic::RandomAnalogOperatorArg {
+                            arg,
+                            stmt: self.stmt,
+                            func: call,
+                            random: *random,
+                        })
+                    }
+                }
+            }
+        }
+
         match (call, signature) {
             (BuiltIn::potential | BuiltIn::flow, Some(NATURE_ACCESS_NODES)) => {
                 let hi = self.parent.infer.expr_types[args[0]].unwrap_node();
@@ -715,6 +966,30 @@ impl ExprValidator<'_, '_> {
                 }
             }
 
+            // unconditional file operations outside of events are repeated during every newton
+            // iteration
+            (BuiltIn::fopen | BuiltIn::fwrite | BuiltIn::fdisplay | BuiltIn::fclose, _)
+                if self.parent.ctx == BodyCtx::AnalogBlock && !self.parent.conditional =>
+            {
+                self.report(BodyValidationDiagnostic::FileIoEveryIteration {
+                    expr,
+                    stmt: self.stmt,
+                    func: call,
+                })
+            }
+
+            // $debug is intended to print during every iteration, the other display
+            // functions usually only make sense in an event (or behind a condition)
+            (BuiltIn::display | BuiltIn::strobe | BuiltIn::write | BuiltIn::monitor, _)
+                if self.parent.ctx == BodyCtx::AnalogBlock && !self.parent.conditional =>
+            {
+                self.report(BodyValidationDiagnostic::DisplayEveryIteration {
+                    expr,
+                    stmt: self.stmt,
+                    func: call,
+                })
+            }
+
             (BuiltIn::absdelay, Some(ABSDELAY_MAX))
             | (BuiltIn::transition, Some(TRANSITION_DELAY_RISET_FALLT_TOL))
             | (BuiltIn::ddt, Some(DDT_TOL))
@@ -1,8 +1,9 @@
 use std::iter::once;
+use std::sync::Arc;
 
 use basedb::{AstId, ErasedAstId, FileId};
 use hir_def::nameres::diagnostics::PathResolveError;
-use hir_def::nameres::{DefMap, ScopeDefItem};
+use hir_def::nameres::{DefMap, NatureAccess, ScopeDefItem};
 use hir_def::{
     AliasParamId, Branch, BranchId, BranchKind, DisciplineId, ItemLoc, ItemTree,
     LocalDisciplineAttrId, LocalNatureAttrId, Lookup, ModuleId, ModuleLoc, NatureId, NodeId,
@@ -14,6 +15,7 @@ use syntax::{ast, AstNode, SyntaxNodePtr};
 use typed_index_collections::TiSlice;
 
 use crate::db::HirTyDB;
+use crate::lower::{DisciplineTy, NatureTy, TimeOperator};
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct DuplicateItem<Item, Def> {
@@ -34,6 +36,8 @@ pub enum TypeValidationDiagnostic {
     NodeWithoutDiscipline { decl: ErasedAstId, name: Name },
     ExpectedPort { node: NodeId, src: ErasedAstId },
     IncompatibleBranch { branch: BranchId, node1: NodeId, node2: NodeId },
+    ShadowedBuiltin { item: ScopeDefItem, decl: ErasedAstId, name: Name, builtin: ScopeDefItem },
+    InconsistentTimeNature { nature: NatureId, op: TimeOperator, res: NatureId, reached: NatureId },
 }
 
 impl TypeValidationDiagnostic {
@@ -73,16 +77,67 @@ impl TypeValidationCtx<'_> {
     fn verify_module(&mut self, module: ModuleId) {
         let loc = module.lookup(self.db.upcast());
         let scope = loc.scope.local_scope;
-        for item in self.def_map[scope].declarations.values() {
+
+        // only the access functions of natures used by the nodes of this module can collide
+        let disciplines: Vec<_> = self.def_map[scope]
+            .declarations
+            .values()
+            .filter_map(|item| match item {
+                ScopeDefItem::NodeId(node) => self.db.node_discipline(*node),
+                _ => None,
+            })
+            .map(|discipline| self.db.discipline_info(discipline))
+            .collect();
+
+        for (name, item) in self.def_map[scope].declarations.iter() {
+            self.verify_not_shadowing(name, *item, &disciplines);
             match item {
                 ScopeDefItem::NodeId(node) => self.verify_node(*node, loc),
                 ScopeDefItem::BranchId(branch) => self.verify_branch(*branch),
                 ScopeDefItem::AliasParamId(alias) => self.verify_alias(*alias),
+                ScopeDefItem::FunctionId(fun) => {
+                    let def_map = self.db.function_def_map(*fun);
+                    for (name, item) in def_map[def_map.entry()].declarations.iter() {
+                        // the implicit return variable is already covered by the function itself
+                        if !matches!(item, ScopeDefItem::FunctionReturn(_)) {
+                            self.verify_not_shadowing(name, *item, &disciplines);
+                        }
+                    }
+                }
                 _ => (),
             }
         }
     }
 
+    /// Check that a declaration inside a module does not shadow a nature access function (like
+    /// `V` or `I`) that could be used to access the nodes of that module.
+    fn verify_not_shadowing(
+        &mut self,
+        name: &Name,
+        item: ScopeDefItem,
+        disciplines: &[Arc<DisciplineTy>],
+    ) {
+        let root = &self.def_map[self.def_map.root()];
+        if let Some(&ScopeDefItem::NatureAccess(NatureAccess(attr))) = root.declarations.get(name) {
+            let nature = attr.lookup(self.db.upcast()).nature;
+            if disciplines.iter().any(|discipline| discipline.access(nature, self.db).is_some()) {
+                self.report_shadowed(item, name, ScopeDefItem::NatureAccess(NatureAccess(attr)))
+            }
+        }
+    }
+
+    fn report_shadowed(&mut self, item: ScopeDefItem, name: &Name, builtin: ScopeDefItem) {
+        // builtin items that are implicitly inserted into scopes have no declaration
+        if let Some(decl) = item.ast_id(self.db.upcast()) {
+            self.report(TypeValidationDiagnostic::ShadowedBuiltin {
+                item,
+                decl,
+                name: name.clone(),
+                builtin,
+            })
+        }
+    }
+
     fn resolve_node(
         &mut self,
         node: &Path,
@@ -303,6 +358,26 @@ impl TypeValidationCtx<'_> {
             nature,
             TypeValidationDiagnostic::DuplicateNatureAttr,
         );
+
+        // inherited relationships are checked for the parent
+        let ops = [
+            (TimeOperator::Ddt, data.ddt_nature.is_some()),
+            (TimeOperator::Idt, data.idt_nature.is_some()),
+        ];
+        for (op, declared) in ops {
+            if !declared {
+                continue;
+            }
+            if let Err(reached) = NatureTy::check_time_operator(self.db, nature, op) {
+                let res = NatureTy::apply_time_operator(self.db, nature, op);
+                self.report(TypeValidationDiagnostic::InconsistentTimeNature {
+                    nature,
+                    op,
+                    res,
+                    reached,
+                })
+            }
+        }
     }
 
     fn verify_unique_attributes<Attr: From<usize> + PartialEq, Def: Copy>(
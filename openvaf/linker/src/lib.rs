@@ -11,6 +11,9 @@ use camino::{Utf8Path, Utf8PathBuf};
 use cc::windows_registry;
 use target::spec::{LinkerFlavor, Target};
 
+#[cfg(test)]
+mod tests;
+
 pub fn link(
     path: Option<Utf8PathBuf>,
     target: &Target,
@@ -25,6 +28,7 @@ pub fn link(
         file.write_all(target.options.import_lib).context("failed to write importlib")?;
         linker.add_object(&import_lib_path);
     }
+    let program = linker.cmd().command.clone();
     let res = exec_linker(linker.take_cmd(), out_filename);
     if !target.options.import_lib.is_empty() {
         remove_file(import_lib_path).context("failed to delete importlib")?;
@@ -38,10 +42,40 @@ pub fn link(
             bail!("linking failed (see linker output for details)")
         }
         Ok(_) => Ok(()),
-        Err(err) => bail!("linker not found: {}", err),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            bail!(
+                "linker '{}' not found\n{}",
+                program.display(),
+                install_linker_help(&program, target.options.linker_flavor)
+            )
+        }
+        Err(err) => bail!("failed to run linker '{}': {}", program.display(), err),
     }
 }
 
+/// Explains how to install the linker `program` which OpenVAF uses to create the shared library.
+fn install_linker_help(program: &Path, flavor: LinkerFlavor) -> String {
+    let install = match flavor {
+        LinkerFlavor::Msvc => "install the Visual Studio Build Tools with the \
+                               \"Desktop development with C++\" workload"
+            .to_owned(),
+        LinkerFlavor::Ld64 => {
+            "install the Xcode command line tools with `xcode-select --install`".to_owned()
+        }
+        LinkerFlavor::Ld if program == Path::new("gcc") => {
+            "install gcc with `pacman -S mingw-w64-x86_64-gcc` (MSYS2)".to_owned()
+        }
+        LinkerFlavor::Ld => "install the binutils package of your distribution \
+                             (for example `apt install binutils`, `dnf install binutils` \
+                             or `pacman -S binutils`)"
+            .to_owned(),
+    };
+    format!(
+        "help: OpenVAF uses the system linker to create the compiled model, {install}\n\
+         help: make sure that the directory containing the linker is part of the PATH"
+    )
+}
+
 fn escape_stdout_stderr_string(s: &[u8]) -> String {
     std::str::from_utf8(s).map(|s| s.to_owned()).unwrap_or_else(|_| {
         let mut x = "Non-UTF-8 output: ".to_string();
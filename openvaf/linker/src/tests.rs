@@ -0,0 +1,17 @@
+use std::env;
+
+use camino::Utf8PathBuf;
+use target::spec::Target;
+
+use crate::link;
+
+#[test]
+fn missing_linker() {
+    let target = Target::host_target().unwrap();
+    let out_file =
+        Utf8PathBuf::from_path_buf(env::temp_dir().join("openvaf_missing_linker.osdi")).unwrap();
+    let linker = Utf8PathBuf::from("/openvaf/does/not/exist/ld");
+    let err = link(Some(linker), &target, &out_file, |_| ()).unwrap_err().to_string();
+    assert!(err.starts_with("linker '/openvaf/does/not/exist/ld' not found\n"), "{err}");
+    assert!(err.contains("help: OpenVAF uses the system linker"), "{err}");
+}
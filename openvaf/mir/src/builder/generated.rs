@@ -206,6 +206,10 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
         let (inst, dfg) = self.unary(Opcode::Atanh, arg0);
         dfg.first_result(inst)
     }
+    fn fabs(self, arg0: Value) -> Value {
+        let (inst, dfg) = self.unary(Opcode::Fabs, arg0);
+        dfg.first_result(inst)
+    }
     fn iadd(self, arg0: Value, arg1: Value) -> Value {
         let (inst, dfg) = self.binary(Opcode::Iadd, arg0, arg1);
         dfg.first_result(inst)
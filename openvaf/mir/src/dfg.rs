@@ -4,12 +4,13 @@ use lasso::Spur;
 use typed_index_collections::TiVec;
 
 use crate::builder::ReplaceBuilder;
+pub use crate::dfg::inst_key::InstKey;
 use crate::dfg::instructions::DfgInsructions;
 pub use crate::dfg::postorder::{Postorder, PostorderParts};
 pub use crate::dfg::uses::{DoubleEndedUseIter, InstUseIter, UseCursor, UseIter};
 use crate::dfg::values::consts::{FALSE, TRUE};
 use crate::dfg::values::ValueDataType;
-pub use crate::dfg::values::{consts, Const, DfgValues, ValueDef};
+pub use crate::dfg::values::{consts, Const, DfgValues, DisplayConst, DisplayStr, ValueDef};
 use crate::entities::{Inst, Param, Tag, Value};
 use crate::instructions::PhiForest;
 use crate::write::write_operands;
@@ -18,6 +19,7 @@ use crate::{Block, FuncRef, FunctionSignature, Ieee64, InstructionData, Use, Val
 #[cfg(test)]
 mod tests;
 
+mod inst_key;
 mod instructions;
 mod phis;
 mod postorder;
@@ -284,6 +286,14 @@ impl DataFlowGraph {
         self.values.uses_double_ended(value)
     }
 
+    /// The number of operands that use `value`. Uses are tracked in a list per value, so
+    /// this only walks the uses of `value` instead of the whole function. The list is
+    /// updated whenever an instruction is removed (with [`zap_inst`](Self::zap_inst)) or
+    /// its operands change, so the count is always up to date.
+    pub fn use_count(&self, value: Value) -> usize {
+        self.values.uses(value).count()
+    }
+
     pub fn uses_head_cursor(&self, value: Value) -> UseCursor {
         self.values.uses_head_cursor(value)
     }
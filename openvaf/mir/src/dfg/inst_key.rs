@@ -0,0 +1,140 @@
+use std::hash::{Hash, Hasher};
+
+use crate::dfg::values::Const;
+use crate::{DataFlowGraph, Inst, InstructionData, Value};
+
+/// A structural view of an instruction that implements [`Hash`] and [`Eq`].
+/// Two keys are equal if both instructions perform the same operation on the
+/// same operands, so they can be used as keys for `HashMap`s in CSE-like passes.
+///
+/// Source locations are not part of the key and constant operands are
+/// compared by value (not by the [`Value`] they are stored in).
+#[derive(Clone, Copy)]
+pub struct InstKey<'a> {
+    dfg: &'a DataFlowGraph,
+    inst: Inst,
+}
+
+impl DataFlowGraph {
+    pub fn inst_key(&self, inst: Inst) -> InstKey<'_> {
+        InstKey { dfg: self, inst }
+    }
+}
+
+impl InstKey<'_> {
+    pub fn inst(&self) -> Inst {
+        self.inst
+    }
+
+    fn data(&self) -> &InstructionData {
+        &self.dfg.insts[self.inst]
+    }
+
+    fn operand(&self, val: Value) -> Operand {
+        match self.dfg.value_def(val).as_const() {
+            Some(val) => Operand::Const(val),
+            None => Operand::Value(val),
+        }
+    }
+
+    fn operands<'b>(&'b self, vals: &'b [Value]) -> impl Iterator<Item = Operand> + 'b {
+        vals.iter().map(move |&val| self.operand(val))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Operand {
+    Const(Const),
+    Value(Value),
+}
+
+impl Hash for InstKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let data = self.data();
+        core::mem::discriminant(data).hash(state);
+        match *data {
+            InstructionData::Unary { opcode, arg } => {
+                opcode.hash(state);
+                self.operand(arg).hash(state);
+            }
+            InstructionData::Binary { opcode, args } => {
+                opcode.hash(state);
+                self.operands(&args).for_each(|arg| arg.hash(state));
+            }
+            InstructionData::Branch { cond, then_dst, else_dst, loop_entry } => {
+                self.operand(cond).hash(state);
+                then_dst.hash(state);
+                else_dst.hash(state);
+                loop_entry.hash(state);
+            }
+            InstructionData::Jump { destination } => destination.hash(state),
+            InstructionData::Call { func_ref, .. } => {
+                func_ref.hash(state);
+                let args = self.dfg.instr_args(self.inst);
+                args.len().hash(state);
+                self.operands(args).for_each(|arg| arg.hash(state));
+            }
+            InstructionData::PhiNode(ref phi) => {
+                for (bb, val) in self.dfg.phi_edges(phi) {
+                    bb.hash(state);
+                    self.operand(val).hash(state);
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for InstKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.data(), other.data()) {
+            (
+                InstructionData::Unary { opcode: l_opcode, arg: l_arg },
+                InstructionData::Unary { opcode: r_opcode, arg: r_arg },
+            ) => l_opcode == r_opcode && self.operand(*l_arg) == other.operand(*r_arg),
+            (
+                InstructionData::Binary { opcode: l_opcode, args: l_args },
+                InstructionData::Binary { opcode: r_opcode, args: r_args },
+            ) => l_opcode == r_opcode && self.operands(l_args).eq(other.operands(r_args)),
+            (
+                InstructionData::Branch {
+                    cond: l_cond,
+                    then_dst: l_then_dst,
+                    else_dst: l_else_dst,
+                    loop_entry: l_loop_entry,
+                },
+                InstructionData::Branch {
+                    cond: r_cond,
+                    then_dst: r_then_dst,
+                    else_dst: r_else_dst,
+                    loop_entry: r_loop_entry,
+                },
+            ) => {
+                self.operand(*l_cond) == other.operand(*r_cond)
+                    && l_then_dst == r_then_dst
+                    && l_else_dst == r_else_dst
+                    && l_loop_entry == r_loop_entry
+            }
+            (
+                InstructionData::Jump { destination: l_destination },
+                InstructionData::Jump { destination: r_destination },
+            ) => l_destination == r_destination,
+            (
+                InstructionData::Call { func_ref: l_func_ref, .. },
+                InstructionData::Call { func_ref: r_func_ref, .. },
+            ) => {
+                l_func_ref == r_func_ref
+                    && self
+                        .operands(self.dfg.instr_args(self.inst))
+                        .eq(other.operands(other.dfg.instr_args(other.inst)))
+            }
+            (InstructionData::PhiNode(l_phi), InstructionData::PhiNode(r_phi)) => self
+                .dfg
+                .phi_edges(l_phi)
+                .map(|(bb, val)| (bb, self.operand(val)))
+                .eq(other.dfg.phi_edges(r_phi).map(|(bb, val)| (bb, other.operand(val)))),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for InstKey<'_> {}
@@ -122,3 +122,68 @@ fn phi() {
         &[(b1, v4)]
     );
 }
+
+fn hash_inst(dfg: &DataFlowGraph, inst: Inst) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    dfg.inst_key(inst).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn inst_key() {
+    let mut dfg = DataFlowGraph::new();
+    let v3 = dfg.make_param(0u32.into());
+    let v4 = dfg.fconst(2f64.into());
+    // a second value that stores the same constant
+    let v5 = dfg.values.make_invalid_value();
+    dfg.values.fconst_at(2f64.into(), v5);
+
+    let fmul = |dfg: &mut DataFlowGraph, args| {
+        let inst = dfg.make_inst(InstructionData::Binary { opcode: Opcode::Fmul, args });
+        dfg.make_inst_results(inst);
+        inst
+    };
+    let inst1 = fmul(&mut dfg, [v3, v4]);
+    let inst2 = fmul(&mut dfg, [v3, v5]);
+    let inst3 = fmul(&mut dfg, [v4, v3]);
+    let inst4 = dfg.make_inst(InstructionData::Binary { opcode: Opcode::Fadd, args: [v3, v4] });
+
+    assert!(dfg.inst_key(inst1) == dfg.inst_key(inst2));
+    assert_eq!(hash_inst(&dfg, inst1), hash_inst(&dfg, inst2));
+    assert!(dfg.inst_key(inst1) != dfg.inst_key(inst3));
+    assert_ne!(hash_inst(&dfg, inst1), hash_inst(&dfg, inst3));
+    assert!(dfg.inst_key(inst1) != dfg.inst_key(inst4));
+    assert_ne!(hash_inst(&dfg, inst1), hash_inst(&dfg, inst4));
+}
+
+#[test]
+fn display_const() {
+    let mut interner = lasso::Rodeo::new();
+    let display = |val: Const, interner: &lasso::Rodeo| val.display(interner).to_string();
+    let real = |val: f64| display(Const::Float(val.into()), &interner);
+
+    assert_eq!(real(1e-6), "1u");
+    assert_eq!(real(4.7e-9), "4.7n");
+    assert_eq!(real(1.5e-5), "15u");
+    assert_eq!(real(-1e-12), "-1p");
+    assert_eq!(real(2.2e4), "22k");
+    assert_eq!(real(1234.5), "1.2345k");
+    assert_eq!(real(3.0), "3.0");
+    assert_eq!(real(2.5), "2.5");
+    assert_eq!(real(0.05), "0.05");
+    assert_eq!(real(0.0), "0.0");
+    assert_eq!(real(1e20), "1e20");
+    assert_eq!(real(1.25e-20), "1.25e-20");
+    assert_eq!(real(f64::INFINITY), "inf");
+
+    assert_eq!(display(Const::Int(3), &interner), "3");
+    assert_eq!(display(Const::Int(-42), &interner), "-42");
+    assert_eq!(display(Const::Bool(true), &interner), "1");
+    assert_eq!(display(Const::Bool(false), &interner), "0");
+
+    let str = interner.get_or_intern("a \"quoted\" path\\\n");
+    assert_eq!(display(Const::Str(str), &interner), r#""a \"quoted\" path\\\n""#);
+}
@@ -1,8 +1,9 @@
 use std::borrow::Borrow;
+use std::fmt::{self, Write};
 
 use ahash::AHashMap;
 use consts::*;
-use lasso::Spur;
+use lasso::{Resolver, Spur};
 use stdx::packed_option::PackedOption;
 use typed_index_collections::TiVec;
 
@@ -220,7 +221,7 @@ impl ValueDef {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Const {
     Float(Ieee64),
     Int(i32),
@@ -236,6 +237,102 @@ impl Const {
             unreachable!("Const is not a float!")
         }
     }
+
+    /// Displays the constant like a Verilog-A literal (`4.7n`, `3`, `"str"`), booleans are
+    /// displayed as integers. Reals use a scale factor (`1u`) when their exponent is large.
+    pub fn display(self, interner: &dyn Resolver) -> DisplayConst<'_> {
+        DisplayConst(self, interner)
+    }
+}
+
+/// Object that can display a constant as a Verilog-A literal.
+pub struct DisplayConst<'a>(Const, &'a dyn Resolver);
+
+impl fmt::Display for DisplayConst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Const::Float(val) => write_real(f, val.into()),
+            Const::Int(val) => write!(f, "{val}"),
+            Const::Bool(val) => write!(f, "{}", val as i32),
+            Const::Str(val) => write!(f, "{}", DisplayStr(self.1.resolve(&val))),
+        }
+    }
+}
+
+/// Object that displays a string as a Verilog-A string literal (quoted and escaped).
+pub struct DisplayStr<'a>(pub &'a str);
+
+impl fmt::Display for DisplayStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\t' => f.write_str("\\t")?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// Verilog-A scale factors by exponent (starting at 1e-15).
+const SCALE_FACTORS: [char; 10] = ['f', 'p', 'n', 'u', 'm', ' ', 'k', 'M', 'G', 'T'];
+
+fn write_real(f: &mut fmt::Formatter<'_>, val: f64) -> fmt::Result {
+    if !val.is_finite() {
+        return write!(f, "{val}");
+    }
+    if val == 0.0 {
+        return f.write_str("0.0");
+    }
+    if val < 0.0 {
+        f.write_char('-')?;
+    }
+
+    // the shortest representation that roundtrips, the decimal point is shifted on the string
+    // to avoid introducing rounding errors
+    let sci = format!("{:e}", val.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let digits = mantissa.replace('.', "");
+
+    let (shift, suffix) = if (-2..3).contains(&exp) {
+        (0, None)
+    } else if (-15..15).contains(&exp) {
+        let scale = exp.div_euclid(3) * 3;
+        (scale, Some(SCALE_FACTORS[(scale / 3 + 5) as usize]))
+    } else {
+        return write!(f, "{sci}");
+    };
+
+    // number of digits before the decimal point
+    let int_digits = exp - shift + 1;
+    if int_digits <= 0 {
+        f.write_str("0.")?;
+        for _ in int_digits..0 {
+            f.write_char('0')?;
+        }
+        f.write_str(&digits)?;
+    } else {
+        let int_digits = int_digits as usize;
+        if digits.len() > int_digits {
+            write!(f, "{}.{}", &digits[..int_digits], &digits[int_digits..])?;
+        } else {
+            write!(f, "{digits:0<int_digits$}")?;
+            // a literal without a decimal point or scale factor is an integer
+            if suffix.is_none() {
+                f.write_str(".0")?;
+            }
+        }
+    }
+
+    if let Some(suffix) = suffix {
+        f.write_char(suffix)?;
+    }
+    Ok(())
 }
 
 /// Handling values.
@@ -7,7 +7,10 @@ use stdx::packed_option::PackedOption;
 use typed_index_collections::{TiSlice, TiVec};
 
 use crate::flowgraph::Successors;
-use crate::{Block, ControlFlowGraph, Function};
+use crate::{Block, ControlFlowGraph, Function, Inst};
+
+#[cfg(test)]
+mod tests;
 
 /* AB: unused
 trait CfgREVERSE {
@@ -21,7 +24,7 @@ trait CfgREVERSE {
 trait ToIter {}
 */
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 struct DomTreeNode {
     /// Number of this node in a (reverse) post-order traversal of the CFG, starting from 1.
     /// This number is monotonic in the reverse postorder
@@ -34,6 +37,12 @@ struct DomTreeNode {
     /// This is `None` for unreachable blocks and the entry block which doesn't have an immediate
     /// dominator.
     idom: PackedOption<Block>,
+
+    /// Numbers assigned when entering/leaving this node during a depth first traversal of the
+    /// dominator tree. A node dominates another node if its interval contains the interval of
+    /// the other node. Both numbers are 0 for unreachable blocks.
+    dfs_in: u32,
+    dfs_out: u32,
 }
 
 const UNDEF: u32 = 0;
@@ -96,6 +105,8 @@ impl DominatorTree {
         &self.postorder
     }
 
+    /// Returns whether `dominator` dominates `block` (every block dominates itself).
+    /// This is a constant time query that uses the numbering computed by `compute()`.
     pub fn dominates(&self, block: Block, dominator: Block) -> bool {
         Self::dominates_(&self.nodes, block, dominator)
     }
@@ -104,15 +115,63 @@ impl DominatorTree {
         Self::dominates_(&self.reverse_nodes, block, dominator)
     }
 
-    fn dominates_(nodes: &TiSlice<Block, DomTreeNode>, mut block: Block, dominator: Block) -> bool {
-        while nodes[block].rpo_number > nodes[dominator].rpo_number {
-            if let Some(parent) = nodes[block].idom.expand() {
-                block = parent;
+    /// Returns whether the instruction `dominator` dominates `inst` (every instruction dominates
+    /// itself). Within the same block this is decided by the order of the instructions.
+    pub fn inst_dominates(&self, func: &Function, inst: Inst, dominator: Inst) -> bool {
+        let block = func.layout.inst_block(inst).expect("instruction must be inserted");
+        let dominator_block =
+            func.layout.inst_block(dominator).expect("instruction must be inserted");
+        if block != dominator_block {
+            return self.dominates(block, dominator_block);
+        }
+        let mut pos = Some(dominator);
+        while let Some(curr) = pos {
+            if curr == inst {
+                return true;
+            }
+            pos = func.layout.next_inst(curr);
+        }
+        false
+    }
+
+    fn dominates_(nodes: &TiSlice<Block, DomTreeNode>, block: Block, dominator: Block) -> bool {
+        let (node, dominator_node) = (&nodes[block], &nodes[dominator]);
+        if node.dfs_in == 0 || dominator_node.dfs_in == 0 {
+            // unreachable blocks only dominate themselves
+            return block == dominator;
+        }
+        dominator_node.dfs_in <= node.dfs_in && node.dfs_out <= dominator_node.dfs_out
+    }
+
+    /// Numbers the nodes of the dominator tree rooted at `root` in the order in which they are
+    /// entered and left by a depth first traversal.
+    fn compute_dfs_numbers(nodes: &mut TiSlice<Block, DomTreeNode>, root: Block) {
+        let mut first_child: TiVec<Block, PackedOption<Block>> =
+            TiVec::from(vec![None.into(); nodes.len()]);
+        let mut next_sibling = first_child.clone();
+        for (block, node) in nodes.iter_enumerated() {
+            if let Some(idom) = node.idom.expand() {
+                next_sibling[block] = first_child[idom];
+                first_child[idom] = block.into();
+            }
+        }
+
+        let mut counter = 1;
+        let mut stack = vec![(root, false)];
+        while let Some((block, visited)) = stack.pop() {
+            if visited {
+                nodes[block].dfs_out = counter;
             } else {
-                return false;
+                nodes[block].dfs_in = counter;
+                stack.push((block, true));
+                let mut child = first_child[block];
+                while let Some(bb) = child.expand() {
+                    stack.push((bb, false));
+                    child = next_sibling[bb];
+                }
             }
+            counter += 1;
         }
-        block == dominator
     }
 
     pub fn ipdom(&self, block: Block) -> Option<Block> {
@@ -184,8 +243,7 @@ impl DominatorTree {
     ///
     fn compute_reverse_postorder(&mut self, func: &Function, cfg: &ControlFlowGraph) {
         // self.compute_reverse_cfg_postorder(func, cfg);
-        self.reverse_nodes
-            .resize(func.layout.num_blocks(), DomTreeNode { rpo_number: UNDEF, idom: None.into() });
+        self.reverse_nodes.resize(func.layout.num_blocks(), DomTreeNode::default());
         match func.layout.last_block() {
             Some(block) => {
                 self.stack.push((block, Successors(None.into(), None.into())));
@@ -231,8 +289,7 @@ impl DominatorTree {
     ///
     fn compute_postorder(&mut self, func: &Function, cfg: &ControlFlowGraph) {
         // self.compute_reverse_cfg_postorder(func, cfg);
-        self.nodes
-            .resize(func.layout.num_blocks(), DomTreeNode { rpo_number: UNDEF, idom: None.into() });
+        self.nodes.resize(func.layout.num_blocks(), DomTreeNode::default());
 
         match func.layout.entry_block() {
             Some(block) => {
@@ -291,6 +348,7 @@ impl DominatorTree {
             let node = DomTreeNode {
                 rpo_number: rpo_idx as u32 + 3,
                 idom: self.compute_idom::<REVERSE>(block, cfg).into(),
+                ..DomTreeNode::default()
             };
 
             let nodes = if REVERSE { &mut self.reverse_nodes } else { &mut self.nodes };
@@ -312,6 +370,9 @@ impl DominatorTree {
                 }
             }
         }
+
+        let nodes = if REVERSE { &mut self.reverse_nodes } else { &mut self.nodes };
+        Self::compute_dfs_numbers(nodes, entry_block);
     }
 
     fn compute_idom<const REVERSE: bool>(&self, block: Block, cfg: &ControlFlowGraph) -> Block {
@@ -0,0 +1,71 @@
+use crate::builder::InstBuilder;
+use crate::cursor::{Cursor, FuncCursor};
+use crate::{ControlFlowGraph, DominatorTree, Function, FALSE, F_ONE, F_ZERO};
+
+/// ```text
+///       block0
+///      /      \
+///  block1    block2
+///      \      /
+///       block3
+///
+///  block4 (unreachable)
+/// ```
+#[test]
+fn diamond() {
+    let mut func = Function::new();
+    let block0 = func.layout.append_new_block();
+    let block1 = func.layout.append_new_block();
+    let block2 = func.layout.append_new_block();
+    let block4 = func.layout.append_new_block();
+    let block3 = func.layout.append_new_block();
+
+    let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
+    let val0 = cur.ins().fadd(F_ZERO, F_ONE);
+    let val1 = cur.ins().fmul(val0, F_ONE);
+    let branch = cur.ins().br(FALSE, block1, block2);
+    cur.goto_bottom(block1);
+    cur.ins().jump(block3);
+    cur.goto_bottom(block2);
+    let val2 = cur.ins().fadd(val1, F_ONE);
+    cur.ins().jump(block3);
+    cur.goto_bottom(block4);
+    cur.ins().jump(block3);
+    cur.goto_bottom(block3);
+    let val3 = cur.ins().fadd(val1, F_ZERO);
+
+    let cfg = ControlFlowGraph::with_function(&func);
+    let mut domtree = DominatorTree::default();
+    domtree.compute(&func, &cfg, true, true, false);
+
+    for (block, dominators) in [
+        (block0, vec![block0]),
+        (block1, vec![block0, block1]),
+        (block2, vec![block0, block2]),
+        (block3, vec![block0, block3]),
+        (block4, vec![block4]),
+    ] {
+        for dominator in [block0, block1, block2, block3, block4] {
+            assert_eq!(
+                domtree.dominates(block, dominator),
+                dominators.contains(&dominator),
+                "{dominator} dominates {block}"
+            );
+        }
+    }
+
+    assert!(domtree.post_dominates(block0, block3));
+    assert!(domtree.post_dominates(block1, block3));
+    assert!(!domtree.post_dominates(block0, block1));
+    assert!(!domtree.post_dominates(block3, block0));
+
+    let [inst0, inst1, inst2, inst3] =
+        [val0, val1, val2, val3].map(|val| func.dfg.value_def(val).unwrap_inst());
+    assert!(domtree.inst_dominates(&func, inst0, inst0));
+    assert!(domtree.inst_dominates(&func, inst1, inst0));
+    assert!(domtree.inst_dominates(&func, branch, inst1));
+    assert!(!domtree.inst_dominates(&func, inst0, inst1));
+    assert!(domtree.inst_dominates(&func, inst2, inst1));
+    assert!(domtree.inst_dominates(&func, inst3, inst0));
+    assert!(!domtree.inst_dominates(&func, inst3, inst2));
+}
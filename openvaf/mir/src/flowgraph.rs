@@ -23,18 +23,21 @@
 //! Here `Block1` and `Block2` would each have a single predecessor denoted as `(Block0, brz)`
 //! and `(Block0, jmp Block2)` respectively.
 
+use std::collections::VecDeque;
 use std::fs::File;
 use std::iter::FilterMap;
 use std::ops::Index;
 use std::path::Path;
 
-use bforest::Set;
+use bforest::{Set, SetForest};
 use stdx::packed_option::PackedOption;
 use typed_index_collections::TiVec;
 
 pub use crate::flowgraph::transversal::{Postorder, ReversePostorder};
-use crate::{Block, Function, InstructionData};
+use crate::{Block, DominatorTree, Function, InstructionData};
 
+#[cfg(test)]
+mod tests;
 mod transversal;
 
 pub type PredecessorIter<'a> = bforest::SetIter<'a, Block>;
@@ -131,7 +134,7 @@ pub type SuccIter = FilterMap<
 #[derive(Clone)]
 pub struct ControlFlowGraph {
     data: TiVec<Block, CFGNode>,
-    pred_forest: bforest::SetForest<Block>,
+    pred_forest: SetForest<Block>,
     valid: bool,
 }
 
@@ -189,7 +192,7 @@ impl<'a> dot::GraphWalk<'a, Block, (Block, Block)> for CfgRender<'a> {
 impl ControlFlowGraph {
     /// Allocate a new blank control flow graph.
     pub fn new() -> Self {
-        Self { data: TiVec::new(), valid: false, pred_forest: bforest::SetForest::new() }
+        Self { data: TiVec::new(), valid: false, pred_forest: SetForest::new() }
     }
 
     /// Clear all data structures in this control flow graph.
@@ -363,6 +366,87 @@ impl ControlFlowGraph {
         self.pred_iter(bb).next().is_none()
     }
 
+    /// Returns the set of blocks from which `block` can be reached. `block` itself is
+    /// only part of the set if it is part of a cycle. The set is allocated in `forest`.
+    pub fn predecessors_transitive(
+        &self,
+        block: Block,
+        forest: &mut SetForest<Block>,
+    ) -> Set<Block> {
+        self.reachable_set(block, forest, |bb| self.pred_iter(bb))
+    }
+
+    /// Returns the set of blocks that can be reached from `block`. `block` itself is
+    /// only part of the set if it is part of a cycle. The set is allocated in `forest`.
+    pub fn successors_transitive(&self, block: Block, forest: &mut SetForest<Block>) -> Set<Block> {
+        self.reachable_set(block, forest, |bb| self.succ_iter(bb))
+    }
+
+    /// Breadth first search starting at (but not including) `block` along `edges`.
+    fn reachable_set<I: Iterator<Item = Block>>(
+        &self,
+        block: Block,
+        forest: &mut SetForest<Block>,
+        edges: impl Fn(Block) -> I,
+    ) -> Set<Block> {
+        let mut res = Set::new();
+        let mut queue: VecDeque<_> = edges(block).collect();
+        while let Some(bb) = queue.pop_front() {
+            if res.insert(bb, forest, &()) {
+                queue.extend(edges(bb));
+            }
+        }
+        res
+    }
+
+    /// Returns all edges `(from, to)` that close a cycle without being the back edge of a
+    /// natural loop (the loop header `to` does not dominate `from`). Such cycles can be
+    /// entered at multiple blocks, so the CFG is reducible exactly if no edges are returned.
+    /// `domtree` must have been computed for this CFG.
+    pub fn irreducible_edges(
+        &self,
+        func: &Function,
+        domtree: &DominatorTree,
+    ) -> Vec<(Block, Block)> {
+        let mut res = Vec::new();
+        let entry = if let Some(entry) = func.layout.entry_block() {
+            entry
+        } else {
+            return res;
+        };
+
+        let mut postorder_number: TiVec<Block, _> = vec![u32::MAX; self.data.len()].into();
+        for (i, bb) in self.postorder_from(entry).enumerate() {
+            postorder_number[bb] = i as u32;
+        }
+
+        for (from, &number) in postorder_number.iter_enumerated() {
+            // unreachable blocks are not part of the dominator tree
+            if number == u32::MAX {
+                continue;
+            }
+            // during a depth first search only edges that close a cycle lead to a block that
+            // is finished after `from`
+            for to in self.succ_iter(from) {
+                if postorder_number[to] >= number && !domtree.dominates(from, to) {
+                    res.push((from, to))
+                }
+            }
+        }
+        res
+    }
+
+    /// Debug check that every cycle of the CFG is a natural loop with a single entry (the CFG
+    /// is reducible). Loop optimizations like LICM rely on this property.
+    ///
+    /// # Panics
+    ///
+    /// If the CFG is irreducible. The offending edges are listed in the panic message.
+    pub fn assert_acyclic_except_loops(&self, func: &Function, domtree: &DominatorTree) {
+        let edges = self.irreducible_edges(func, domtree);
+        assert!(edges.is_empty(), "irreducible control flow, cycles are entered by {edges:?}");
+    }
+
     #[inline]
     pub fn reverse_postorder_from(&self, start: Block) -> ReversePostorder {
         ReversePostorder::new(self, start)
@@ -1,8 +1,8 @@
-use super::*;
+use bforest::{Set, SetForest};
 
-use mir::builder::InstBuilder;
-use mir::cursor::{Cursor, FuncCursor};
-use mir::Function;
+use crate::builder::InstBuilder;
+use crate::cursor::{Cursor, FuncCursor};
+use crate::{Block, ControlFlowGraph, DominatorTree, Function, FALSE};
 
 #[test]
 fn empty() {
@@ -13,18 +13,12 @@ fn empty() {
 #[test]
 fn no_predecessors() {
     let mut func = Function::new();
-    let block0 = func.dfg.make_block();
-    let block1 = func.dfg.make_block();
-    let block2 = func.dfg.make_block();
-    func.layout.append_block(block0);
-    func.layout.append_block(block1);
-    func.layout.append_block(block2);
+    func.layout.append_new_block();
+    func.layout.append_new_block();
+    func.layout.append_new_block();
 
     let cfg = ControlFlowGraph::with_function(&func);
-
-    let mut fun_blocks = func.layout.blocks();
     for block in func.layout.blocks() {
-        assert_eq!(block, fun_blocks.next().unwrap());
         assert_eq!(cfg.pred_iter(block).count(), 0);
         assert_eq!(cfg.succ_iter(block).count(), 0);
     }
@@ -33,83 +27,158 @@ fn no_predecessors() {
 #[test]
 fn branches_and_jumps() {
     let mut func = Function::new();
-    let block0 = func.dfg.make_block();
-    let cond = func.dfg.append_block_param(block0);
-    let block1 = func.dfg.make_block();
-    let block2 = func.dfg.make_block();
-    let block3 = func.dfg.make_block();
-
-    let br_block0_block2;
-    let br_block1_block1;
-    let jmp_block0_block1;
-    let jmp_block1_block2;
-
-    {
-        let mut cur = FuncCursor::new(&mut func);
-
-        cur.insert_block(block0);
-        br_block0_block2 = cur.ins().brnz(cond, block2, &[]);
-        jmp_block0_block1 = cur.ins().jump(block1, &[]);
-
-        cur.insert_block(block1);
-        br_block1_block1 = cur.ins().brnz(cond, block1, &[]);
-        jmp_block1_block2 = cur.ins().jump(block2, &[]);
-
-        cur.insert_block(block2);
-        cur.insert_block(block3);
-    }
+    let block0 = func.layout.append_new_block();
+    let block1 = func.layout.append_new_block();
+    let block2 = func.layout.append_new_block();
+    let block3 = func.layout.append_new_block();
+
+    let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
+    let br_block0 = cur.ins().br(FALSE, block2, block1);
+    cur.goto_bottom(block1);
+    cur.ins().br(FALSE, block1, block2);
 
     let mut cfg = ControlFlowGraph::with_function(&func);
+    assert_eq!(cfg.pred_iter(block0).count(), 0);
+    assert_eq!(cfg.pred_iter(block1).collect::<Vec<_>>(), [block0, block1]);
+    assert_eq!(cfg.pred_iter(block2).collect::<Vec<_>>(), [block0, block1]);
 
-    {
-        let block0_predecessors = cfg.pred_iter(block0).count();
-        let block1_predecessors = cfg.pred_iter(block1).collect::<Vec<_>>();
-        let block2_predecessors = cfg.pred_iter(block2).collect::<Vec<_>>();
+    assert_eq!(cfg.succ_iter(block0).collect::<Vec<_>>(), [block1, block2]);
+    assert_eq!(cfg.succ_iter(block1).collect::<Vec<_>>(), [block2, block1]);
+    assert_eq!(cfg.succ_iter(block2).count(), 0);
 
-        let block0_successors = cfg.succ_iter(block0).collect::<Vec<_>>();
-        let block1_successors = cfg.succ_iter(block1).collect::<Vec<_>>();
-        let block2_successors = cfg.succ_iter(block2).collect::<Vec<_>>();
+    // Change the terminator of block0 and recompute it
+    func.dfg.replace(br_block0).br(FALSE, block3, block1);
+    cfg.recompute_block(&func, block0);
 
-        assert_eq!(block0_predecessors, 0);
-        assert_eq!(block1_predecessors.len(), 2);
-        assert_eq!(block2_predecessors.len(), 2);
+    assert_eq!(cfg.pred_iter(block0).count(), 0);
+    assert_eq!(cfg.pred_iter(block1).collect::<Vec<_>>(), [block0, block1]);
+    assert_eq!(cfg.pred_iter(block2).collect::<Vec<_>>(), [block1]);
+    assert_eq!(cfg.pred_iter(block3).collect::<Vec<_>>(), [block0]);
 
-        assert!(block1_predecessors.contains(&BlockPredecessor::new(block0, jmp_block0_block1)),);
-        assert!(block1_predecessors.contains(&BlockPredecessor::new(block1, br_block1_block1)),);
-        assert!(block2_predecessors.contains(&BlockPredecessor::new(block0, br_block0_block2)),);
-        assert!(block2_predecessors.contains(&BlockPredecessor::new(block1, jmp_block1_block2)),);
+    assert_eq!(cfg.succ_iter(block0).collect::<Vec<_>>(), [block1, block3]);
+    assert_eq!(cfg.succ_iter(block1).collect::<Vec<_>>(), [block2, block1]);
+    assert_eq!(cfg.succ_iter(block2).count(), 0);
+}
 
-        assert_eq!(block0_successors, [block1, block2]);
-        assert_eq!(block1_successors, [block1, block2]);
-        assert_eq!(block2_successors, []);
-    }
+/// ```text
+///       block0
+///      /      \
+///  block1    block2
+///      \      /
+///       block3
+/// ```
+#[test]
+fn transitive_diamond() {
+    let mut func = Function::new();
+    let block0 = func.layout.append_new_block();
+    let block1 = func.layout.append_new_block();
+    let block2 = func.layout.append_new_block();
+    let block3 = func.layout.append_new_block();
+
+    let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
+    cur.ins().br(FALSE, block1, block2);
+    cur.goto_bottom(block1);
+    cur.ins().jump(block3);
+    cur.goto_bottom(block2);
+    cur.ins().jump(block3);
 
-    // Change some instructions and recompute block0
-    func.dfg.replace(br_block0_block2).brnz(cond, block1, &[]);
-    func.dfg.replace(jmp_block0_block1).jump(block3, &[]);
-    cfg.recompute_block(&func, block0);
-    let br_block0_block1 = br_block0_block2;
+    let cfg = ControlFlowGraph::with_function(&func);
+    let preds = |block| transitive(&cfg, block, ControlFlowGraph::predecessors_transitive);
+    assert_eq!(preds(block0), []);
+    assert_eq!(preds(block1), [block0]);
+    assert_eq!(preds(block3), [block0, block1, block2]);
+
+    let succs = |block| transitive(&cfg, block, ControlFlowGraph::successors_transitive);
+    assert_eq!(succs(block0), [block1, block2, block3]);
+    assert_eq!(succs(block2), [block3]);
+    assert_eq!(succs(block3), []);
+}
+
+fn transitive(
+    cfg: &ControlFlowGraph,
+    block: Block,
+    query: fn(&ControlFlowGraph, Block, &mut SetForest<Block>) -> Set<Block>,
+) -> Vec<Block> {
+    let mut forest = SetForest::new();
+    let set = query(cfg, block, &mut forest);
+    set.iter(&forest).collect()
+}
+
+#[test]
+fn transitive_loop() {
+    let mut func = Function::new();
+    let block0 = func.layout.append_new_block();
+    let block1 = func.layout.append_new_block();
+    let block2 = func.layout.append_new_block();
 
-    {
-        let block0_predecessors = cfg.pred_iter(block0).count();
-        let block1_predecessors = cfg.pred_iter(block1).collect::<Vec<_>>();
-        let block2_predecessors = cfg.pred_iter(block2).collect::<Vec<_>>();
+    let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
+    cur.ins().jump(block1);
+    cur.goto_bottom(block1);
+    cur.ins().br(FALSE, block1, block2);
+
+    let cfg = ControlFlowGraph::with_function(&func);
+    let preds = transitive(&cfg, block1, ControlFlowGraph::predecessors_transitive);
+    assert_eq!(preds, [block0, block1]);
+    let succs = transitive(&cfg, block1, ControlFlowGraph::successors_transitive);
+    assert_eq!(succs, [block1, block2]);
+}
+
+/// ```text
+///     block0
+///       |
+///     block1 <-+
+///       |      |
+///     block2 --+
+///       |
+///     block3
+/// ```
+#[test]
+fn reducible_loop() {
+    let mut func = Function::new();
+    let block0 = func.layout.append_new_block();
+    let block1 = func.layout.append_new_block();
+    let block2 = func.layout.append_new_block();
+    let block3 = func.layout.append_new_block();
+
+    let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
+    cur.ins().jump(block1);
+    cur.goto_bottom(block1);
+    cur.ins().jump(block2);
+    cur.goto_bottom(block2);
+    cur.ins().br(FALSE, block1, block3);
 
-        let block0_successors = cfg.succ_iter(block0);
-        let block1_successors = cfg.succ_iter(block1);
-        let block2_successors = cfg.succ_iter(block2);
+    let cfg = ControlFlowGraph::with_function(&func);
+    let mut domtree = DominatorTree::default();
+    domtree.compute(&func, &cfg, true, false, false);
+    assert!(cfg.irreducible_edges(&func, &domtree).is_empty());
+    cfg.assert_acyclic_except_loops(&func, &domtree);
+}
 
-        assert!(block1_predecessors.contains(&BlockPredecessor::new(block0, br_block0_block1)),);
-        assert!(block1_predecessors.contains(&BlockPredecessor::new(block1, br_block1_block1)),);
-        assert!(!block2_predecessors.contains(&BlockPredecessor::new(block0, br_block0_block2)),);
-        assert!(block2_predecessors.contains(&BlockPredecessor::new(block1, jmp_block1_block2)),);
+/// The cycle between `block1` and `block2` can be entered at either block.
+///
+/// ```text
+///       block0
+///      /      \
+///  block1 <-> block2
+/// ```
+#[test]
+#[should_panic(expected = "irreducible control flow")]
+fn irreducible_cycle() {
+    let mut func = Function::new();
+    let block0 = func.layout.append_new_block();
+    let block1 = func.layout.append_new_block();
+    let block2 = func.layout.append_new_block();
 
-        assert_eq!(block0_predecessors, 0);
-        assert_eq!(block1_predecessors.len(), 2);
-        assert_eq!(block2_predecessors.len(), 1);
+    let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
+    cur.ins().br(FALSE, block1, block2);
+    cur.goto_bottom(block1);
+    cur.ins().jump(block2);
+    cur.goto_bottom(block2);
+    cur.ins().jump(block1);
 
-        assert_eq!(block0_successors.collect::<Vec<_>>(), [block1, block3]);
-        assert_eq!(block1_successors.collect::<Vec<_>>(), [block1, block2]);
-        assert_eq!(block2_successors.collect::<Vec<_>>(), []);
-    }
+    let cfg = ControlFlowGraph::with_function(&func);
+    let mut domtree = DominatorTree::default();
+    domtree.compute(&func, &cfg, true, false, false);
+    assert_eq!(cfg.irreducible_edges(&func, &domtree), [(block2, block1)]);
+    cfg.assert_acyclic_except_loops(&func, &domtree);
 }
@@ -43,46 +43,47 @@ pub enum Opcode {
     Asinh = 28u8,
     Acosh = 29u8,
     Atanh = 30u8,
-    Iadd = 31u8,
-    Isub = 32u8,
-    Imul = 33u8,
-    Idiv = 34u8,
-    Irem = 35u8,
-    Ishl = 36u8,
-    Ishr = 37u8,
-    Ixor = 38u8,
-    Iand = 39u8,
-    Ior = 40u8,
-    Fadd = 41u8,
-    Fsub = 42u8,
-    Fmul = 43u8,
-    Fdiv = 44u8,
-    Frem = 45u8,
-    Ilt = 46u8,
-    Igt = 47u8,
-    Ige = 48u8,
-    Ile = 49u8,
-    Flt = 50u8,
-    Fgt = 51u8,
-    Fge = 52u8,
-    Fle = 53u8,
-    Ieq = 54u8,
-    Feq = 55u8,
-    Seq = 56u8,
-    Beq = 57u8,
-    Ine = 58u8,
-    Fne = 59u8,
-    Sne = 60u8,
-    Bne = 61u8,
-    Hypot = 62u8,
-    Atan2 = 63u8,
-    Pow = 64u8,
-    Br = 65u8,
-    Jmp = 66u8,
-    Call = 67u8,
-    Phi = 68u8,
+    Fabs = 31u8,
+    Iadd = 32u8,
+    Isub = 33u8,
+    Imul = 34u8,
+    Idiv = 35u8,
+    Irem = 36u8,
+    Ishl = 37u8,
+    Ishr = 38u8,
+    Ixor = 39u8,
+    Iand = 40u8,
+    Ior = 41u8,
+    Fadd = 42u8,
+    Fsub = 43u8,
+    Fmul = 44u8,
+    Fdiv = 45u8,
+    Frem = 46u8,
+    Ilt = 47u8,
+    Igt = 48u8,
+    Ige = 49u8,
+    Ile = 50u8,
+    Flt = 51u8,
+    Fgt = 52u8,
+    Fge = 53u8,
+    Fle = 54u8,
+    Ieq = 55u8,
+    Feq = 56u8,
+    Seq = 57u8,
+    Beq = 58u8,
+    Ine = 59u8,
+    Fne = 60u8,
+    Sne = 61u8,
+    Bne = 62u8,
+    Hypot = 63u8,
+    Atan2 = 64u8,
+    Pow = 65u8,
+    Br = 66u8,
+    Jmp = 67u8,
+    Call = 68u8,
+    Phi = 69u8,
 }
-pub(super) const OPCODE_CONSTRAINTS: [OpcodeConstraints; 68usize + 1] = [
+pub(super) const OPCODE_CONSTRAINTS: [OpcodeConstraints; 69usize + 1] = [
     OpcodeConstraints::new(0, 0),
     OpcodeConstraints::new(1u8, 1u8),
     OpcodeConstraints::new(1u8, 1u8),
@@ -114,6 +115,7 @@ pub(super) const OPCODE_CONSTRAINTS: [OpcodeConstraints; 68usize + 1] = [
     OpcodeConstraints::new(1u8, 1u8),
     OpcodeConstraints::new(1u8, 1u8),
     OpcodeConstraints::new(1u8, 1u8),
+    OpcodeConstraints::new(1u8, 1u8),
     OpcodeConstraints::new(2u8, 1u8),
     OpcodeConstraints::new(2u8, 1u8),
     OpcodeConstraints::new(2u8, 1u8),
@@ -153,7 +155,7 @@ pub(super) const OPCODE_CONSTRAINTS: [OpcodeConstraints; 68usize + 1] = [
     OpcodeConstraints::new(0u8, 0u8),
     OpcodeConstraints::new(0u8, 1u8),
 ];
-pub(super) const OPCODE_NAMES: [&str; 68usize + 1] = [
+pub(super) const OPCODE_NAMES: [&str; 69usize + 1] = [
     "",
     "inot",
     "bnot",
@@ -185,6 +187,7 @@ pub(super) const OPCODE_NAMES: [&str; 68usize + 1] = [
     "asinh",
     "acosh",
     "atanh",
+    "fabs",
     "iadd",
     "isub",
     "imul",
@@ -224,7 +227,7 @@ pub(super) const OPCODE_NAMES: [&str; 68usize + 1] = [
     "call",
     "phi",
 ];
-pub(super) const OPCODE_FORMAT: [InstructionFormat; 68usize + 1] = [
+pub(super) const OPCODE_FORMAT: [InstructionFormat; 69usize + 1] = [
     InstructionFormat::Binary,
     InstructionFormat::Unary,
     InstructionFormat::Unary,
@@ -256,6 +259,7 @@ pub(super) const OPCODE_FORMAT: [InstructionFormat; 68usize + 1] = [
     InstructionFormat::Unary,
     InstructionFormat::Unary,
     InstructionFormat::Unary,
+    InstructionFormat::Unary,
     InstructionFormat::Binary,
     InstructionFormat::Binary,
     InstructionFormat::Binary,
@@ -329,6 +333,7 @@ impl std::str::FromStr for Opcode {
             "asinh" => Ok(Opcode::Asinh),
             "acosh" => Ok(Opcode::Acosh),
             "atanh" => Ok(Opcode::Atanh),
+            "fabs" => Ok(Opcode::Fabs),
             "iadd" => Ok(Opcode::Iadd),
             "isub" => Ok(Opcode::Isub),
             "imul" => Ok(Opcode::Imul),
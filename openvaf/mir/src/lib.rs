@@ -46,8 +46,8 @@ use typed_indexmap::TiSet;
 
 pub use crate::dfg::consts::*;
 pub use crate::dfg::{
-    Const, DataFlowGraph, DfgValues, InstUseIter, Postorder, PostorderParts, UseCursor, UseIter,
-    ValueDef,
+    Const, DataFlowGraph, DfgValues, DisplayConst, DisplayStr, InstKey, InstUseIter, Postorder,
+    PostorderParts, UseCursor, UseIter, ValueDef,
 };
 pub use crate::dominators::DominatorTree;
 pub use crate::entities::{AnyEntity, Block, FuncRef, Inst, Param, Use, Value};
@@ -5,8 +5,8 @@ use arena::{Arena, IdxRange};
 use bitset::{BitSet, HybridBitSet};
 use mir::builder::{InsertBuilder, InstBuilder, InstInserterBase};
 use mir::{
-    Block, Function, Inst, InstructionData, Opcode, SourceLoc, Unknown, Value, F_LOG10_E, F_ONE,
-    F_TWO, F_ZERO,
+    Block, Function, Inst, InstructionData, Opcode, SourceLoc, Unknown, Value, F_LOG10_E, F_N_ONE,
+    F_ONE, F_TWO, F_ZERO,
 };
 use stdx::iter::zip;
 use stdx::packed_option::{PackedOption, ReservedValue};
@@ -147,15 +147,7 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
                 self.derivative_of_1(arg, next_unknown)
             };
 
-            if val == F_ZERO {
-                continue;
-            }
-
-            if derivative == F_ZERO {
-                derivative = val
-            } else {
-                derivative = self.ins().fadd(derivative, val)
-            }
+            derivative = self.add(derivative, val);
         }
 
         for next_unknown in neg_unknowns.iter() {
@@ -166,15 +158,8 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
                 self.derivative_of_1(arg, next_unknown)
             };
 
-            if val == F_ZERO {
-                continue;
-            }
-
-            if derivative == F_ZERO {
-                derivative = self.ins().fneg(val);
-            } else {
-                derivative = self.ins().fsub(derivative, val)
-            }
+            derivative =
+                if derivative == F_ZERO { self.neg(val) } else { self.sub(derivative, val) };
         }
 
         derivative
@@ -391,7 +376,7 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
                     self.prev_order_derivative_of(chain_rule.val, chain_rule.dst_derivative);
                 debug_assert_ne!(prev_order, F_ZERO);
                 let unknown = self.intern.get_unknown(chain_rule.dst_derivative);
-                let val = self.ins().fmul(inner_derivative, outer_derivative);
+                let val = self.mul(inner_derivative, outer_derivative);
                 self.derivative_values.insert((prev_order, unknown), val);
             }
         }
@@ -587,6 +572,9 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
                 self.ins().fsub(F_ONE, arg_squared)
             }
 
+            // fabs(x) -> x/fabs(x)
+            Opcode::Fabs => self.ins().fdiv(arg0, res),
+
             // // x << y = x*pow(2,y)-> ln(2) * x * y'* pow(2,y)  + x' * pow(2,y)
             // // = ln(2) * y' * x<<y + x' * 1<<y
             // Opcode::Ishl => {
@@ -672,10 +660,7 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
                     sel.simplified_mul(rhs, drhs, lhs, res)
                 };
 
-                if dlhs == F_ZERO {
-                    return sum2;
-                }
-                sel.ins().fadd(sum1, sum2)
+                sel.add(sum1, sum2)
             };
 
         // (f/g)' -> (f'*g - g' *f) / g^2 = f'/g - g'*f/g^2
@@ -711,12 +696,12 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
                     }
                     res
                 } else {
-                    sel.ins().fmul(drhs, lhs)
+                    sel.mul(drhs, lhs)
                 };
                 let bot = cache[0].unwrap_unchecked();
                 let sum2 = sel.ins().fdiv(top, bot);
 
-                sel.ins().fsub(sum1, sum2)
+                sel.sub(sum1, sum2)
             };
 
         let val = match op {
@@ -765,7 +750,7 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
 
             Opcode::Fneg  => {
                 let arg = arg_derivative(self, 0);
-                self.ins().fneg(arg)
+                self.neg(arg)
             }
 
             Opcode::OptBarrier
@@ -774,13 +759,13 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
             Opcode::Fadd => {
                 let dlhs = arg_derivative(self, 0);
                 let drhs = arg_derivative(self, 1);
-                self.ins().fadd(dlhs, drhs)
+                self.add(dlhs, drhs)
             }
 
             Opcode::Fsub => {
                 let dlhs = arg_derivative(self, 0);
                 let drhs = arg_derivative(self, 1);
-                self.ins().fsub(dlhs, drhs)
+                self.sub(dlhs, drhs)
             }
 
             Opcode::Fmul => gen_mul_derivative(self,  arg0,arg1, false),
@@ -793,13 +778,13 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
             | Opcode::Sinh
             | Opcode::Cosh
             | Opcode::Tan
-            | Opcode::Tanh => {
+            | Opcode::Tanh
+            | Opcode::Fabs => {
                 let darg = arg_derivative(self, 0);
-                match darg{
-                        F_ZERO => return,
-                        F_ONE => cache[0].unwrap_unchecked(),
-                        _ => self.ins().fmul(darg, cache[0].unwrap_unchecked()),
+                if darg == F_ZERO{
+                    return
                 }
+                self.mul(darg, cache[0].unwrap_unchecked())
             }
 
             Opcode::Ln
@@ -823,41 +808,29 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
 
                 let sum1 = if dlhs == F_ZERO{
                     F_ZERO
-                }else if dlhs == F_ONE{
-                   cache[0].unwrap_unchecked()
-                }else if cache[0].unwrap_unchecked() == F_ONE{
-                    dlhs
-                }
-                else{
-                    self.ins().fmul(dlhs, cache[0].unwrap_unchecked())
+                }else{
+                    self.mul(dlhs, cache[0].unwrap_unchecked())
                 };
 
                 let inner = if drhs == F_ZERO{
                     sum1
                 }else{
-                    let sum2 = if drhs == F_ONE{
-                       cache[1].unwrap_unchecked()
-                    }else{
-                        self.ins().fmul(drhs, cache[1].unwrap_unchecked())
-                    };
-
-                    if sum1 == F_ZERO{
-                        sum2
-                    }else{
-                        self.ins().fadd(sum1, sum2)
-                    }
+                    let sum2 = self.mul(drhs, cache[1].unwrap_unchecked());
+                    self.add(sum1, sum2)
                 };
                 if inner == F_ZERO{
-                    F_ZERO
-                }else{
-                    self.ins().fmul(inner, cache[2].unwrap_unchecked())
+                    return
                 }
+                self.mul(inner, cache[2].unwrap_unchecked())
             }
 
             Opcode::Hypot => {
                 let dlhs = arg_derivative(self, 0);
                 let drhs = arg_derivative(self, 1);
-                let sum = self.ins().fadd(dlhs, drhs);
+                let sum = self.add(dlhs, drhs);
+                if sum == F_ZERO{
+                    return
+                }
                 self.ins().fdiv(sum, cache[0].unwrap_unchecked())
             }
             Opcode::Br | Opcode::Jmp | Opcode::Phi  => unreachable!(),
@@ -866,6 +839,43 @@ impl<'a, 'u> DerivativeBuilder<'a, 'u> {
         self.insert_derivative(res, unknown, val)
     }
 
+    /// Builds `lhs + rhs` without emitting an instruction if either operand is zero.
+    fn add(&mut self, lhs: Value, rhs: Value) -> Value {
+        match (lhs, rhs) {
+            (F_ZERO, val) | (val, F_ZERO) => val,
+            _ => self.ins().fadd(lhs, rhs),
+        }
+    }
+
+    /// Builds `lhs - rhs` without emitting an instruction if `rhs` is zero.
+    /// `0 - rhs` is kept as is since it is not the same as `-rhs` for signed zeros and NaNs.
+    fn sub(&mut self, lhs: Value, rhs: Value) -> Value {
+        if rhs == F_ZERO {
+            lhs
+        } else {
+            self.ins().fsub(lhs, rhs)
+        }
+    }
+
+    /// Builds `lhs * rhs` without emitting an instruction if either operand is zero or one.
+    fn mul(&mut self, lhs: Value, rhs: Value) -> Value {
+        match (lhs, rhs) {
+            (F_ZERO, _) | (_, F_ZERO) => F_ZERO,
+            (F_ONE, val) | (val, F_ONE) => val,
+            _ => self.ins().fmul(lhs, rhs),
+        }
+    }
+
+    /// Builds `-val` without emitting an instruction if `val` is zero or (minus) one.
+    fn neg(&mut self, val: Value) -> Value {
+        match val {
+            F_ZERO => F_ZERO,
+            F_ONE => F_N_ONE,
+            F_N_ONE => F_ONE,
+            _ => self.ins().fneg(val),
+        }
+    }
+
     fn simplified_mul(&mut self, lhs: Value, dlhs: Value, rhs: Value, res: Value) -> Value {
         // make sure that x = A * exp(C) is derived as
         // A' * exp(C) + x*C' instead of A'*B + A*(exp(C)*C')
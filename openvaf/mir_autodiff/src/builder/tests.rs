@@ -475,28 +475,26 @@ fn third_order_asin() {
             v107 = fadd v10, v10
             v108 = fsub v3, v107
             v109 = fdiv v108, v105
-            v110 = fmul v109, v6
-            v111 = fdiv v110, v106
-            v112 = fsub v3, v111
-            v113 = fmul v105, v105
-            v114 = fmul v106, v106
-            v115 = fmul v109, v11
+            v110 = fdiv v109, v106
+            v111 = fsub v3, v110
+            v112 = fmul v105, v105
+            v113 = fmul v106, v106
+            v114 = fmul v109, v11
+            v115 = fmul v109, v103
             v116 = fmul v109, v103
-            v117 = fmul v109, v103
-            v118 = fadd v116, v117
-            v119 = fadd v6, v6
-            v120 = fsub v3, v119
-            v121 = fdiv v120, v105
-            v122 = fmul v115, v108
-            v123 = fdiv v122, v113
-            v124 = fsub v121, v123
-            v125 = fmul v124, v6
-            v126 = fdiv v125, v106
-            v127 = fmul v118, v110
-            v128 = fdiv v127, v114
-            v129 = fsub v126, v128
-            v130 = fsub v3, v129
-            v100 = optbarrier v130
+            v117 = fadd v115, v116
+            v118 = fadd v6, v6
+            v119 = fsub v3, v118
+            v120 = fdiv v119, v105
+            v121 = fmul v114, v108
+            v122 = fdiv v121, v112
+            v123 = fsub v120, v122
+            v124 = fdiv v123, v106
+            v125 = fmul v117, v109
+            v126 = fdiv v125, v113
+            v127 = fsub v124, v126
+            v128 = fsub v3, v127
+            v100 = optbarrier v128
         }
     "#]];
 
@@ -538,29 +536,27 @@ fn third_order_acos() {
             v109 = fsub v3, v108
             v110 = fdiv v109, v106
             v111 = fneg v110
-            v112 = fmul v111, v6
-            v113 = fdiv v112, v107
-            v114 = fsub v3, v113
-            v115 = fmul v106, v106
-            v116 = fmul v107, v107
-            v117 = fmul v110, v11
+            v112 = fdiv v111, v107
+            v113 = fsub v3, v112
+            v114 = fmul v106, v106
+            v115 = fmul v107, v107
+            v116 = fmul v110, v11
+            v117 = fmul v111, v104
             v118 = fmul v111, v104
-            v119 = fmul v111, v104
-            v120 = fadd v118, v119
-            v121 = fadd v6, v6
-            v122 = fsub v3, v121
-            v123 = fdiv v122, v106
-            v124 = fmul v117, v109
-            v125 = fdiv v124, v115
-            v126 = fsub v123, v125
-            v127 = fneg v126
-            v128 = fmul v127, v6
-            v129 = fdiv v128, v107
-            v130 = fmul v120, v112
-            v131 = fdiv v130, v116
-            v132 = fsub v129, v131
-            v133 = fsub v3, v132
-            v100 = optbarrier v133
+            v119 = fadd v117, v118
+            v120 = fadd v6, v6
+            v121 = fsub v3, v120
+            v122 = fdiv v121, v106
+            v123 = fmul v116, v109
+            v124 = fdiv v123, v114
+            v125 = fsub v122, v124
+            v126 = fneg v125
+            v127 = fdiv v126, v107
+            v128 = fmul v119, v111
+            v129 = fdiv v128, v115
+            v130 = fsub v127, v129
+            v131 = fsub v3, v130
+            v100 = optbarrier v131
         }
     "#]];
 
@@ -598,30 +594,26 @@ fn third_order_acosh() {
             v105 = fmul v11, v103
             v106 = fmul v103, v103
             v107 = fadd v10, v10
-            v108 = fsub v107, v3
-            v109 = fdiv v108, v105
-            v110 = fmul v109, v6
-            v111 = fdiv v110, v106
-            v112 = fsub v3, v111
-            v113 = fmul v105, v105
-            v114 = fmul v106, v106
-            v115 = fmul v109, v11
-            v116 = fmul v109, v103
-            v117 = fmul v109, v103
-            v118 = fadd v116, v117
-            v119 = fadd v6, v6
-            v120 = fsub v119, v3
-            v121 = fdiv v120, v105
-            v122 = fmul v115, v108
-            v123 = fdiv v122, v113
-            v124 = fsub v121, v123
-            v125 = fmul v124, v6
-            v126 = fdiv v125, v106
-            v127 = fmul v118, v110
-            v128 = fdiv v127, v114
-            v129 = fsub v126, v128
-            v130 = fsub v3, v129
-            v100 = optbarrier v130
+            v108 = fdiv v107, v105
+            v109 = fdiv v108, v106
+            v110 = fsub v3, v109
+            v111 = fmul v105, v105
+            v112 = fmul v106, v106
+            v113 = fmul v108, v11
+            v114 = fmul v108, v103
+            v115 = fmul v108, v103
+            v116 = fadd v114, v115
+            v117 = fadd v6, v6
+            v118 = fdiv v117, v105
+            v119 = fmul v113, v107
+            v120 = fdiv v119, v111
+            v121 = fsub v118, v120
+            v122 = fdiv v121, v106
+            v123 = fmul v116, v108
+            v124 = fdiv v123, v112
+            v125 = fsub v122, v124
+            v126 = fsub v3, v125
+            v100 = optbarrier v126
         }
     "#]];
 
@@ -646,7 +638,6 @@ fn third_order_tan() {
     let expect = expect![[r#"
         function %bar(v10) {
             inst0 = const fn %ddx_v10(1) -> 1
-            v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
 
         block0:
@@ -656,16 +647,14 @@ fn third_order_tan() {
             v103 = fmul v102, v12
             v104 = fmul v102, v12
             v105 = fadd v103, v104
-            v106 = fadd v3, v105
-            v107 = fmul v106, v12
-            v108 = fmul v102, v102
-            v109 = fadd v107, v108
-            v110 = fmul v106, v12
-            v111 = fmul v102, v102
-            v112 = fadd v110, v111
-            v113 = fadd v109, v112
-            v114 = fadd v3, v113
-            v100 = optbarrier v114
+            v106 = fmul v105, v12
+            v107 = fmul v102, v102
+            v108 = fadd v106, v107
+            v109 = fmul v105, v12
+            v110 = fmul v102, v102
+            v111 = fadd v109, v110
+            v112 = fadd v108, v111
+            v100 = optbarrier v112
         }
     "#]];
 
@@ -746,16 +735,15 @@ fn second_order_pow() {
             v103 = fadd v6, v102
             v104 = fmul v103, v12
             v105 = fdiv v6, v10
-            v106 = fadd v3, v105
-            v107 = fmul v106, v12
-            v108 = fmul v104, v103
-            v109 = fadd v107, v108
+            v106 = fmul v105, v12
+            v107 = fmul v104, v103
+            v108 = fadd v106, v107
             jmp block1
 
         block1:
-            v110 = phi [v3, block0], [v104, block2]
-            v111 = phi [v3, block0], [v109, block2]
-            v100 = optbarrier v111
+            v109 = phi [v3, block0], [v104, block2]
+            v110 = phi [v3, block0], [v108, block2]
+            v100 = optbarrier v110
         }
     "#]];
 
@@ -790,23 +778,19 @@ fn third_order_atan() {
             v103 = fdiv v6, v102
             v104 = fmul v102, v102
             v105 = fadd v10, v10
-            v106 = fadd v3, v105
-            v107 = fmul v106, v6
-            v108 = fdiv v107, v104
-            v109 = fsub v3, v108
-            v110 = fmul v104, v104
-            v111 = fmul v106, v102
-            v112 = fmul v106, v102
-            v113 = fadd v111, v112
-            v114 = fadd v6, v6
-            v115 = fadd v3, v114
-            v116 = fmul v115, v6
-            v117 = fdiv v116, v104
-            v118 = fmul v113, v107
-            v119 = fdiv v118, v110
-            v120 = fsub v117, v119
-            v121 = fsub v3, v120
-            v100 = optbarrier v121
+            v106 = fdiv v105, v104
+            v107 = fsub v3, v106
+            v108 = fmul v104, v104
+            v109 = fmul v105, v102
+            v110 = fmul v105, v102
+            v111 = fadd v109, v110
+            v112 = fadd v6, v6
+            v113 = fdiv v112, v104
+            v114 = fmul v111, v105
+            v115 = fdiv v114, v108
+            v116 = fsub v113, v115
+            v117 = fsub v3, v116
+            v100 = optbarrier v117
         }
     "#]];
 
@@ -842,22 +826,20 @@ fn third_order_atanh() {
             v104 = fmul v102, v102
             v105 = fadd v10, v10
             v106 = fsub v3, v105
-            v107 = fmul v106, v6
-            v108 = fdiv v107, v104
-            v109 = fsub v3, v108
-            v110 = fmul v104, v104
+            v107 = fdiv v106, v104
+            v108 = fsub v3, v107
+            v109 = fmul v104, v104
+            v110 = fmul v106, v102
             v111 = fmul v106, v102
-            v112 = fmul v106, v102
-            v113 = fadd v111, v112
-            v114 = fadd v6, v6
-            v115 = fsub v3, v114
-            v116 = fmul v115, v6
-            v117 = fdiv v116, v104
-            v118 = fmul v113, v107
-            v119 = fdiv v118, v110
-            v120 = fsub v117, v119
-            v121 = fsub v3, v120
-            v100 = optbarrier v121
+            v112 = fadd v110, v111
+            v113 = fadd v6, v6
+            v114 = fsub v3, v113
+            v115 = fdiv v114, v104
+            v116 = fmul v112, v106
+            v117 = fdiv v116, v109
+            v118 = fsub v115, v117
+            v119 = fsub v3, v118
+            v100 = optbarrier v119
         }
     "#]];
 
@@ -947,50 +929,90 @@ fn subgraph() {
             inst0 = const fn %ddx_v10(1) -> 1
             inst1 = const fn %ddx_v11(1) -> 1
             inst2 = const fn %ddx_v12(1) -> 1
-            v3 = fconst 0.0
-            v6 = fconst 0x1.0000000000000p0
             v20 = fconst 0x1.bcb7b1526e50ep-2
 
         block0:
             v22 = fadd v10, v11
-            v106 = fadd v6, v3
-            v107 = fadd v3, v6
             v14 = fadd v22, v12
-            v108 = fadd v3, v6
-            v109 = fadd v6, v3
             v15 = fmul v14, v20
             v16 = fmul v15, v20
-            v110 = fmul v20, v20
+            v106 = fmul v20, v20
             v17 = fmul v16, v20
-            v111 = fmul v110, v20
+            v107 = fmul v106, v20
             v23 = fmul v22, v20
             v24 = fmul v23, v20
-            v112 = fmul v20, v20
+            v108 = fmul v20, v20
             v25 = fmul v24, v20
-            v113 = fmul v112, v20
+            v109 = fmul v108, v20
             v26 = fmul v25, v20
-            v114 = fmul v113, v20
+            v110 = fmul v109, v20
             v27 = fmul v26, v20
-            v115 = fmul v114, v20
-            v116 = fmul v106, v115
-            v117 = fmul v107, v115
+            v111 = fmul v110, v20
             v18 = fmul v17, v20
-            v118 = fmul v111, v20
+            v112 = fmul v107, v20
             v19 = fmul v18, v20
-            v119 = fmul v118, v20
+            v113 = fmul v112, v20
             v40 = fmul v19, v20
-            v120 = fmul v119, v20
+            v114 = fmul v113, v20
             v41 = fmul v40, v20
-            v121 = fmul v120, v20
-            v122 = fmul v108, v121
-            v123 = fmul v109, v121
-            v124 = fmul v106, v123
-            v125 = fmul v107, v123
-            v101 = optbarrier v124
+            v115 = fmul v114, v20
+            v101 = optbarrier v115
             v102 = optbarrier v29
-            v103 = optbarrier v122
-            v104 = optbarrier v116
-            v105 = optbarrier v117
+            v103 = optbarrier v115
+            v104 = optbarrier v111
+            v105 = optbarrier v111
+        }
+    "#]];
+
+    check_simple(src, expect);
+}
+
+#[test]
+fn trivial_derivatives() {
+    // derivatives that are zero or one do not require any instructions
+    let src = r##"
+        function %bar(v10, v11, v12) {
+            fn0 = const fn %ddx_v10(1) -> 1
+            v20 = fconst 0x1.8000000000000p1
+
+        block0:
+            v13 = fadd v10, v11
+            v14 = fsub v10, v11
+            v15 = fneg v10
+            v16 = fmul v10, v11
+            v17 = fmul v11, v20
+            v18 = fneg v17
+            v21 = call fn0 (v13)
+            v22 = call fn0 (v14)
+            v23 = call fn0 (v15)
+            v24 = call fn0 (v16)
+            v25 = call fn0 (v18)
+            v101 = optbarrier v21
+            v102 = optbarrier v22
+            v103 = optbarrier v23
+            v104 = optbarrier v24
+            v105 = optbarrier v25
+        }"##;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+            inst0 = const fn %ddx_v10(1) -> 1
+            v3 = fconst 0.0
+            v6 = fconst 0x1.0000000000000p0
+            v7 = fconst -0x1.0000000000000p0
+            v20 = fconst 0x1.8000000000000p1
+
+        block0:
+            v13 = fadd v10, v11
+            v14 = fsub v10, v11
+            v15 = fneg v10
+            v16 = fmul v10, v11
+            v17 = fmul v11, v20
+            v18 = fneg v17
+            v101 = optbarrier v6
+            v102 = optbarrier v6
+            v103 = optbarrier v7
+            v104 = optbarrier v11
+            v105 = optbarrier v3
         }
     "#]];
 
@@ -145,13 +145,24 @@ impl<'a> Interpreter<'a> {
             mir::Opcode::Asinh => f64::asinh(args(0).f64()).into(),
             mir::Opcode::Acosh => f64::acosh(args(0).f64()).into(),
             mir::Opcode::Atanh => f64::atanh(args(0).f64()).into(),
-            mir::Opcode::Iadd => (args(0).i32() + args(1).i32()).into(),
-            mir::Opcode::Isub => (args(0).i32() - args(1).i32()).into(),
-            mir::Opcode::Imul => (args(0).i32() * args(1).i32()).into(),
-            mir::Opcode::Idiv => (args(0).i32() / args(1).i32()).into(),
-            mir::Opcode::Irem => (args(0).i32() % args(1).i32()).into(),
-            mir::Opcode::Ishl => (args(0).i32() << args(1).i32()).into(),
-            mir::Opcode::Ishr => (args(0).i32() >> args(1).i32()).into(),
+            mir::Opcode::Fabs => f64::abs(args(0).f64()).into(),
+            mir::Opcode::Iadd => args(0).i32().wrapping_add(args(1).i32()).into(),
+            mir::Opcode::Isub => args(0).i32().wrapping_sub(args(1).i32()).into(),
+            mir::Opcode::Imul => args(0).i32().wrapping_mul(args(1).i32()).into(),
+            mir::Opcode::Idiv => args(0).i32().wrapping_div(args(1).i32()).into(),
+            mir::Opcode::Irem => args(0).i32().wrapping_rem(args(1).i32()).into(),
+            // out of range shifts are undefined (and not folded by the const_eval), shift out
+            // all bits instead of panicking
+            mir::Opcode::Ishl => {
+                let (lhs, rhs) = (args(0).i32(), args(1).i32());
+                let val = if (0..32).contains(&rhs) { lhs << rhs } else { 0 };
+                val.into()
+            }
+            mir::Opcode::Ishr => {
+                let (lhs, rhs) = (args(0).i32(), args(1).i32());
+                let val = if (0..32).contains(&rhs) { lhs >> rhs } else { lhs >> 31 };
+                val.into()
+            }
             mir::Opcode::Ixor => (args(0).i32() ^ args(1).i32()).into(),
             mir::Opcode::Iand => (args(0).i32() & args(1).i32()).into(),
             mir::Opcode::Ior => (args(0).i32() | args(1).i32()).into(),
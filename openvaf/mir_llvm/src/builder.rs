@@ -818,6 +818,7 @@ impl<'ll> Builder<'_, '_, 'll> {
             Opcode::Asinh => NonNull::from(self.intrinsic(args, "asinh")).as_ptr(),
             Opcode::Acosh => NonNull::from(self.intrinsic(args, "acosh")).as_ptr(),
             Opcode::Atanh => NonNull::from(self.intrinsic(args, "atanh")).as_ptr(),
+            Opcode::Fabs => NonNull::from(self.intrinsic(args, "llvm.fabs.f64")).as_ptr(),
             Opcode::Pow => NonNull::from(self.intrinsic(args, "llvm.pow.f64")).as_ptr(),
             Opcode::OptBarrier => NonNull::from(self.values[args[0]].get(self)).as_ptr(),
             Opcode::Br | Opcode::Jmp | Opcode::Call | Opcode::Phi => unreachable!(),
@@ -862,6 +863,7 @@ impl<'ll> Builder<'_, '_, 'll> {
                 | Opcode::Asinh
                 | Opcode::Acosh
                 | Opcode::Atanh
+                | Opcode::Fabs
                 | Opcode::Pow
         ) {
             match fast_math_mode {
@@ -23,6 +23,8 @@ pub struct CodegenCx<'a, 'll> {
     // pub target_cpu: &'a str,
     pub literals: &'a Rodeo,
     str_lit_cache: RefCell<AHashMap<Spur, &'ll Value>>,
+    /// Constant globals by their initializer, see [`const_global`](Self::const_global).
+    pub(crate) const_globals: RefCell<AHashMap<*const Value, &'ll Value>>,
     pub(crate) intrinsics: RefCell<AHashMap<&'static str, (&'ll Type, &'ll Value)>>,
     pub(crate) local_gen_sym_counter: Cell<u32>,
     pub(crate) tys: Types<'ll>,
@@ -42,6 +44,7 @@ impl<'a, 'll> CodegenCx<'a, 'll> {
             llcx: unsafe { &*llvm_module.llcx },
             str_lit_cache: RefCell::new(AHashMap::with_capacity(literals.len())),
             literals,
+            const_globals: RefCell::new(AHashMap::new()),
             intrinsics: RefCell::new(AHashMap::new()),
             local_gen_sym_counter: Cell::new(0),
             // target_cpu,
@@ -115,20 +118,7 @@ impl<'a, 'll> CodegenCx<'a, 'll> {
                 0,
             )
         };
-        let sym = self.generate_local_symbol_name("str");
-        let ty = self.val_ty(unsafe { &*val });
-        let global = self
-            .define_global(&sym, ty)
-            .unwrap_or_else(|| unreachable!("symbol {} already defined", sym));
-
-        unsafe {
-            llvm_sys::core::LLVMSetInitializer(NonNull::from(global).as_ptr(), val);
-            llvm_sys::core::LLVMSetGlobalConstant(NonNull::from(global).as_ptr(), 1);
-            llvm_sys::core::LLVMSetLinkage(
-                NonNull::from(global).as_ptr(),
-                llvm_sys::LLVMLinkage::LLVMInternalLinkage,
-            );
-        }
+        let global = self.const_global("str", unsafe { &*val });
         self.str_lit_cache.borrow_mut().insert(lit, global);
         global
     }
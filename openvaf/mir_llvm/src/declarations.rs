@@ -139,10 +139,15 @@ impl<'a, 'll> CodegenCx<'a, 'll> {
     pub fn get_declared_value(&self, name: &str) -> Option<&'ll Value> {
         let name = CString::new(name).unwrap();
         unsafe {
-            Some(&*llvm_sys::core::LLVMGetNamedGlobal(
+            let global = llvm_sys::core::LLVMGetNamedGlobal(
                 NonNull::from(self.llmod).as_ptr(),
                 name.as_ptr(),
-            ))
+            );
+            if global.is_null() {
+                None
+            } else {
+                Some(&*global)
+            }
         }
     }
 
@@ -216,11 +221,22 @@ impl<'a, 'll> CodegenCx<'a, 'll> {
         }
 
         let val = self.const_arr(elem_ty, vals);
-        let ty = self.ty_array(elem_ty, vals.len() as u32);
+        self.const_global("arr", val)
+    }
+
+    /// Returns an internal constant global that is initialized with `val`.
+    ///
+    /// LLVM uniques constants by their contents, so structurally identical initializers
+    /// are the same value. The global is therefore only emitted once and shared by all
+    /// users with an identical initializer.
+    pub fn const_global(&self, prefix: &str, val: &'ll Value) -> &'ll Value {
+        if let Some(&global) = self.const_globals.borrow().get(&(val as *const Value)) {
+            return global;
+        }
 
-        let sym = self.generate_local_symbol_name("arr");
+        let sym = self.generate_local_symbol_name(prefix);
         let global = self
-            .define_global(&sym, ty)
+            .define_global(&sym, self.val_ty(val))
             .unwrap_or_else(|| unreachable!("symbol {} already defined", sym));
 
         unsafe {
@@ -234,6 +250,7 @@ impl<'a, 'll> CodegenCx<'a, 'll> {
                 llvm_sys::LLVMLinkage::LLVMInternalLinkage,
             );
         }
+        self.const_globals.borrow_mut().insert(val, global);
         global
     }
 
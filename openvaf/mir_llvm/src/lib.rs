@@ -81,8 +81,17 @@ impl Drop for LLVMString {
     }
 }
 
-use llvm_sys::core::{LLVMGetDiagInfoDescription, LLVMGetDiagInfoSeverity};
-use llvm_sys::target_machine::{LLVMCodeGenOptLevel, LLVMGetHostCPUFeatures, LLVMGetHostCPUName};
+use std::sync::Once;
+
+use llvm_sys::core::{LLVMGetDiagInfoDescription, LLVMGetDiagInfoSeverity, LLVMGetVersion};
+use llvm_sys::target::{
+    LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargetMCs,
+    LLVM_InitializeAllTargets, LLVM_InitializeNativeAsmPrinter, LLVM_InitializeNativeTarget,
+};
+use llvm_sys::target_machine::{
+    LLVMCodeGenOptLevel, LLVMCodeModel, LLVMDisposeTargetMachine, LLVMGetHostCPUFeatures,
+    LLVMGetHostCPUName, LLVMRelocMode,
+};
 use target::spec::Target;
 
 mod builder;
@@ -98,6 +107,31 @@ mod tests;
 pub use builder::{Builder, BuilderVal, MemLoc};
 pub use callbacks::CallbackFun;
 pub use context::CodegenCx;
+
+static LLVM_INIT: Once = Once::new();
+
+fn initialize_llvm() {
+    LLVM_INIT.call_once(|| unsafe {
+        if LLVM_InitializeNativeTarget() != 0 {
+            panic!("Failed to initialize native target");
+        }
+        if LLVM_InitializeNativeAsmPrinter() != 0 {
+            panic!("Failed to initialize native ASM printer");
+        }
+        // other targets are only required for cross compilation
+        LLVM_InitializeAllTargetInfos();
+        LLVM_InitializeAllTargets();
+        LLVM_InitializeAllTargetMCs();
+        LLVM_InitializeAllAsmPrinters();
+    });
+}
+
+/// The version of the LLVM library OpenVAF is linked against.
+fn llvm_version() -> (u32, u32, u32) {
+    let (mut major, mut minor, mut patch) = (0, 0, 0);
+    unsafe { LLVMGetVersion(&mut major, &mut minor, &mut patch) };
+    (major, minor, patch)
+}
 pub struct LLVMBackend<'t> {
     target: &'t Target,
     target_cpu: String,
@@ -105,12 +139,15 @@ pub struct LLVMBackend<'t> {
 }
 
 impl<'t> LLVMBackend<'t> {
+    /// Initializes LLVM and checks that it can generate code for `target`.
+    /// Returns an error that explains how to obtain a suitable LLVM otherwise.
     pub fn new(
         _cg_opts: &[String],
         target: &'t Target,
         mut target_cpu: String,
         target_features: &[String],
-    ) -> LLVMBackend<'t> {
+    ) -> Result<LLVMBackend<'t>, String> {
+        initialize_llvm();
         if target_cpu == "generic" {
             target_cpu = target.options.cpu.clone();
         }
@@ -167,7 +204,31 @@ impl<'t> LLVMBackend<'t> {
         // https://reviews.llvm.org/D145043
         //llvm_sys::initialization::init(cg_opts, &[]);
         //https://github.com/llvm/llvm-project/commit/62ef97e0631ff41ad53436477cecc7d3eb244d1b
-        LLVMBackend { target, target_cpu, features: features.join(",") }
+        let features = features.join(",");
+        let tm = unsafe {
+            create_target(
+                &target.llvm_target,
+                &target_cpu,
+                &features,
+                LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
+                LLVMRelocMode::LLVMRelocPIC,
+                LLVMCodeModel::LLVMCodeModelDefault,
+            )
+        };
+        match tm {
+            Ok(tm) => unsafe { LLVMDisposeTargetMachine(tm) },
+            Err(err) => {
+                let (major, minor, patch) = llvm_version();
+                return Err(format!(
+                    "LLVM {major}.{minor}.{patch} can not generate code for target '{}': {err}\n\
+                     help: the LLVM library OpenVAF is linked against was built without the {} \
+                     backend, install an LLVM {major} build that includes all targets",
+                    target.llvm_target, target.arch
+                ));
+            }
+        }
+
+        Ok(LLVMBackend { target, target_cpu, features })
     }
 
     /// # Safety
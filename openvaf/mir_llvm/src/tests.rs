@@ -1 +1,35 @@
+use std::ptr;
 
+use lasso::Rodeo;
+use llvm_sys::target::LLVM_InitializeNativeTarget;
+use llvm_sys::target_machine::LLVMCodeGenOptLevel;
+use target::spec::Target;
+
+use crate::LLVMBackend;
+
+#[test]
+fn shared_const_globals() {
+    assert_eq!(unsafe { LLVM_InitializeNativeTarget() }, 0);
+    let target = Target::host_target().unwrap();
+    let back = LLVMBackend::new(&[], &target, "generic".to_owned(), &[]).unwrap();
+    let module =
+        unsafe { back.new_module("const_globals", LLVMCodeGenOptLevel::LLVMCodeGenLevelNone) }
+            .unwrap();
+    let mut literals = Rodeo::new();
+    let lit = literals.get_or_intern("repeated literal");
+    let cx = unsafe { back.new_ctx(&literals, &module) };
+
+    let string = cx.const_str(lit);
+    assert!(ptr::eq(string, cx.const_str(lit)));
+    assert!(ptr::eq(string, cx.const_str_uninterned("repeated literal")));
+
+    let arr = cx.const_arr_ptr(cx.ty_int(), &[cx.const_int(1), cx.const_int(2)]);
+    assert!(ptr::eq(arr, cx.const_arr_ptr(cx.ty_int(), &[cx.const_int(1), cx.const_int(2)])));
+    let other = cx.const_arr_ptr(cx.ty_int(), &[cx.const_int(2), cx.const_int(1)]);
+    assert!(!ptr::eq(arr, other));
+
+    let ir = cx.to_str().to_string();
+    assert_eq!(ir.matches("c\"repeated literal").count(), 1, "{ir}");
+    assert_eq!(ir.matches("[i32 1, i32 2]").count(), 1, "{ir}");
+    assert_eq!(ir.matches("[i32 2, i32 1]").count(), 1, "{ir}");
+}
@@ -0,0 +1,103 @@
+use bitset::BitSet;
+use mir::{Block, ControlFlowGraph, DominatorTree, Function, Inst, InstructionData, Opcode};
+
+#[cfg(test)]
+mod tests;
+
+/// Moves pure instructions whose result has exactly one use into the block that contains that
+/// use. Instructions that are only needed in a rarely taken branch are therefore only computed
+/// when that branch is actually taken.
+///
+/// Instructions are only moved into blocks dominated by the block they were originally placed
+/// in and never into a loop body. Sinking can therefore never increase the number of times an
+/// instruction is executed. Phis, terminators, optbarriers and calls with sideeffects are never
+/// moved.
+///
+/// Requires that `cfg` and the dominator tree (not the post dominator tree) are up to date.
+pub fn sink_instructions(func: &mut Function, cfg: &ControlFlowGraph, dom_tree: &DominatorTree) {
+    let entry = if let Some(entry) = func.layout.entry_block() { entry } else { return };
+    let loop_blocks = loop_blocks(func, cfg, dom_tree);
+
+    // visit successors before their predecessors (and the instructions in a block back to
+    // front) so that an instruction that was just sunk can pull its operands along
+    let blocks: Vec<_> = cfg.postorder_from(entry).collect();
+    for bb in blocks {
+        let mut cursor = func.layout.block_inst_cursor(bb);
+        while let Some(inst) = cursor.next_back(&func.layout) {
+            if let Some(target) = sink_target(func, dom_tree, &loop_blocks, bb, inst) {
+                func.layout.remove_inst(inst);
+                func.layout.prepend_inst(inst, target);
+            }
+        }
+    }
+}
+
+/// Returns the instruction that `inst` should be placed in front of (if `inst` can be sunk).
+fn sink_target(
+    func: &Function,
+    dom_tree: &DominatorTree,
+    loop_blocks: &BitSet<Block>,
+    bb: Block,
+    inst: Inst,
+) -> Option<Inst> {
+    match func.dfg.insts[inst] {
+        InstructionData::PhiNode(_) | InstructionData::Unary { opcode: Opcode::OptBarrier, .. } => {
+            return None
+        }
+        ref data if data.is_terminator() => return None,
+        _ => (),
+    }
+    if func.dfg.has_sideeffects(inst, true) {
+        return None;
+    }
+
+    let res = if let [res] = func.dfg.inst_results(inst) { *res } else { return None };
+    let mut uses = func.dfg.uses(res);
+    let use_ = uses.next()?;
+    if uses.next().is_some() {
+        return None;
+    }
+
+    let (user, pos) = func.dfg.use_to_operand(use_);
+    let (target_bb, target) = match func.dfg.insts[user] {
+        // a value used by a phi must be available at the end of the corresponding predecessor
+        InstructionData::PhiNode(ref phi) => {
+            let (pred, _) =
+                phi.blocks.iter(&func.dfg.phi_forest).find(|&(_, arg)| arg == pos as u32)?;
+            (pred, func.layout.block_terminator(pred)?)
+        }
+        _ => (func.layout.inst_block(user)?, user),
+    };
+
+    if target_bb == bb || loop_blocks.contains(target_bb) || !dom_tree.dominates(target_bb, bb) {
+        return None;
+    }
+
+    Some(target)
+}
+
+/// Computes all blocks that are part of a (natural) loop.
+fn loop_blocks(func: &Function, cfg: &ControlFlowGraph, dom_tree: &DominatorTree) -> BitSet<Block> {
+    let mut res = BitSet::new_empty(func.layout.num_blocks());
+    let mut work_list = Vec::new();
+    for header in func.layout.blocks() {
+        for latch in cfg.pred_iter(header) {
+            // a backedge is an edge to a block that dominates the source of the edge
+            if !dom_tree.dominates(latch, header) {
+                continue;
+            }
+            res.insert(header);
+            if res.insert(latch) {
+                work_list.push(latch);
+            }
+            while let Some(bb) = work_list.pop() {
+                for pred in cfg.pred_iter(bb) {
+                    if res.insert(pred) {
+                        work_list.push(pred);
+                    }
+                }
+            }
+        }
+    }
+    res
+}
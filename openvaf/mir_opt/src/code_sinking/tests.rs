@@ -0,0 +1,134 @@
+use expect_test::{expect, Expect};
+use mir::{ControlFlowGraph, DominatorTree};
+use mir_reader::parse_function;
+
+use crate::sink_instructions;
+
+fn check(src: &str, expect: Expect) {
+    let (mut func, _) = parse_function(src).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(&func, &cfg, true, false, false);
+    sink_instructions(&mut func, &cfg, &dom_tree);
+    expect.assert_eq(&func.to_debug_string());
+}
+
+#[test]
+fn sink_into_branch() {
+    let src = r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fdiv v10, v11
+            v14 = fmul v13, v10
+            br v12, block1, block2
+        block1:
+            v15 = fadd v14, v11
+            jmp block2
+        block2:
+            v16 = phi [v15, block1], [v10, block0]
+            v17 = optbarrier v16
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            br v12, block1, block2
+
+        block1:
+            v13 = fdiv v10, v11
+            v14 = fmul v13, v10
+            v15 = fadd v14, v11
+            jmp block2
+
+        block2:
+            v16 = phi [v10, block0], [v15, block1]
+            v17 = optbarrier v16
+        }
+    "#]];
+    check(src, expect);
+}
+
+#[test]
+fn sink_to_phi_edge() {
+    let src = r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fdiv v10, v11
+            v14 = fmul v10, v11
+            br v12, block1, block2
+        block1:
+            jmp block3
+        block2:
+            jmp block3
+        block3:
+            v15 = phi [v13, block1], [v14, block2]
+            v16 = optbarrier v15
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            br v12, block1, block2
+
+        block1:
+            v13 = fdiv v10, v11
+            jmp block3
+
+        block2:
+            v14 = fmul v10, v11
+            jmp block3
+
+        block3:
+            v15 = phi [v13, block1], [v14, block2]
+            v16 = optbarrier v15
+        }
+    "#]];
+    check(src, expect);
+}
+
+#[test]
+fn keep_multiple_uses_and_loops() {
+    let src = r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fdiv v10, v11
+            v14 = fmul v10, v11
+            br v12, block1, block3
+        block1:
+            v15 = phi [v14, block0], [v16, block1]
+            v16 = fadd v15, v13
+            v17 = flt v16, v11
+            br v17, block1, block2
+        block2:
+            v18 = fadd v14, v16
+            jmp block3
+        block3:
+            v19 = phi [v18, block2], [v10, block0]
+            v20 = optbarrier v19
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fdiv v10, v11
+            v14 = fmul v10, v11
+            br v12, block1, block3
+
+        block1:
+            v15 = phi [v14, block0], [v16, block1]
+            v16 = fadd v15, v13
+            v17 = flt v16, v11
+            br v17, block1, block2
+
+        block2:
+            v18 = fadd v14, v16
+            jmp block3
+
+        block3:
+            v19 = phi [v10, block0], [v18, block2]
+            v20 = optbarrier v19
+        }
+    "#]];
+    check(src, expect);
+}
@@ -2,15 +2,24 @@ use std::mem::size_of_val;
 
 use mir::{Const, Function, Opcode, Value, FALSE, F_ONE, F_ZERO, ONE, TRUE, ZERO};
 
-pub fn eval_binary(func: &mut Function, op: Opcode, lhs: Const, rhs: Const) -> Value {
-    match (lhs, rhs) {
+/// Folds a binary operation with constant operands.
+///
+/// Verilog-A integers are 32 bit two's complement integers. Integer arithmetic
+/// that overflows wraps around (just like the generated machine code) instead of
+/// being an error (this includes `i32::MIN / -1`). Divisions by zero and shifts
+/// by a negative amount or by 32 bits or more are not folded because their
+/// result is undefined in the generated machine code.
+pub fn eval_binary(func: &mut Function, op: Opcode, lhs: Const, rhs: Const) -> Option<Value> {
+    let val = match (lhs, rhs) {
         (Const::Int(lhs), Const::Int(rhs)) => match op {
-            Opcode::Iadd => func.dfg.iconst(lhs + rhs),
-            Opcode::Isub => func.dfg.iconst(lhs - rhs),
-            Opcode::Imul => func.dfg.iconst(lhs * rhs),
-            Opcode::Idiv => func.dfg.iconst(lhs / rhs),
-            Opcode::Irem => func.dfg.iconst(lhs % rhs),
+            Opcode::Iadd => func.dfg.iconst(lhs.wrapping_add(rhs)),
+            Opcode::Isub => func.dfg.iconst(lhs.wrapping_sub(rhs)),
+            Opcode::Imul => func.dfg.iconst(lhs.wrapping_mul(rhs)),
+            Opcode::Idiv | Opcode::Irem if rhs == 0 => return None,
+            Opcode::Idiv => func.dfg.iconst(lhs.wrapping_div(rhs)),
+            Opcode::Irem => func.dfg.iconst(lhs.wrapping_rem(rhs)),
 
+            Opcode::Ishl | Opcode::Ishr if !(0..32).contains(&rhs) => return None,
             Opcode::Ishl => func.dfg.iconst(lhs << rhs),
             Opcode::Ishr => func.dfg.iconst(lhs >> rhs),
             Opcode::Ixor => func.dfg.iconst(lhs ^ rhs),
@@ -55,7 +64,8 @@ pub fn eval_binary(func: &mut Function, op: Opcode, lhs: Const, rhs: Const) -> V
             Opcode::Sne | Opcode::Bne => (lhs != rhs).into(),
             _ => unreachable!("invalid operation {} {:?} {:?}", op, lhs, rhs),
         },
-    }
+    };
+    Some(val)
 }
 
 pub fn eval_unary(func: &mut Function, op: Opcode, val: Const) -> Option<Value> {
@@ -84,6 +94,7 @@ pub fn eval_unary(func: &mut Function, op: Opcode, val: Const) -> Option<Value>
                 Opcode::Asinh => func.dfg.f64const(val.asinh()),
                 Opcode::Acosh => func.dfg.f64const(val.acosh()),
                 Opcode::Atanh => func.dfg.f64const(val.atanh()),
+                Opcode::Fabs => func.dfg.f64const(val.abs()),
                 Opcode::FIcast => func.dfg.iconst(val.round() as i32),
                 Opcode::FBcast => (val.abs() != 0.0).into(),
                 Opcode::Fneg => func.dfg.f64const(-val),
@@ -12,10 +12,27 @@ use crate::simplify::SimplifyCtx;
 mod tests;
 
 pub fn sparse_conditional_constant_propagation(func: &mut Function, cfg: &ControlFlowGraph) {
+    propagate_known_params(func, cfg, &[])
+}
+
+/// Runs [`sparse_conditional_constant_propagation`] under the assumption that each parameter
+/// in `known_params` always has the paired constant value. This is useful for facts that are
+/// fixed for a compilation, for example a `$param_given` parameter that is known to be
+/// `true` or `false`. All uses of these parameters are replaced with the constant and branches
+/// that depend on them are folded (the unreachable blocks are removed by `simplify_cfg`).
+pub fn propagate_known_params(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    known_params: &[(Value, Value)],
+) {
     let vals = (0..func.dfg.num_values())
         .map(|val| match func.dfg.value_def(val.into()) {
             ValueDef::Const(_) => FlatSet::Elem(val.into()),
-            ValueDef::Param(_) | ValueDef::Invalid => FlatSet::Top,
+            ValueDef::Param(_) => known_params
+                .iter()
+                .find(|(param, _)| *param == val.into())
+                .map_or(FlatSet::Top, |&(_, const_)| FlatSet::Elem(const_)),
+            ValueDef::Invalid => FlatSet::Top,
             ValueDef::Result(_, _) => FlatSet::Bottom,
         })
         .collect();
@@ -41,12 +58,16 @@ pub fn sparse_conditional_constant_propagation(func: &mut Function, cfg: &Contro
 
     for (val, lattice) in vals.iter_enumerated() {
         if let FlatSet::Elem(const_) = lattice {
-            if let ValueDef::Result(inst, _) = func.dfg.value_def(val) {
-                func.dfg.replace_uses(val, *const_);
-                if func.dfg.inst_results(inst).len() == 1 {
-                    func.dfg.zap_inst(inst);
-                    func.layout.remove_inst(inst)
+            match func.dfg.value_def(val) {
+                ValueDef::Result(inst, _) => {
+                    func.dfg.replace_uses(val, *const_);
+                    if func.dfg.inst_results(inst).len() == 1 {
+                        func.dfg.zap_inst(inst);
+                        func.layout.remove_inst(inst)
+                    }
                 }
+                ValueDef::Param(_) => func.dfg.replace_uses(val, *const_),
+                _ => (),
             }
         } else if let ValueDef::Result(inst, _) = func.dfg.value_def(val) {
             if let Some(bb) = func.layout.inst_block(inst) {
@@ -258,7 +279,8 @@ impl ConstSolver<'_> {
             (FlatSet::Bottom, FlatSet::Top) | (FlatSet::Top, FlatSet::Bottom | FlatSet::Top) => {
                 (lhs, rhs, true)
             }
-            (FlatSet::Elem(lhs), FlatSet::Elem(rhs)) => (lhs, rhs, false),
+            // constant operands that can not be folded (out of range shifts)
+            (FlatSet::Elem(lhs), FlatSet::Elem(rhs)) => (lhs, rhs, true),
             (FlatSet::Elem(lhs), FlatSet::Bottom) => (lhs, rhs, false),
             (FlatSet::Bottom, FlatSet::Elem(rhs)) => (lhs, rhs, false),
             (FlatSet::Bottom, FlatSet::Bottom) => (lhs, rhs, false),
@@ -1,8 +1,13 @@
 use expect_test::{expect, Expect};
-use mir::ControlFlowGraph;
+use mir::{Const, ControlFlowGraph, FALSE};
+use mir_interpret::{Data, Interpreter};
 use mir_reader::parse_function;
+use typed_index_collections::TiSlice;
 
-use crate::sparse_conditional_constant_propagation;
+use crate::{
+    inst_combine, propagate_known_params, simplify_cfg, simplify_cfg_no_phi_merge,
+    sparse_conditional_constant_propagation,
+};
 
 fn check(src: &str, data_flow_result: Expect) {
     let (mut func, _) = parse_function(src).unwrap();
@@ -12,6 +17,26 @@ fn check(src: &str, data_flow_result: Expect) {
     data_flow_result.assert_eq(&func.to_debug_string());
 }
 
+/// Checks that integer operations are folded to the same value that is
+/// computed at runtime (by the interpreter). The folded value is expected
+/// to be the first operand of `v40 = imul vX, v20` where `v20` is set to one.
+fn check_folded_int(src: &str, data_flow_result: Expect) {
+    let (func, _) = parse_function(src).unwrap();
+    let args = [Data::from(1i32)];
+    let mut interpreter = Interpreter::new(&func, TiSlice::from_ref(&[]), TiSlice::from_ref(&args));
+    interpreter.run();
+    let runtime: i32 = interpreter.state.read(40u32.into());
+
+    let (mut folded, _) = parse_function(src).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&folded);
+    sparse_conditional_constant_propagation(&mut folded, &cfg);
+    data_flow_result.assert_eq(&folded.to_debug_string());
+    let inst = folded.dfg.value_def(40u32.into()).unwrap_inst();
+    let val = folded.dfg.instr_args(inst)[0];
+    assert_eq!(folded.dfg.value_def(val).as_const(), Some(Const::Int(runtime)));
+}
+
 #[test]
 pub fn const_phi() {
     let raw = r##"
@@ -136,3 +161,350 @@ pub fn const_backward_edge() {
 
     check(raw, expect)
 }
+
+#[test]
+pub fn int_overflow_wraps() {
+    let raw = r##"
+        function %bar(v20) {
+            v30 = iconst 2147483647
+        block0:
+            v31 = iadd v30, v5
+            v32 = imul v31, v30
+            v33 = isub v31, v5
+            v34 = iadd v32, v33
+            v40 = imul v34, v20
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20) {
+            v12 = iconst -1
+        block0:
+            v40 = imul v12, v20
+        }
+    "#]];
+
+    check_folded_int(raw, expect)
+}
+
+#[test]
+pub fn int_shl_overflow_wraps() {
+    let raw = r##"
+        function %bar(v20) {
+            v30 = iconst 3
+            v31 = iconst 31
+        block0:
+            v32 = ishl v30, v31
+            v40 = imul v32, v20
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20) {
+            v41 = iconst -2147483648
+        block0:
+            v40 = imul v41, v20
+        }
+    "#]];
+
+    check_folded_int(raw, expect)
+}
+
+#[test]
+pub fn known_param_given() {
+    // v20 is the `$param_given` value of parameter v21
+    let raw = r##"
+        function %bar(v20, v21) {
+        block0:
+            br v20, block1, block2
+        block1:
+            v22 = fmul v21, v21
+            jmp block3
+        block2:
+            v23 = fadd v21, v21
+            jmp block3
+        block3:
+            v24 = phi [v22, block1], [v23, block2]
+            v25 = fmul v24, v21
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20, v21) {
+        block0:
+            v23 = fadd v21, v21
+            v25 = fmul v23, v21
+        }
+    "#]];
+
+    let (mut func, _) = parse_function(raw).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    propagate_known_params(&mut func, &cfg, &[(20u32.into(), FALSE)]);
+    simplify_cfg(&mut func, &mut cfg);
+    expect.assert_eq(&func.to_debug_string());
+}
+
+/// `lhs && rhs` and `lhs || rhs` are lowered to a branch on `lhs` where one edge
+/// evaluates `rhs` and the other edge provides a constant (`false` for `&&` and
+/// `true` for `||`). If `rhs` is a constant the branch must be removed entirely.
+/// `v20 < v21` is `true` and `v21 < v20` is `false`.
+fn check_short_circuit(then_edge: (&str, &str), else_edge: (&str, &str), data_flow_result: Expect) {
+    let ((then_insts, then_val), (else_insts, else_val)) = (then_edge, else_edge);
+    let raw = format!(
+        r##"
+        function %bar(v10, v11) {{
+            v20 = iconst 1
+            v21 = iconst 2
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+        block1:
+            {then_insts}
+            jmp block3
+        block2:
+            {else_insts}
+            jmp block3
+        block3:
+            v13 = phi [{then_val}, block1], [{else_val}, block2]
+            v14 = optbarrier v13
+        }}
+    "##
+    );
+    // the branch must also be removed when phis are not merged (the early optimization stages)
+    for merge_phis in [true, false] {
+        let (mut func, _) = parse_function(&raw).unwrap();
+        let mut cfg = ControlFlowGraph::new();
+        cfg.compute(&func);
+        sparse_conditional_constant_propagation(&mut func, &cfg);
+        inst_combine(&mut func);
+        if merge_phis {
+            simplify_cfg(&mut func, &mut cfg);
+        } else {
+            simplify_cfg_no_phi_merge(&mut func, &mut cfg);
+        }
+        data_flow_result.assert_eq(&func.to_debug_string());
+    }
+}
+
+#[test]
+pub fn and_true() {
+    // cond && true -> cond
+    check_short_circuit(
+        ("v15 = ilt v20, v21", "v15"),
+        ("", "v1"),
+        expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            v14 = optbarrier v12
+        }
+    "#]],
+    );
+}
+
+#[test]
+pub fn or_false() {
+    // cond || false -> cond
+    check_short_circuit(
+        ("", "v2"),
+        ("v15 = ilt v21, v20", "v15"),
+        expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            v14 = optbarrier v12
+        }
+    "#]],
+    );
+}
+
+#[test]
+pub fn and_false() {
+    // cond && false -> false
+    check_short_circuit(
+        ("v15 = ilt v21, v20", "v15"),
+        ("", "v1"),
+        expect![[r#"
+        function %bar(v10, v11) {
+            // v1 = bconst false
+        block0:
+            v12 = flt v10, v11
+            v14 = optbarrier v1
+        }
+    "#]],
+    );
+}
+
+#[test]
+pub fn or_true() {
+    // cond || true -> true
+    check_short_circuit(
+        ("", "v2"),
+        ("v15 = ilt v20, v21", "v15"),
+        expect![[r#"
+        function %bar(v10, v11) {
+            // v2 = bconst true
+        block0:
+            v12 = flt v10, v11
+            v14 = optbarrier v2
+        }
+    "#]],
+    );
+}
+
+#[test]
+pub fn equal_phi_operands() {
+    // both edges compute `2.0` which is only known after constant propagation
+    let raw = r##"
+        function %bar(v10, v11) {
+            v20 = fconst 0x1.0p0
+            v21 = fconst 0x1.0p1
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+        block1:
+            v13 = fadd v20, v20
+            jmp block3
+        block2:
+            v14 = fmul v20, v21
+            jmp block3
+        block3:
+            v15 = phi [v13, block1], [v14, block2]
+            v16 = optbarrier v15
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+            v21 = fconst 0x1.0000000000000p1
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+
+        block1:
+            jmp block3
+
+        block2:
+            jmp block3
+
+        block3:
+            v16 = optbarrier v21
+        }
+    "#]];
+
+    let (mut func, _) = parse_function(raw).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    sparse_conditional_constant_propagation(&mut func, &cfg);
+    inst_combine(&mut func);
+    expect.assert_eq(&func.to_debug_string());
+}
+
+#[test]
+pub fn int_shl_out_of_range() {
+    let raw = r##"
+        function %bar(v20) {
+            v30 = iconst 3
+            v31 = iconst 32
+        block0:
+            v32 = ishl v30, v31
+            v40 = imul v32, v20
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20) {
+            v30 = iconst 3
+            v31 = iconst 32
+        block0:
+            v32 = ishl v30, v31
+            v40 = imul v32, v20
+        }
+    "#]];
+
+    check(raw, expect)
+}
+
+#[test]
+pub fn int_div_overflow_wraps() {
+    let raw = r##"
+        function %bar(v20) {
+            v30 = iconst -2147483648
+            v31 = iconst -1
+        block0:
+            v32 = idiv v30, v31
+            v33 = irem v30, v31
+            v34 = iadd v32, v33
+            v40 = imul v34, v20
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20) {
+            v30 = iconst -2147483648
+        block0:
+            v40 = imul v30, v20
+        }
+    "#]];
+
+    check_folded_int(raw, expect)
+}
+
+#[test]
+pub fn int_div_by_zero() {
+    let raw = r##"
+        function %bar(v20) {
+            v30 = iconst 3
+            v31 = iconst 0
+        block0:
+            v32 = idiv v30, v31
+            v40 = imul v32, v20
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20) {
+            v30 = iconst 3
+            v31 = iconst 0
+        block0:
+            v32 = idiv v30, v31
+            v40 = imul v32, v20
+        }
+    "#]];
+
+    check(raw, expect)
+}
+
+#[test]
+pub fn int_shr_out_of_range() {
+    let raw = r##"
+        function %bar(v20) {
+            v30 = iconst -3
+            v31 = iconst 32
+        block0:
+            v32 = ishr v30, v31
+            v40 = imul v32, v20
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v20) {
+            v30 = iconst -3
+            v31 = iconst 32
+        block0:
+            v32 = ishr v30, v31
+            v40 = imul v32, v20
+        }
+    "#]];
+
+    check(raw, expect);
+
+    // the interpreter does not panic and shifts out all bits
+    let (func, _) = parse_function(raw).unwrap();
+    let args = [Data::from(1i32)];
+    let mut interpreter = Interpreter::new(&func, TiSlice::from_ref(&[]), TiSlice::from_ref(&args));
+    interpreter.run();
+    let runtime: i32 = interpreter.state.read(40u32.into());
+    assert_eq!(runtime, -1);
+}
@@ -4,6 +4,9 @@ use bitset::BitSet;
 use mir::{Function, Inst, Value, ValueDef};
 use workqueue::WorkQueue;
 
+#[cfg(test)]
+mod tests;
+
 pub fn dead_code_elimination(func: &mut Function, output_values: &BitSet<Value>) {
     let mut work_list =
         WorkQueue { deque: VecDeque::new(), set: BitSet::new_filled(func.dfg.num_insts()) };
@@ -0,0 +1,34 @@
+use bitset::BitSet;
+use mir::Value;
+use mir_reader::parse_function;
+
+use crate::dead_code_elimination;
+
+#[test]
+fn use_count() {
+    let src = r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = fmul v10, v11
+            v13 = fadd v10, v12
+            v14 = fsub v10, v11
+            v15 = optbarrier v13
+        }
+    "#;
+    let (mut func, _) = parse_function(src).unwrap();
+    let v10 = Value::with_number(10).unwrap();
+    let v11 = Value::with_number(11).unwrap();
+    let v13 = Value::with_number(13).unwrap();
+    assert_eq!(func.dfg.use_count(v10), 3);
+    assert_eq!(func.dfg.use_count(v11), 2);
+    assert_eq!(func.dfg.use_count(v13), 1);
+
+    let mut output_values = BitSet::new_empty(func.dfg.num_values());
+    output_values.insert(Value::with_number(15).unwrap());
+    dead_code_elimination(&mut func, &output_values);
+
+    // v14 is unused, removing it removes one use of v10 and v11
+    assert_eq!(func.dfg.use_count(v10), 2);
+    assert_eq!(func.dfg.use_count(v11), 1);
+    assert_eq!(func.dfg.use_count(v13), 1);
+}
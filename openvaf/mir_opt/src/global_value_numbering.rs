@@ -122,7 +122,7 @@ impl GVNExpression {
             }
 
             InstructionData::PhiNode(phi) => {
-                let simplified_val = gvn.simplify_ctx(func).simplify_phi(phi.clone());
+                let simplified_val = gvn.simplify_ctx(func).simplify_phi(inst, phi.clone());
                 if let Some(val) = simplified_val {
                     if let Some(expr) = gvn.check_simplified(val, func) {
                         return Some(expr);
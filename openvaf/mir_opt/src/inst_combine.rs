@@ -1,9 +1,18 @@
-use mir::{Function, Inst, Value};
+use mir::builder::InstBuilder;
+use mir::cursor::{Cursor, FuncCursor};
+use mir::{
+    Block, ControlFlowGraph, Function, Inst, InstructionData, Opcode, PhiNode, Value, ValueDef,
+    FALSE, F_TWO, TRUE,
+};
 
 use crate::simplify::SimplifyCtx;
 
+#[cfg(test)]
+mod test;
+
 pub fn inst_combine(func: &mut Function) {
     let mut work_list = Vec::new();
+    let cfg = ControlFlowGraph::with_function(func);
     let mut ctx = SimplifyCtx::<f64, _>::new(func, |val, _| val);
 
     let mut block_cursor = ctx.func.layout.blocks_cursor();
@@ -12,6 +21,8 @@ pub fn inst_combine(func: &mut Function) {
         while let Some(inst) = inst_cursor.next(&ctx.func.layout) {
             if let Some(val) = ctx.simplify_inst(inst) {
                 replace_uses(ctx.func, &mut work_list, inst, val)
+            } else {
+                combine_inst(ctx.func, &cfg, &mut work_list, inst)
             }
         }
     }
@@ -20,8 +31,130 @@ pub fn inst_combine(func: &mut Function) {
         if ctx.func.layout.inst_block(inst).is_some() {
             if let Some(val) = ctx.simplify_inst(inst) {
                 replace_uses(ctx.func, &mut work_list, inst, val)
+            } else {
+                combine_inst(ctx.func, &cfg, &mut work_list, inst)
+            }
+        }
+    }
+}
+
+/// Rewrites instructions that can not be replaced with an existing value
+/// but can be replaced with a cheaper instruction.
+fn combine_inst(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    work_list: &mut Vec<Inst>,
+    inst: Inst,
+) {
+    match func.dfg.insts[inst].clone() {
+        InstructionData::PhiNode(phi) => {
+            let block = func.layout.inst_block(inst).unwrap();
+            if let Some((cond, negate)) = materialized_bool(func, cfg, block, &phi) {
+                let val = if negate {
+                    let mut cursor = FuncCursor::new(func).at_first_insertion_point(block);
+                    while let Some(inst) = cursor.current_inst() {
+                        if !matches!(cursor.func.dfg.insts[inst], InstructionData::PhiNode(_)) {
+                            break;
+                        }
+                        cursor.next_inst();
+                    }
+                    cursor.ins().bnot(cond)
+                } else {
+                    cond
+                };
+                replace_uses(func, work_list, inst, val)
             }
         }
+
+        // X == false -> !X, X != true -> !X
+        InstructionData::Binary { opcode: opcode @ (Opcode::Beq | Opcode::Bne), args } => {
+            let negation = if opcode == Opcode::Beq { FALSE } else { TRUE };
+            let arg = match args {
+                [arg, rhs] if rhs == negation => arg,
+                [lhs, arg] if lhs == negation => arg,
+                _ => return,
+            };
+            func.dfg.replace(inst).bnot(arg);
+            // the result might simplify further (!!X -> X)
+            work_list.push(inst);
+        }
+
+        // sqrt(X*X) -> fabs(X), sqrt(pow(X, 2)) -> fabs(X)
+        InstructionData::Unary { opcode: Opcode::Sqrt, arg } => {
+            let arg = match func.dfg.value_def(arg) {
+                ValueDef::Result(def, _) => match func.dfg.insts[def] {
+                    InstructionData::Binary { opcode: Opcode::Fmul, args: [lhs, rhs] }
+                        if lhs == rhs =>
+                    {
+                        lhs
+                    }
+                    InstructionData::Binary { opcode: Opcode::Pow, args: [base, F_TWO] } => base,
+                    _ => return,
+                },
+                _ => return,
+            };
+            func.dfg.replace(inst).fabs(arg);
+            // the result might simplify further (fabs(fabs(X)) -> fabs(X))
+            work_list.push(inst);
+        }
+        _ => (),
+    }
+}
+
+/// Checks whether `phi` materializes the condition of a branch as a bool, that is
+/// `phi [true, then_pred], [false, else_pred]` where `then_pred` and `else_pred`
+/// are only reachable through the `then` and `else` edge of the same branch.
+/// Returns the condition and whether it is negated.
+fn materialized_bool(
+    func: &Function,
+    cfg: &ControlFlowGraph,
+    block: Block,
+    phi: &PhiNode,
+) -> Option<(Value, bool)> {
+    let mut edges = func.dfg.phi_edges(phi);
+    let (pred1, val1) = edges.next()?;
+    let (pred2, val2) = edges.next()?;
+    if edges.next().is_some() {
+        return None;
+    }
+
+    let (true_pred, false_pred) = match (val1, val2) {
+        (TRUE, FALSE) => (pred1, pred2),
+        (FALSE, TRUE) => (pred2, pred1),
+        _ => return None,
+    };
+
+    let (branch, true_dst) = branch_edge(func, cfg, true_pred, block)?;
+    let (false_branch, false_dst) = branch_edge(func, cfg, false_pred, block)?;
+    if branch != false_branch {
+        return None;
+    }
+
+    let (cond, then_dst, else_dst) = func.dfg.as_branch(func.layout.last_inst(branch)?)?;
+    if (true_dst, false_dst) == (then_dst, else_dst) {
+        Some((cond, false))
+    } else if (true_dst, false_dst) == (else_dst, then_dst) {
+        Some((cond, true))
+    } else {
+        None
+    }
+}
+
+/// Finds the branch instruction that decides whether the edge from `pred` to `block`
+/// is taken. Returns the block that contains the branch and the destination
+/// of the branch edge.
+fn branch_edge(
+    func: &Function,
+    cfg: &ControlFlowGraph,
+    pred: Block,
+    block: Block,
+) -> Option<(Block, Block)> {
+    if cfg.unique_succ(pred) == Some(block) {
+        let branch = cfg.single_predecessor(pred)?;
+        func.dfg.as_branch(func.layout.last_inst(branch)?)?;
+        Some((branch, pred))
+    } else {
+        Some((pred, block))
     }
 }
 
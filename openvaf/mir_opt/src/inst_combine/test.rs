@@ -67,3 +67,221 @@ fn diode() {
     "#]];
     check(raw, expect)
 }
+
+#[test]
+fn bool_phi() {
+    let raw = r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+
+        block1:
+            jmp block3
+
+        block2:
+            jmp block3
+
+        block3:
+            v13 = phi [v2, block1], [v1, block2]
+            v14 = beq v13, v2
+            v15 = optbarrier v14
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+
+        block1:
+            jmp block3
+
+        block2:
+            jmp block3
+
+        block3:
+            v15 = optbarrier v12
+        }
+    "#]];
+    check(raw, expect);
+}
+
+#[test]
+fn negated_bool_phi() {
+    let raw = r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+
+        block1:
+            jmp block2
+
+        block2:
+            v13 = phi [v2, block0], [v1, block1]
+            v14 = fadd v10, v11
+            v15 = optbarrier v13
+            v16 = optbarrier v14
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+
+        block1:
+            jmp block2
+
+        block2:
+            v17 = bnot v12
+            v14 = fadd v10, v11
+            v15 = optbarrier v17
+            v16 = optbarrier v14
+        }
+    "#]];
+    check(raw, expect);
+}
+
+#[test]
+fn bool_cmp() {
+    let raw = r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            v13 = bne v12, v1
+            v14 = beq v1, v12
+            v15 = bne v14, v2
+            v16 = optbarrier v13
+            v17 = optbarrier v14
+            v18 = optbarrier v15
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            v14 = bnot v12
+            v16 = optbarrier v12
+            v17 = optbarrier v14
+            v18 = optbarrier v12
+        }
+    "#]];
+    check(raw, expect);
+}
+
+#[test]
+fn sqrt_of_square() {
+    let raw = r#"
+        function %bar(v20, v21) {
+            v11 = fconst 0x1.0000000000000p1
+        block0:
+            v22 = fmul v20, v20
+            v23 = sqrt v22
+            v24 = fmul v20, v21
+            v25 = sqrt v24
+            v26 = pow v21, v11
+            v27 = sqrt v26
+            v28 = optbarrier v23
+            v29 = optbarrier v25
+            v30 = optbarrier v27
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v20, v21) {
+            v11 = fconst 0x1.0000000000000p1
+        block0:
+            v22 = fmul v20, v20
+            v23 = fabs v20
+            v24 = fmul v20, v21
+            v25 = sqrt v24
+            v26 = pow v21, v11
+            v27 = fabs v21
+            v28 = optbarrier v23
+            v29 = optbarrier v25
+            v30 = optbarrier v27
+        }
+    "#]];
+    check(raw, expect);
+}
+
+#[test]
+fn phi_equal_operands() {
+    // simplifying v12 makes both operands of the phi equal
+    let raw = r#"
+        function %bar(v10, v11) {
+            v6 = fconst 0x1.0000000000000p0
+
+        block0:
+            br v11, block1, block2
+
+        block1:
+            v12 = fmul v10, v6
+            jmp block3
+
+        block2:
+            jmp block3
+
+        block3:
+            v13 = phi [v12, block1], [v10, block2]
+            v14 = optbarrier v13
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            br v11, block1, block2
+
+        block1:
+            jmp block3
+
+        block2:
+            jmp block3
+
+        block3:
+            v14 = optbarrier v10
+        }
+    "#]];
+    check(raw, expect);
+}
+
+#[test]
+fn loop_phi_equal_operands() {
+    // the value is not changed by the loop once v15 is simplified to the phi itself
+    let raw = r#"
+        function %bar(v10, v11) {
+            v6 = fconst 0x1.0000000000000p0
+
+        block0:
+            jmp block1
+
+        block1:
+            v13 = phi [v10, block0], [v15, block2]
+            br v11, block2, block3
+
+        block2:
+            v15 = fmul v13, v6
+            jmp block1
+
+        block3:
+            v14 = optbarrier v13
+        }
+    "#;
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+        block0:
+            jmp block1
+
+        block1:
+            br v11, block2, block3
+
+        block2:
+            jmp block1
+
+        block3:
+            v14 = optbarrier v10
+        }
+    "#]];
+    check(raw, expect);
+}
@@ -1,17 +1,29 @@
+mod code_sinking;
 mod const_eval;
 mod const_prop;
 mod dead_code;
 mod dead_code_aggressive;
 mod global_value_numbering;
 mod inst_combine;
+mod merge_blocks;
+mod propagate_conditions;
+mod reassociate;
+mod register_pressure;
 mod simplify;
 mod simplify_cfg;
 mod split_tainted;
 
-pub use const_prop::sparse_conditional_constant_propagation;
+pub use code_sinking::sink_instructions;
+pub use const_prop::{propagate_known_params, sparse_conditional_constant_propagation};
 pub use dead_code::dead_code_elimination;
 pub use dead_code_aggressive::aggressive_dead_code_elimination;
 pub use global_value_numbering::{ClassId, GVN};
 pub use inst_combine::inst_combine;
+pub use merge_blocks::merge_identical_blocks;
+pub use propagate_conditions::propagate_conditions;
+pub use reassociate::reassociate;
+pub use register_pressure::register_pressure;
 pub use simplify_cfg::{simplify_cfg, simplify_cfg_no_phi_merge};
-pub use split_tainted::{propagate_direct_taint, propagate_taint};
+pub use split_tainted::{
+    propagate_direct_taint, propagate_taint, propagate_taint_with_blocks, taint_inst,
+};
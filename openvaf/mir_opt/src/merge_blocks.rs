@@ -0,0 +1,200 @@
+//! Merges basic blocks that perform exactly the same computation. Wide `case`
+//! statements often contain multiple arms that are identical:
+//!
+//! ```text
+//! block6:
+//!     v30 = ifcast v16
+//!     v31 = fdiv v30, v23
+//!     jmp block5
+//!
+//! block8:
+//!     v40 = ifcast v16
+//!     v41 = fdiv v40, v23
+//!     jmp block5
+//!
+//! block5:
+//!     v35 = phi [v31, block6], [v41, block8]
+//! ```
+//!
+//! The predecessors of `block8` can simply jump to `block6` instead so that
+//! `block8` (and its phi edges) can be removed.
+
+use ahash::AHashMap;
+use mir::{
+    Block, ControlFlowGraph, FuncRef, Function, Inst, InstructionData, Opcode, Value, ValueDef,
+};
+
+#[cfg(test)]
+mod tests;
+
+pub fn merge_identical_blocks(func: &mut Function, cfg: &mut ControlFlowGraph) {
+    while merge_identical_blocks_once(func, cfg) {}
+}
+
+fn merge_identical_blocks_once(func: &mut Function, cfg: &mut ControlFlowGraph) -> bool {
+    let mut changed = false;
+    let mut canonical_blocks: AHashMap<Vec<Token>, Vec<Block>> = AHashMap::default();
+    let blocks: Vec<_> = func.layout.blocks().collect();
+    for bb in blocks {
+        let signature =
+            if let Some(signature) = block_signature(func, cfg, bb) { signature } else { continue };
+        let candidates = canonical_blocks.entry(signature).or_default();
+        let canonical =
+            candidates.iter().copied().find(|&canonical| phi_edges_match(func, cfg, canonical, bb));
+        if let Some(canonical) = canonical {
+            replace_block(func, cfg, bb, canonical);
+            changed = true;
+        } else {
+            candidates.push(bb);
+        }
+    }
+    changed
+}
+
+/// Structural description of a block, two blocks with the same signature
+/// compute the same values (up to phi edges in their successors).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Token {
+    Opcode(Opcode),
+    FuncRef(FuncRef),
+    Block(Block),
+    LoopEntry(bool),
+    /// A value defined within the block (position of the instruction and result)
+    Local(u32, u32),
+    /// A value defined outside of the block
+    External(Value),
+}
+
+fn block_signature(func: &Function, cfg: &ControlFlowGraph, bb: Block) -> Option<Vec<Token>> {
+    if Some(bb) == func.layout.entry_block()
+        || cfg.pred_iter(bb).next().is_none()
+        || cfg.succ_iter(bb).any(|succ| succ == bb)
+    {
+        return None;
+    }
+
+    let positions = local_positions(func, bb);
+    let mut signature = Vec::new();
+    for inst in func.layout.block_insts(bb) {
+        let data = &func.dfg.insts[inst];
+        if data.is_phi() || escapes(func, bb, inst) {
+            return None;
+        }
+        signature.push(Token::Opcode(data.opcode()));
+        match *data {
+            InstructionData::Branch { then_dst, else_dst, loop_entry, .. } => {
+                signature.extend([
+                    Token::Block(then_dst),
+                    Token::Block(else_dst),
+                    Token::LoopEntry(loop_entry),
+                ]);
+            }
+            InstructionData::Jump { destination } => signature.push(Token::Block(destination)),
+            InstructionData::Call { func_ref, .. } => signature.push(Token::FuncRef(func_ref)),
+            _ => (),
+        }
+        signature.extend(
+            func.dfg.instr_args(inst).iter().map(|&arg| operand(func, bb, &positions, arg)),
+        );
+    }
+
+    let term = func.layout.last_inst(bb)?;
+    if func.dfg.insts[term].is_terminator() {
+        Some(signature)
+    } else {
+        None
+    }
+}
+
+/// Checks whether the results of `inst` are used anywhere but within
+/// `bb` and the phi edges originating from `bb`. Such uses are not dominated
+/// by the block anymore once it has been merged.
+fn escapes(func: &Function, bb: Block, inst: Inst) -> bool {
+    func.dfg.inst_results(inst).iter().any(|&val| {
+        func.dfg.uses(val).any(|use_| {
+            let user = func.dfg.use_to_operand(use_).0;
+            if func.layout.inst_block(user) == Some(bb) {
+                return false;
+            }
+            match &func.dfg.insts[user] {
+                InstructionData::PhiNode(phi) => {
+                    func.dfg.phi_edges(phi).any(|(pred, edge_val)| edge_val == val && pred != bb)
+                }
+                _ => true,
+            }
+        })
+    })
+}
+
+/// Checks that the phis in the successors of `bb` receive the same
+/// values from `bb` and `canonical`.
+fn phi_edges_match(func: &Function, cfg: &ControlFlowGraph, canonical: Block, bb: Block) -> bool {
+    let canonical_positions = local_positions(func, canonical);
+    let positions = local_positions(func, bb);
+    cfg.succ_iter(bb).all(|succ| {
+        for inst in func.layout.block_insts(succ) {
+            let phi = if let InstructionData::PhiNode(phi) = &func.dfg.insts[inst] {
+                phi
+            } else {
+                break;
+            };
+            let canonical_val = func.dfg.phi_edge_val(phi, canonical).unwrap();
+            let val = func.dfg.phi_edge_val(phi, bb).unwrap();
+            if operand(func, canonical, &canonical_positions, canonical_val)
+                != operand(func, bb, &positions, val)
+            {
+                return false;
+            }
+        }
+        true
+    })
+}
+
+fn local_positions(func: &Function, bb: Block) -> AHashMap<Inst, u32> {
+    func.layout.block_insts(bb).enumerate().map(|(pos, inst)| (inst, pos as u32)).collect()
+}
+
+fn operand(func: &Function, bb: Block, positions: &AHashMap<Inst, u32>, val: Value) -> Token {
+    if let ValueDef::Result(inst, res) = func.dfg.value_def(val) {
+        if func.layout.inst_block(inst) == Some(bb) {
+            return Token::Local(positions[&inst], res as u32);
+        }
+    }
+    Token::External(val)
+}
+
+/// Redirects all predecessors of `bb` to `canonical` and removes `bb`.
+fn replace_block(func: &mut Function, cfg: &mut ControlFlowGraph, bb: Block, canonical: Block) {
+    for succ in cfg.succ_iter(bb) {
+        for inst in func.layout.block_insts(succ) {
+            if func.dfg.try_remove_phi_edge_at(inst, bb).is_none() {
+                break;
+            }
+        }
+    }
+
+    for pred in cfg.pred_iter(bb) {
+        let term = func.layout.last_inst(pred).unwrap();
+        match &mut func.dfg.insts[term] {
+            InstructionData::Branch { then_dst, else_dst, .. } => {
+                if *then_dst == bb {
+                    *then_dst = canonical;
+                }
+                if *else_dst == bb {
+                    *else_dst = canonical;
+                }
+            }
+            InstructionData::Jump { destination } => {
+                debug_assert_eq!(*destination, bb);
+                *destination = canonical;
+            }
+            _ => unreachable!("last instruction must be terminator"),
+        }
+    }
+
+    cfg.replace(bb, canonical);
+    for inst in func.layout.block_insts(bb) {
+        func.dfg.zap_inst(inst)
+    }
+    func.layout.remove_and_clear_block(bb);
+}
@@ -0,0 +1,145 @@
+use expect_test::{expect, Expect};
+use mir::ControlFlowGraph;
+use mir_reader::parse_function;
+
+use crate::merge_identical_blocks;
+
+fn expect_test(raw: &str, expect: Expect) {
+    let (mut func, _) = parse_function(raw).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    merge_identical_blocks(&mut func, &mut cfg);
+    assert!(func.validate());
+
+    let mut expected_cfg = ControlFlowGraph::new();
+    expected_cfg.compute(&func);
+    for bb in func.layout.blocks() {
+        assert!(cfg.pred_iter(bb).eq(expected_cfg.pred_iter(bb)));
+        assert!(cfg.succ_iter(bb).eq(expected_cfg.succ_iter(bb)));
+    }
+
+    expect.assert_eq(&func.to_debug_string())
+}
+
+#[test]
+fn identical_case_arms() {
+    // case (v21)
+    //     0: test = foo / 3.141;
+    //     1: test = foo / 3.141;
+    //     default: test = test2;
+    // endcase
+    let raw = r##"
+        function %bar(v16, v21, v33) {
+            v4 = iconst 0
+            v5 = iconst 1
+            v23 = fconst 0x1.920c49ba5e354p1
+        block0:
+            v22 = ieq v4, v21
+            br v22, block6, block7
+
+        block6:
+            v30 = ifcast v16
+            v31 = fdiv v30, v23
+            jmp block5
+
+        block7:
+            v25 = ieq v5, v21
+            br v25, block8, block9
+
+        block8:
+            v40 = ifcast v16
+            v41 = fdiv v40, v23
+            jmp block5
+
+        block9:
+            jmp block5
+
+        block5:
+            v35 = phi [v31, block6], [v41, block8], [v33, block9]
+            v36 = optbarrier v35
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v16, v21, v33) {
+            v4 = iconst 0
+            v5 = iconst 1
+            v23 = fconst 0x1.920c49ba5e354p1
+        block0:
+            v22 = ieq v4, v21
+            br v22, block6, block7
+
+        block6:
+            v30 = ifcast v16
+            v31 = fdiv v30, v23
+            jmp block5
+
+        block7:
+            v25 = ieq v5, v21
+            br v25, block6, block9
+
+        block9:
+            jmp block5
+
+        block5:
+            v35 = phi [v31, block6], [v33, block9]
+            v36 = optbarrier v35
+        }
+    "#]];
+
+    expect_test(raw, expect)
+}
+
+#[test]
+fn different_phi_edges() {
+    // both arms compute the same value but assign it to different variables
+    let raw = r##"
+        function %bar(v16, v21, v33) {
+            v4 = iconst 0
+            v23 = fconst 0x1.920c49ba5e354p1
+        block0:
+            v22 = ieq v4, v21
+            br v22, block6, block8
+
+        block6:
+            v31 = fdiv v16, v23
+            jmp block5
+
+        block8:
+            v41 = fdiv v16, v23
+            jmp block5
+
+        block5:
+            v35 = phi [v31, block6], [v33, block8]
+            v37 = phi [v33, block6], [v41, block8]
+            v36 = optbarrier v35
+            v38 = optbarrier v37
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v16, v21, v33) {
+            v4 = iconst 0
+            v23 = fconst 0x1.920c49ba5e354p1
+        block0:
+            v22 = ieq v4, v21
+            br v22, block6, block8
+
+        block6:
+            v31 = fdiv v16, v23
+            jmp block5
+
+        block8:
+            v41 = fdiv v16, v23
+            jmp block5
+
+        block5:
+            v35 = phi [v31, block6], [v33, block8]
+            v37 = phi [v33, block6], [v41, block8]
+            v36 = optbarrier v35
+            v38 = optbarrier v37
+        }
+    "#]];
+
+    expect_test(raw, expect)
+}
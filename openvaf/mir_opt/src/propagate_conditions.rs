@@ -0,0 +1,139 @@
+//! Propagates the value of branch conditions into the blocks controlled by the branch.
+//! Nested conditions frequently test a condition that is already known:
+//!
+//! ```text
+//! block0:
+//!     v3 = fgt v1, v2
+//!     br v3, block1, block2
+//!
+//! block1:
+//!     v4 = fgt v1, v2
+//!     br v4, block3, block4
+//! ```
+//!
+//! `block1` is only reachable if `v3` was true so `v4` (which computes the same value)
+//! must be true as well. All uses of a condition that are dominated by an edge of the
+//! branch (and all structurally identical conditions computed there) are replaced with
+//! the corresponding constant. The now constant branches are removed by [`simplify_cfg`].
+//!
+//! [`simplify_cfg`]: crate::simplify_cfg
+
+use ahash::AHashMap;
+use mir::{
+    Block, ControlFlowGraph, DominatorTree, Function, Inst, InstKey, InstructionData, Value,
+    ValueDef,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Replaces branch conditions with constants where their value is implied by a dominating
+/// branch. Returns whether any condition was replaced.
+///
+/// Requires that `cfg` and the dominator tree (not the post dominator tree) are up to date.
+pub fn propagate_conditions(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    dom_tree: &DominatorTree,
+) -> bool {
+    let facts = collect_facts(func, cfg);
+    if facts.is_empty() {
+        return false;
+    }
+
+    // operands of instructions that are replaced by a constant
+    let mut operands: Vec<(Inst, u16, bool)> = Vec::new();
+    // values that are identical to a known condition and can be replaced entirely
+    let mut values: Vec<(Value, bool)> = Vec::new();
+
+    {
+        let mut equivalent_conds: AHashMap<InstKey, Vec<Inst>> = AHashMap::default();
+        for bb in func.layout.blocks() {
+            for inst in func.layout.block_insts(bb) {
+                if is_pure_cond(func, inst) {
+                    equivalent_conds.entry(func.dfg.inst_key(inst)).or_default().push(inst);
+                }
+            }
+        }
+
+        for &Fact { root, cond, val } in &facts {
+            for use_ in func.dfg.uses(cond) {
+                let (user, pos) = func.dfg.use_to_operand(use_);
+                let bb = match func.dfg.insts[user] {
+                    // the value of a phi operand is used at the end of the predecessor
+                    InstructionData::PhiNode(ref phi) => phi
+                        .blocks
+                        .iter(&func.dfg.phi_forest)
+                        .find(|&(_, arg)| arg == pos as u32)
+                        .map(|(pred, _)| pred),
+                    _ => func.layout.inst_block(user),
+                };
+                match bb {
+                    Some(bb) if dom_tree.dominates(bb, root) => operands.push((user, pos, val)),
+                    _ => (),
+                }
+            }
+
+            let def = match func.dfg.value_def(cond) {
+                ValueDef::Result(def, 0) if is_pure_cond(func, def) => def,
+                _ => continue,
+            };
+            let equivalent = &equivalent_conds[&func.dfg.inst_key(def)];
+            for &inst in equivalent {
+                if inst == def {
+                    continue;
+                }
+                let bb = func.layout.inst_block(inst).unwrap();
+                if dom_tree.dominates(bb, root) {
+                    values.push((func.dfg.first_result(inst), val));
+                }
+            }
+        }
+    }
+
+    let changed = !operands.is_empty() || !values.is_empty();
+    for (inst, pos, val) in operands {
+        func.dfg.set_operand_value(val.into(), inst, pos);
+    }
+    for (old, val) in values {
+        // a value may be implied by multiple facts (nested branches)
+        if func.dfg.value_attached(old) {
+            func.dfg.replace_uses(old, val.into());
+        }
+    }
+    changed
+}
+
+/// The value of `cond` is known to be `val` in all blocks dominated by `root`.
+struct Fact {
+    root: Block,
+    cond: Value,
+    val: bool,
+}
+
+fn collect_facts(func: &Function, cfg: &ControlFlowGraph) -> Vec<Fact> {
+    let mut facts = Vec::new();
+    for bb in func.layout.blocks() {
+        let term = if let Some(term) = func.layout.last_inst(bb) { term } else { continue };
+        let (cond, then_dst, else_dst) =
+            if let Some(branch) = func.dfg.as_branch(term) { branch } else { continue };
+        if then_dst == else_dst || func.dfg.value_def(cond).as_const().is_some() {
+            continue;
+        }
+        // the edge only dominates its destination if there is no other way to enter it
+        for (dst, val) in [(then_dst, true), (else_dst, false)] {
+            if cfg.pred_iter(dst).count() == 1 {
+                facts.push(Fact { root: dst, cond, val });
+            }
+        }
+    }
+    facts
+}
+
+/// Conditions that can be recomputed without changing their value (comparisons and
+/// other pure instructions with a single result).
+fn is_pure_cond(func: &Function, inst: Inst) -> bool {
+    matches!(func.dfg.insts[inst], InstructionData::Unary { .. } | InstructionData::Binary { .. })
+        && !func.dfg.has_sideeffects(inst, false)
+        && func.dfg.inst_results(inst).len() == 1
+}
@@ -0,0 +1,131 @@
+use expect_test::{expect, Expect};
+use mir::{ControlFlowGraph, DominatorTree};
+use mir_reader::parse_function;
+
+use crate::propagate_conditions;
+
+fn expect_test(raw: &str, expect: Expect) {
+    let (mut func, _) = parse_function(raw).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(&func, &cfg, true, false, true);
+    propagate_conditions(&mut func, &cfg, &dom_tree);
+    assert!(func.validate());
+    expect.assert_eq(&func.to_debug_string())
+}
+
+#[test]
+fn nested_condition() {
+    // if (x > 0) begin
+    //     if (x > 0) y = 1; else y = 2;
+    // end else y = 3;
+    let raw = r##"
+        function %bar(v10) {
+            v11 = fconst 0.0
+            v12 = fconst 0x1.0000000000000p0
+            v13 = fconst 0x1.0000000000000p1
+            v14 = fconst 0x1.8000000000000p1
+        block0:
+            v15 = fgt v10, v11
+            br v15, block1, block4
+
+        block1:
+            v16 = fgt v10, v11
+            br v16, block2, block3
+
+        block2:
+            jmp block5
+
+        block3:
+            jmp block5
+
+        block4:
+            jmp block5
+
+        block5:
+            v17 = phi [v12, block2], [v13, block3], [v14, block4]
+            v18 = optbarrier v17
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v10) {
+            // v2 = bconst true
+            v11 = fconst 0.0
+            v12 = fconst 0x1.0000000000000p0
+            v13 = fconst 0x1.0000000000000p1
+            v14 = fconst 0x1.8000000000000p1
+        block0:
+            v15 = fgt v10, v11
+            br v15, block1, block4
+
+        block1:
+            v16 = fgt v10, v11
+            br v2, block2, block3
+
+        block2:
+            jmp block5
+
+        block3:
+            jmp block5
+
+        block4:
+            jmp block5
+
+        block5:
+            v17 = phi [v12, block2], [v13, block3], [v14, block4]
+            v18 = optbarrier v17
+        }
+    "#]];
+    expect_test(raw, expect)
+}
+
+#[test]
+fn reused_condition() {
+    // the condition is only known within the branches and not after they merge
+    let raw = r##"
+        function %bar(v10, v11) {
+        block0:
+            v15 = fgt v10, v11
+            br v15, block1, block2
+
+        block1:
+            v16 = optbarrier v15
+            jmp block3
+
+        block2:
+            v17 = optbarrier v15
+            jmp block3
+
+        block3:
+            v18 = phi [v15, block1], [v15, block2]
+            v19 = optbarrier v15
+            v20 = optbarrier v18
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+            // v1 = bconst false
+            // v2 = bconst true
+        block0:
+            v15 = fgt v10, v11
+            br v15, block1, block2
+
+        block1:
+            v16 = optbarrier v2
+            jmp block3
+
+        block2:
+            v17 = optbarrier v1
+            jmp block3
+
+        block3:
+            v18 = phi [v2, block1], [v1, block2]
+            v19 = optbarrier v15
+            v20 = optbarrier v18
+        }
+    "#]];
+    expect_test(raw, expect)
+}
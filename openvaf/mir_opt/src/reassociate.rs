@@ -0,0 +1,169 @@
+//! Rebalances chains of associative floating point operations. Derivatives are usually
+//! computed as long left-leaning chains:
+//!
+//! ```text
+//! v5 = fadd v1, v2
+//! v6 = fadd v5, v3
+//! v7 = fadd v6, v4
+//! ```
+//!
+//! Every `fadd` depends on the previous one, so the chain can only be evaluated serially.
+//! Rewriting it into a balanced tree (`(v1 + v2) + (v3 + v4)`) allows the independent
+//! operations to execute in parallel.
+//!
+//! Reassociation changes the rounding of the result so only instructions that are marked as
+//! fast-math (a negative source location, see `mir_autodiff`) are considered.
+
+use mir::builder::InstBuilder;
+use mir::cursor::{Cursor, FuncCursor};
+use mir::{Function, Inst, InstructionData, Opcode, SourceLoc, Value, ValueDef};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum number of instructions of a chain that are rebalanced at once.
+/// Longer chains are split into multiple balanced trees.
+const MAX_CHAIN_LEN: usize = 64;
+
+/// Rebalances all chains of `fadd` and `fmul` instructions with fast-math enabled.
+/// Returns whether any chain was changed.
+pub fn reassociate(func: &mut Function) -> bool {
+    let mut roots = Vec::new();
+    for bb in func.layout.blocks() {
+        for inst in func.layout.block_insts(bb) {
+            if chain_opcode(func, inst).is_some() && !is_chain_interior(func, inst) {
+                roots.push(inst);
+            }
+        }
+    }
+
+    let mut changed = false;
+    let mut operands = Vec::new();
+    let mut interior = Vec::new();
+    while let Some(root) = roots.pop() {
+        changed |= rebalance_chain(func, root, &mut operands, &mut interior, &mut roots);
+    }
+    changed
+}
+
+fn rebalance_chain(
+    func: &mut Function,
+    root: Inst,
+    operands: &mut Vec<Value>,
+    interior: &mut Vec<Inst>,
+    roots: &mut Vec<Inst>,
+) -> bool {
+    let opcode = chain_opcode(func, root).unwrap();
+    operands.clear();
+    interior.clear();
+    let depth = collect_chain(func, root, opcode, operands, interior, roots);
+
+    // a tree with n leaves has at least a depth of ceil(log2(n))
+    let min_depth = usize::BITS - (operands.len() - 1).leading_zeros();
+    if depth <= min_depth {
+        return false;
+    }
+
+    // the new instructions inherit the fast-math flag from the root
+    let srcloc = func.srclocs[root];
+    let mut cursor = FuncCursor::new(func).at_inst(root);
+    while operands.len() > 2 {
+        let mut level = Vec::with_capacity(operands.len() / 2 + 1);
+        for pair in operands.chunks(2) {
+            let val = match *pair {
+                [lhs, rhs] => {
+                    let (inst, dfg) = cursor.ins().binary(opcode, lhs, rhs);
+                    let val = dfg.first_result(inst);
+                    set_srcloc(cursor.func, inst, srcloc);
+                    val
+                }
+                [val] => val,
+                _ => unreachable!(),
+            };
+            level.push(val);
+        }
+        *operands = level;
+    }
+    func.dfg.replace(root).binary1(opcode, operands[0], operands[1]);
+
+    for &inst in &*interior {
+        func.dfg.zap_inst(inst);
+        func.layout.remove_inst(inst);
+    }
+    true
+}
+
+/// Collects the operands of the chain rooted at `inst` (from left to right) and returns the
+/// depth of the chain. Parts of the chain beyond [`MAX_CHAIN_LEN`] are added to `roots`
+/// so they are rebalanced separately.
+fn collect_chain(
+    func: &Function,
+    inst: Inst,
+    opcode: Opcode,
+    operands: &mut Vec<Value>,
+    interior: &mut Vec<Inst>,
+    roots: &mut Vec<Inst>,
+) -> u32 {
+    let mut depth = 0;
+    for &arg in func.dfg.instr_args(inst) {
+        let arg_depth = match func.dfg.value_def(arg) {
+            ValueDef::Result(def, _)
+                if chain_opcode(func, def) == Some(opcode) && is_chain_interior(func, def) =>
+            {
+                if interior.len() < MAX_CHAIN_LEN {
+                    interior.push(def);
+                    collect_chain(func, def, opcode, operands, interior, roots)
+                } else {
+                    roots.push(def);
+                    operands.push(arg);
+                    0
+                }
+            }
+            _ => {
+                operands.push(arg);
+                0
+            }
+        };
+        depth = depth.max(arg_depth);
+    }
+    depth + 1
+}
+
+/// Returns the opcode of `inst` if it is an associative operation that may be reassociated.
+fn chain_opcode(func: &Function, inst: Inst) -> Option<Opcode> {
+    match func.dfg.insts[inst] {
+        InstructionData::Binary { opcode: opcode @ (Opcode::Fadd | Opcode::Fmul), .. }
+            if is_fast_math(func, inst) =>
+        {
+            Some(opcode)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the result of `inst` is only used as the operand of a single instruction of the
+/// same chain (which can then absorb `inst` into its tree).
+fn is_chain_interior(func: &Function, inst: Inst) -> bool {
+    let res = func.dfg.first_result(inst);
+    let mut uses = func.dfg.uses(res);
+    let user = match (uses.next(), uses.next()) {
+        (Some(use_), None) => func.dfg.use_to_operand(use_).0,
+        _ => return false,
+    };
+    chain_opcode(func, user) == chain_opcode(func, inst)
+        && func.layout.inst_block(user) == func.layout.inst_block(inst)
+}
+
+fn is_fast_math(func: &Function, inst: Inst) -> bool {
+    match func.srclocs.get(inst) {
+        Some(loc) => loc.0 < 0,
+        None => false,
+    }
+}
+
+fn set_srcloc(func: &mut Function, inst: Inst, srcloc: SourceLoc) {
+    if func.srclocs.len() <= inst.into() {
+        func.srclocs.resize(usize::from(inst) + 1, SourceLoc::default());
+    }
+    func.srclocs[inst] = srcloc;
+}
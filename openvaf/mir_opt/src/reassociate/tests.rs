@@ -0,0 +1,128 @@
+use expect_test::{expect, Expect};
+use mir::{Function, SourceLoc, Value, ValueDef};
+use mir_reader::parse_function;
+
+use crate::reassociate;
+
+const ADD_CHAIN: &str = r##"
+    function %bar(v10, v11, v12, v13, v14, v15, v16, v17) {
+    block0:
+        v20 = fadd v10, v11
+        v21 = fadd v20, v12
+        v22 = fadd v21, v13
+        v23 = fadd v22, v14
+        v24 = fadd v23, v15
+        v25 = fadd v24, v16
+        v26 = fadd v25, v17
+        v27 = optbarrier v26
+    }
+"##;
+
+/// Runs the pass on `raw`. All instructions are marked as fast-math if `fast_math` is set.
+fn run(raw: &str, fast_math: bool) -> Function {
+    let (mut func, _) = parse_function(raw).unwrap();
+    if fast_math {
+        func.srclocs = vec![SourceLoc::new(-1); func.dfg.num_insts()].into();
+    }
+    reassociate(&mut func);
+    assert!(func.validate());
+    func
+}
+
+fn expect_test(raw: &str, fast_math: bool, expect: Expect) {
+    let mut func = run(raw, fast_math);
+    // negative source locations can't be printed in a readable way
+    func.srclocs.clear();
+    expect.assert_eq(&func.to_debug_string())
+}
+
+/// The number of arithmetic instructions on the longest path from `val` to a parameter.
+fn depth(func: &Function, val: Value) -> u32 {
+    match func.dfg.value_def(val) {
+        ValueDef::Result(inst, _) if func.dfg.instr_args(inst).len() == 2 => {
+            1 + func.dfg.instr_args(inst).iter().map(|&arg| depth(func, arg)).max().unwrap()
+        }
+        _ => 0,
+    }
+}
+
+fn output(func: &Function) -> Value {
+    let bb = func.layout.entry_block().unwrap();
+    let barrier = func.layout.last_inst(bb).unwrap();
+    func.dfg.instr_args(barrier)[0]
+}
+
+#[test]
+fn balance_add_chain() {
+    let func = run(ADD_CHAIN, true);
+    assert_eq!(depth(&func, output(&func)), 3);
+
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12, v13, v14, v15, v16, v17) {
+        block0:
+            v28 = fadd v10, v11
+            v29 = fadd v12, v13
+            v30 = fadd v14, v15
+            v31 = fadd v16, v17
+            v32 = fadd v28, v29
+            v33 = fadd v30, v31
+            v26 = fadd v32, v33
+            v27 = optbarrier v26
+        }
+    "#]];
+    expect_test(ADD_CHAIN, true, expect);
+}
+
+#[test]
+fn no_fast_math() {
+    let func = run(ADD_CHAIN, false);
+    assert_eq!(depth(&func, output(&func)), 7);
+
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12, v13, v14, v15, v16, v17) {
+        block0:
+            v20 = fadd v10, v11
+            v21 = fadd v20, v12
+            v22 = fadd v21, v13
+            v23 = fadd v22, v14
+            v24 = fadd v23, v15
+            v25 = fadd v24, v16
+            v26 = fadd v25, v17
+            v27 = optbarrier v26
+        }
+    "#]];
+    expect_test(ADD_CHAIN, false, expect);
+}
+
+#[test]
+fn shared_subexpression() {
+    // v23 is used twice so the chain is split into two chains that are balanced separately
+    let raw = r##"
+        function %bar(v10, v11, v12, v13, v14, v15, v16) {
+        block0:
+            v20 = fmul v10, v11
+            v21 = fmul v20, v12
+            v23 = fmul v21, v13
+            v24 = fmul v23, v14
+            v25 = fmul v24, v15
+            v26 = fmul v25, v16
+            v27 = fadd v26, v23
+            v28 = optbarrier v27
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12, v13, v14, v15, v16) {
+        block0:
+            v31 = fmul v10, v11
+            v32 = fmul v12, v13
+            v23 = fmul v31, v32
+            v29 = fmul v23, v14
+            v30 = fmul v15, v16
+            v26 = fmul v29, v30
+            v27 = fadd v26, v23
+            v28 = optbarrier v27
+        }
+    "#]];
+    expect_test(raw, true, expect);
+}
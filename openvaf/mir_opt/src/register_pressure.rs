@@ -0,0 +1,103 @@
+use bitset::BitSet;
+use mir::{Block, ControlFlowGraph, Function, Inst, InstructionData, Value, ValueDef};
+use typed_index_collections::TiVec;
+
+#[cfg(test)]
+mod tests;
+
+/// Estimates the register pressure of `func` as the maximum number of values that are live at
+/// the same time at any point of the function. This is much cheaper than actual register
+/// allocation and is only meant as a heuristic (for example to decide how aggressively a
+/// function should be optimized).
+///
+/// Constants are not counted since they are usually materialized as immediates. Phi arguments
+/// are considered live at the end of the corresponding predecessor (and not at the start of the
+/// block containing the phi).
+///
+/// Requires that `cfg` is up to date.
+pub fn register_pressure(func: &Function, cfg: &ControlFlowGraph) -> usize {
+    let entry = if let Some(entry) = func.layout.entry_block() { entry } else { return 0 };
+    let blocks: Vec<_> = cfg.postorder_from(entry).collect();
+    let num_values = func.dfg.num_values();
+    let mut live_in: TiVec<Block, BitSet<Value>> =
+        (0..func.layout.num_blocks()).map(|_| BitSet::new_empty(num_values)).collect();
+
+    // standard backwards dataflow analysis, visiting the blocks in postorder ensures that
+    // (apart from backedges) the successors of a block are always processed first
+    let mut live = BitSet::new_empty(num_values);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &bb in &blocks {
+            live_out(func, cfg, &live_in, bb, &mut live);
+            for inst in func.layout.block_insts(bb).rev() {
+                transfer(func, inst, &mut live);
+            }
+            if live != live_in[bb] {
+                live_in[bb].copy_from(&live);
+                changed = true;
+            }
+        }
+    }
+
+    let mut max_pressure = 0;
+    for &bb in &blocks {
+        live_out(func, cfg, &live_in, bb, &mut live);
+        max_pressure = max_pressure.max(live.count());
+        for inst in func.layout.block_insts(bb).rev() {
+            // the results of an instruction are defined while its operands are still live
+            let defined =
+                func.dfg.inst_results(inst).iter().filter(|&&val| !live.contains(val)).count();
+            max_pressure = max_pressure.max(live.count() + defined);
+            transfer(func, inst, &mut live);
+            max_pressure = max_pressure.max(live.count());
+        }
+    }
+
+    max_pressure
+}
+
+/// Computes the values that are live at the end of `bb`.
+fn live_out(
+    func: &Function,
+    cfg: &ControlFlowGraph,
+    live_in: &TiVec<Block, BitSet<Value>>,
+    bb: Block,
+    dst: &mut BitSet<Value>,
+) {
+    dst.clear();
+    for succ in cfg.succ_iter(bb) {
+        dst.union(&live_in[succ]);
+        for inst in func.layout.block_insts(succ) {
+            if let InstructionData::PhiNode(ref phi) = func.dfg.insts[inst] {
+                if let Some(val) = func.dfg.phi_edge_val(phi, bb) {
+                    if is_register(func, val) {
+                        dst.insert(val);
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Updates `live` (the values that are live after `inst`) to the values that are live before
+/// `inst`.
+fn transfer(func: &Function, inst: Inst, live: &mut BitSet<Value>) {
+    for &res in func.dfg.inst_results(inst) {
+        live.remove(res);
+    }
+    if func.dfg.insts[inst].is_phi() {
+        return;
+    }
+    for &arg in func.dfg.instr_args(inst) {
+        if is_register(func, arg) {
+            live.insert(arg);
+        }
+    }
+}
+
+fn is_register(func: &Function, val: Value) -> bool {
+    matches!(func.dfg.value_def(val), ValueDef::Result(..) | ValueDef::Param(_))
+}
@@ -0,0 +1,83 @@
+use mir::ControlFlowGraph;
+use mir_reader::parse_function;
+
+use crate::register_pressure;
+
+fn pressure(src: &str) -> usize {
+    let (func, _) = parse_function(src).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    register_pressure(&func, &cfg)
+}
+
+#[test]
+fn straight_line() {
+    // v10, v11, v12 and v13 are all live before v14 is computed
+    let src = r#"
+        function %bar(v10, v11) {
+        block0:
+            v12 = fadd v10, v11
+            v13 = fmul v10, v11
+            v14 = fsub v10, v11
+            v15 = fadd v12, v13
+            v16 = fadd v15, v14
+            v17 = optbarrier v16
+        }
+    "#;
+    assert_eq!(pressure(src), 4);
+}
+
+#[test]
+fn phi_edge() {
+    // v14 is only live on the edge block1 -> block2
+    let src = r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fadd v10, v11
+            br v12, block1, block2
+        block1:
+            v14 = fmul v13, v13
+            jmp block2
+        block2:
+            v15 = phi [v14, block1], [v10, block0]
+            v16 = optbarrier v15
+        }
+    "#;
+    assert_eq!(pressure(src), 3);
+}
+
+#[test]
+fn constants() {
+    let src = r#"
+        function %bar(v10) {
+            v3 = fconst 0.0
+            v6 = fconst 0x1.0000000000000p0
+        block0:
+            v11 = fadd v10, v6
+            v12 = fmul v11, v3
+            v13 = optbarrier v12
+        }
+    "#;
+    assert_eq!(pressure(src), 1);
+}
+
+#[test]
+fn loop_carried() {
+    // v10 and v11 are live throughout the entire loop
+    let src = r#"
+        function %bar(v10, v11) {
+        block0:
+            jmp block1
+        block1:
+            v12 = phi [v10, block0], [v13, block2]
+            v14 = flt v12, v11
+            br v14, block2, block3
+        block2:
+            v13 = fadd v12, v10
+            jmp block1
+        block3:
+            v15 = optbarrier v12
+        }
+    "#;
+    assert_eq!(pressure(src), 4);
+}
@@ -2,8 +2,8 @@ use std::marker::PhantomData;
 use std::mem::swap;
 
 use mir::{
-    Function, Inst, InstructionData, Opcode, PhiNode, Value, ValueDef, F_N_ONE, F_ONE, F_TEN,
-    F_TWO, F_ZERO, N_ONE, ONE, ZERO,
+    Function, Inst, InstructionData, Opcode, PhiNode, Value, ValueDef, FALSE, F_N_ONE, F_ONE,
+    F_TEN, F_ZERO, N_ONE, ONE, TRUE, ZERO,
 };
 
 use crate::const_eval::{eval_binary, eval_unary};
@@ -66,20 +66,27 @@ impl<'a, FP: Arithmetic, M: Fn(Value, &Function) -> Value> SimplifyCtx<'a, FP, M
             InstructionData::Binary { opcode, args } => {
                 self.simplify_binop(opcode, args[0], args[1])
             }
-            InstructionData::PhiNode(phi) => self.simplify_phi(phi),
+            InstructionData::PhiNode(phi) => self.simplify_phi(inst, phi),
             _ => None,
         }
     }
 
-    pub fn simplify_phi(&mut self, phi: PhiNode) -> Option<Value> {
-        let mut iter = self.func.dfg.phi_edges(&phi);
-        if let Some((_, all_eq_val)) = iter.next() {
-            let all_eq_val = self.map_val(all_eq_val);
-            if iter.all(|(_, val)| self.map_val(val) == all_eq_val) {
-                return Some(all_eq_val);
-            }
+    /// Replaces a phi whose operands are all equal with that operand. Operands that
+    /// refer to the phi itself (loops that don't change the value) are ignored.
+    pub fn simplify_phi(&mut self, inst: Inst, phi: PhiNode) -> Option<Value> {
+        let phi_val = self.func.dfg.first_result(inst);
+        let mut iter = self
+            .func
+            .dfg
+            .phi_edges(&phi)
+            .map(|(_, val)| self.map_val(val))
+            .filter(|&val| val != phi_val);
+        let all_eq_val = iter.next()?;
+        if iter.all(|val| val == all_eq_val) {
+            Some(all_eq_val)
+        } else {
+            None
         }
-        None
     }
 
     pub fn simplify_unary_op(&mut self, op: Opcode, arg: Value) -> Option<Value> {
@@ -103,21 +110,10 @@ impl<'a, FP: Arithmetic, M: Fn(Value, &Function) -> Value> SimplifyCtx<'a, FP, M
             | Opcode::Clog2 => return None,
             Opcode::IBcast => Opcode::BIcast,
             Opcode::FBcast => Opcode::BFcast,
-            Opcode::Sqrt => {
-                if let Some([x, y]) = self.as_binary(arg, Opcode::Fmul) {
-                    if x == y {
-                        return Some(x);
-                    }
-                }
-
-                if let Some([x, y]) = self.as_binary(arg, Opcode::Pow) {
-                    if y == F_TWO {
-                        return Some(x);
-                    }
-                }
-
-                return None;
-            }
+            // sqrt(X*X) is |X| and not X, inst_combine replaces it with fabs(X)
+            Opcode::Sqrt => return None,
+            // fabs(fabs(X)) -> fabs(X)
+            Opcode::Fabs => return self.as_unary(arg, Opcode::Fabs).map(|_| arg),
             Opcode::Exp => Opcode::Ln,
             Opcode::Ln => Opcode::Exp,
             Opcode::Log => {
@@ -172,6 +168,7 @@ impl<'a, FP: Arithmetic, M: Fn(Value, &Function) -> Value> SimplifyCtx<'a, FP, M
             Opcode::Fmul => self.simplify_mul_inst::<FP>(lhs, rhs),
             Opcode::Fdiv => self.simplify_div_inst::<FP>(lhs, rhs),
             Opcode::Pow => self.simplify_pow_inst(lhs, rhs),
+            Opcode::Beq | Opcode::Bne => self.simplify_bool_cmp_inst(op, lhs, rhs),
 
             // we only care about instructions that
             //
@@ -192,11 +189,9 @@ impl<'a, FP: Arithmetic, M: Fn(Value, &Function) -> Value> SimplifyCtx<'a, FP, M
             | Opcode::Ieq
             | Opcode::Feq
             | Opcode::Seq
-            | Opcode::Beq
             | Opcode::Ine
             | Opcode::Fne
             | Opcode::Sne
-            | Opcode::Bne
             | Opcode::Frem
             | Opcode::Ishl
             | Opcode::Ishr
@@ -271,7 +266,7 @@ impl<'a, FP: Arithmetic, M: Fn(Value, &Function) -> Value> SimplifyCtx<'a, FP, M
     ) -> Option<Value> {
         if let ValueDef::Const(lhs_) = self.func.dfg.value_def(*lhs) {
             if let ValueDef::Const(rhs_) = self.func.dfg.value_def(*rhs) {
-                return Some(eval_binary(self.func, op, lhs_, rhs_));
+                return eval_binary(self.func, op, lhs_, rhs_);
             }
 
             // Canonicalize the constant to the RHS if this is a commutative operation.
@@ -420,6 +415,38 @@ impl<'a, FP: Arithmetic, M: Fn(Value, &Function) -> Value> SimplifyCtx<'a, FP, M
         }
     }
 
+    /// Given operands for a `Beq` or `Bne` instruction, see if we can fold the result.
+    /// If not, this returns None.
+    fn simplify_bool_cmp_inst(
+        &mut self,
+        op: Opcode,
+        mut lhs: Value,
+        mut rhs: Value,
+    ) -> Option<Value> {
+        if let Some(val) = self.fold_or_commute_consts(op, &mut lhs, &mut rhs) {
+            return Some(val);
+        }
+
+        let (identity, negation) = if op == Opcode::Beq { (TRUE, FALSE) } else { (FALSE, TRUE) };
+
+        // X == true -> X, X != false -> X
+        if rhs == identity {
+            return Some(lhs);
+        }
+
+        // !X == false -> X, !X != true -> X
+        if rhs == negation {
+            return self.as_unary(lhs, Opcode::Bnot);
+        }
+
+        // X == X -> true, X != X -> false
+        if lhs == rhs {
+            return Some(identity);
+        }
+
+        None
+    }
+
     /// Given operands for an `A::MUL` instruction, see if we can fold the result.
     /// If not, this returns None.
     fn simplify_mul_inst<A: Arithmetic>(
@@ -2,7 +2,9 @@
 use std::iter::repeat;
 
 use bitset::BitSet;
-use mir::{Block, ControlFlowGraph, Function, InstructionData, /* Value,*/ ValueDef, FALSE, TRUE,};
+use mir::{
+    Block, ControlFlowGraph, Function, InstructionData, Opcode, Value, ValueDef, FALSE, TRUE,
+};
 
 #[cfg(test)]
 mod tests;
@@ -109,10 +111,12 @@ impl<'a> SimplifyCfg<'a> {
         let mut cursor = self.func.layout.block_inst_cursor(bb);
         while let Some(inst) = cursor.next(&self.func.layout) {
             if let InstructionData::PhiNode(phi) = self.func.dfg.insts[inst].clone() {
-                let mut edges = self.func.dfg.phi_edges(&phi);
+                let func = &*self.func;
+                let mut edges =
+                    func.dfg.phi_edges(&phi).map(|(_, val)| strip_lossless_casts(func, val));
                 let phi_val = self.func.dfg.first_result(inst);
-                if let Some((_, first_val)) = edges.find(|it| it.1 != phi_val) {
-                    if edges.all(|(_, val)| val == first_val || val == phi_val) {
+                if let Some(first_val) = edges.find(|&val| val != phi_val) {
+                    if edges.all(|val| val == first_val || val == phi_val) {
                         for use_ in self.func.dfg.uses(phi_val) {
                             let inst = self.func.dfg.use_to_operand(use_).0;
                             if let Some(inst) = self.func.layout.inst_block(inst) {
@@ -429,6 +433,12 @@ impl<'a> SimplifyCfg<'a> {
             return;
         }
 
+        // the entry block has no predecessors that could be redirected to dst, removing it would
+        // turn an arbitrary (potentially unreachable) block into the new entry block
+        if Some(src) == self.func.layout.entry_block() {
+            return;
+        }
+
         // check that the block only contains phi and a terminator
         let mut insts = self.func.layout.block_insts(src);
         insts.next_back();
@@ -623,16 +633,22 @@ impl<'a> SimplifyCfg<'a> {
         //     return;
         // }
 
-        if self.merge_phis {
-            if let Some(term) = self.func.layout.last_inst(bb) {
-                if let InstructionData::Jump { destination } = self.func.dfg.insts[term] {
+        if let Some(term) = self.func.layout.last_inst(bb) {
+            if let InstructionData::Jump { destination } = self.func.dfg.insts[term] {
+                // without phi merging empty blocks can still be skipped if there are no phis
+                // in the destination (like the remains of a short circuiting `&&`)
+                if self.merge_phis || !self.has_phis(destination) {
                     self.simplify_unconditional_jmp_term(bb, destination)
                 }
-
-                // TODO merge common code in successor (for branch)
             }
+
+            // TODO merge common code in successor (for branch)
         }
     }
+
+    fn has_phis(&self, bb: Block) -> bool {
+        matches!(self.func.layout.first_inst(bb), Some(inst) if self.func.dfg.insts[inst].is_phi())
+    }
 }
 
 /* AB: unused
@@ -654,3 +670,29 @@ impl<I: Iterator<Item = Value> + Clone> PartialEq for ResolvedPhi<I> {
 }
 impl<I: Iterator<Item = Value> + Clone> Eq for ResolvedPhi<I> {}
 */
+
+/// Follows chains of casts that convert a value back to its original type without any
+/// loss (like `ibcast(bicast(x))`) to the original value. A single cast changes the type
+/// of its argument so it is never equivalent to its argument.
+fn strip_lossless_casts(func: &Function, mut val: Value) -> Value {
+    let unary = |val| match func.dfg.value_def(val) {
+        ValueDef::Result(inst, _) => match func.dfg.insts[inst] {
+            InstructionData::Unary { opcode, arg } => Some((opcode, arg)),
+            _ => None,
+        },
+        _ => None,
+    };
+    while let Some((opcode, arg)) = unary(val) {
+        let inverse = match opcode {
+            Opcode::FIcast => Opcode::IFcast,
+            Opcode::IBcast => Opcode::BIcast,
+            Opcode::FBcast => Opcode::BFcast,
+            _ => break,
+        };
+        match unary(arg) {
+            Some((opcode, orig)) if opcode == inverse => val = orig,
+            _ => break,
+        }
+    }
+    val
+}
@@ -238,3 +238,92 @@ pub fn duplicate_phis_set() {
     "#]];
     expect.assert_eq(&func.to_debug_string())
 }
+
+#[test]
+pub fn trivial_phi_through_casts() {
+    // v13 and v15 round trip v10 through a different type so the phi is redundant, while the
+    // type-changing cast v17 is not equivalent to v16
+    let raw = r##"
+        function %bar(v4, v10, v16) {
+        block0:
+            v17 = ficast v16
+            br v4, block1, block2
+        block1:
+            v11 = bicast v10
+            v12 = ibcast v11
+            jmp block3
+        block2:
+            v13 = bfcast v10
+            v14 = fbcast v13
+            jmp block3
+        block3:
+            v15 = phi [v12, block1], [v14, block2]
+            v19 = phi [v17, block1], [v16, block2]
+            v20 = optbarrier v15
+            v21 = optbarrier v19
+        }
+    "##;
+
+    let expect = expect![[r#"
+        function %bar(v4, v10, v16) {
+        block0:
+            v17 = ficast v16
+            br v4, block1, block2
+
+        block1:
+            v11 = bicast v10
+            v12 = ibcast v11
+            jmp block3
+
+        block2:
+            v13 = bfcast v10
+            v14 = fbcast v13
+            jmp block3
+
+        block3:
+            v19 = phi [v17, block1], [v16, block2]
+            v20 = optbarrier v10
+            v21 = optbarrier v19
+        }
+    "#]];
+    expect_test(raw, expect);
+}
+
+#[test]
+pub fn constant_case_discriminant() {
+    // case (2) 1: x = a; 2: x = b; default: x = c; endcase
+    let raw = r##"
+        function %bar(v10, v11, v12) {
+        v20 = iconst 2
+        v21 = iconst 1
+        block0:
+            v30 = ieq v21, v20
+            br v30, block1, block2
+        block1:
+            jmp block5
+        block2:
+            v31 = ieq v20, v20
+            br v31, block3, block4
+        block3:
+            jmp block5
+        block4:
+            jmp block5
+        block5:
+            v40 = phi [v10, block1], [v11, block3], [v12, block4]
+            v41 = fadd v40, v40
+        }
+    "##;
+    let (mut func, _) = parse_function(raw).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    crate::sparse_conditional_constant_propagation(&mut func, &cfg);
+    simplify_cfg(&mut func, &mut cfg);
+
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v41 = fadd v11, v11
+        }
+    "#]];
+    expect.assert_eq(&func.to_debug_string())
+}
@@ -1,19 +1,38 @@
 use bitset::{BitSet, HybridBitSet, SparseBitMatrix};
 use mir::{Block, ControlFlowGraph, DominatorTree, Function, Inst, InstructionData, Value};
 
+#[cfg(test)]
+mod tests;
+
 pub fn propagate_taint(
     func: &Function,
     dom_tree: &DominatorTree,
     cfg: &ControlFlowGraph,
     tainted: impl Iterator<Item = Value>,
     tainted_insts: &mut BitSet<Inst>,
+) {
+    let mut tainted_blocks = BitSet::new_empty(func.layout.num_blocks());
+    propagate_taint_with_blocks(func, dom_tree, cfg, tainted, tainted_insts, &mut tainted_blocks)
+}
+
+/// Same as [`propagate_taint`] but also records all blocks that are tainted
+/// because they are control dependent on a tainted branch in `tainted_blocks`.
+/// The result can be updated incrementally with [`taint_inst`].
+pub fn propagate_taint_with_blocks(
+    func: &Function,
+    dom_tree: &DominatorTree,
+    cfg: &ControlFlowGraph,
+    tainted: impl Iterator<Item = Value>,
+    tainted_insts: &mut BitSet<Inst>,
+    tainted_blocks: &mut BitSet<Block>,
 ) {
     tainted_insts.ensure(func.dfg.num_insts());
+    tainted_blocks.ensure(func.layout.num_blocks());
     let mut solver = TaintSolver {
         dom_tree,
         func,
         inst_queue: Vec::new(),
-        tainted_blocks: BitSet::new_empty(func.layout.num_blocks()),
+        tainted_blocks,
         tainted_insts,
         cfg,
         bb_queue: Vec::new(),
@@ -29,6 +48,37 @@ pub fn propagate_taint(
     solver.solve();
 }
 
+/// Marks `inst` as tainted and propagates the taint to everything that depends
+/// on it. `tainted_insts` and `tainted_blocks` must have been computed with
+/// [`propagate_taint_with_blocks`] (and only updated with this function since).
+/// Returns `false` if `inst` was already tainted.
+pub fn taint_inst(
+    func: &Function,
+    dom_tree: &DominatorTree,
+    cfg: &ControlFlowGraph,
+    inst: Inst,
+    tainted_insts: &mut BitSet<Inst>,
+    tainted_blocks: &mut BitSet<Block>,
+) -> bool {
+    tainted_insts.ensure(func.dfg.num_insts());
+    tainted_blocks.ensure(func.layout.num_blocks());
+    let mut solver = TaintSolver {
+        dom_tree,
+        func,
+        inst_queue: Vec::new(),
+        tainted_blocks,
+        tainted_insts,
+        cfg,
+        bb_queue: Vec::new(),
+    };
+    if !solver.tainted_insts.insert(inst) {
+        return false;
+    }
+    solver.inst_queue.push(inst);
+    solver.solve();
+    true
+}
+
 struct TaintSolver<'a> {
     dom_tree: &'a DominatorTree,
     cfg: &'a ControlFlowGraph,
@@ -37,7 +87,7 @@ struct TaintSolver<'a> {
 
     inst_queue: Vec<Inst>,
     bb_queue: Vec<Block>,
-    tainted_blocks: BitSet<Block>,
+    tainted_blocks: &'a mut BitSet<Block>,
 }
 
 impl TaintSolver<'_> {
@@ -0,0 +1,96 @@
+use bitset::BitSet;
+use mir::builder::InstBuilder;
+use mir::cursor::{Cursor, FuncCursor};
+use mir::{Block, ControlFlowGraph, DominatorTree, Function, Inst, Value, ValueDef};
+use mir_reader::parse_function;
+
+use crate::{propagate_taint_with_blocks, taint_inst};
+
+fn full_taint(func: &Function, tainted: Value) -> (BitSet<Inst>, BitSet<Block>) {
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(func);
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(func, &cfg, true, true, false);
+    let mut tainted_insts = BitSet::new_empty(0);
+    let mut tainted_blocks = BitSet::new_empty(0);
+    propagate_taint_with_blocks(
+        func,
+        &dom_tree,
+        &cfg,
+        [tainted].into_iter(),
+        &mut tainted_insts,
+        &mut tainted_blocks,
+    );
+    (tainted_insts, tainted_blocks)
+}
+
+#[test]
+fn incremental_taint() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fadd v10, v11
+            v14 = flt v12, v11
+            br v14, block1, block2
+        block1:
+            v15 = fmul v11, v12
+            jmp block3
+        block2:
+            v16 = flt v13, v12
+            br v16, block4, block5
+        block4:
+            v17 = fdiv v11, v12
+            jmp block3
+        block5:
+            jmp block3
+        block3:
+            v18 = phi [v15, block1], [v17, block4], [v11, block5]
+            v19 = fadd v18, v12
+        }"##;
+    let (mut func, _) = parse_function(src).unwrap();
+    let tainted = Value::from(10u32);
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(&func, &cfg, true, true, false);
+    let (mut tainted_insts, mut tainted_blocks) = full_taint(&func, tainted);
+
+    let block1 = Block::from(1u32);
+    let block3 = Block::from(3u32);
+    let block4 = Block::from(4u32);
+    let v11 = Value::from(11u32);
+    let v12 = Value::from(12u32);
+    let v13 = Value::from(13u32);
+    let v18 = Value::from(18u32);
+
+    let mut new_insts = Vec::new();
+    let mut cursor = FuncCursor::new(&mut func).at_last_inst(block1);
+    let val = cursor.ins().fmul(v11, v12);
+    new_insts.push(val);
+    new_insts.push(cursor.ins().fadd(val, v13));
+    cursor.goto_last_inst(block4);
+    new_insts.push(cursor.ins().fsub(v11, v12));
+    cursor.goto_bottom(block3);
+    new_insts.push(cursor.ins().fmul(v18, v11));
+    new_insts.push(cursor.ins().fneg(v11));
+
+    for val in new_insts {
+        let inst = func.dfg.value_def(val).inst().unwrap();
+        let bb = func.layout.inst_block(inst).unwrap();
+        let op_tainted = func.dfg.instr_args(inst).iter().any(|&arg| {
+            arg == tainted
+                || matches!(func.dfg.value_def(arg), ValueDef::Result(inst, _) if tainted_insts.contains(inst))
+        });
+        if op_tainted || tainted_blocks.contains(bb) {
+            taint_inst(&func, &dom_tree, &cfg, inst, &mut tainted_insts, &mut tainted_blocks);
+        }
+    }
+
+    let (expected_insts, expected_blocks) = full_taint(&func, tainted);
+    assert_eq!(tainted_insts.iter().collect::<Vec<_>>(), expected_insts.iter().collect::<Vec<_>>());
+    assert_eq!(
+        tainted_blocks.iter().collect::<Vec<_>>(),
+        expected_blocks.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(tainted_insts.count(), 11);
+}
@@ -19,15 +19,24 @@ pub fn main_command() -> Command {
         .args([
             def_arg(),
             include_dir(),
+            include_dir_recursive(),
             lint_arg(LintLevel::Allow),
             lint_arg(LintLevel::Warn),
             lint_arg(LintLevel::Deny),
+            werror(),
             lints(),
             output(),
+            summary(),
             batchmode(),
             dry_run(),
+            quiet(),
+            no_link(),
+            collapse_resistance(),
+            sensitivity(),
+            reactive_formulation(),
             cache_dir(),
             opt_lvl(),
+            opt_lvl_for(),
             target(),
             supported_targets(),
             target_cpu(),
@@ -35,6 +44,10 @@ pub fn main_command() -> Command {
             interface(),
             expand(),
             dump_json(),
+            list_modules(),
+            print_config(),
+            weak_unknowns(),
+            stats(),
             input(),
         ])
         .subcommand_required(false)
@@ -44,6 +57,11 @@ pub fn main_command() -> Command {
 pub const INTERFACE: &str = "interface";
 pub const BATCHMODE: &str = "batchmode";
 pub const DRYRUN: &str = "dry-run";
+pub const QUIET: &str = "quiet";
+pub const NO_LINK: &str = "no-link";
+pub const COLLAPSE_RESISTANCE: &str = "collapse-resistance";
+pub const SENSITIVITY: &str = "sensitivity";
+pub const REACTIVE_FORMULATION: &str = "reactive-formulation";
 pub const TARGET: &str = "target";
 pub const SUPPORTED_TARGETS: &str = "supported-targets";
 pub const LINTS: &str = "lints";
@@ -51,15 +69,23 @@ pub const TARGET_CPU: &str = "target_cpu";
 pub const CODEGEN: &str = "codegen";
 pub const INPUT: &str = "input";
 pub const INCLUDE: &str = "include";
+pub const INCLUDE_RECURSIVE: &str = "include-dir-recursive";
 pub const OUTPUT: &str = "output";
+pub const SUMMARY: &str = "summary";
 pub const CACHE_DIR: &str = "cache-dir";
 pub const OPT_LVL: &str = "opt_lvl";
+pub const OPT_LVL_FOR: &str = "opt-level-for";
 pub const DEFINE: &str = "define";
 pub const PRINT_EXPANSION: &str = "print-expansion";
 pub const DUMP_JSON: &str = "dump-json";
+pub const LIST_MODULES: &str = "list-modules";
+pub const PRINT_CONFIG: &str = "print-config";
+pub const WEAK_UNKNOWNS: &str = "weak-unknowns";
+pub const STATS: &str = "stats";
 pub const ALLOW: &str = "allow";
 pub const WARN: &str = "warn";
 pub const DENY: &str = "deny";
+pub const WERROR: &str = "werror";
 
 fn interface() -> Arg {
     Arg::new(INTERFACE)
@@ -85,6 +111,62 @@ fn dry_run() -> Arg {
         .long_help("Run compilation without producing any output.\nUsed for testing.")
 }
 
+fn quiet() -> Arg {
+    flag(QUIET, "quiet")
+        .short('q')
+        .help("Don't print progress and status messages.")
+        .long_help("Don't print progress and status messages.\nBy default each module is reported while it is compiled (\"Compiling module X (3/20)\") followed by a final \"Finished\" line.\nDiagnostics are still printed.")
+}
+
+fn no_link() -> Arg {
+    dir_path_arg(NO_LINK)
+        .long(NO_LINK)
+        .help("Write the object files to DIR instead of linking them.")
+        .long_help("Write the object files to DIR and keep them instead of linking them into a shared library.\nThe objects are named after the output file and can be linked into a simulator manually.")
+        .required(false)
+}
+
+fn collapse_resistance() -> Arg {
+    let parse = |raw: &str| match raw.parse::<f64>() {
+        Ok(threshold) if threshold > 0.0 && threshold.is_finite() => Ok(threshold),
+        Ok(_) => bail!("must be a positive resistance"),
+        Err(err) => bail!("{err}"),
+    };
+
+    Arg::new(COLLAPSE_RESISTANCE)
+        .long(COLLAPSE_RESISTANCE)
+        .help("Collapse linear resistors below OHM.")
+        .long_help("Collapse branches that are linear resistors (I(a, b) <+ V(a, b) / R or I(a, b) <+ G * V(a, b)) if their resistance is below OHM.\nOnly resistances that depend on parameters alone are collapsed (at the start of the simulation).\nCollapsing removes nodes from the system which speeds up the simulation, but the voltage drop across (and the noise of) the collapsed resistors is lost.\nBy default only the collapse hints of the model (V(a, b) <+ 0) are used.")
+        .value_name("OHM")
+        .value_hint(ValueHint::Other)
+        .value_parser(ValueParser::new(parse))
+        .required(false)
+}
+
+fn sensitivity() -> Arg {
+    Arg::new(SENSITIVITY)
+        .long(SENSITIVITY)
+        .help("Export the derivatives of the residual with respect to PARAM.")
+        .long_help("Export the derivatives of the residual with respect to the parameter PARAM (or one of its aliases).\nThe derivatives are computed together with the residual and loaded with the load_sensitivities function of the OSDI descriptor.\nModules without a parameter named PARAM are not affected.\nCan be passed multiple times.")
+        .value_name("PARAM")
+        .action(ArgAction::Append)
+        .required(false)
+        .value_hint(ValueHint::Other)
+}
+
+fn reactive_formulation() -> Arg {
+    Arg::new(REACTIVE_FORMULATION)
+        .long(REACTIVE_FORMULATION)
+        .help("Select how the reactive residual is formulated.")
+        .long_help("Select how the reactive residual is formulated:\nThe reactive jacobian always contains the capacitances C = ddx(Q, x).\n\npossible values\n\ncharge - the reactive residual is the charge Q(x), the simulator applies ddt (default)\ncapacitance - the reactive residual is C(x) x for simulators that integrate C dx/dt\n\nBoth formulations are identical for linear capacitances.")
+        .value_name("FORMULATION")
+        .value_hint(ValueHint::Other)
+        .value_parser(["charge", "capacitance"])
+        .hide_possible_values(true)
+        .default_value("charge")
+        .required(false)
+}
+
 fn target() -> Arg {
     let vals = get_target_names().fold(String::new(), |mut dst, it| {
         dst.push('\n');
@@ -95,21 +177,19 @@ fn target() -> Arg {
         .long(TARGET)
         .help("Target triple for which the code is compiled.")
         .long_help(format!(
-            "Target triple for which the code is compiled.\n\npossible values:\n{vals}"
+            "Target triple for which the code is compiled.\nThe full LLVM target triple (for example aarch64-unknown-linux-gnu) is accepted as well.\n\npossible values:\n{vals}"
         ))
-        .value_parser(PossibleValuesParser::new(get_target_names()))
         .default_value(host_triple())
         .value_name("TARGET")
         .required(false)
         .value_hint(ValueHint::Other)
-        .hide_possible_values(true)
 }
 
 fn supported_targets() -> Arg {
     flag(SUPPORTED_TARGETS, SUPPORTED_TARGETS)
         .help("Print target triples supported by OpenVAF.")
         .long_help(
-        "Print target triples supported by OpenVAF.\nOnly these values can be passed to --target.",
+        "Print target triples supported by OpenVAF.\nOnly these values (or the corresponding LLVM target triples) can be passed to --target.",
     )
 }
 
@@ -134,7 +214,7 @@ fn codegen_opts() -> Arg {
         .long(CODEGEN)
         .short('C')
         .help("Set a codegen option.")
-        .long_help("Set a codegen option.\nThese options are passed directly to LLVM.")
+        .long_help("Set a codegen option.\nUnknown options are ignored (with a warning).\n\nOPTIONS:\ntarget-feature=FEATURES - comma separated LLVM target features (like +avx2) that are enabled in addition to the features of the target cpu")
         .value_name("OPT[=VALUE]")
         .action(ArgAction::Append)
         .required(false)
@@ -143,7 +223,9 @@ fn codegen_opts() -> Arg {
 
 fn input() -> Arg {
     input_file_path_arg(INPUT)
-        .help("The root Verilog-A file.")
+        .help("The root Verilog-A file(s).")
+        .long_help("The root Verilog-A file(s).\nIf multiple files are passed each file is compiled separately.\nA file that fails to compile does not prevent the remaining files from being compiled.")
+        .num_args(1..)
         .required_unless_present_any([LINTS, SUPPORTED_TARGETS])
 }
 
@@ -156,6 +238,22 @@ fn include_dir() -> Arg {
         .action(ArgAction::Append)
 }
 
+fn include_dir_recursive() -> Arg {
+    dir_path_arg(INCLUDE_RECURSIVE)
+        .long(INCLUDE_RECURSIVE)
+        .help("Search directory for include files (including all subdirectories).")
+        .long_help("Search directory for include files (including all subdirectories).\nThe directories passed with --include are searched first. Afterwards each directory passed with this option is searched followed by its subdirectories, shallower directories before deeper ones and directories at the same depth in alphabetical order.\nSymbolic links are followed but every directory is only searched once.")
+        .required(false)
+        .action(ArgAction::Append)
+}
+
+fn summary() -> Arg {
+    output_file_path_arg(SUMMARY)
+        .long(SUMMARY)
+        .help("Write a JSON summary of the compilation to FILE.")
+        .long_help("Write a JSON summary of the compilation to FILE.\nFor each input file the summary records whether it was compiled successfully, the output file, the number of warnings and errors and the time spent compiling it.\nThe same information (except the time) is recorded for each module of the file.")
+}
+
 fn output() -> Arg {
     output_file_path_arg(OUTPUT)
         .long(OUTPUT)
@@ -257,6 +355,32 @@ fn opt_lvl() -> Arg {
         .default_value("3").required(false)
 }
 
+fn opt_lvl_for() -> Arg {
+    let parse = |raw: &str| {
+        let (module, lvl) = match raw.rsplit_once('=') {
+            Some(res) => res,
+            None => bail!("expected MODULE=LEVEL"),
+        };
+        if module.is_empty() {
+            bail!("missing module name");
+        }
+        if !matches!(lvl, "0" | "1" | "2" | "3") {
+            bail!("unknown opt lvl {lvl}");
+        }
+        Ok((module.to_owned(), lvl.to_owned()))
+    };
+
+    Arg::new(OPT_LVL_FOR)
+        .long(OPT_LVL_FOR)
+        .help("Set how much the code of a single module is optimized.")
+        .long_help("Set how much the generated machine code of a single module is optimized.\nOverwrites the level set with --opt_lvl for the module MODULE.\nAccepts the same levels as --opt_lvl and can be specified multiple times.")
+        .value_name("MODULE=LEVEL")
+        .value_hint(ValueHint::Other)
+        .action(ArgAction::Append)
+        .value_parser(ValueParser::new(parse))
+        .required(false)
+}
+
 fn expand() -> Arg {
     flag(PRINT_EXPANSION, "print-expansion")
         .help("Abort after preprocessing and print expanded sourcecode.")
@@ -271,6 +395,55 @@ fn dump_json() -> Arg {
     flag(DUMP_JSON, "dump-json").help("Abort after lowering and serialize MIR as json.")
 }
 
+fn list_modules() -> Arg {
+    Arg::new(LIST_MODULES)
+        .long(LIST_MODULES)
+        .help("Print the modules with their ports and parameters without compiling.")
+        .long_help("Print the modules with their ports and parameters without compiling.\nThe files are only checked for errors and no code is generated.\nWith --list-modules=json the listing is printed as JSON instead.")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("text")
+        .value_name("FORMAT")
+        .value_parser(["text", "json"])
+        .conflicts_with_all([PRINT_EXPANSION, DUMP_JSON, SUMMARY])
+        .required(false)
+}
+
+fn print_config() -> Arg {
+    Arg::new(PRINT_CONFIG)
+        .long(PRINT_CONFIG)
+        .help("Print the effective configuration without compiling.")
+        .long_help("Print the effective configuration without compiling.\nThe configuration contains the compiler version, target, target cpu, optimization levels, include directories, defines, codegen options, the reactive formulation and lint levels after all defaults were applied.\nAttaching it to bug reports helps to reproduce the compilation.\nWith --print-config=json the configuration is printed as JSON instead.")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("text")
+        .value_name("FORMAT")
+        .value_parser(["text", "json"])
+        .conflicts_with_all([PRINT_EXPANSION, DUMP_JSON, SUMMARY, LIST_MODULES])
+        .required(false)
+}
+
+fn weak_unknowns() -> Arg {
+    Arg::new(WEAK_UNKNOWNS)
+        .long(WEAK_UNKNOWNS)
+        .help("Print the unknowns without DC conductance without generating code.")
+        .long_help("Print the unknowns whose row of the jacobian is empty or only has reactive (ddt) entries without generating code.\nThese rows have no conductance during DC analysis and are a common cause of singular matrices.\nWith --weak-unknowns=json the report is printed as JSON instead.")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("text")
+        .value_name("FORMAT")
+        .value_parser(["text", "json"])
+        .conflicts_with_all([PRINT_EXPANSION, DUMP_JSON, SUMMARY, LIST_MODULES, PRINT_CONFIG])
+        .required(false)
+}
+
+fn stats() -> Arg {
+    flag(STATS, STATS)
+        .help("Print the size of the eval function of each module without generating code.")
+        .long_help("Print the number of blocks and instructions and the register pressure (the maximum number of values that are live at the same time) of the eval function of each module without generating code.\nA high register pressure usually leads to slow code because values are spilled to memory.")
+        .conflicts_with_all([PRINT_EXPANSION, DUMP_JSON, SUMMARY, LIST_MODULES, PRINT_CONFIG, WEAK_UNKNOWNS])
+}
+
 fn def_arg() -> Arg {
     Arg::new(DEFINE)
         .short('D')
@@ -281,6 +454,28 @@ fn def_arg() -> Arg {
         .value_hint(ValueHint::Other).required(false)
 }
 
+fn werror() -> Arg {
+    let all_lints = builtin_lints::ALL.iter().map(|lint| PossibleValue::new(lint.name));
+
+    Arg::new(WERROR)
+        .long(WERROR)
+        .help("Treat warnings as errors.")
+        .long_help("Treat warnings as errors.\nWithout a value all lints whose lvl is warn are made errors.\nWith a value (--werror=LINT) only the specified lint is made an error.\nOnly lints that would be reported as warnings are affected:\nlints that are allowed (by default, with --allow or with an attribute) remain allowed.")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("warnings")
+        .action(ArgAction::Append)
+        .value_name("LINT")
+        .value_hint(ValueHint::Other)
+        .value_parser(PossibleValuesParser::new(
+            [PossibleValue::new("warnings").help("all lints whose lvl is set to warn")]
+                .into_iter()
+                .chain(all_lints),
+        ))
+        .required(false)
+        .hide_possible_values(true)
+}
+
 fn lint_arg(lvl: LintLevel) -> Arg {
     let arg = match lvl {
         LintLevel::Warn => Arg::new(WARN).long(WARN).short('W').help("Make this lint a warning.")
@@ -1,21 +1,26 @@
+use std::collections::VecDeque;
+use std::fs;
 use std::io::Write;
 use std::process::exit;
 
 use anyhow::{bail, Context, Result};
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::ArgMatches;
 use openvaf::{
     builtin_lints, get_target_names, host_triple, AbsPathBuf, LLVMCodeGenOptLevel, LintLevel,
+    ReactiveFormulation, CODEGEN_OPTS,
 };
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
 use crate::cli_def::{
-    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DEFINE, DENY, DRYRUN, INCLUDE, INPUT, LINTS, OPT_LVL,
-    OUTPUT, SUPPORTED_TARGETS, TARGET, TARGET_CPU, WARN,
+    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, COLLAPSE_RESISTANCE, DEFINE, DENY, DRYRUN, INCLUDE,
+    INCLUDE_RECURSIVE, INPUT, LINTS, NO_LINK, OPT_LVL, OPT_LVL_FOR, OUTPUT, QUIET,
+    REACTIVE_FORMULATION, SENSITIVITY, SUPPORTED_TARGETS, TARGET, TARGET_CPU, WARN, WERROR,
 };
 use crate::{CompilationDestination, Opts};
 
-pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
+/// Returns the options for each input file.
+pub fn matches_to_opts(matches: ArgMatches) -> Result<Vec<Opts>> {
     if matches.get_flag(LINTS) {
         print_lints();
         exit(0)
@@ -25,7 +30,8 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         exit(0)
     }
 
-    let input: Utf8PathBuf = matches.get_one::<Utf8PathBuf>(INPUT).unwrap().clone();
+    let inputs: Vec<Utf8PathBuf> =
+        matches.get_many::<Utf8PathBuf>(INPUT).unwrap().cloned().collect();
 
     let mut lints = Vec::new();
 
@@ -39,8 +45,18 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
     if let Some(deny) = matches.get_many::<String>(DENY) {
         lints.extend(deny.map(|lint| (lint.to_owned(), LintLevel::Deny)));
     }
+    // must come last and only upgrades lints that would be reported as warnings so that lints
+    // which are allowed (explicitly or by default) stay allowed. A bare `--werror` is
+    // equivalent to `-D warnings`
+    if let Some(werror) = matches.get_many::<String>(WERROR) {
+        let werror: Vec<_> = werror
+            .filter(|lint| *lint == "warnings" || lint_lvl(lint, &lints) == Some(LintLevel::Warn))
+            .map(|lint| (lint.to_owned(), LintLevel::Deny))
+            .collect();
+        lints.extend(werror);
+    }
 
-    let output = if matches.get_flag(BATCHMODE) {
+    let cache_dir = if matches.get_flag(BATCHMODE) {
         let cache_dir = if let Some(val) = matches.get_one::<Utf8PathBuf>(CACHE_DIR) {
             val.clone()
         } else {
@@ -58,20 +74,20 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
                 )
             }
         };
-        CompilationDestination::Cache { cache_dir }
+        Some(cache_dir)
     } else {
-        let lib_file = if let Some(output) = matches.get_one::<Utf8PathBuf>(OUTPUT) {
-            output.clone()
-        } else {
-            input.with_extension("osdi")
-        };
-
-        CompilationDestination::Path { lib_file }
+        None
     };
 
+    let output_file = matches.get_one::<Utf8PathBuf>(OUTPUT);
+    if output_file.is_some() && inputs.len() > 1 {
+        bail!("--{OUTPUT} can not be used when compiling multiple files");
+    }
+
     let codegen_opts = matches
         .get_many::<String>(CODEGEN)
         .map_or_else(Vec::new, |values| values.cloned().collect());
+    check_codegen_opts(&codegen_opts);
 
     let defines = matches
         .get_many::<String>(DEFINE)
@@ -82,42 +98,174 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         |include| include.map(|path| Ok(AbsPathBuf::assert(path.canonicalize()?))).collect(),
     );
 
-    let include = include?;
+    let mut include = include?;
+    if let Some(dirs) = matches.get_many::<Utf8PathBuf>(INCLUDE_RECURSIVE) {
+        for dir in dirs {
+            add_include_dir_recursive(&mut include, dir)?;
+        }
+    }
+
+    let opt_lvl = parse_opt_lvl(matches.get_one::<String>(OPT_LVL).unwrap())?;
+    let module_opt_lvls: Result<_> = matches.get_many::<(String, String)>(OPT_LVL_FOR).map_or_else(
+        || Ok(Vec::new()),
+        |lvls| lvls.map(|(module, lvl)| Ok((module.clone(), parse_opt_lvl(lvl)?))).collect(),
+    );
+    let module_opt_lvls = module_opt_lvls?;
+
+    let target = matches.get_one::<String>(TARGET).map_or(host_triple(), String::as_str);
+    let target = match openvaf::Target::parse(target) {
+        Ok(target) => target,
+        Err(err) => bail!(err),
+    };
+    let is_host = openvaf::Target::host_target().as_ref() == Some(&target);
+    let default_cpu = if is_host { "native" } else { "generic" };
+
+    let target_cpu: String =
+        matches.get_one(TARGET_CPU).cloned().unwrap_or_else(|| default_cpu.to_owned());
+
+    let dry_run = matches.get_flag(DRYRUN);
+    let quiet = matches.get_flag(QUIET);
+    let no_link = matches.get_one::<Utf8PathBuf>(NO_LINK).cloned();
+    let collapse_resistance = matches.get_one::<f64>(COLLAPSE_RESISTANCE).copied();
+    let sensitivity_params: Vec<String> = matches
+        .get_many::<String>(SENSITIVITY)
+        .map_or_else(Vec::new, |params| params.cloned().collect());
+    let reactive_formulation =
+        match matches.get_one::<String>(REACTIVE_FORMULATION).unwrap().as_str() {
+            "charge" => ReactiveFormulation::Charge,
+            "capacitance" => ReactiveFormulation::Capacitance,
+            formulation => bail!("unknown reactive formulation {formulation}"),
+        };
+    let opts = inputs
+        .into_iter()
+        .map(|input| {
+            let output = if let Some(cache_dir) = &cache_dir {
+                CompilationDestination::Cache { cache_dir: cache_dir.clone() }
+            } else {
+                let lib_file = output_file.cloned().unwrap_or_else(|| input.with_extension("osdi"));
+                CompilationDestination::Path { lib_file }
+            };
+            Opts {
+                input,
+                lints: lints.clone(),
+                codegen_opts: codegen_opts.clone(),
+                defines: defines.clone(),
+                include: include.clone(),
+                output,
+                opt_lvl,
+                module_opt_lvls: module_opt_lvls.clone(),
+                target: target.clone(),
+                target_cpu: target_cpu.clone(),
+                dry_run,
+                no_link: no_link.clone(),
+                collapse_resistance,
+                sensitivity_params: sensitivity_params.clone(),
+                reactive_formulation,
+                quiet,
+            }
+        })
+        .collect();
+    Ok(opts)
+}
+
+/// Adds `root` and all of its subdirectories to the include search path. Shallower directories
+/// are searched first and directories at the same depth are ordered by name. Symbolic links are
+/// followed but each directory is only added once, so symlink cycles terminate.
+fn add_include_dir_recursive(include: &mut Vec<AbsPathBuf>, root: &Utf8Path) -> Result<()> {
+    let mut queue = VecDeque::new();
+    queue.push_back(AbsPathBuf::assert(root.canonicalize()?));
+    while let Some(dir) = queue.pop_front() {
+        if include.contains(&dir) {
+            continue;
+        }
+
+        let mut subdirs = Vec::new();
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                subdirs.push(path);
+            }
+        }
+        subdirs.sort();
+        for subdir in subdirs {
+            queue.push_back(AbsPathBuf::assert(subdir.canonicalize()?));
+        }
+        include.push(dir);
+    }
+    Ok(())
+}
+
+/// Warns about codegen options that are not part of [`CODEGEN_OPTS`], these are ignored.
+fn check_codegen_opts(codegen_opts: &[String]) {
+    let mut stderr = termcolor::StandardStream::stderr(ColorChoice::Auto);
+    for opt in codegen_opts {
+        let name = opt.split_once('=').map_or(opt.as_str(), |(name, _)| name);
+        if CODEGEN_OPTS.contains(&name) {
+            continue;
+        }
+
+        stderr.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)).set_bold(true)).unwrap();
+        write!(&mut stderr, "warning").unwrap();
+        stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
+        write!(&mut stderr, ":").unwrap();
+        stderr.set_color(&ColorSpec::new()).unwrap();
+        writeln!(&mut stderr, " unknown codegen option '{name}' is ignored").unwrap();
+
+        let closest = CODEGEN_OPTS.iter().min_by_key(|known| edit_distance(name, known));
+        match closest {
+            Some(known) if edit_distance(name, known) <= name.len().max(3) / 3 => {
+                writeln!(&mut stderr, "  = help: did you mean '{known}'?").unwrap()
+            }
+            _ => writeln!(&mut stderr, "  = help: known options: {}", CODEGEN_OPTS.join(", "))
+                .unwrap(),
+        }
+    }
+}
+
+/// The levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let subst = diag + (ca != cb) as usize;
+            diag = row[j + 1];
+            row[j + 1] = subst.min(row[j] + 1).min(diag + 1);
+        }
+    }
+    row[b.len()]
+}
 
-    let opt_lvl = match &**matches.get_one::<String>(OPT_LVL).unwrap() {
+fn parse_opt_lvl(lvl: &str) -> Result<LLVMCodeGenOptLevel> {
+    let lvl = match lvl {
         "0" => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
         "1" => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
         "2" => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
         "3" => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
         lvl => bail!("unknown opt lvl {lvl}"),
     };
+    Ok(lvl)
+}
 
-    let host = host_triple();
-    let target = matches.get_one::<String>(TARGET).cloned().unwrap_or_else(|| host.to_owned());
-    let default_cpu = if host != target { "generic" } else { "native" };
-
-    let target = if let Some(target) = openvaf::Target::search(&target) {
-        target
-    } else {
-        // should never happened but helpful to provide support just in case
-        bail!("The target {target} is not supported by  this binary")
-    };
-
-    let target_cpu: String =
-        matches.get_one(TARGET_CPU).cloned().unwrap_or_else(|| default_cpu.to_owned());
-
-    Ok(Opts {
-        input,
-        lints,
-        codegen_opts,
-        defines,
-        include,
-        output,
-        opt_lvl,
-        target,
-        target_cpu,
-        dry_run: matches.get_flag(DRYRUN),
-    })
+/// The level of the builtin `lint` after the command line overwrites in `lints` were applied.
+fn lint_lvl(lint: &str, lints: &[(String, LintLevel)]) -> Option<LintLevel> {
+    let mut lvl = builtin_lints::ALL.iter().find(|data| data.name == lint)?.default_lvl;
+    for (name, new_lvl) in lints {
+        let overwritten = match name.as_str() {
+            "all" => true,
+            "warnings" => lvl == LintLevel::Warn,
+            "errors" => lvl == LintLevel::Deny,
+            name => name == lint,
+        };
+        if overwritten {
+            lvl = *new_lvl;
+        }
+    }
+    Some(lvl)
 }
 
 fn print_lints() {
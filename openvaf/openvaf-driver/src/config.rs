@@ -0,0 +1,124 @@
+//! Formats the configuration printed with `--print-config`. The configuration contains all
+//! options after defaults were applied, so that a compilation can be reproduced exactly.
+//! The JSON output looks as follows:
+//!
+//! ```text
+//! {
+//!   "version": "23.5.0",
+//!   "target": "x86_64-unknown-linux-gnu",
+//!   "target_cpu": "native",
+//!   "opt_level": 3,
+//!   "module_opt_levels": [
+//!     {"module": "diode_va", "opt_level": 0}
+//!   ],
+//!   "include": ["/usr/share/va"],
+//!   "defines": ["foo"],
+//!   "codegen": ["target-feature=+avx2"],
+//!   "collapse_resistance": 0.001,
+//!   "sensitivity_params": ["is"],
+//!   "reactive_formulation": "charge",
+//!   "lints": [
+//!     {"name": "macro_overwritten", "level": "allow"}
+//!   ],
+//!   "inputs": ["diode.va"]
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use openvaf::{LLVMCodeGenOptLevel, Opts, ReactiveFormulation};
+
+use crate::json::Json;
+
+#[cfg(test)]
+mod tests;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// All inputs are compiled with the same options, so only the options of the first
+/// input are printed (together with the list of all inputs).
+pub fn to_text(opts: &[Opts]) -> String {
+    let mut dst = format!("version: {VERSION}\n");
+    if let Some(opts) = opts.first() {
+        writeln!(dst, "target: {}", opts.target.llvm_target).unwrap();
+        writeln!(dst, "target cpu: {}", opts.target_cpu).unwrap();
+        writeln!(dst, "opt level: {}", opt_lvl_number(opts.opt_lvl)).unwrap();
+        for (module, lvl) in &opts.module_opt_lvls {
+            writeln!(dst, "opt level for {module}: {}", opt_lvl_number(*lvl)).unwrap();
+        }
+        for dir in &opts.include {
+            writeln!(dst, "include: {}", dir.display()).unwrap();
+        }
+        for define in &opts.defines {
+            writeln!(dst, "define: {define}").unwrap();
+        }
+        for opt in &opts.codegen_opts {
+            writeln!(dst, "codegen: {opt}").unwrap();
+        }
+        if let Some(threshold) = opts.collapse_resistance {
+            writeln!(dst, "collapse resistance: {threshold}").unwrap();
+        }
+        for param in &opts.sensitivity_params {
+            writeln!(dst, "sensitivity: {param}").unwrap();
+        }
+        writeln!(dst, "reactive formulation: {}", formulation_name(opts.reactive_formulation))
+            .unwrap();
+        for (lint, lvl) in &opts.lints {
+            writeln!(dst, "lint {lint}: {lvl}").unwrap();
+        }
+    }
+    for opts in opts {
+        writeln!(dst, "input: {}", opts.input).unwrap();
+    }
+    dst
+}
+
+pub fn to_json(opts: &[Opts]) -> String {
+    let mut fields = vec![("version", VERSION.into())];
+    if let Some(opts) = opts.first() {
+        let module_opt_lvls = opts.module_opt_lvls.iter().map(|(module, lvl)| {
+            Json::Object(vec![
+                ("module", module.as_str().into()),
+                ("opt_level", Json::number(opt_lvl_number(*lvl))),
+            ])
+        });
+        let lints = opts.lints.iter().map(|(lint, lvl)| {
+            Json::Object(vec![("name", lint.as_str().into()), ("level", lvl.to_string().into())])
+        });
+        let collapse_resistance =
+            opts.collapse_resistance.map(|threshold| Json::Number(format!("{threshold:?}")));
+        fields.extend([
+            ("target", opts.target.llvm_target.as_str().into()),
+            ("target_cpu", opts.target_cpu.as_str().into()),
+            ("opt_level", Json::number(opt_lvl_number(opts.opt_lvl))),
+            ("module_opt_levels", Json::array(module_opt_lvls)),
+            ("include", Json::array(opts.include.iter().map(|dir| dir.display().to_string()))),
+            ("defines", Json::array(opts.defines.iter().map(String::as_str))),
+            ("codegen", Json::array(opts.codegen_opts.iter().map(String::as_str))),
+            ("collapse_resistance", collapse_resistance.into()),
+            ("sensitivity_params", Json::array(opts.sensitivity_params.iter().map(String::as_str))),
+            ("reactive_formulation", formulation_name(opts.reactive_formulation).into()),
+            ("lints", Json::array(lints)),
+        ]);
+    }
+    fields.push(("inputs", Json::array(opts.iter().map(|opts| opts.input.as_str()))));
+    Json::Object(fields).to_pretty_string()
+}
+
+/// The formulation as passed to `--reactive-formulation`.
+fn formulation_name(formulation: ReactiveFormulation) -> &'static str {
+    match formulation {
+        ReactiveFormulation::Charge => "charge",
+        ReactiveFormulation::Capacitance => "capacitance",
+    }
+}
+
+/// The optimization level as passed to `--opt_lvl`.
+fn opt_lvl_number(lvl: LLVMCodeGenOptLevel) -> u8 {
+    match lvl {
+        LLVMCodeGenOptLevel::LLVMCodeGenLevelNone => 0,
+        LLVMCodeGenOptLevel::LLVMCodeGenLevelLess => 1,
+        LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault => 2,
+        LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive => 3,
+    }
+}
@@ -0,0 +1,73 @@
+use camino::Utf8PathBuf;
+use openvaf::{
+    CompilationDestination, LLVMCodeGenOptLevel, LintLevel, Opts, ReactiveFormulation, Target,
+};
+
+use super::{to_json, to_text, VERSION};
+
+fn opts(input: &str) -> Opts {
+    Opts {
+        dry_run: false,
+        no_link: None,
+        defines: vec!["foo=1".to_owned()],
+        codegen_opts: Vec::new(),
+        lints: vec![("macro_overwritten".to_owned(), LintLevel::Allow)],
+        input: Utf8PathBuf::from(input),
+        output: CompilationDestination::Path { lib_file: Utf8PathBuf::from("diode.osdi") },
+        include: Vec::new(),
+        opt_lvl: LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+        module_opt_lvls: vec![("diode_va".to_owned(), LLVMCodeGenOptLevel::LLVMCodeGenLevelNone)],
+        target: Target::parse("x86_64-unknown-linux-gnu").unwrap(),
+        target_cpu: "generic".to_owned(),
+        collapse_resistance: None,
+        sensitivity_params: vec!["is".to_owned()],
+        reactive_formulation: ReactiveFormulation::Capacitance,
+        quiet: false,
+    }
+}
+
+#[test]
+fn text() {
+    let expected = format!(
+        "version: {VERSION}
+target: x86_64-unknown-linux-gnu
+target cpu: generic
+opt level: 2
+opt level for diode_va: 0
+define: foo=1
+sensitivity: is
+reactive formulation: capacitance
+lint macro_overwritten: allow
+input: diode.va
+input: resistor.va
+"
+    );
+    assert_eq!(to_text(&[opts("diode.va"), opts("resistor.va")]), expected);
+}
+
+#[test]
+fn json() {
+    let expected = format!(
+        r#"{{
+  "version": "{VERSION}",
+  "target": "x86_64-unknown-linux-gnu",
+  "target_cpu": "generic",
+  "opt_level": 2,
+  "module_opt_levels": [
+    {{"module": "diode_va", "opt_level": 0}}
+  ],
+  "include": [],
+  "defines": ["foo=1"],
+  "codegen": [],
+  "collapse_resistance": null,
+  "sensitivity_params": ["is"],
+  "reactive_formulation": "capacitance",
+  "lints": [
+    {{"name": "macro_overwritten", "level": "allow"}}
+  ],
+  "inputs": ["diode.va"]
+}}
+"#
+    );
+    assert_eq!(to_json(&[opts("diode.va")]), expected);
+}
@@ -12,40 +12,15 @@ use std::{env, io, mem, panic};
 
 use backtrace::Backtrace;
 use backtrace_ext::short_frames_strict;
+use openvaf::CrashContext;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+#[cfg(test)]
+mod tests;
+
 // Utility function which will handle dumping information to disk
 pub fn handle_dump(panic_info: &PanicHookInfo) -> Option<PathBuf> {
-    let mut expl = String::new();
-
-    #[cfg(feature = "nightly")]
-    let message = panic_info.message().map(|m| format!("{}", m));
-
-    #[cfg(not(feature = "nightly"))]
-    let message = match (
-        panic_info.payload().downcast_ref::<&str>(),
-        panic_info.payload().downcast_ref::<String>(),
-    ) {
-        (Some(s), _) => Some(s.to_string()),
-        (_, Some(s)) => Some(s.to_string()),
-        (None, None) => None,
-    };
-
-    let cause = match message {
-        Some(m) => m,
-        None => "Unknown".into(),
-    };
-
-    match panic_info.location() {
-        Some(location) => expl.push_str(&format!(
-            "Panic occurred in file '{}' at line {}\n",
-            location.file(),
-            location.line()
-        )),
-        None => expl.push_str("Panic location unknown.\n"),
-    }
-
-    let report = Report::new(expl, cause);
+    let report = Report::from_panic(panic_info);
 
     match report.persist() {
         Ok(f) => Some(f),
@@ -97,6 +72,40 @@ If possible please also attach the source file that OpenVAF was compiling.",
 struct Report(String);
 
 impl Report {
+    /// Create a report for the panic described by `panic_info`.
+    pub fn from_panic(panic_info: &PanicHookInfo) -> Self {
+        let mut expl = String::new();
+
+        #[cfg(feature = "nightly")]
+        let message = panic_info.message().map(|m| format!("{}", m));
+
+        #[cfg(not(feature = "nightly"))]
+        let message = match (
+            panic_info.payload().downcast_ref::<&str>(),
+            panic_info.payload().downcast_ref::<String>(),
+        ) {
+            (Some(s), _) => Some(s.to_string()),
+            (_, Some(s)) => Some(s.to_string()),
+            (None, None) => None,
+        };
+
+        let cause = match message {
+            Some(m) => m,
+            None => "Unknown".into(),
+        };
+
+        match panic_info.location() {
+            Some(location) => expl.push_str(&format!(
+                "Panic occurred in file '{}' at line {}\n",
+                location.file(),
+                location.line()
+            )),
+            None => expl.push_str("Panic location unknown.\n"),
+        }
+
+        Report::new(expl, cause)
+    }
+
     /// Create a new instance.
     pub fn new(explanation: String, cause: String) -> Self {
         let mut dst = String::new();
@@ -106,6 +115,15 @@ impl Report {
                 let _ = writeln!(dst, "{:#?}", args);
             }
         }
+        // the model that was being compiled when the panic occurred
+        let cx = CrashContext::current();
+        let _ = writeln!(dst, "Compilation context:\n{cx}");
+        if let (Some(file), Some(module)) = (&cx.file, &cx.module) {
+            let _ = writeln!(
+                dst,
+                "The crash can likely be reproduced by compiling the module {module} in {file}\n"
+            );
+        }
         let _ = write!(dst, "{explanation}{cause}");
         //We take padding for address and extra two letters
         //to padd after index.
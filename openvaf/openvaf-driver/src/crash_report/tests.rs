@@ -0,0 +1,35 @@
+use std::panic;
+use std::sync::{Arc, Mutex};
+
+use openvaf::CrashContext;
+
+use super::Report;
+
+#[test]
+fn report_contains_module() {
+    let report = Arc::new(Mutex::new(None));
+    let report_ = report.clone();
+    panic::set_hook(Box::new(move |info| {
+        *report_.lock().unwrap() = Some(Report::from_panic(info).0);
+    }));
+
+    let res = panic::catch_unwind(|| {
+        let _cx = CrashContext {
+            file: Some("diode.va".to_owned()),
+            module: Some("diode_va".to_owned()),
+            phase: Some("codegen"),
+        }
+        .enter();
+        panic!("simulated crash")
+    });
+    let _ = panic::take_hook();
+    assert!(res.is_err());
+
+    let report = report.lock().unwrap().take().unwrap();
+    assert!(report.contains("module: diode_va"), "{report}");
+    assert!(report.contains("file: diode.va"), "{report}");
+    assert!(report.contains("phase: codegen"), "{report}");
+    assert!(report.contains("simulated crash"), "{report}");
+    // the context is reset once the guard is dropped
+    assert_eq!(CrashContext::current(), CrashContext::default());
+}
@@ -0,0 +1,168 @@
+//! A minimal JSON writer shared by all machine readable outputs of the driver.
+//! Arrays and objects that only contain scalar values are written on a single line,
+//! everything else is indented by two spaces:
+//!
+//! ```text
+//! {
+//!   "modules": [
+//!     {"name": "diode", "success": true}
+//!   ],
+//!   "inputs": ["diode.va"]
+//! }
+//! ```
+
+use std::fmt::{Display, Write};
+
+#[cfg(test)]
+mod tests;
+
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// A number that was already formatted.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub fn number(val: impl Display) -> Json {
+        Json::Number(val.to_string())
+    }
+
+    pub fn array<T: Into<Json>>(vals: impl IntoIterator<Item = T>) -> Json {
+        Json::Array(vals.into_iter().map(Into::into).collect())
+    }
+
+    fn is_scalar(&self) -> bool {
+        !matches!(self, Json::Array(_) | Json::Object(_))
+    }
+
+    /// Formats the value (terminated by a newline).
+    pub fn to_pretty_string(&self) -> String {
+        let mut dst = String::new();
+        self.write(&mut dst, 0);
+        dst.push('\n');
+        dst
+    }
+
+    fn write(&self, dst: &mut String, indent: usize) {
+        match self {
+            Json::Null => dst.push_str("null"),
+            Json::Bool(val) => write!(dst, "{val}").unwrap(),
+            Json::Number(val) => dst.push_str(val),
+            Json::String(val) => write_str(dst, val),
+            Json::Array(vals) => {
+                write_list(dst, indent, '[', ']', vals, |dst, val, indent| val.write(dst, indent))
+            }
+            Json::Object(fields) => {
+                write_list(dst, indent, '{', '}', fields, |dst, (name, val), indent| {
+                    write_str(dst, name);
+                    dst.push_str(": ");
+                    val.write(dst, indent)
+                })
+            }
+        }
+    }
+}
+
+fn write_list<T: AsJson>(
+    dst: &mut String,
+    indent: usize,
+    open: char,
+    close: char,
+    vals: &[T],
+    mut write_val: impl FnMut(&mut String, &T, usize),
+) {
+    dst.push(open);
+    if vals.iter().all(|val| val.json().is_scalar()) {
+        for (i, val) in vals.iter().enumerate() {
+            if i != 0 {
+                dst.push_str(", ");
+            }
+            write_val(dst, val, indent);
+        }
+    } else {
+        for (i, val) in vals.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            dst.push('\n');
+            push_indent(dst, indent + 1);
+            write_val(dst, val, indent + 1);
+        }
+        dst.push('\n');
+        push_indent(dst, indent);
+    }
+    dst.push(close);
+}
+
+/// The value of an array element or object field.
+trait AsJson {
+    fn json(&self) -> &Json;
+}
+
+impl AsJson for Json {
+    fn json(&self) -> &Json {
+        self
+    }
+}
+
+impl AsJson for (&'static str, Json) {
+    fn json(&self) -> &Json {
+        &self.1
+    }
+}
+
+fn push_indent(dst: &mut String, indent: usize) {
+    for _ in 0..indent {
+        dst.push_str("  ");
+    }
+}
+
+fn write_str(dst: &mut String, val: &str) {
+    dst.push('"');
+    for c in val.chars() {
+        match c {
+            '"' => dst.push_str("\\\""),
+            '\\' => dst.push_str("\\\\"),
+            '\n' => dst.push_str("\\n"),
+            '\r' => dst.push_str("\\r"),
+            '\t' => dst.push_str("\\t"),
+            c if c.is_control() => write!(dst, "\\u{:04x}", c as u32).unwrap(),
+            c => dst.push(c),
+        }
+    }
+    dst.push('"');
+}
+
+impl From<bool> for Json {
+    fn from(val: bool) -> Json {
+        Json::Bool(val)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(val: usize) -> Json {
+        Json::number(val)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(val: &str) -> Json {
+        Json::String(val.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(val: String) -> Json {
+        Json::String(val)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(val: Option<T>) -> Json {
+        val.map_or(Json::Null, Into::into)
+    }
+}
@@ -0,0 +1,40 @@
+use super::Json;
+
+#[test]
+fn scalars() {
+    assert_eq!(Json::Null.to_pretty_string(), "null\n");
+    assert_eq!(Json::from(true).to_pretty_string(), "true\n");
+    assert_eq!(Json::from(42usize).to_pretty_string(), "42\n");
+    assert_eq!(Json::from(None::<&str>).to_pretty_string(), "null\n");
+    assert_eq!(Json::from("a\"b\\c\n\u{1}").to_pretty_string(), "\"a\\\"b\\\\c\\n\\u0001\"\n");
+}
+
+#[test]
+fn nested() {
+    let val = Json::Object(vec![
+        ("name", "diode".into()),
+        ("empty", Json::array(Vec::<Json>::new())),
+        ("inputs", Json::array(["a.va", "b.va"])),
+        (
+            "modules",
+            Json::array([
+                Json::Object(vec![("name", "a".into()), ("time", Json::number(1.5))]),
+                Json::Object(vec![("ports", Json::array(["p"])), ("params", Json::array([""; 0]))]),
+            ]),
+        ),
+    ]);
+    let expected = r#"{
+  "name": "diode",
+  "empty": [],
+  "inputs": ["a.va", "b.va"],
+  "modules": [
+    {"name": "a", "time": 1.5},
+    {
+      "ports": ["p"],
+      "params": []
+    }
+  ]
+}
+"#;
+    assert_eq!(val.to_pretty_string(), expected);
+}
@@ -0,0 +1,64 @@
+//! Formats the modules listed with `--list-modules`. The JSON output looks as follows:
+//!
+//! ```text
+//! {
+//!   "modules": [
+//!     {
+//!       "name": "diode",
+//!       "ports": [
+//!         {"name": "a", "direction": "inout"}
+//!       ],
+//!       "parameters": [
+//!         {"name": "is", "type": "real", "default": "1e-14", "declaration": "real is = 10f"}
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use openvaf::ModuleListing;
+
+use crate::json::Json;
+
+#[cfg(test)]
+mod tests;
+
+pub fn to_text(modules: &[ModuleListing]) -> String {
+    let mut dst = String::new();
+    for module in modules {
+        writeln!(dst, "module {}", module.name).unwrap();
+        dst.push_str("  ports:\n");
+        for (name, direction) in &module.ports {
+            writeln!(dst, "    {direction} {name}").unwrap();
+        }
+        dst.push_str("  parameters:\n");
+        for param in &module.params {
+            writeln!(dst, "    {}", param.declaration).unwrap();
+        }
+    }
+    dst
+}
+
+pub fn to_json(modules: &[ModuleListing]) -> String {
+    let modules = modules.iter().map(|module| {
+        let ports = module.ports.iter().map(|(name, direction)| {
+            Json::Object(vec![("name", name.as_str().into()), ("direction", (*direction).into())])
+        });
+        let params = module.params.iter().map(|param| {
+            Json::Object(vec![
+                ("name", param.name.as_str().into()),
+                ("type", param.ty.as_str().into()),
+                ("default", param.default.as_str().into()),
+                ("declaration", param.declaration.as_str().into()),
+            ])
+        });
+        Json::Object(vec![
+            ("name", module.name.as_str().into()),
+            ("ports", Json::array(ports)),
+            ("parameters", Json::array(params)),
+        ])
+    });
+    Json::Object(vec![("modules", Json::array(modules))]).to_pretty_string()
+}
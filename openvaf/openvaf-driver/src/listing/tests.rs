@@ -0,0 +1,70 @@
+use openvaf::{ModuleListing, ParamListing};
+
+use super::{to_json, to_text};
+
+fn modules() -> [ModuleListing; 2] {
+    [
+        ModuleListing {
+            name: "diode".to_owned(),
+            ports: vec![("a".to_owned(), "inout"), ("c".to_owned(), "output")],
+            params: vec![
+                ParamListing {
+                    name: "is".to_owned(),
+                    ty: "real".to_owned(),
+                    default: "1e-14".to_owned(),
+                    declaration: r#"real is = 10f from [0.0:inf] units="A""#.to_owned(),
+                },
+                ParamListing {
+                    name: "type".to_owned(),
+                    ty: "string".to_owned(),
+                    default: "\"n\"".to_owned(),
+                    declaration: r#"string type = "n""#.to_owned(),
+                },
+            ],
+        },
+        ModuleListing { name: "empty".to_owned(), ports: Vec::new(), params: Vec::new() },
+    ]
+}
+
+#[test]
+fn text() {
+    let expected = r#"module diode
+  ports:
+    inout a
+    output c
+  parameters:
+    real is = 10f from [0.0:inf] units="A"
+    string type = "n"
+module empty
+  ports:
+  parameters:
+"#;
+    assert_eq!(to_text(&modules()), expected);
+}
+
+#[test]
+fn json() {
+    let expected = r#"{
+  "modules": [
+    {
+      "name": "diode",
+      "ports": [
+        {"name": "a", "direction": "inout"},
+        {"name": "c", "direction": "output"}
+      ],
+      "parameters": [
+        {"name": "is", "type": "real", "default": "1e-14", "declaration": "real is = 10f from [0.0:inf] units=\"A\""},
+        {"name": "type", "type": "string", "default": "\"n\"", "declaration": "string type = \"n\""}
+      ]
+    },
+    {
+      "name": "empty",
+      "ports": [],
+      "parameters": []
+    }
+  ]
+}
+"#;
+    assert_eq!(to_json(&modules()), expected);
+    assert_eq!(to_json(&[]), "{\n  \"modules\": []\n}\n");
+}
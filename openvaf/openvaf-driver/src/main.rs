@@ -1,21 +1,35 @@
+use std::fs;
 use std::io::Write;
 use std::process::exit;
 use std::sync::Mutex;
+use std::time::Instant;
 
-use anyhow::{bail, Result};
-use camino::Utf8PathBuf;
+use anyhow::{bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::ArgMatches;
 use cli_def::{main_command, INPUT};
 use mimalloc::MiMalloc;
-use openvaf::{compile, expand, CompilationDestination, CompilationTermination, Opts};
+use openvaf::{
+    compile, compile_and_count, expand, list_modules, module_stats, weak_unknowns,
+    CompilationDestination, CompilationTermination, DiagnosticCount, Opts,
+};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-use crate::cli_def::{DUMP_JSON, PRINT_EXPANSION};
+use crate::cli_def::{
+    DUMP_JSON, LIST_MODULES, PRINT_CONFIG, PRINT_EXPANSION, STATS, SUMMARY, WEAK_UNKNOWNS,
+};
 use crate::cli_process::matches_to_opts;
+use crate::summary::FileSummary;
 
 mod cli_def;
 mod cli_process;
+mod config;
 mod crash_report;
+mod json;
+mod listing;
+mod stats;
+mod summary;
+mod weak_unknowns;
 
 static ARGS: Mutex<Option<Opts>> = Mutex::new(None);
 #[global_allocator]
@@ -34,26 +48,28 @@ pub fn main() {
         .init();
     match wrapped_main(matches) {
         Ok(err_code) => exit(err_code),
-        Err(err) => {
-            let mut stderr = StandardStream::stderr(ColorChoice::Auto);
-
-            for cause in err.chain() {
-                stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true)).unwrap();
-                write!(&mut stderr, "error").unwrap();
-                stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
-                write!(&mut stderr, ":").unwrap();
-                stderr.set_color(&ColorSpec::new()).unwrap();
-                writeln!(&mut stderr, " {cause}").unwrap();
-            }
+        Err(err) => print_error(&err, &input),
+    }
+}
 
-            stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true)).unwrap();
-            write!(&mut stderr, "error").unwrap();
-            stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
-            write!(&mut stderr, ":").unwrap();
-            stderr.set_color(&ColorSpec::new()).unwrap();
-            writeln!(&mut stderr, " failed to compile {input}").unwrap();
-        }
+fn print_error(err: &anyhow::Error, input: &Utf8Path) {
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+
+    for cause in err.chain() {
+        stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true)).unwrap();
+        write!(&mut stderr, "error").unwrap();
+        stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
+        write!(&mut stderr, ":").unwrap();
+        stderr.set_color(&ColorSpec::new()).unwrap();
+        writeln!(&mut stderr, " {cause}").unwrap();
     }
+
+    stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true)).unwrap();
+    write!(&mut stderr, "error").unwrap();
+    stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
+    write!(&mut stderr, ":").unwrap();
+    stderr.set_color(&ColorSpec::new()).unwrap();
+    writeln!(&mut stderr, " failed to compile {input}").unwrap();
 }
 
 pub const DATA_ERROR: i32 = 65;
@@ -61,7 +77,36 @@ pub const DATA_ERROR: i32 = 65;
 fn wrapped_main(matches: ArgMatches) -> Result<i32> {
     let print_expansion = matches.get_flag(PRINT_EXPANSION);
     let dump_json_ = matches.get_flag(DUMP_JSON);
-    let opts = matches_to_opts(matches)?;
+    let summary: Option<Utf8PathBuf> = matches.get_one(SUMMARY).cloned();
+    let list_format: Option<String> = matches.get_one(LIST_MODULES).cloned();
+    let config_format: Option<String> = matches.get_one(PRINT_CONFIG).cloned();
+    let weak_format: Option<String> = matches.get_one(WEAK_UNKNOWNS).cloned();
+    let print_stats = matches.get_flag(STATS);
+    let mut opts = matches_to_opts(matches)?;
+    if let Some(format) = config_format {
+        let config = if format == "json" { config::to_json(&opts) } else { config::to_text(&opts) };
+        print!("{config}");
+        return Ok(0);
+    }
+    if let Some(format) = list_format {
+        return list_files(&opts, format == "json");
+    }
+    if let Some(format) = weak_format {
+        return report_weak_unknowns(&opts, format == "json");
+    }
+    if print_stats {
+        return report_stats(&opts);
+    }
+    if opts.len() == 1 && summary.is_none() {
+        return compile_file(opts.pop().unwrap(), print_expansion, dump_json_);
+    }
+    if print_expansion || dump_json_ {
+        bail!("--{PRINT_EXPANSION} and --{DUMP_JSON} can only be used to compile a single file without --{SUMMARY}");
+    }
+    compile_files(opts, summary)
+}
+
+fn compile_file(opts: Opts, print_expansion: bool, dump_json_: bool) -> Result<i32> {
     *ARGS.lock().unwrap() = Some(opts.clone());
     if print_expansion {
         let res = match expand(&opts)? {
@@ -91,3 +136,94 @@ fn wrapped_main(matches: ArgMatches) -> Result<i32> {
 
     Ok(res)
 }
+
+/// Prints the modules of all files (in the order of the files) without generating code.
+fn list_files(opts: &[Opts], json: bool) -> Result<i32> {
+    let mut modules = Vec::new();
+    let mut res = 0;
+    for opts in opts {
+        *ARGS.lock().unwrap() = Some(opts.clone());
+        match list_modules(opts)? {
+            Some(listing) => modules.extend(listing),
+            None => res = DATA_ERROR,
+        }
+    }
+    let listing = if json { listing::to_json(&modules) } else { listing::to_text(&modules) };
+    print!("{listing}");
+    Ok(res)
+}
+
+/// Prints the unknowns without DC conductance of all files (in the order of the files)
+/// without generating code.
+fn report_weak_unknowns(opts: &[Opts], json: bool) -> Result<i32> {
+    let mut modules = Vec::new();
+    let mut res = 0;
+    for opts in opts {
+        *ARGS.lock().unwrap() = Some(opts.clone());
+        match weak_unknowns(opts)? {
+            Some(report) => modules.extend(report),
+            None => res = DATA_ERROR,
+        }
+    }
+    let report =
+        if json { weak_unknowns::to_json(&modules) } else { weak_unknowns::to_text(&modules) };
+    print!("{report}");
+    Ok(res)
+}
+
+/// Prints the size of the eval function of each module of all files (in the order of the
+/// files) without generating code.
+fn report_stats(opts: &[Opts]) -> Result<i32> {
+    let mut modules = Vec::new();
+    let mut res = 0;
+    for opts in opts {
+        *ARGS.lock().unwrap() = Some(opts.clone());
+        match module_stats(opts)? {
+            Some(stats) => modules.extend(stats),
+            None => res = DATA_ERROR,
+        }
+    }
+    print!("{}", stats::to_text(&modules));
+    Ok(res)
+}
+
+/// Compiles each file separately. A file that fails to compile does not stop
+/// the remaining files from being compiled.
+fn compile_files(opts: Vec<Opts>, summary: Option<Utf8PathBuf>) -> Result<i32> {
+    let mut files = Vec::with_capacity(opts.len());
+    for opts in opts {
+        *ARGS.lock().unwrap() = Some(opts.clone());
+        let start = Instant::now();
+        let mut diagnostics = DiagnosticCount::default();
+        let mut modules = Vec::new();
+        let lib_file = match compile_and_count(&opts, &mut diagnostics, &mut modules) {
+            Ok(CompilationTermination::Compiled { lib_file }) => {
+                if matches!(opts.output, CompilationDestination::Cache { .. }) {
+                    println!("{lib_file}");
+                }
+                Some(lib_file)
+            }
+            Ok(CompilationTermination::FatalDiagnostic) => None,
+            Err(err) => {
+                print_error(&err, &opts.input);
+                None
+            }
+        };
+        files.push(FileSummary {
+            success: lib_file.is_some(),
+            output: if opts.dry_run { None } else { lib_file },
+            input: opts.input,
+            diagnostics,
+            modules,
+            time: start.elapsed(),
+        });
+    }
+
+    if let Some(path) = summary {
+        fs::write(&path, summary::to_json(&files))
+            .with_context(|| format!("failed to write {path}"))?;
+    }
+
+    let res = if files.iter().all(|file| file.success) { 0 } else { DATA_ERROR };
+    Ok(res)
+}
@@ -0,0 +1,25 @@
+//! Formats the statistics printed with `--stats`, one line per module:
+//!
+//! ```text
+//! module diode: 12 blocks, 345 instructions, register pressure 67
+//! ```
+
+use std::fmt::Write;
+
+use openvaf::ModuleStats;
+
+#[cfg(test)]
+mod tests;
+
+pub fn to_text(modules: &[ModuleStats]) -> String {
+    let mut dst = String::new();
+    for module in modules {
+        writeln!(
+            dst,
+            "module {}: {} blocks, {} instructions, register pressure {}",
+            module.module, module.blocks, module.instructions, module.register_pressure
+        )
+        .unwrap();
+    }
+    dst
+}
@@ -0,0 +1,25 @@
+use openvaf::ModuleStats;
+
+use super::to_text;
+
+#[test]
+fn text() {
+    let modules = [
+        ModuleStats {
+            module: "diode".to_owned(),
+            blocks: 12,
+            instructions: 345,
+            register_pressure: 67,
+        },
+        ModuleStats {
+            module: "resistor".to_owned(),
+            blocks: 1,
+            instructions: 4,
+            register_pressure: 2,
+        },
+    ];
+    let expected = "module diode: 12 blocks, 345 instructions, register pressure 67
+module resistor: 1 blocks, 4 instructions, register pressure 2
+";
+    assert_eq!(to_text(&modules), expected);
+}
@@ -0,0 +1,67 @@
+//! A machine readable (JSON) summary of compiling multiple files:
+//!
+//! ```text
+//! {
+//!   "files": [
+//!     {
+//!       "input": "diode.va",
+//!       "success": true,
+//!       "output": "diode.osdi",
+//!       "warnings": 0,
+//!       "errors": 0,
+//!       "time": 0.125,
+//!       "modules": [
+//!         {"name": "diode_va", "success": true, "warnings": 0, "errors": 0}
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `output` is `null` if no file was produced (because compilation failed or `--dry-run` was used)
+//! and `time` is the time spent compiling the file in seconds. The diagnostics of a module only
+//! include the diagnostics emitted while lowering that module, the diagnostics of the frontend
+//! are only counted for the file.
+
+use std::time::Duration;
+
+use camino::Utf8PathBuf;
+use openvaf::{DiagnosticCount, ModuleSummary};
+
+use crate::json::Json;
+
+#[cfg(test)]
+mod tests;
+
+pub struct FileSummary {
+    pub input: Utf8PathBuf,
+    pub success: bool,
+    pub output: Option<Utf8PathBuf>,
+    pub diagnostics: DiagnosticCount,
+    pub modules: Vec<ModuleSummary>,
+    pub time: Duration,
+}
+
+fn module_json(module: &ModuleSummary) -> Json {
+    Json::Object(vec![
+        ("name", module.name.as_str().into()),
+        ("success", module.success.into()),
+        ("warnings", module.diagnostics.warnings.into()),
+        ("errors", module.diagnostics.errors.into()),
+    ])
+}
+
+pub fn to_json(files: &[FileSummary]) -> String {
+    let files = files.iter().map(|file| {
+        Json::Object(vec![
+            ("input", file.input.as_str().into()),
+            ("success", file.success.into()),
+            ("output", file.output.as_ref().map(|output| output.as_str()).into()),
+            ("warnings", file.diagnostics.warnings.into()),
+            ("errors", file.diagnostics.errors.into()),
+            ("time", Json::number(format_args!("{:.3}", file.time.as_secs_f64()))),
+            ("modules", Json::array(file.modules.iter().map(module_json))),
+        ])
+    });
+    Json::Object(vec![("files", Json::array(files))]).to_pretty_string()
+}
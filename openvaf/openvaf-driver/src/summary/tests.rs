@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use openvaf::{DiagnosticCount, ModuleSummary};
+
+use super::{to_json, FileSummary};
+
+#[test]
+fn json() {
+    let files = [
+        FileSummary {
+            input: "diode.va".into(),
+            success: true,
+            output: Some("diode.osdi".into()),
+            diagnostics: DiagnosticCount { warnings: 1, errors: 0 },
+            modules: vec![
+                ModuleSummary {
+                    name: "diode_va".to_owned(),
+                    success: true,
+                    diagnostics: DiagnosticCount { warnings: 1, errors: 0 },
+                },
+                ModuleSummary {
+                    name: "loop".to_owned(),
+                    success: false,
+                    diagnostics: DiagnosticCount { warnings: 0, errors: 1 },
+                },
+            ],
+            time: Duration::from_millis(1500),
+        },
+        FileSummary {
+            input: "dir\\\"broken\".va".into(),
+            success: false,
+            output: None,
+            diagnostics: DiagnosticCount { warnings: 0, errors: 2 },
+            modules: Vec::new(),
+            time: Duration::from_millis(20),
+        },
+    ];
+    let expected = r#"{
+  "files": [
+    {
+      "input": "diode.va",
+      "success": true,
+      "output": "diode.osdi",
+      "warnings": 1,
+      "errors": 0,
+      "time": 1.500,
+      "modules": [
+        {"name": "diode_va", "success": true, "warnings": 1, "errors": 0},
+        {"name": "loop", "success": false, "warnings": 0, "errors": 1}
+      ]
+    },
+    {
+      "input": "dir\\\"broken\".va",
+      "success": false,
+      "output": null,
+      "warnings": 0,
+      "errors": 2,
+      "time": 0.020,
+      "modules": []
+    }
+  ]
+}
+"#;
+    assert_eq!(to_json(&files), expected);
+    assert_eq!(to_json(&[]), "{\n  \"files\": []\n}\n");
+}
@@ -0,0 +1,54 @@
+//! Formats the report printed with `--weak-unknowns`. The JSON output looks as follows:
+//!
+//! ```text
+//! {
+//!   "modules": [
+//!     {
+//!       "name": "diode",
+//!       "weak_unknowns": [
+//!         {"name": "m", "kind": "reactive_only"}
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use openvaf::{WeakRow, WeakUnknowns};
+
+use crate::json::Json;
+
+#[cfg(test)]
+mod tests;
+
+fn kind(row: WeakRow) -> &'static str {
+    match row {
+        WeakRow::Empty => "empty",
+        WeakRow::ReactiveOnly => "reactive_only",
+    }
+}
+
+pub fn to_text(modules: &[WeakUnknowns]) -> String {
+    let mut dst = String::new();
+    for module in modules {
+        writeln!(dst, "module {}", module.module).unwrap();
+        for (name, row) in &module.unknowns {
+            writeln!(dst, "  {name}: {}", kind(*row)).unwrap();
+        }
+    }
+    dst
+}
+
+pub fn to_json(modules: &[WeakUnknowns]) -> String {
+    let modules = modules.iter().map(|module| {
+        let unknowns = module.unknowns.iter().map(|(name, row)| {
+            Json::Object(vec![("name", name.as_str().into()), ("kind", kind(*row).into())])
+        });
+        Json::Object(vec![
+            ("name", module.module.as_str().into()),
+            ("weak_unknowns", Json::array(unknowns)),
+        ])
+    });
+    Json::Object(vec![("modules", Json::array(modules))]).to_pretty_string()
+}
@@ -0,0 +1,48 @@
+use openvaf::{WeakRow, WeakUnknowns};
+
+use super::{to_json, to_text};
+
+fn modules() -> [WeakUnknowns; 2] {
+    [
+        WeakUnknowns {
+            module: "diode".to_owned(),
+            unknowns: vec![
+                ("m".to_owned(), WeakRow::ReactiveOnly),
+                ("flow(br)".to_owned(), WeakRow::Empty),
+            ],
+        },
+        WeakUnknowns { module: "resistor".to_owned(), unknowns: Vec::new() },
+    ]
+}
+
+#[test]
+fn text() {
+    let expected = r#"module diode
+  m: reactive_only
+  flow(br): empty
+module resistor
+"#;
+    assert_eq!(to_text(&modules()), expected);
+}
+
+#[test]
+fn json() {
+    let expected = r#"{
+  "modules": [
+    {
+      "name": "diode",
+      "weak_unknowns": [
+        {"name": "m", "kind": "reactive_only"},
+        {"name": "flow(br)", "kind": "empty"}
+      ]
+    },
+    {
+      "name": "resistor",
+      "weak_unknowns": []
+    }
+  ]
+}
+"#;
+    assert_eq!(to_json(&modules()), expected);
+    assert_eq!(to_json(&[]), "{\n  \"modules\": []\n}\n");
+}
@@ -45,8 +45,264 @@ fn link_diode() -> Result {
     Ok(())
 }
 
+fn no_link() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let dir = env::temp_dir().join("openvaf_no_link");
+    let _ = sh.remove_path(&dir);
+    sh.create_dir(&dir)?;
+    xshell::cmd!(sh, "{openvaf} -O 0 --no-link {dir} integration_tests/DIODE/diode.va").run()?;
+
+    // the objects are kept and no library is linked
+    let objects = sh.read_dir(&dir)?;
+    assert!(objects.iter().any(|obj| obj.ends_with("diode.o")), "{objects:?}");
+    assert!(objects.iter().all(|obj| obj.extension().unwrap() != "osdi"), "{objects:?}");
+    assert!(!sh.path_exists("integration_tests/DIODE/diode.osdi"));
+    sh.remove_path(&dir)?;
+    Ok(())
+}
+
+fn cross_compile_aarch64() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let dir = env::temp_dir().join("openvaf_aarch64");
+    let _ = sh.remove_path(&dir);
+    sh.create_dir(&dir)?;
+    xshell::cmd!(
+        sh,
+        "{openvaf} -O 0 --target aarch64-unknown-linux-gnu --no-link {dir} integration_tests/DIODE/diode.va"
+    )
+    .run()?;
+
+    let objects = sh.read_dir(&dir)?;
+    assert!(!objects.is_empty());
+    for obj in objects {
+        let data = sh.read_binary_file(&obj)?;
+        assert_eq!(&data[..4], b"\x7fELF", "{obj:?} is not an ELF object");
+        // e_machine is stored at offset 18 (little endian), 183 is EM_AARCH64
+        let machine = u16::from_le_bytes([data[18], data[19]]);
+        assert_eq!(machine, 183, "{obj:?} was not compiled for aarch64");
+    }
+    sh.remove_path(&dir)?;
+    Ok(())
+}
+
+fn progress() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "openvaf/test_data/osdi/opt_lvl_for.va";
+    let dir = env::temp_dir().join("openvaf_progress");
+    let _ = sh.remove_path(&dir);
+    sh.create_dir(&dir)?;
+
+    let stderr = xshell::cmd!(sh, "{openvaf} -O 0 --no-link {dir} {model}")
+        .env("NO_COLOR", "1")
+        .read_stderr()?;
+    assert!(stderr.contains("Compiling module diode_hot (1/2)\n"), "{stderr}");
+    assert!(stderr.contains("Compiling module diode_cold (2/2)\n"), "{stderr}");
+    assert!(stderr.contains("Finished building opt_lvl_for.va"), "{stderr}");
+
+    let stderr = xshell::cmd!(sh, "{openvaf} -O 0 --quiet --no-link {dir} {model}")
+        .env("NO_COLOR", "1")
+        .read_stderr()?;
+    assert!(!stderr.contains("Compiling"), "{stderr}");
+    assert!(!stderr.contains("Finished"), "{stderr}");
+    sh.remove_path(&dir)?;
+    Ok(())
+}
+
+fn werror(flags: &str) -> Result {
+    let args = flags.split(' ');
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "openvaf/test_data/ui/shadowed_builtin.va";
+    xshell::cmd!(sh, "{openvaf} --dry-run {model}").run()?;
+    let res = xshell::cmd!(sh, "{openvaf} --dry-run {args...} {model}").quiet().run();
+    assert!(res.is_err(), "warning was not treated as an error with {flags}");
+    Ok(())
+}
+
+fn werror_allowed(flags: &str) -> Result {
+    let args = flags.split(' ');
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "openvaf/test_data/ui/shadowed_builtin.va";
+    let res = xshell::cmd!(sh, "{openvaf} --dry-run {args...} {model}").quiet().run();
+    assert!(res.is_ok(), "allowed lint was treated as an error with {flags}");
+    Ok(())
+}
+
+fn summary() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let good = "integration_tests/DIODE/diode.va";
+    let broken = "openvaf/test_data/ui/duplicate_declaration.va";
+    let summary_file = env::temp_dir().join("openvaf_summary.json");
+    let res = xshell::cmd!(sh, "{openvaf} --dry-run --summary {summary_file} {broken} {good}")
+        .quiet()
+        .run();
+    assert!(res.is_err(), "a failed compilation must produce a non-zero exit code");
+
+    // the broken module must not prevent the diode from being compiled
+    let summary = sh.read_file(&summary_file).unwrap();
+    sh.remove_path(&summary_file).unwrap();
+    // one entry for each file and one for each module
+    assert_eq!(summary.matches("\"success\": true").count(), 2, "{summary}");
+    assert_eq!(summary.matches("\"success\": false").count(), 2, "{summary}");
+    let good_pos = summary.find(good).unwrap();
+    let broken_pos = summary.find(broken).unwrap();
+    assert!(summary[broken_pos..good_pos].contains("\"success\": false"), "{summary}");
+    assert!(
+        summary[broken_pos..good_pos]
+            .contains("{\"name\": \"duplicate_declaration\", \"success\": false"),
+        "{summary}"
+    );
+    assert!(summary[good_pos..].contains("\"success\": true"), "{summary}");
+    assert!(
+        summary[good_pos..].contains("{\"name\": \"diode_va\", \"success\": true"),
+        "{summary}"
+    );
+    Ok(())
+}
+
+fn list_modules() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "integration_tests/DIODE/diode.va";
+    let listing = xshell::cmd!(sh, "{openvaf} --list-modules {model}").read()?;
+    let ports: Vec<_> = listing
+        .lines()
+        .skip_while(|line| line.trim() != "ports:")
+        .skip(1)
+        .take_while(|line| line.trim() != "parameters:")
+        .map(str::trim)
+        .collect();
+    assert_eq!(ports, ["inout A", "inout C", "inout dT"], "{listing}");
+    let is = r#"    real is = 10f from [0.0:inf] units="A" desc="Saturation current""#;
+    assert!(listing.contains(is), "{listing}");
+
+    let json = xshell::cmd!(sh, "{openvaf} --list-modules=json {model}").read()?;
+    assert!(json.contains(r#""name": "diode_va""#), "{json}");
+    assert!(json.contains(r#"{"name": "dT", "direction": "inout"}"#), "{json}");
+    // no code is generated
+    assert!(!sh.path_exists("integration_tests/DIODE/diode.osdi"));
+    Ok(())
+}
+
+fn include_dir_recursive() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "openvaf/test_data/include_dir_recursive/top.va";
+    let lib = "openvaf/test_data/include_dir_recursive/lib";
+    // the included file is only found in a subdirectory of lib
+    let res = xshell::cmd!(sh, "{openvaf} --dry-run -I {lib} {model}").quiet().run();
+    assert!(res.is_err(), "nested include was found without --include-dir-recursive");
+    xshell::cmd!(sh, "{openvaf} --dry-run --include-dir-recursive={lib} {model}").run()?;
+    Ok(())
+}
+
+fn codegen_opt_typo() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "integration_tests/DIODE/diode.va";
+    // the misspelled option is ignored, compilation continues
+    let stderr = xshell::cmd!(sh, "{openvaf} --dry-run -C target-featur=+avx2 {model}")
+        .env("NO_COLOR", "1")
+        .read_stderr()?;
+    assert!(
+        stderr.contains("warning: unknown codegen option 'target-featur' is ignored"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("help: did you mean 'target-feature'?"), "{stderr}");
+    Ok(())
+}
+
+fn print_config() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "integration_tests/DIODE/diode.va";
+    let target = "x86_64-unknown-linux";
+    let config =
+        xshell::cmd!(sh, "{openvaf} --print-config --target {target} -O 1 {model}").read()?;
+    assert!(config.contains("target: x86_64-unknown-linux-gnu\n"), "{config}");
+    assert!(config.contains("opt level: 1\n"), "{config}");
+    assert!(config.contains("reactive formulation: charge\n"), "{config}");
+
+    let json = xshell::cmd!(
+        sh,
+        "{openvaf} --print-config=json -O 2 --reactive-formulation capacitance {model}"
+    )
+    .read()?;
+    assert!(json.contains(r#""opt_level": 2"#), "{json}");
+    assert!(json.contains(r#""reactive_formulation": "capacitance""#), "{json}");
+    assert!(json.contains(r#""target": ""#), "{json}");
+    // no code is generated
+    assert!(!sh.path_exists("integration_tests/DIODE/diode.osdi"));
+    Ok(())
+}
+
+fn weak_unknowns() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "openvaf/test_data/weak_unknowns/reactive_only.va";
+    let report = xshell::cmd!(sh, "{openvaf} --weak-unknowns {model}").read()?;
+    assert_eq!(report, "module reactive_only\n  m: reactive_only");
+
+    let json = xshell::cmd!(sh, "{openvaf} --weak-unknowns=json {model}").read()?;
+    assert!(json.contains(r#"{"name": "m", "kind": "reactive_only"}"#), "{json}");
+    // no code is generated
+    assert!(!sh.path_exists("openvaf/test_data/weak_unknowns/reactive_only.osdi"));
+    Ok(())
+}
+
+fn stats() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf-r");
+    let model = "openvaf/test_data/weak_unknowns/reactive_only.va";
+    let stats = xshell::cmd!(sh, "{openvaf} --stats {model}").read()?;
+    assert!(stats.starts_with("module reactive_only: "), "{stats}");
+    assert!(stats.contains(" register pressure "), "{stats}");
+    // no code is generated
+    assert!(!sh.path_exists("openvaf/test_data/weak_unknowns/reactive_only.osdi"));
+    Ok(())
+}
+
 harness! {
     Test::new("cli::link_diode", &link_diode),
+    Test::new("cli::no_link", &no_link),
+    Test::new("cli::cross_compile_aarch64", &cross_compile_aarch64),
+    Test::new("cli::progress", &progress),
+    Test::new("cli::summary", &summary),
+    Test::new("cli::list_modules", &list_modules),
+    Test::new("cli::include_dir_recursive", &include_dir_recursive),
+    Test::new("cli::codegen_opt_typo", &codegen_opt_typo),
+    Test::new("cli::print_config", &print_config),
+    Test::new("cli::weak_unknowns", &weak_unknowns),
+    Test::new("cli::stats", &stats),
+    Test::from_list(
+        "cli::werror",
+         &werror,
+         &ignore_never,
+         &["--werror", "--werror=shadowed_builtin", "-A macro_overwritten --werror"]
+    ),
+    Test::from_list(
+        "cli::werror_allowed",
+         &werror_allowed,
+         &ignore_never,
+         &["-A shadowed_builtin --werror=shadowed_builtin", "-A shadowed_builtin --werror"]
+    ),
     Test::from_list(
         "cli::smoke_test",
          &smoke_test,
@@ -56,12 +312,16 @@ harness! {
             "--target_cpu skylake",
              "--dump-json",
              "--supported-targets",
+             "--target aarch64-unknown-linux",
+             "--target aarch64-unknown-linux-gnu",
              "--batch",
              "--batch --cache-dir sourcegen",
              "-O 0",
              "-O 1",
              "-O 2",
              "-O 3",
+             "--opt-level-for diode_va=0",
+             "-O 1 --opt-level-for=diode_va=3 --opt-level-for=foo=2",
              "-I sourcegen",
              "-D foo",
              "--print-expansion",
@@ -1,25 +1,36 @@
 use core::slice;
+use std::fs::{copy, create_dir_all, rename};
 use std::mem::{size_of, size_of_val};
 
+use anyhow::{Context, Result};
 use basedb::lints::LintLevel;
 use basedb::{BaseDB, VfsStorage};
+use camino::{Utf8Path, Utf8PathBuf};
 use hir::CompilationDB;
 
 use crate::Opts;
 
 // TODO: use high level hir API instead of low leve database API
-fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
-    let mut hash_builder = md5::Context::new();
+fn hash_settings(hash_builder: &mut md5::Context, db: &CompilationDB, opts: &Opts) {
     let cu = db.compilation_unit();
 
     // hash settings
     hash_builder.consume(cu.root_file().0.to_ne_bytes());
 
-    hash_builder.consume(defines.len().to_ne_bytes());
-    for def in defines {
+    hash_builder.consume(opts.defines.len().to_ne_bytes());
+    for def in &opts.defines {
         hash_builder.consume(def)
     }
 
+    // sensitivities change the layout of the descriptor
+    hash_builder.consume(opts.sensitivity_params.len().to_ne_bytes());
+    for param in &opts.sensitivity_params {
+        hash_builder.consume(param);
+        hash_builder.consume(" ");
+    }
+    // the formulation changes the reactive residual
+    hash_builder.consume([opts.reactive_formulation as u8]);
+
     hash_builder.consume(env!("CARGO_PKG_VERSION"));
     let lints = db.global_lint_overwrites(cu.root_file());
     if cfg!(debug_assertions) && !lints.is_empty() {
@@ -32,8 +43,14 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
         )
     };
     hash_builder.consume(lints);
+}
+
+fn hash(db: &CompilationDB, opts: &Opts) -> md5::Digest {
+    let mut hash_builder = md5::Context::new();
+    hash_settings(&mut hash_builder, db, opts);
 
     // Hash the full preprocessor result
+    let cu = db.compilation_unit();
     let preprocess = cu.preprocess(db);
     let vfs = db.vfs().read();
     for token in &*preprocess.ts {
@@ -49,7 +66,71 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
 }
 
 pub fn file_name(db: &CompilationDB, opts: &Opts) -> String {
-    let hash = u128::from_ne_bytes(*hash(db, &opts.defines));
+    let hash = u128::from_ne_bytes(*hash(db, opts));
     let hash = base_n::encode(hash, base_n::CASE_INSENSITIVE);
     format!("{}.osdi", hash)
 }
+
+/// Hashes everything except the values of literal parameter defaults. Object files are
+/// reused directly so the codegen settings are included here.
+fn structural_hash(db: &CompilationDB, opts: &Opts) -> md5::Digest {
+    let mut hash_builder = md5::Context::new();
+    hash_settings(&mut hash_builder, db, opts);
+
+    hash_builder.consume(opts.target.llvm_target.as_str());
+    hash_builder.consume(" ");
+    hash_builder.consume(opts.target_cpu.as_str());
+    hash_builder.consume((opts.opt_lvl as u32).to_ne_bytes());
+    for (module, opt_lvl) in &opts.module_opt_lvls {
+        hash_builder.consume(module);
+        hash_builder.consume((*opt_lvl as u32).to_ne_bytes());
+    }
+    for opt in &opts.codegen_opts {
+        hash_builder.consume(opt);
+        hash_builder.consume(" ");
+    }
+    if let Some(threshold) = opts.collapse_resistance {
+        hash_builder.consume(threshold.to_ne_bytes());
+    }
+
+    db.compilation_unit().visit_structure(db, |token| {
+        hash_builder.consume(token);
+        hash_builder.consume(" ");
+    });
+
+    hash_builder.compute()
+}
+
+/// The directory that contains the object files which are shared by all compilations
+/// that only differ in the default values of parameters.
+pub fn structure_dir(db: &CompilationDB, opts: &Opts) -> String {
+    let hash = u128::from_ne_bytes(*structural_hash(db, opts));
+    let hash = base_n::encode(hash, base_n::CASE_INSENSITIVE);
+    format!("{}.structure", hash)
+}
+
+fn structural_object(dir: &Utf8Path, i: usize) -> Utf8PathBuf {
+    dir.join(format!("{i}.o"))
+}
+
+/// Returns the cached access and eval objects of `num_modules` modules
+/// if all of them exist in `dir`.
+pub fn structural_objects(dir: &Utf8Path, num_modules: usize) -> Option<Vec<Utf8PathBuf>> {
+    let objects: Vec<_> = (0..2 * num_modules).map(|i| structural_object(dir, i)).collect();
+    if objects.iter().all(|obj| obj.exists()) {
+        Some(objects)
+    } else {
+        None
+    }
+}
+
+pub fn store_structural_objects(dir: &Utf8Path, objects: &[Utf8PathBuf]) -> Result<()> {
+    create_dir_all(dir).context("failed to create cache directory")?;
+    for (i, obj) in objects.iter().enumerate() {
+        // copy to a temporary file first so that an interrupted copy is never reused
+        let dst = structural_object(dir, i);
+        let tmp = dst.with_extension("tmp");
+        copy(obj, &tmp).and_then(|_| rename(&tmp, &dst)).context("failed to cache object file")?;
+    }
+    Ok(())
+}
@@ -1,18 +1,24 @@
+use std::cell::Cell;
 use std::fs::{create_dir_all, remove_file};
 use std::io::Write;
 use std::time::Instant;
 
 use anyhow::{Context, Result};
-use basedb::diagnostics::{ConsoleSink, DiagnosticSink};
+use basedb::diagnostics::{ConsoleSink, DiagnosticSink, Report, Severity};
 pub use basedb::lints::{builtin as builtin_lints, LintLevel};
 use basedb::BaseDB;
 use camino::Utf8PathBuf;
 use hir::CompilationDB;
+use lasso::Rodeo;
 use linker::link;
 pub use llvm_sys::target_machine::LLVMCodeGenOptLevel;
 use mir_llvm::LLVMBackend;
+pub use osdi::OsdiParamSlot;
+use osdi::{CodegenParts, OptLevel};
 pub use paths::AbsPathBuf;
-use sim_back::collect_modules;
+pub use sim_back::dae::WeakRow;
+use sim_back::{collect_modules, fmt_param, CompiledModule, ModuleInfo};
+pub use sim_back::{CrashContext, ReactiveFormulation};
 pub use target::host_triple;
 pub use target::spec::{get_target_names, Target};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
@@ -30,9 +36,42 @@ pub enum CompilationTermination {
     FatalDiagnostic,
 }
 
+/// The number of diagnostics emitted while compiling a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiagnosticCount {
+    pub warnings: usize,
+    pub errors: usize,
+}
+
+impl DiagnosticCount {
+    fn update(&mut self, sink: &ConsoleSink) {
+        self.warnings = sink.warning_count();
+        self.errors = sink.error_count();
+    }
+}
+
+/// The codegen options (`-C OPT[=VALUE]`) understood by [`compile`]:
+///
+/// * `target-feature=FEATURES`: comma separated LLVM target features (like `+avx2,-fma`) that
+///   are enabled in addition to the features of the target and the target cpu.
+pub const CODEGEN_OPTS: [&str; 1] = ["target-feature"];
+
+fn target_features(codegen_opts: &[String]) -> Vec<String> {
+    codegen_opts
+        .iter()
+        .filter_map(|opt| opt.strip_prefix("target-feature="))
+        .flat_map(|features| features.split(','))
+        .filter(|feature| !feature.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct Opts {
     pub dry_run: bool,
+    /// Write the object files to this directory and keep them instead of linking
+    /// them into a shared library. [`compile`] then returns the directory as `lib_file`.
+    pub no_link: Option<Utf8PathBuf>,
     pub defines: Vec<String>,
     pub codegen_opts: Vec<String>,
     pub lints: Vec<(String, LintLevel)>,
@@ -40,8 +79,22 @@ pub struct Opts {
     pub output: CompilationDestination,
     pub include: Vec<AbsPathBuf>,
     pub opt_lvl: LLVMCodeGenOptLevel,
+    /// Overwrites `opt_lvl` for individual modules (identified by their name).
+    pub module_opt_lvls: Vec<(String, LLVMCodeGenOptLevel)>,
     pub target: Target,
     pub target_cpu: String,
+    /// Collapse linear resistors below this resistance (see
+    /// [`ModuleInfo::collapse_resistance`](sim_back::ModuleInfo::collapse_resistance)).
+    pub collapse_resistance: Option<f64>,
+    /// Names (or aliases) of the parameters whose sensitivities are exported with the
+    /// `load_sensitivities` callback of the OSDI descriptor. Modules without a parameter
+    /// of that name are not affected.
+    pub sensitivity_params: Vec<String>,
+    /// How the reactive residual is formulated (see
+    /// [`ModuleInfo::reactive_formulation`](sim_back::ModuleInfo::reactive_formulation)).
+    pub reactive_formulation: ReactiveFormulation,
+    /// Don't print progress and status messages to stderr.
+    pub quiet: bool,
 }
 // pub fn dump_json(opts: &Opts) -> Result<CompilationTermination> {
 //     let input =
@@ -104,10 +157,7 @@ pub struct Opts {
 pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     let start = Instant::now();
 
-    let input =
-        opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
-    let input = AbsPathBuf::assert(input);
-    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    let db = open_db(opts)?;
     let cu = db.compilation_unit();
 
     let preprocess = cu.preprocess(&db);
@@ -149,55 +199,385 @@ pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     Ok(CompilationTermination::Compiled { lib_file: Utf8PathBuf::default() })
 }
 
-pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
-    let start = Instant::now();
-
+fn open_db(opts: &Opts) -> Result<CompilationDB> {
     let input =
         opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
     let input = AbsPathBuf::assert(input);
-    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)
+}
+
+/// Opens `opts.input` and collects its modules (diagnostics are printed to stderr).
+/// Returns `None` if the file could not be compiled.
+fn load_modules(opts: &Opts) -> Result<Option<(CompilationDB, Vec<ModuleInfo>)>> {
+    let db = open_db(opts)?;
+    let modules = collect_modules(&db, false, &mut ConsoleSink::new(&db));
+    Ok(modules.map(|modules| (db, modules)))
+}
 
-    let lib_file = match &opts.output {
+/// A module with its ports and parameters as listed by [`list_modules`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleListing {
+    pub name: String,
+    /// The ports (in the order of the module header) and their direction
+    /// (`input`, `output` or `inout`).
+    pub ports: Vec<(String, &'static str)>,
+    pub params: Vec<ParamListing>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamListing {
+    pub name: String,
+    pub ty: String,
+    /// The source code of the default value.
+    pub default: String,
+    /// The type, default, range, units and description of the parameter
+    /// as formatted by [`sim_back::fmt_param`].
+    pub declaration: String,
+}
+
+/// Collects the modules of `opts.input` with their ports and parameters without generating
+/// any code. Returns `None` if the file could not be compiled.
+pub fn list_modules(opts: &Opts) -> Result<Option<Vec<ModuleListing>>> {
+    let (db, modules) = if let Some(res) = load_modules(opts)? {
+        res
+    } else {
+        return Ok(None);
+    };
+
+    let mut literals = Rodeo::new();
+    let listing = modules
+        .iter()
+        .map(|module| {
+            // only the MIR is built to evaluate constant defaults and bounds
+            let compiled = CompiledModule::new(&db, module, &mut literals);
+            let ports = module
+                .module
+                .ports(&db)
+                .into_iter()
+                .map(|port| {
+                    let direction = match (port.is_input(&db), port.is_output(&db)) {
+                        (true, false) => "input",
+                        (false, true) => "output",
+                        _ => "inout",
+                    };
+                    (port.name(&db).to_string(), direction)
+                })
+                .collect();
+            let params = module
+                .params
+                .iter()
+                .map(|(param, info)| ParamListing {
+                    name: info.name.to_string(),
+                    ty: param.ty(&db).to_string(),
+                    default: param.default_src(&db),
+                    declaration: fmt_param(&db, &compiled, &literals, *param),
+                })
+                .collect();
+            ModuleListing { name: module.module.name(&db), ports, params }
+        })
+        .collect();
+    Ok(Some(listing))
+}
+
+/// Returns the parameters of each module of `opts.input` in the order in which they
+/// are listed in the OSDI descriptor (see [`OsdiParamSlot`]). Returns `None` if the
+/// file could not be compiled.
+pub fn param_slots(opts: &Opts) -> Result<Option<Vec<Vec<OsdiParamSlot>>>> {
+    let (db, modules) = if let Some(res) = load_modules(opts)? {
+        res
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(osdi::param_slots(&db, &modules)))
+}
+
+/// The unknowns of a module whose row of the jacobian has no conductance during DC analysis
+/// as reported by [`weak_unknowns`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeakUnknowns {
+    pub module: String,
+    /// The names of the unknowns (like `m` or `flow(br)`) in the order of the jacobian rows.
+    pub unknowns: Vec<(String, WeakRow)>,
+}
+
+/// Finds the unknowns of each module of `opts.input` whose row of the jacobian is empty or only
+/// has reactive entries (see [`WeakRow`]), these rows are a common cause of singular matrices.
+/// No code is generated. Returns `None` if the file could not be compiled.
+pub fn weak_unknowns(opts: &Opts) -> Result<Option<Vec<WeakUnknowns>>> {
+    let (db, mut modules) = if let Some(res) = load_modules(opts)? {
+        res
+    } else {
+        return Ok(None);
+    };
+    configure_modules(&mut modules, opts);
+
+    let mut literals = Rodeo::new();
+    let report = modules
+        .iter()
+        .map(|module| {
+            let mir = CompiledModule::new(&db, module, &mut literals);
+            let dae_system = &mir.dae_system;
+            let unknowns = dae_system
+                .weak_rows()
+                .into_iter()
+                .map(|(unknown, row)| (dae_system.unknowns[unknown].name(&db, &mir.intern), row))
+                .collect();
+            WeakUnknowns { module: module.module.name(&db), unknowns }
+        })
+        .collect();
+    Ok(Some(report))
+}
+
+/// Size statistics of the eval function of a module as reported by [`module_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleStats {
+    pub module: String,
+    pub blocks: usize,
+    pub instructions: usize,
+    /// The maximum number of values that are live at the same time.
+    pub register_pressure: usize,
+}
+
+/// Computes the size of the eval function of each module of `opts.input` without generating
+/// any code. Returns `None` if the file could not be compiled.
+pub fn module_stats(opts: &Opts) -> Result<Option<Vec<ModuleStats>>> {
+    let (db, mut modules) = if let Some(res) = load_modules(opts)? {
+        res
+    } else {
+        return Ok(None);
+    };
+    configure_modules(&mut modules, opts);
+
+    let mut literals = Rodeo::new();
+    let stats = modules
+        .iter()
+        .map(|module| {
+            let mir = CompiledModule::new(&db, module, &mut literals);
+            let layout = &mir.eval.layout;
+            ModuleStats {
+                module: module.module.name(&db),
+                blocks: layout.blocks().count(),
+                instructions: layout.blocks().map(|bb| layout.block_insts(bb).count()).sum(),
+                register_pressure: mir.eval_register_pressure(),
+            }
+        })
+        .collect();
+    Ok(Some(stats))
+}
+
+/// Applies the options that change how the modules are compiled.
+fn configure_modules(modules: &mut [ModuleInfo], opts: &Opts) {
+    for module in modules {
+        module.collapse_resistance = opts.collapse_resistance;
+        module.reactive_formulation = opts.reactive_formulation;
+        let mut sensitivity_params = Vec::new();
+        for name in &opts.sensitivity_params {
+            let param = module.params.iter().find(|(_, info)| {
+                info.name == name.as_str() || info.alias.iter().any(|alias| alias == name.as_str())
+            });
+            if let Some((&param, _)) = param {
+                if !sensitivity_params.contains(&param) {
+                    sensitivity_params.push(param);
+                }
+            }
+        }
+        module.sensitivity_params = sensitivity_params;
+    }
+}
+
+/// The result of compiling a single module as recorded by [`compile_and_count`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleSummary {
+    pub name: String,
+    /// Whether code was generated for the module (or would have been with `dry_run`).
+    pub success: bool,
+    /// The diagnostics emitted while lowering the module. Diagnostics of the frontend
+    /// are only included in the count of the whole file.
+    pub diagnostics: DiagnosticCount,
+}
+
+/// Forwards diagnostics to a [`ConsoleSink`] and counts them for the module that is
+/// currently lowered.
+struct ModuleSink<'a, 'db> {
+    sink: &'a mut ConsoleSink<'db>,
+    current: &'a Cell<usize>,
+    modules: &'a mut [ModuleSummary],
+}
+
+impl DiagnosticSink for ModuleSink<'_, '_> {
+    fn add_report(&mut self, report: Report) {
+        let count = &mut self.modules[self.current.get()].diagnostics;
+        match report.severity {
+            Severity::Error => count.errors += 1,
+            Severity::Warning => count.warnings += 1,
+            _ => (),
+        }
+        self.sink.add_report(report)
+    }
+}
+
+pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
+    compile_and_count(opts, &mut DiagnosticCount::default(), &mut Vec::new())
+}
+
+/// Same as [`compile`] but additionally records how many warnings and errors
+/// were emitted in `count` and the result of each module in `modules`. Both are
+/// also updated when compilation fails. `modules` stays empty if a cached
+/// library is reused.
+pub fn compile_and_count(
+    opts: &Opts,
+    count: &mut DiagnosticCount,
+    module_summaries: &mut Vec<ModuleSummary>,
+) -> Result<CompilationTermination> {
+    let start = Instant::now();
+    CrashContext::set_file(opts.input.as_str());
+    CrashContext::set_phase("frontend");
+
+    let db = open_db(opts)?;
+
+    let (lib_file, structure_dir) = match &opts.output {
         CompilationDestination::Cache { cache_dir } => {
             let file_name = cache::file_name(&db, opts);
             let lib_file = cache_dir.join(file_name);
-            if cfg!(not(debug_assertions)) && lib_file.exists() {
+            if cfg!(not(debug_assertions)) && opts.no_link.is_none() && lib_file.exists() {
                 return Ok(CompilationTermination::Compiled { lib_file });
             }
             create_dir_all(cache_dir).context("failed to create cache directory")?;
-            lib_file
+            (lib_file, Some(cache_dir.join(cache::structure_dir(&db, opts))))
         }
-        CompilationDestination::Path { lib_file } => lib_file.clone(),
+        CompilationDestination::Path { lib_file } => (lib_file.clone(), None),
     };
 
-    let modules = if let Some(modules) = collect_modules(&db, false, &mut ConsoleSink::new(&db)) {
+    let mut sink = ConsoleSink::new(&db);
+    let modules = collect_modules(&db, false, &mut sink);
+    count.update(&sink);
+    let mut modules = if let Some(modules) = modules {
         modules
     } else {
+        *module_summaries = db
+            .compilation_unit()
+            .modules(&db)
+            .into_iter()
+            .map(|module| ModuleSummary {
+                name: module.name(&db),
+                success: false,
+                diagnostics: DiagnosticCount::default(),
+            })
+            .collect();
         return Ok(CompilationTermination::FatalDiagnostic);
     };
+    configure_modules(&mut modules, opts);
+    *module_summaries = modules
+        .iter()
+        .map(|module| ModuleSummary {
+            name: module.module.name(&db),
+            success: false,
+            diagnostics: DiagnosticCount::default(),
+        })
+        .collect();
 
-    let back = LLVMBackend::new(&opts.codegen_opts, &opts.target, opts.target_cpu.clone(), &[]);
+    let target_features = target_features(&opts.codegen_opts);
+    let back = LLVMBackend::new(
+        &opts.codegen_opts,
+        &opts.target,
+        opts.target_cpu.clone(),
+        &target_features,
+    )
+    .map_err(anyhow::Error::msg)?;
     if opts.dry_run {
+        for module in module_summaries.iter_mut() {
+            module.success = true;
+        }
         return Ok(CompilationTermination::Compiled { lib_file });
     }
-    let paths = osdi::compile(&db, &modules, &lib_file, &opts.target, &back, true, opts.opt_lvl);
-    // TODO configure linker
-    link(None, &opts.target, lib_file.as_ref(), |linker| {
-        for path in &paths {
-            linker.add_object(path);
+    let opt_lvl = OptLevel { default: opts.opt_lvl, modules: opts.module_opt_lvls.clone() };
+    // if only the default values of parameters changed, the access and eval functions
+    // of a previous compilation are reused and only the setup functions are regenerated.
+    // Without linking the objects are not cached since all of them are kept.
+    let cached_objects = if opts.no_link.is_some() {
+        None
+    } else {
+        structure_dir.as_deref().and_then(|dir| cache::structural_objects(dir, modules.len()))
+    };
+    let parts = if cached_objects.is_some() { CodegenParts::SetupOnly } else { CodegenParts::All };
+    // the object files are named after the destination
+    let obj_dst = match &opts.no_link {
+        Some(dir) => dir.join(lib_file.file_name().expect("destination is a file")),
+        None => lib_file.clone(),
+    };
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    let num_modules = modules.len();
+    let current = Cell::new(0);
+    let mut module_sink =
+        ModuleSink { sink: &mut sink, current: &current, modules: &mut module_summaries[..] };
+    let objects = osdi::compile(
+        &db,
+        &modules,
+        &obj_dst,
+        &opts.target,
+        &back,
+        true,
+        parts,
+        &opt_lvl,
+        &mut module_sink,
+        |i, module| {
+            current.set(i);
+            if !opts.quiet {
+                let name = module.module.name(&db);
+                // failing to report progress is not worth aborting the compilation
+                let _ = print_status(
+                    &mut stderr,
+                    "Compiling",
+                    format_args!("module {name} ({}/{num_modules})", i + 1),
+                );
+            }
+        },
+    );
+    count.update(&sink);
+    let output = if let Some(dir) = &opts.no_link {
+        dir.clone()
+    } else {
+        CrashContext::set_phase("linking");
+        // TODO configure linker
+        link(None, &opts.target, lib_file.as_ref(), |linker| {
+            for path in cached_objects.iter().flatten().chain(objects.iter()) {
+                linker.add_object(path);
+            }
+        })?;
+
+        if let (Some(dir), None) = (&structure_dir, &cached_objects) {
+            cache::store_structural_objects(dir, &objects.structural)?;
         }
-    })?;
 
-    for obj_file in paths {
-        remove_file(obj_file).context("failed to delete intermediate compile artifact")?;
+        for obj_file in objects.iter() {
+            remove_file(obj_file).context("failed to delete intermediate compile artifact")?;
+        }
+        lib_file
+    };
+    for module in module_summaries.iter_mut() {
+        module.success = module.diagnostics.errors == 0;
     }
 
-    let seconds = Instant::elapsed(&start).as_secs_f64();
-    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    if !opts.quiet {
+        let seconds = Instant::elapsed(&start).as_secs_f64();
+        print_status(
+            &mut stderr,
+            "Finished",
+            format_args!("building {} in {:.2}s", opts.input.file_name().unwrap(), seconds),
+        )?;
+    }
+
+    Ok(CompilationTermination::Compiled { lib_file: output })
+}
+
+/// Prints `status` highlighted (unless colors are disabled) followed by `msg`.
+fn print_status(
+    stderr: &mut StandardStream,
+    status: &str,
+    msg: std::fmt::Arguments,
+) -> std::io::Result<()> {
     stderr.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
-    write!(&mut stderr, "Finished")?;
+    write!(stderr, "{status}")?;
     stderr.set_color(&ColorSpec::new())?;
-    writeln!(&mut stderr, " building {} in {:.2}s", opts.input.file_name().unwrap(), seconds)?;
-
-    Ok(CompilationTermination::Compiled { lib_file })
+    writeln!(stderr, " {msg}")
 }
@@ -1,47 +1,69 @@
 use std::f64::consts;
+use std::ffi::CStr;
+use std::fs;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use expect_test::expect_file;
 use float_cmp::assert_approx_eq;
 use llvm_sys::target_machine::LLVMCodeGenOptLevel;
 use mini_harness::{harness, Result};
-use openvaf::{CompilationDestination, CompilationTermination};
+use openvaf::{CompilationDestination, CompilationTermination, ReactiveFormulation};
+use stdx::iter::zip;
 use stdx::{ignore_dev_tests, openvaf_test_data, project_root};
 use target::spec::Target;
 
-use crate::load::{load_osdi_lib, EvalFlags, OsdiDescriptor};
+use crate::load::{
+    load_osdi_lib, osdi_str, EvalFlags, OsdiDescriptor, NODE_ABSTOL_COMPUTED, NODE_ABSTOL_STATIC,
+};
 use crate::mock_sim::{MockSimulation, ALPHA};
 
 mod load;
 mod mock_sim;
 
-fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
-    let openvaf_opts = openvaf::Opts {
+fn opts(root_file: &Utf8Path, output: CompilationDestination) -> openvaf::Opts {
+    openvaf::Opts {
         defines: Vec::new(),
         codegen_opts: Vec::new(),
         lints: Vec::new(),
         input: root_file.to_path_buf(),
-        output: CompilationDestination::Path { lib_file: root_file.with_extension("osdi") },
+        output,
         include: Vec::new(),
         opt_lvl: LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
+        module_opt_lvls: Vec::new(),
         target: Target::host_target().unwrap(),
         target_cpu: "native".to_owned(),
         dry_run: false,
-    };
+        no_link: None,
+        collapse_resistance: None,
+        sensitivity_params: Vec::new(),
+        reactive_formulation: ReactiveFormulation::Charge,
+        quiet: false,
+    }
+}
 
-    let res = openvaf::compile(&openvaf_opts).unwrap();
-    let lib_file = match res {
+fn compile_to(root_file: &Utf8Path, output: CompilationDestination) -> Utf8PathBuf {
+    let res = openvaf::compile(&opts(root_file, output)).unwrap();
+    match res {
         CompilationTermination::Compiled { lib_file } => lib_file,
         CompilationTermination::FatalDiagnostic => {
             panic!("openvaf: compilation of {root_file} failed");
         }
-    };
-    let libs = unsafe { load_osdi_lib(&lib_file).unwrap() };
+    }
+}
+
+fn load(lib_file: &Utf8Path) -> &'static OsdiDescriptor {
+    let libs = unsafe { load_osdi_lib(lib_file).unwrap() };
     assert_eq!(libs.len(), 1);
     &libs[0]
 }
 
+fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
+    let lib_file = root_file.with_extension("osdi");
+    load(&compile_to(root_file, CompilationDestination::Path { lib_file }))
+}
+
 // fn integration_test(dir: &str) -> Result {
 //     let path: Utf8PathBuf = project_root().join("integration_tests").try_into().unwrap();
 //     let name = dir.to_lowercase();
@@ -215,8 +237,279 @@ fn test_noise() -> Result<()> {
     Ok(())
 }
 
+fn test_noise_names() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    // unnamed noise sources are named after the nodes they are connected to
+    let root_file = openvaf_test_data("osdi").join("noise_names.va");
+    let desc = compile_and_load(root_file.as_path().try_into().unwrap());
+    let names: Vec<_> = desc.noise().iter().map(|src| unsafe { osdi_str(src.name) }).collect();
+    assert_eq!(names, ["thermal", "noise(a,c)", "noise(a,c)_1", "noise(a)"]);
+    Ok(())
+}
+
+fn test_node_abstol() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let root_file = openvaf_test_data("osdi").join("node_abstol.va");
+    let desc = compile_and_load(root_file.as_path().try_into().unwrap());
+    assert!(desc.nodes().iter().any(|node| node.is_flow));
+    let model = desc.new_model();
+    let vntol = CStr::from_bytes_with_nul(b"vntol\0").unwrap();
+    model.process_params_with(&[(vntol, 1e-3)])?;
+    for (node, abstol) in zip(desc.nodes(), model.node_abstol()) {
+        let name = unsafe { osdi_str(node.name) };
+        let expected = match name {
+            // computed by setup_model from $simparam
+            "s" => 2e-3,
+            // folded to a constant
+            "e" => 2e-6,
+            // the tolerances of the electrical natures in disciplines.vams
+            _ if node.is_flow => 1e-12,
+            _ => 1e-6,
+        };
+        assert_eq!(abstol, Some(expected), "{name}");
+    }
+    let flags: Vec<_> = desc.node_abstol().iter().map(|abstol| abstol.flags).collect();
+    assert!(flags.contains(&NODE_ABSTOL_COMPUTED));
+    assert!(flags.contains(&NODE_ABSTOL_STATIC));
+    Ok(())
+}
+
+fn test_sensitivities() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const R: f64 = 2.0;
+    const V: f64 = 3.0;
+
+    let root_file = openvaf_test_data("osdi").join("sensitivity.va");
+    let root_file: &Utf8Path = root_file.as_path().try_into().unwrap();
+    let lib_file = root_file.with_extension("osdi");
+    let mut opts = opts(root_file, CompilationDestination::Path { lib_file });
+    opts.sensitivity_params = ["r", "c0", "unused", "missing"].map(str::to_owned).to_vec();
+    let lib_file = match openvaf::compile(&opts)? {
+        CompilationTermination::Compiled { lib_file } => lib_file,
+        CompilationTermination::FatalDiagnostic => panic!("openvaf: compilation failed"),
+    };
+    let desc = load(&lib_file);
+
+    // parameters that don't exist in the module are ignored
+    assert_eq!(desc.num_sensitivity_params, 3);
+    let params = unsafe {
+        std::slice::from_raw_parts(desc.sensitivity_params, desc.num_sensitivity_params as usize)
+    };
+    let names: Vec<_> =
+        params.iter().map(|&i| unsafe { osdi_str(*desc.params()[i as usize].name) }).collect();
+    assert_eq!(names, ["r", "c0", "unused"]);
+
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", V);
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+    let (resist, react) = instance.load_sensitivities(&model);
+
+    // I(a, c) = V / r + ddt(c0 * V), nodes are ordered a, c
+    let expected_resist = [-V / (R * R), V / (R * R), 0.0, 0.0, 0.0, 0.0];
+    let expected_react = [0.0, 0.0, V, -V, 0.0, 0.0];
+    assert_eq!(resist.len(), expected_resist.len());
+    for (val, expected) in resist.into_iter().zip(expected_resist) {
+        float_cmp::assert_approx_eq!(f64, val, expected, epsilon = 1e-12);
+    }
+    for (val, expected) in react.into_iter().zip(expected_react) {
+        float_cmp::assert_approx_eq!(f64, val, expected, epsilon = 1e-12);
+    }
+    Ok(())
+}
+
+fn test_param_slots() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let root_file = openvaf_test_data("osdi").join("param_slots.va");
+    let root_file: &Utf8Path = root_file.as_path().try_into().unwrap();
+    let desc = compile_and_load(root_file);
+    let lib_file = root_file.with_extension("osdi");
+    let slots = openvaf::param_slots(&opts(root_file, CompilationDestination::Path { lib_file }))
+        .unwrap()
+        .unwrap()
+        .remove(0);
+    let names: Vec<_> =
+        desc.params().iter().map(|param| unsafe { osdi_str(*param.name) }).collect();
+    let slot_names: Vec<_> = slots.iter().map(|slot| slot.name.as_str()).collect();
+    assert_eq!(names, slot_names);
+
+    let r = slots.iter().position(|slot| slot.name == "r").unwrap();
+    assert_eq!(unsafe { osdi_str(desc.params()[r].units) }, "Ohm");
+    let (file, line) = slots[r].decl.clone().unwrap();
+    assert!(file.ends_with("param_slots.va"));
+    assert_eq!(line, 4);
+    for slot in &slots {
+        assert_eq!(slot.decl.is_none(), slot.name.starts_with('$'));
+    }
+    Ok(())
+}
+
+fn test_cache_defaults() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const SRC: &str = r#"
+        `include "disciplines.vams"
+        module resistor(inout a, inout c);
+            electrical a, c;
+            parameter real r = {R} from (0:inf);
+            analog I(a, c) <+ V(a, c) / r;
+        endmodule
+    "#;
+
+    let dir = Utf8PathBuf::try_from(std::env::temp_dir().join("openvaf_cache_defaults")).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    let cache_dir = dir.join("cache");
+    fs::create_dir_all(&dir)?;
+    let root_file = dir.join("resistor.va");
+    let compile = |default: &str| {
+        fs::write(&root_file, SRC.replace("{R}", default)).unwrap();
+        let output = CompilationDestination::Cache { cache_dir: cache_dir.clone() };
+        compile_to(&root_file, output)
+    };
+    let current = |lib_file: &Utf8Path| -> Result<f64> {
+        let desc = load(lib_file);
+        let model = desc.new_model();
+        model.process_params()?;
+        let mut instance = model.new_instance();
+        let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+        sim.set_voltage("a", 1.0);
+        instance.eval(&model, &mut sim, EvalFlags::empty());
+        instance.load_dae(&model, &mut sim);
+        Ok(sim.read_residual("a").0)
+    };
+
+    let lib_file1 = compile("1.0");
+    let mut structure_dirs = fs::read_dir(&cache_dir)?
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("structure".as_ref()));
+    let structure_dir = structure_dirs.next().expect("eval code was not cached");
+    assert!(structure_dirs.next().is_none());
+    // mark the cached objects so that a rewrite can be detected
+    let marker = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    let objects: Vec<_> =
+        fs::read_dir(&structure_dir)?.map(|entry| entry.unwrap().path()).collect();
+    for obj in &objects {
+        fs::File::options().write(true).open(obj)?.set_modified(marker)?;
+    }
+
+    // only the default changed so the access and eval functions are reused
+    let lib_file2 = compile("2.0");
+    assert_ne!(lib_file1, lib_file2);
+    for obj in &objects {
+        assert_eq!(fs::metadata(obj)?.modified()?, marker, "{} was regenerated", obj.display());
+    }
+
+    // the setup functions are regenerated with the new default
+    assert_approx_eq!(current(&lib_file1)?, 1.0);
+    assert_approx_eq!(current(&lib_file2)?, 0.5);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+fn test_cache_default_types() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    // the type of r is inferred from its default
+    const SRC: &str = r#"
+        `include "disciplines.vams"
+        module resistor(inout a, inout c);
+            electrical a, c;
+            parameter r = {R};
+            analog I(a, c) <+ V(a, c) / r;
+        endmodule
+    "#;
+
+    let dir =
+        Utf8PathBuf::try_from(std::env::temp_dir().join("openvaf_cache_default_types")).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    let cache_dir = dir.join("cache");
+    fs::create_dir_all(&dir)?;
+    let root_file = dir.join("resistor.va");
+    let compile = |default: &str| {
+        fs::write(&root_file, SRC.replace("{R}", default)).unwrap();
+        let output = CompilationDestination::Cache { cache_dir: cache_dir.clone() };
+        compile_to(&root_file, output);
+        fs::read_dir(&cache_dir)
+            .unwrap()
+            .filter(|entry| {
+                entry.as_ref().unwrap().path().extension() == Some("structure".as_ref())
+            })
+            .count()
+    };
+
+    assert_eq!(compile("1"), 1);
+    // same type, only the value changed
+    assert_eq!(compile("2"), 1);
+    // r turns into a real parameter so the eval code must not be reused
+    assert_eq!(compile("2.0"), 2);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+fn test_port_connected() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let root_file = openvaf_test_data("osdi").join("port_connected.va");
+    let desc = compile_and_load(root_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+
+    // the thermal port is connected: self heating is simulated
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("dt", 3.0);
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+    instance.load_dae(&model, &mut sim);
+    assert_approx_eq!(sim.read_residual("a").0, 1.0);
+    assert_approx_eq!(sim.read_residual("dt").0, 0.5);
+
+    // the thermal port is not connected: $port_connected(dt) is false and
+    // dt is collapsed into ground so its contributions vanish
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals - 1, 300.0)?;
+    assert!(!sim.nodes.contains("dt"));
+    sim.set_voltage("a", 1.0);
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+    instance.load_dae(&model, &mut sim);
+    assert_approx_eq!(sim.read_residual("a").0, 1.0);
+    Ok(())
+}
+
 harness! {
     // TODO: run this in CI, somehow this test is flakey tough regarding the linker invocation (and really slow)
     Test::from_dir("integration", &integration_test, &ignore_dev_tests, &project_root().join("integration_tests")),
-    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise)]
+    [
+        Test::new("$limit", &test_limit),
+        Test::new("noise", &test_noise),
+        Test::new("noise_names", &test_noise_names),
+        Test::new("node_abstol", &test_node_abstol),
+        Test::new("sensitivities", &test_sensitivities),
+        Test::new("param_slots", &test_param_slots),
+        Test::new("cache_defaults", &test_cache_defaults),
+        Test::new("cache_default_types", &test_cache_default_types),
+        Test::new("port_connected", &test_port_connected)
+    ]
 }
@@ -16,9 +16,9 @@ use stdx::format_to;
 use stdx::iter::zip;
 
 #[allow(warnings)]
-mod osdi_0_4;
+mod osdi_0_5;
 
-pub use osdi_0_4::*;
+pub use osdi_0_5::*;
 
 impl OsdiDescriptor {
     pub fn nodes(&self) -> &[OsdiNode] {
@@ -43,6 +43,11 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.noise_sources, self.num_noise_src as usize) }
     }
 
+    pub fn node_abstol(&self) -> &[OsdiNodeAbstol] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { slice::from_raw_parts(self.node_abstol, self.num_nodes as usize) }
+    }
+
     pub fn matrix_entries(&self) -> &[OsdiJacobianEntry] {
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
         unsafe { slice::from_raw_parts(self.jacobian_entries, self.num_jacobian_entries as usize) }
@@ -150,9 +155,19 @@ impl Drop for OsdiModel {
 
 impl OsdiModel {
     pub fn process_params(&self) -> Result<()> {
+        self.process_params_with(&[])
+    }
+
+    /// Same as [`process_params`](Self::process_params) but passes `sim_params`
+    /// (the values of `$simparam`) to the model.
+    pub fn process_params_with(&self, sim_params: &[(&CStr, f64)]) -> Result<()> {
+        let mut names: Vec<_> =
+            sim_params.iter().map(|(name, _)| name.as_ptr() as *mut c_char).collect();
+        names.push(ptr::null_mut());
+        let mut vals: Vec<_> = sim_params.iter().map(|(_, val)| *val).collect();
         let mut sim_params = OsdiSimParas {
-            names: &mut ptr::null_mut(),
-            vals: ptr::null_mut(),
+            names: names.as_mut_ptr(),
+            vals: vals.as_mut_ptr(),
             names_str: &mut ptr::null_mut(),
             vals_str: ptr::null_mut(),
         };
@@ -167,6 +182,22 @@ impl OsdiModel {
         self.descriptor.check_init_result(res)
     }
 
+    /// The tolerance of each node after `setup_model` (`None` if the node has none).
+    pub fn node_abstol(&self) -> Vec<Option<f64>> {
+        self.descriptor
+            .node_abstol()
+            .iter()
+            .map(|abstol| match abstol.flags {
+                NODE_ABSTOL_STATIC => Some(abstol.val),
+                // SAFETY: setup_model stores the tolerance at model_off
+                NODE_ABSTOL_COMPUTED => unsafe {
+                    Some(*(self.data.add(abstol.model_off as usize) as *const f64))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn set_real_param(&self, param: u32, val: f64) {
         let ptr = self.descriptor.access(ptr::null_mut(), self.data, param, ACCESS_FLAG_SET);
         let ptr = ptr as *mut f64;
@@ -328,7 +359,7 @@ pub unsafe fn load_osdi_lib(path: &Utf8Path) -> Result<&'static [OsdiDescriptor]
     let major_version: &u32 = *lib.get(b"OSDI_VERSION_MAJOR\0")?;
     let minor_version: &u32 = *lib.get(b"OSDI_VERSION_MINOR\0")?;
 
-    if *major_version != 0 || *minor_version != 4 {
+    if *major_version != 0 || *minor_version != 5 {
         bail!("invalid version v{major_version}.{minor_version}",);
     }
 
@@ -196,6 +196,17 @@ impl OsdiInstance {
         self.descriptor.load_noise(self.data, model.data, freq, sim.noise_dense.as_mut_ptr())
     }
 
+    /// Returns the resistive and reactive sensitivities written by `load_sensitivities`.
+    /// The entries are initialized with NaN to detect entries that are not written.
+    pub fn load_sensitivities(&self, model: &OsdiModel) -> (Vec<f64>, Vec<f64>) {
+        let len = (self.descriptor.num_sensitivity_params * self.descriptor.num_nodes) as usize;
+        let mut resist = vec![f64::NAN; len];
+        let mut react = vec![f64::NAN; len];
+        let load = self.descriptor.load_sensitivities.expect("no sensitivities were requested");
+        load(self.data, model.data, resist.as_mut_ptr(), react.as_mut_ptr());
+        (resist, react)
+    }
+
     pub fn load_dae(&self, model: &OsdiModel, sim: &mut MockSimulation) {
         self.descriptor.load_residual_resist(
             self.data,
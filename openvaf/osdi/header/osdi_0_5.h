@@ -8,7 +8,7 @@
 
 
 #define OSDI_VERSION_MAJOR_CURR 0
-#define OSDI_VERSION_MINOR_CURR 4
+#define OSDI_VERSION_MINOR_CURR 5
 
 #define PARA_TY_MASK 3
 #define PARA_TY_REAL 0
@@ -19,6 +19,13 @@
 #define PARA_KIND_INST  (1 << 30)
 #define PARA_KIND_OPVAR (2 << 30)
 
+#define PARA_DEFAULT_STATIC 0
+#define PARA_DEFAULT_COMPUTED 1
+
+#define NODE_ABSTOL_NONE 0
+#define NODE_ABSTOL_STATIC 1
+#define NODE_ABSTOL_COMPUTED 2
+
 #define ACCESS_FLAG_READ 0
 #define ACCESS_FLAG_SET 1
 #define ACCESS_FLAG_INSTANCE 4
@@ -124,6 +131,12 @@ typedef struct OsdiNode {
   bool is_flow;
 }OsdiNode;
 
+typedef struct OsdiNodeAbstol {
+  uint32_t flags;
+  uint32_t model_off;
+  double val;
+}OsdiNodeAbstol;
+
 typedef struct OsdiParamOpvar {
   char **name;
   uint32_t num_alias;
@@ -133,6 +146,13 @@ typedef struct OsdiParamOpvar {
   uint32_t len;
 }OsdiParamOpvar;
 
+typedef struct OsdiParamDefault {
+  uint32_t flags;
+  int32_t int_val;
+  double real_val;
+  char *str_val;
+}OsdiParamDefault;
+
 typedef struct OsdiNoiseSource {
   char *name;
   OsdiNodePair nodes;
@@ -202,6 +222,14 @@ typedef struct OsdiDescriptor {
   OsdiNodePair* inputs;
   void (*load_jacobian_with_offset_resist)(void *inst, void* model, size_t offset);
   void (*load_jacobian_with_offset_react)(void *inst, void* model, size_t offset);
+  OsdiParamDefault *param_defaults;
+  uint32_t (*eval_residual)(void *handle, void *inst, void *model, OsdiSimInfo *info);
+  uint32_t num_gmin_nodes;
+  uint32_t *gmin_nodes;
+  uint32_t num_sensitivity_params;
+  uint32_t *sensitivity_params;
+  void (*load_sensitivities)(void *inst, void *model, double *resist, double *react);
+  OsdiNodeAbstol *node_abstol;
 }OsdiDescriptor;
 
 
@@ -23,7 +23,7 @@ use typed_index_collections::TiVec;
 use typed_indexmap::TiSet;
 
 use crate::inst_data::OsdiInstanceData;
-use crate::metadata::osdi_0_4::{
+use crate::metadata::osdi_0_5::{
     stdlib_bitcode, OsdiTys, LOG_FMT_ERR, LOG_LVL_DEBUG, LOG_LVL_DISPLAY, LOG_LVL_ERR,
     LOG_LVL_FATAL, LOG_LVL_INFO, LOG_LVL_WARN,
 };
@@ -133,6 +133,7 @@ pub struct OsdiModule<'a> {
     pub info: &'a ModuleInfo,
     pub dae_system: &'a DaeSystem,
     pub eval: &'a Function,
+    pub residual_eval: Option<&'a Function>,
     pub intern: &'a HirInterner,
     pub init: &'a Initialization,
     pub model_param_setup: &'a Function,
@@ -153,10 +154,18 @@ impl<'a> OsdiModule<'a> {
             info,
             dae_system,
             eval,
+            residual_eval,
             intern,
             init,
+            // never requested by the OSDI backend
+            model_init: _,
             model_param_setup,
             model_param_intern,
+            // reported by `CompiledModule::diagnostics`
+            ignored_abstols: _,
+            invalid_param_defaults: _,
+            // only used for formatting parameters
+            param_ranges: _,
             node_collapse,
         } = module;
         OsdiModule {
@@ -165,6 +174,7 @@ impl<'a> OsdiModule<'a> {
             info,
             dae_system,
             eval,
+            residual_eval: residual_eval.as_ref(),
             intern,
             init,
             model_param_setup,
@@ -242,6 +252,7 @@ pub fn general_callbacks<'ll>(
                     builder.cx.const_callback(&[builder.cx.ty_double()], zero)
                 }
                 CallBackKind::ParamInfo(_, _)
+                | CallBackKind::ParamBoundViolated { .. }
                 | CallBackKind::CollapseHint(_, _)
                 | CallBackKind::BuiltinLimit { .. }
                 | CallBackKind::StoreLimit(_)
@@ -253,7 +264,28 @@ pub fn general_callbacks<'ll>(
                 | CallBackKind::TimeDerivative => return None,
 
                 CallBackKind::Print { kind, arg_tys } => {
-                    let (fun, fun_ty) = print_callback(builder.cx, *kind, arg_tys);
+                    let (fun, fun_ty) = print_callback(builder.cx, PrintSink::Log(*kind), arg_tys);
+                    CallbackFun { fun_ty, fun, state: Box::new([handle]), num_state: 0 }
+                }
+                CallBackKind::FileWrite { arg_tys } => {
+                    let (fun, fun_ty) = print_callback(builder.cx, PrintSink::File, arg_tys);
+                    CallbackFun { fun_ty, fun, state: Box::new([handle]), num_state: 0 }
+                }
+                CallBackKind::FileOpen => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("file_open")
+                        .expect("stdlib function file_open is missing");
+                    let fun_ty = builder.cx.ty_func(&[ptr_ty, ptr_ty, ptr_ty], builder.cx.ty_int());
+                    CallbackFun { fun_ty, fun, state: Box::new([handle]), num_state: 0 }
+                }
+                CallBackKind::FileClose => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("file_close")
+                        .expect("stdlib function file_close is missing");
+                    let fun_ty =
+                        builder.cx.ty_func(&[ptr_ty, builder.cx.ty_int()], builder.cx.ty_void());
                     CallbackFun { fun_ty, fun, state: Box::new([handle]), num_state: 0 }
                 }
             };
@@ -293,12 +325,26 @@ fn print_module_ir(cx: &CodegenCx, message: &str) {
     }
 }*/
 
+/// Destination of the message formatted by a print callback.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PrintSink {
+    /// Passed to `osdi_log` with the log level corresponding to the display task.
+    Log(DisplayKind),
+    /// Written to the file descriptor passed as the first argument (after the handle).
+    File,
+}
+
 fn print_callback<'ll>(
     cx: &CodegenCx<'_, 'll>,
-    kind: hir_lower::fmt::DisplayKind,
+    sink: PrintSink,
     arg_tys: &[FmtArg],
 ) -> (&'ll llvm_sys::LLVMValue, &'ll llvm_sys::LLVMType) {
-    let mut args = vec![cx.ty_ptr(), cx.ty_ptr()];
+    let mut args = vec![cx.ty_ptr()];
+    if sink == PrintSink::File {
+        args.push(cx.ty_int());
+    }
+    let fmt_pos = args.len() as u32;
+    args.push(cx.ty_ptr());
     args.extend(arg_tys.iter().map(|arg| lltype(&arg.ty, cx)));
     let fun_ty = cx.ty_func(&args, cx.ty_void());
     let name = cx.local_callback_name();
@@ -337,11 +383,11 @@ fn print_callback<'ll>(
 
         LLVMPositionBuilderAtEnd(llbuilder, entry_bb);
         let handle = LLVMGetParam(NonNull::from(fun).as_ptr(), 0);
-        let fmt_lit = LLVMGetParam(NonNull::from(fun).as_ptr(), 1);
+        let fmt_lit = LLVMGetParam(NonNull::from(fun).as_ptr(), fmt_pos);
         let mut args = vec![
             cx.const_null_ptr(),
             cx.const_usize(0),
-            &*LLVMGetParam(NonNull::from(fun).as_ptr(), 1),
+            &*LLVMGetParam(NonNull::from(fun).as_ptr(), fmt_pos),
         ];
 
         let exp_table = cx.get_declared_value("EXP").expect("constant EXP missing from stdlib");
@@ -357,7 +403,7 @@ fn print_callback<'ll>(
         let mut free = Vec::new();
 
         for (i, arg) in arg_tys.iter().enumerate() {
-            let val = LLVMGetParam(NonNull::from(fun).as_ptr(), i as u32 + 2);
+            let val = LLVMGetParam(NonNull::from(fun).as_ptr(), i as u32 + fmt_pos + 1);
             match arg.kind {
                 FmtArgKind::Binary => {
                     let mut val_array = [val];
@@ -413,8 +459,8 @@ fn print_callback<'ll>(
             }
         }
         args.extend(
-            (1..(2 + arg_tys.len()))
-                .map(|arg| &*LLVMGetParam(NonNull::from(fun).as_ptr(), arg as u32)),
+            (fmt_pos..(fmt_pos + 1 + arg_tys.len() as u32))
+                .map(|arg| &*LLVMGetParam(NonNull::from(fun).as_ptr(), arg)),
         );
         let (fun_ty, fun) = cx.intrinsic("snprintf").unwrap();
         // Convert Vec<&LLVMValue> to Vec<*mut LLVMValue>
@@ -500,13 +546,15 @@ fn print_callback<'ll>(
 
         LLVMPositionBuilderAtEnd(llbuilder, exit_bb);
         let flags = LLVMBuildPhi(llbuilder, NonNull::from(cx.ty_int()).as_ptr(), UNNAMED);
-        let lvl = match kind {
-            DisplayKind::Debug => LOG_LVL_DEBUG,
-            DisplayKind::Display | DisplayKind::Monitor => LOG_LVL_DISPLAY,
-            DisplayKind::Info => LOG_LVL_INFO,
-            DisplayKind::Warn => LOG_LVL_WARN,
-            DisplayKind::Error => LOG_LVL_ERR,
-            DisplayKind::Fatal => LOG_LVL_FATAL,
+        let lvl = match sink {
+            PrintSink::Log(DisplayKind::Debug) => LOG_LVL_DEBUG,
+            PrintSink::Log(DisplayKind::Display | DisplayKind::Monitor) => LOG_LVL_DISPLAY,
+            PrintSink::Log(DisplayKind::Info) => LOG_LVL_INFO,
+            PrintSink::Log(DisplayKind::Warn) => LOG_LVL_WARN,
+            PrintSink::Log(DisplayKind::Error) => LOG_LVL_ERR,
+            PrintSink::Log(DisplayKind::Fatal) => LOG_LVL_FATAL,
+            // file_write only checks for LOG_FMT_ERR
+            PrintSink::File => 0,
         };
         let lvl_and_err = lvl | LOG_FMT_ERR;
         let lvl = cx.const_unsigned_int(lvl);
@@ -529,25 +577,42 @@ fn print_callback<'ll>(
         let mut incoming_blocks = [write_bb, err_bb];
         LLVMAddIncoming(msg, incoming_values.as_mut_ptr(), incoming_blocks.as_mut_ptr(), 2);
 
-        let fun_ptr = cx.get_declared_value("osdi_log").expect("symbol osdi_log is missing");
-        let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
-        let fun = LLVMBuildLoad2(
-            llbuilder,
-            NonNull::from(cx.ty_ptr()).as_ptr(),
-            NonNull::from(fun_ptr).as_ptr(),
-            UNNAMED,
-        );
-
-        // Fix for LLVMBuildCall2
-        let mut args = [handle, msg, flags];
-        LLVMBuildCall2(
-            llbuilder,
-            NonNull::from(fun_ty).as_ptr(),
-            fun,
-            args.as_mut_ptr(),
-            3,
-            UNNAMED,
-        );
+        if let PrintSink::File = sink {
+            let fd = LLVMGetParam(NonNull::from(fun).as_ptr(), 1);
+            let fun =
+                cx.get_func_by_name("file_write").expect("stdlib function file_write is missing");
+            let fun_ty =
+                cx.ty_func(&[cx.ty_ptr(), cx.ty_int(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+            let mut args = [handle, fd, msg, flags];
+            LLVMBuildCall2(
+                llbuilder,
+                NonNull::from(fun_ty).as_ptr(),
+                NonNull::from(fun).as_ptr(),
+                args.as_mut_ptr(),
+                4,
+                UNNAMED,
+            );
+        } else {
+            let fun_ptr = cx.get_declared_value("osdi_log").expect("symbol osdi_log is missing");
+            let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+            let fun = LLVMBuildLoad2(
+                llbuilder,
+                NonNull::from(cx.ty_ptr()).as_ptr(),
+                NonNull::from(fun_ptr).as_ptr(),
+                UNNAMED,
+            );
+
+            // Fix for LLVMBuildCall2
+            let mut args = [handle, msg, flags];
+            LLVMBuildCall2(
+                llbuilder,
+                NonNull::from(fun_ty).as_ptr(),
+                fun,
+                args.as_mut_ptr(),
+                3,
+                UNNAMED,
+            );
+        }
         llvm_sys::core::LLVMBuildRetVoid(llbuilder);
         llvm_sys::core::LLVMDisposeBuilder(llbuilder);
     }
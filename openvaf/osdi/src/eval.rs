@@ -9,6 +9,7 @@ use llvm_sys::core::{
 };
 use llvm_sys::LLVMIntPredicate::{LLVMIntNE, LLVMIntULT};
 use log::info;
+use mir::Function;
 use mir_llvm::{Builder, BuilderVal, CallbackFun, MemLoc, UNNAMED};
 use sim_back::SimUnknownKind;
 use typed_index_collections::TiVec;
@@ -16,7 +17,7 @@ use typed_index_collections::TiVec;
 use crate::bitfield::{is_flag_set, is_flag_set_mem, is_flag_unset};
 use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
 use crate::inst_data::OsdiInstanceParam;
-use crate::metadata::osdi_0_4::{
+use crate::metadata::osdi_0_5::{
     ANALYSIS_IC, CALC_NOISE, CALC_OP, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS, CALC_REACT_RESIDUAL,
     CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, ENABLE_LIM, EVAL_RET_FLAG_LIM,
     INIT_LIM,
@@ -26,6 +27,20 @@ use crate::OsdiLimId;
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
     pub fn eval_prototype(&self) -> &'ll llvm_sys::LLVMValue {
         let name = &format!("eval_{}", &self.module.sym);
+        self.eval_fn_prototype(name)
+    }
+
+    /// The residual only variant of `eval` has the same signature as `eval`.
+    /// A null pointer is returned if the variant was not generated for this module.
+    pub fn eval_residual_prototype(&self) -> &'ll llvm_sys::LLVMValue {
+        if self.module.residual_eval.is_none() {
+            return self.cx.const_null_ptr();
+        }
+        let name = &format!("eval_residual_{}", &self.module.sym);
+        self.eval_fn_prototype(name)
+    }
+
+    fn eval_fn_prototype(&self, name: &str) -> &'ll llvm_sys::LLVMValue {
         let cx = &self.cx;
 
         let ty_ptr = cx.ty_ptr();
@@ -36,9 +51,23 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
     pub fn eval(&self) -> &'ll llvm_sys::LLVMValue {
         let llfunc = self.eval_prototype();
+        self.build_eval(llfunc, self.module.eval, false);
+        llfunc
+    }
+
+    /// Generates the residual only variant of `eval` if it was requested for this module.
+    /// The jacobian and the limit rhs are never computed by this function (even
+    /// if the corresponding flags are set).
+    pub fn eval_residual(&self) {
+        if let Some(func) = self.module.residual_eval {
+            let llfunc = self.eval_residual_prototype();
+            self.build_eval(llfunc, func, true);
+        }
+    }
+
+    fn build_eval(&self, llfunc: &'ll llvm_sys::LLVMValue, func: &Function, residual_only: bool) {
         let OsdiCompilationUnit { inst_data, model_data, cx, module, .. } = self;
 
-        let func = module.eval;
         let intern = module.intern;
 
         let mut builder = Builder::new(cx, func, llfunc);
@@ -304,18 +333,20 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     (CALC_RESIST_JACOBIAN, CALC_RESIST_RESIDUAL, CALC_RESIST_LIM_RHS)
                 };
 
-                let store_matrix = |builder: &mut Builder<'_, '_, 'll>| {
-                    for entry in module.dae_system.jacobian.keys() {
-                        inst_data.store_jacobian(entry, instance, builder, reactive)
-                    }
-                };
-                Self::build_store_results(
-                    &mut builder,
-                    llfunc,
-                    &flags,
-                    jacobian_flag,
-                    &store_matrix,
-                );
+                if !residual_only {
+                    let store_matrix = |builder: &mut Builder<'_, '_, 'll>| {
+                        for entry in module.dae_system.jacobian.keys() {
+                            inst_data.store_jacobian(entry, instance, builder, reactive)
+                        }
+                    };
+                    Self::build_store_results(
+                        &mut builder,
+                        llfunc,
+                        &flags,
+                        jacobian_flag,
+                        &store_matrix,
+                    );
+                }
 
                 let store_residual = |builder: &mut Builder<'_, '_, 'll>| {
                     for unknown in module.dae_system.unknowns.indices() {
@@ -330,18 +361,37 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     &store_residual,
                 );
 
-                let store_lim_rhs = |builder: &mut Builder<'_, '_, 'll>| {
-                    for unknown in module.dae_system.unknowns.indices() {
-                        inst_data.store_lim_rhs(unknown, instance, builder, reactive);
-                    }
-                };
-                Self::build_store_results(
-                    &mut builder,
-                    llfunc,
-                    &flags,
-                    lim_rhs_flag,
-                    &store_lim_rhs,
-                );
+                if !residual_only {
+                    let store_sensitivities = |builder: &mut Builder<'_, '_, 'll>| {
+                        for sensitivity in &inst_data.sensitivities {
+                            let output =
+                                if reactive { sensitivity.react } else { sensitivity.resist };
+                            if let Some(output) = output {
+                                inst_data.store_eval_output(output, instance, builder)
+                            }
+                        }
+                    };
+                    Self::build_store_results(
+                        &mut builder,
+                        llfunc,
+                        &flags,
+                        residual_flag,
+                        &store_sensitivities,
+                    );
+
+                    let store_lim_rhs = |builder: &mut Builder<'_, '_, 'll>| {
+                        for unknown in module.dae_system.unknowns.indices() {
+                            inst_data.store_lim_rhs(unknown, instance, builder, reactive);
+                        }
+                    };
+                    Self::build_store_results(
+                        &mut builder,
+                        llfunc,
+                        &flags,
+                        lim_rhs_flag,
+                        &store_lim_rhs,
+                    );
+                }
             }
 
             let store_opvars = |builder: &mut Builder<'_, '_, 'll>| {
@@ -364,8 +414,6 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             let ret_flags = builder.load(cx.ty_int(), ret_flags);
             builder.ret(ret_flags);
         }
-
-        llfunc
     }
 
     unsafe fn build_store_results(
@@ -4,7 +4,7 @@ use core::ptr::NonNull;
 use ahash::RandomState;
 use hir::{CompilationDB, ParamSysFun, Parameter, Variable};
 use hir_lower::{HirInterner, LimitState, ParamKind, PlaceKind};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use llvm_sys::core::{
     LLVMBuildFAdd,
     LLVMBuildFSub,
@@ -168,6 +168,31 @@ impl MatrixEntry {
     }
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Sensitivity {
+    pub resist: Option<EvalOutput>,
+    pub react: Option<EvalOutput>,
+}
+
+impl Sensitivity {
+    pub fn new<'ll>(
+        sensitivity: &dae::ParamSensitivity,
+        module: &OsdiModule<'_>,
+        slots: &mut TiMap<EvalOutputSlot, mir::Value, &'ll llvm_sys::LLVMType>,
+        ty_real: &'ll llvm_sys::LLVMType,
+    ) -> Sensitivity {
+        let mut get_output = |mut val| {
+            val = strip_optbarrier(module.eval, val);
+            if val == F_ZERO {
+                None
+            } else {
+                Some(EvalOutput::new(module, val, slots, false, ty_real))
+            }
+        };
+        Sensitivity { resist: get_output(sensitivity.resist), react: get_output(sensitivity.react) }
+    }
+}
+
 #[derive(Debug)]
 pub struct NoiseSource {
     pub factor: EvalOutput,
@@ -220,32 +245,48 @@ pub struct OsdiInstanceData<'ll> {
     pub noise: Vec<NoiseSource>,
     pub opvars: IndexMap<Variable, EvalOutput, RandomState>,
     pub jacobian: TiVec<MatrixEntryId, MatrixEntry>,
+    /// aligned with `param_sensitivities` of the dae system
+    pub sensitivities: Vec<Sensitivity>,
     pub bound_step: Option<EvalOutputSlot>,
 }
 
+impl OsdiModule<'_> {
+    /// The instance parameters in the order in which they are stored in the
+    /// instance data (and listed in the `param_opvar` array of the descriptor).
+    pub fn inst_params(&self) -> IndexSet<OsdiInstanceParam, RandomState> {
+        let builtin_inst_params = ParamSysFun::iter().filter(|&param| {
+            let is_live = |intern: &HirInterner, func| {
+                intern.is_param_live(func, &ParamKind::ParamSysFun(param))
+            };
+            is_live(self.intern, self.eval) || is_live(&self.init.intern, &self.init.func)
+        });
+        let alias_inst_params = self.info.sys_fun_alias.keys().copied();
+        let user_inst_params =
+            self.info.params.iter().filter_map(|(param, info)| info.is_instance.then_some(*param));
+        builtin_inst_params
+            .chain(alias_inst_params)
+            .map(OsdiInstanceParam::Builtin)
+            .chain(user_inst_params.map(OsdiInstanceParam::User))
+            .collect()
+    }
+}
+
 impl<'ll> OsdiInstanceData<'ll> {
     pub fn new(db: &CompilationDB, module: &OsdiModule<'_>, cx: &CodegenCx<'_, 'll>) -> Self {
         let ty_f64 = cx.ty_double();
         let ty_u32 = cx.ty_int();
 
-        let builtin_inst_params = ParamSysFun::iter().filter_map(|param| {
-            let is_live = |intern: &HirInterner, func| {
-                intern.is_param_live(func, &ParamKind::ParamSysFun(param))
-            };
-            let is_live = is_live(module.intern, module.eval)
-                || is_live(&module.init.intern, &module.init.func);
-            is_live.then_some((OsdiInstanceParam::Builtin(param), ty_f64))
-        });
-        let alias_inst_params = module
-            .info
-            .sys_fun_alias
-            .keys()
-            .map(|param| (OsdiInstanceParam::Builtin(*param), ty_f64));
-        let user_inst_params = module.info.params.iter().filter_map(|(param, info)| {
-            info.is_instance.then(|| (OsdiInstanceParam::User(*param), lltype(&param.ty(db), cx)))
-        });
-        let params: IndexMap<_, _, _> =
-            builtin_inst_params.chain(alias_inst_params).chain(user_inst_params).collect();
+        let params: IndexMap<_, _, _> = module
+            .inst_params()
+            .into_iter()
+            .map(|param| {
+                let ty = match param {
+                    OsdiInstanceParam::Builtin(_) => ty_f64,
+                    OsdiInstanceParam::User(param) => lltype(&param.ty(db), cx),
+                };
+                (param, ty)
+            })
+            .collect();
 
         let mut eval_outputs = TiMap::default();
         let opvars = module
@@ -272,12 +313,20 @@ impl<'ll> OsdiInstanceData<'ll> {
             .iter()
             .map(|entry| MatrixEntry::new(entry, module, &mut eval_outputs, ty_f64, &mut num_react))
             .collect();
+        // the descriptor advertises `num_reactive` reactive pointers
+        debug_assert_eq!(num_react, module.dae_system.num_reactive);
         let noise = module
             .dae_system
             .noise_sources
             .iter()
             .map(|source| NoiseSource::new(source, module, &mut eval_outputs, ty_f64))
             .collect();
+        let sensitivities = module
+            .dae_system
+            .param_sensitivities
+            .iter()
+            .map(|sensitivity| Sensitivity::new(sensitivity, module, &mut eval_outputs, ty_f64))
+            .collect();
         let bound_step = module.intern.outputs.get(&PlaceKind::BoundStep).and_then(|val| {
             let mut val = val.expand()?;
             val = strip_optbarrier(module.eval, val);
@@ -334,6 +383,7 @@ impl<'ll> OsdiInstanceData<'ll> {
             noise,
             opvars,
             jacobian,
+            sensitivities,
             bound_step,
         }
     }
@@ -1,8 +1,10 @@
 use core::ptr::NonNull;
+use std::cmp::Reverse;
 use std::ffi::CString;
 
 use base_n::CASE_INSENSITIVE;
 use camino::{Utf8Path, Utf8PathBuf};
+use hir::diagnostics::DiagnosticSink;
 use hir::{CompilationDB, ParamSysFun, Type};
 use hir_lower::{CallBackKind, HirInterner, ParamKind};
 use lasso::Rodeo;
@@ -10,13 +12,13 @@ use llvm_sys::target::{LLVMABISizeOfType, LLVMDisposeTargetData};
 use llvm_sys::target_machine::LLVMCodeGenOptLevel;
 use mir_llvm::{CodegenCx, LLVMBackend};
 use salsa::ParallelDatabase;
-use sim_back::{CompiledModule, ModuleInfo};
+use sim_back::{CompiledModule, CrashContext, ModuleInfo, Scratch};
 use stdx::{impl_debug_display, impl_idx_from};
 use target::spec::Target;
 use typed_indexmap::TiSet;
 
 use crate::compilation_unit::{new_codegen, OsdiCompilationUnit, OsdiModule};
-use crate::metadata::osdi_0_4::OsdiTys;
+use crate::metadata::osdi_0_5::OsdiTys;
 use crate::metadata::OsdiLimFunction;
 
 mod access;
@@ -31,25 +33,91 @@ mod load;
 mod noise;
 mod setup;
 
-const OSDI_VERSION: (u32, u32) = (0, 4);
+const OSDI_VERSION: (u32, u32) = (0, 5);
+
+/// The optimization level used to compile a compilation unit. Individual
+/// modules can be compiled with a different level than the rest of the
+/// compilation unit (for example to speedup the compilation of trivial models).
+#[derive(Debug, Clone)]
+pub struct OptLevel {
+    pub default: LLVMCodeGenOptLevel,
+    /// Per module overwrites of the default level, if a module is listed
+    /// multiple times the last entry wins.
+    pub modules: Vec<(String, LLVMCodeGenOptLevel)>,
+}
 
-use std::sync::Once;
+impl OptLevel {
+    pub fn for_module(&self, module: &str) -> LLVMCodeGenOptLevel {
+        self.modules
+            .iter()
+            .rev()
+            .find(|(name, _)| name == module)
+            .map_or(self.default, |&(_, lvl)| lvl)
+    }
+}
 
-use llvm_sys::target::{LLVM_InitializeNativeAsmPrinter, LLVM_InitializeNativeTarget};
+impl From<LLVMCodeGenOptLevel> for OptLevel {
+    fn from(default: LLVMCodeGenOptLevel) -> OptLevel {
+        OptLevel { default, modules: Vec::new() }
+    }
+}
 
-static LLVM_INIT: Once = Once::new();
+/// Which parts of a compilation unit are generated by [`compile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodegenParts {
+    All,
+    /// Only generate the code that depends on the default values of parameters
+    /// (the model/instance setup functions and the descriptors). The access and eval
+    /// functions are reused from a previous compilation with the same structure.
+    SetupOnly,
+}
 
-fn initialize_llvm() {
-    LLVM_INIT.call_once(|| unsafe {
-        if LLVM_InitializeNativeTarget() != 0 {
-            panic!("Failed to initialize native target");
-        }
-        if LLVM_InitializeNativeAsmPrinter() != 0 {
-            panic!("Failed to initialize native ASM printer");
-        }
-    });
+/// The object files created by [`compile`].
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFiles {
+    /// The access and eval functions of each module (in that order). These are
+    /// independent of the default values of parameters. Empty for [`CodegenParts::SetupOnly`].
+    pub structural: Vec<Utf8PathBuf>,
+    /// The setup functions of each module and the descriptors.
+    pub setup: Vec<Utf8PathBuf>,
+}
+
+impl ObjectFiles {
+    pub fn iter(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.structural.iter().chain(&self.setup)
+    }
+}
+
+/// The source of an entry of the `param_opvar` array of an OSDI descriptor.
+/// Allows mapping the index of a parameter (reported by a simulator) back to
+/// the Verilog-A source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsdiParamSlot {
+    pub name: String,
+    pub units: String,
+    /// The file and (one-based) line of the declaration. `None` for builtin
+    /// instance parameters like `$mfactor`.
+    pub decl: Option<(String, u32)>,
 }
 
+/// Returns the parameters of each module in the same order as the `param_opvar`
+/// array of the descriptor generated by [`compile`].
+pub fn param_slots(db: &CompilationDB, modules: &[ModuleInfo]) -> Vec<Vec<OsdiParamSlot>> {
+    let mut literals = Rodeo::new();
+    let lim_table = TiSet::default();
+    let mut scratch = Scratch::default();
+    modules
+        .iter()
+        .map(|module| {
+            let mir = CompiledModule::new_with_scratch(db, module, &mut literals, &mut scratch);
+            OsdiModule::new(db, &mir, &lim_table).param_slots(db)
+        })
+        .collect()
+}
+
+/// Generates the object files for `modules`. `progress` is called with the index of
+/// each module before it is lowered.
+#[allow(clippy::too_many_arguments)]
 pub fn compile(
     db: &CompilationDB,
     modules: &[ModuleInfo],
@@ -57,15 +125,21 @@ pub fn compile(
     target: &Target,
     back: &LLVMBackend,
     emit: bool,
-    opt_lvl: LLVMCodeGenOptLevel,
-) -> Vec<Utf8PathBuf> {
-    initialize_llvm();
+    parts: CodegenParts,
+    opt_lvl: &OptLevel,
+    sink: &mut impl DiagnosticSink,
+    mut progress: impl FnMut(usize, &ModuleInfo),
+) -> ObjectFiles {
     let mut literals = Rodeo::new();
     let mut lim_table = TiSet::default();
+    let mut scratch = Scratch::default();
     let modules: Vec<_> = modules
         .iter()
-        .map(|module| {
-            let mir = CompiledModule::new(db, module, &mut literals);
+        .enumerate()
+        .map(|(i, module)| {
+            progress(i, module);
+            let mir = CompiledModule::new_with_scratch(db, module, &mut literals, &mut scratch);
+            mir.diagnostics(db, sink);
             for cb in mir.intern.callbacks.iter() {
                 if let CallBackKind::BuiltinLimit { name, num_args } = *cb {
                     lim_table.ensure(OsdiLimFunction { name, num_args: num_args - 2 });
@@ -74,9 +148,16 @@ pub fn compile(
             mir
         })
         .collect();
+    // The eval functions with the highest register pressure take the longest to compile.
+    // Their jobs are spawned first, so that they are picked up first by idle threads and
+    // don't delay the end of the compilation.
+    let mut order: Vec<usize> = (0..modules.len()).collect();
+    if modules.len() > 1 {
+        order.sort_by_cached_key(|&i| Reverse(modules[i].eval_register_pressure()));
+    }
     let name = dst.file_stem().expect("destination is a file").to_owned();
 
-    let mut paths: Vec<Utf8PathBuf> = (0..modules.len() * 4)
+    let paths: Vec<Utf8PathBuf> = (0..modules.len() * 4)
         .map(|i| {
             let num = base_n::encode((i + 1) as u128, CASE_INSENSITIVE);
             let extension = format!("o{num}");
@@ -108,29 +189,42 @@ pub fn compile(
         let target_data_ = target_data;
         let paths = &paths;
 
-        for (i, module) in modules.iter().enumerate() {
-            let _db = db.snapshot();
-            scope.spawn(move |_| {
-                let access = format!("access_{}", &module.sym);
-                let llmod = unsafe { back.new_module(&access, opt_lvl).unwrap() };
-                let cx = new_codegen(back, &llmod, literals_);
-                let tys = OsdiTys::new(&cx, NonNull::from(target_data_).as_ptr());
-                let cguint = OsdiCompilationUnit::new(&_db, module, &cx, &tys, false);
-
-                cguint.access_function();
-                debug_assert!(llmod.verify_and_print());
-
-                if emit {
-                    let path = &paths[i * 4];
-                    llmod.optimize();
-                    assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
-                }
-            });
+        let crash_cx = CrashContext { phase: Some("codegen"), ..CrashContext::current() };
+        for &i in &order {
+            let module = &modules[i];
+            // forward the crash context to the worker threads
+            let module_name = module.info.module.name(&db);
+            let module_opt_lvl = opt_lvl.for_module(&module_name);
+            let crash_cx = CrashContext { module: Some(module_name), ..crash_cx.clone() };
+
+            if parts == CodegenParts::All {
+                let _db = db.snapshot();
+                let crash_cx_ = crash_cx.clone();
+                scope.spawn(move |_| {
+                    let _crash_cx = crash_cx_.enter();
+                    let access = format!("access_{}", &module.sym);
+                    let llmod = unsafe { back.new_module(&access, module_opt_lvl).unwrap() };
+                    let cx = new_codegen(back, &llmod, literals_);
+                    let tys = OsdiTys::new(&cx, NonNull::from(target_data_).as_ptr());
+                    let cguint = OsdiCompilationUnit::new(&_db, module, &cx, &tys, false);
+
+                    cguint.access_function();
+                    debug_assert!(llmod.verify_and_print());
+
+                    if emit {
+                        let path = &paths[i * 4];
+                        llmod.optimize();
+                        assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
+                    }
+                });
+            }
 
             let _db = db.snapshot();
+            let crash_cx_ = crash_cx.clone();
             scope.spawn(move |_| {
+                let _crash_cx = crash_cx_.enter();
                 let name = format!("setup_model_{}", &module.sym);
-                let llmod = unsafe { back.new_module(&name, opt_lvl).unwrap() };
+                let llmod = unsafe { back.new_module(&name, module_opt_lvl).unwrap() };
                 let cx = new_codegen(back, &llmod, literals_);
                 let tys = OsdiTys::new(&cx, NonNull::from(target_data_).as_ptr());
                 let cguint = OsdiCompilationUnit::new(&_db, module, &cx, &tys, false);
@@ -146,9 +240,11 @@ pub fn compile(
             });
 
             let _db = db.snapshot();
+            let crash_cx_ = crash_cx.clone();
             scope.spawn(move |_| {
+                let _crash_cx = crash_cx_.enter();
                 let name = format!("setup_instance_{}", &module.sym);
-                let llmod = unsafe { back.new_module(&name, opt_lvl).unwrap() };
+                let llmod = unsafe { back.new_module(&name, module_opt_lvl).unwrap() };
                 let cx = new_codegen(back, &llmod, literals_);
                 let tys = OsdiTys::new(&cx, NonNull::from(target_data_).as_ptr());
                 let mut cguint = OsdiCompilationUnit::new(&_db, module, &cx, &tys, false);
@@ -166,28 +262,33 @@ pub fn compile(
                 }
             });
 
-            let _db = db.snapshot();
-            scope.spawn(move |_| {
-                let access = format!("eval_{}", &module.sym);
-                let llmod = unsafe { back.new_module(&access, opt_lvl).unwrap() };
-                let cx = new_codegen(back, &llmod, literals_);
-                let tys = OsdiTys::new(&cx, NonNull::from(target_data_).as_ptr());
-                let cguint = OsdiCompilationUnit::new(&_db, module, &cx, &tys, true);
-
-                // println!("{:?}", module.eval);
-                cguint.eval();
-                // println!("{}", llmod.to_str());
-                debug_assert!(llmod.verify_and_print());
-
-                if emit {
-                    let path = &paths[i * 4 + 3];
-                    llmod.optimize();
-                    assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
-                }
-            });
+            if parts == CodegenParts::All {
+                let _db = db.snapshot();
+                let crash_cx_ = crash_cx.clone();
+                scope.spawn(move |_| {
+                    let _crash_cx = crash_cx_.enter();
+                    let access = format!("eval_{}", &module.sym);
+                    let llmod = unsafe { back.new_module(&access, module_opt_lvl).unwrap() };
+                    let cx = new_codegen(back, &llmod, literals_);
+                    let tys = OsdiTys::new(&cx, NonNull::from(target_data_).as_ptr());
+                    let cguint = OsdiCompilationUnit::new(&_db, module, &cx, &tys, true);
+
+                    // println!("{:?}", module.eval);
+                    cguint.eval();
+                    cguint.eval_residual();
+                    // println!("{}", llmod.to_str());
+                    debug_assert!(llmod.verify_and_print());
+
+                    if emit {
+                        let path = &paths[i * 4 + 3];
+                        llmod.optimize();
+                        assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
+                    }
+                });
+            }
         }
 
-        let llmod = unsafe { back.new_module(&name, opt_lvl).unwrap() };
+        let llmod = unsafe { back.new_module(&name, opt_lvl.default).unwrap() };
         let cx = new_codegen(back, &llmod, &literals);
         let tys = OsdiTys::new(&cx, NonNull::from(target_data).as_ptr());
 
@@ -241,26 +342,30 @@ pub fn compile(
             );
         }
 
-        let osdi_log =
-            cx.get_declared_value("osdi_log").expect("symbol osdi_log missing from std lib");
-        let val = cx.const_null_ptr();
-        unsafe {
-            llvm_sys::core::LLVMSetInitializer(
-                NonNull::from(osdi_log).as_ptr(),
-                NonNull::from(val).as_ptr(),
-            );
-            llvm_sys::core::LLVMSetLinkage(
-                NonNull::from(osdi_log).as_ptr(),
-                llvm_sys::LLVMLinkage::LLVMExternalLinkage,
-            );
-            llvm_sys::core::LLVMSetUnnamedAddress(
-                NonNull::from(osdi_log).as_ptr(),
-                llvm_sys::LLVMUnnamedAddr::LLVMNoUnnamedAddr,
-            );
-            llvm_sys::core::LLVMSetDLLStorageClass(
-                NonNull::from(osdi_log).as_ptr(),
-                llvm_sys::LLVMDLLStorageClass::LLVMDLLExportStorageClass,
-            );
+        // function pointers that are set by the simulator after loading the library
+        for sym in ["osdi_log", "osdi_fopen", "osdi_fwrite", "osdi_fclose"] {
+            let global = cx
+                .get_declared_value(sym)
+                .unwrap_or_else(|| panic!("symbol {sym} missing from std lib"));
+            let val = cx.const_null_ptr();
+            unsafe {
+                llvm_sys::core::LLVMSetInitializer(
+                    NonNull::from(global).as_ptr(),
+                    NonNull::from(val).as_ptr(),
+                );
+                llvm_sys::core::LLVMSetLinkage(
+                    NonNull::from(global).as_ptr(),
+                    llvm_sys::LLVMLinkage::LLVMExternalLinkage,
+                );
+                llvm_sys::core::LLVMSetUnnamedAddress(
+                    NonNull::from(global).as_ptr(),
+                    llvm_sys::LLVMUnnamedAddr::LLVMNoUnnamedAddr,
+                );
+                llvm_sys::core::LLVMSetDLLStorageClass(
+                    NonNull::from(global).as_ptr(),
+                    llvm_sys::LLVMDLLStorageClass::LLVMDLLExportStorageClass,
+                );
+            }
         }
 
         debug_assert!(llmod.verify_and_print());
@@ -273,9 +378,18 @@ pub fn compile(
         }
     });
 
-    paths.push(main_file);
     unsafe { LLVMDisposeTargetData(NonNull::from(target_data).as_ptr()) };
-    paths
+
+    let mut objects = ObjectFiles::default();
+    for module_paths in paths.chunks(4) {
+        if parts == CodegenParts::All {
+            objects.structural.push(module_paths[0].clone());
+            objects.structural.push(module_paths[3].clone());
+        }
+        objects.setup.extend_from_slice(&module_paths[1..3]);
+    }
+    objects.setup.push(main_file);
+    objects
 }
 
 impl OsdiModule<'_> {
@@ -2,13 +2,16 @@ use core::ffi::c_uint;
 use std::ptr::NonNull;
 
 use llvm_sys::core::{
-    LLVMAppendBasicBlockInContext, LLVMBuildCall2, LLVMBuildFAdd, LLVMBuildFDiv, LLVMBuildFMul,
-    LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildRetVoid, LLVMBuildStore, LLVMCreateBuilderInContext,
-    LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd,
+    LLVMAppendBasicBlockInContext, LLVMBuildCall2, LLVMBuildFAdd, LLVMBuildFCmp, LLVMBuildFDiv,
+    LLVMBuildFMul, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildRetVoid, LLVMBuildSelect, LLVMBuildStore,
+    LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd,
 };
+use llvm_sys::prelude::{LLVMBuilderRef, LLVMValueRef};
+use llvm_sys::LLVMRealPredicate::LLVMRealOGT;
 use mir_llvm::UNNAMED;
 use sim_back::dae::NoiseSourceKind;
 use stdx::iter::zip;
+use stdx::Ieee64;
 use typed_index_collections::TiVec;
 
 use crate::compilation_unit::OsdiCompilationUnit;
@@ -120,7 +123,9 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
                         pwr
                     }
-                    NoiseSourceKind::NoiseTable { .. } => unimplemented!("noise tables"),
+                    NoiseSourceKind::NoiseTable { log, ref vals } => {
+                        self.interpolate_noise_table(log, vals, freq, llbuilder)
+                    }
                 };
 
                 // Multiply with squared factor because factor is in terms of signal, but
@@ -164,6 +169,125 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         llfunc
     }
 
+    /// Writes the derivatives of the residual with respect to the parameters listed in
+    /// `sensitivity_params` to `resist[i*num_nodes + node]` and `react[i*num_nodes + node]`.
+    /// Every entry is written, entries that do not depend on a parameter are set to zero.
+    pub fn load_sensitivities(&self) -> &'ll llvm_sys::LLVMValue {
+        let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
+        let params = &module.info.sensitivity_params;
+        if params.is_empty() {
+            return cx.const_null_ptr();
+        }
+
+        let ptr_ty = cx.ty_ptr();
+        let fun_ty = cx.ty_func(&[ptr_ty, ptr_ty, ptr_ty, ptr_ty], cx.ty_void());
+        let name = &format!("load_sensitivities_{}", module.sym);
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        let num_nodes = module.dae_system.unknowns.len();
+        let mut entries = vec![(None, None); params.len() * num_nodes];
+        for (src, outputs) in zip(&module.dae_system.param_sensitivities, &inst_data.sensitivities)
+        {
+            let i = params.iter().position(|&param| param == src.param).unwrap();
+            entries[i * num_nodes + usize::from(src.row)] = (outputs.resist, outputs.react);
+        }
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(
+                NonNull::from(cx.llcx).as_ptr(),
+                NonNull::from(llfunc).as_ptr(),
+                UNNAMED,
+            );
+            let llbuilder = LLVMCreateBuilderInContext(NonNull::from(cx.llcx).as_ptr());
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let inst = &*LLVMGetParam(NonNull::from(llfunc).as_ptr(), 0);
+            let model = &*LLVMGetParam(NonNull::from(llfunc).as_ptr(), 1);
+            let resist_dst = LLVMGetParam(NonNull::from(llfunc).as_ptr(), 2);
+            let react_dst = LLVMGetParam(NonNull::from(llfunc).as_ptr(), 3);
+
+            for (i, (resist, react)) in entries.into_iter().enumerate() {
+                for (output, dst) in [(resist, resist_dst), (react, react_dst)] {
+                    let val = match output {
+                        Some(output) => self.load_eval_output(output, inst, model, &*llbuilder),
+                        None => cx.const_real(0.0),
+                    };
+                    let index_val =
+                        cx.const_unsigned_int(i as u32) as *const llvm_sys::LLVMValue as *mut _;
+                    let mut gep_indices: [LLVMValueRef; 1] = [index_val];
+                    let dst = LLVMBuildGEP2(
+                        llbuilder,
+                        NonNull::from(cx.ty_double()).as_ptr(),
+                        dst,
+                        gep_indices.as_mut_ptr(),
+                        1,
+                        UNNAMED,
+                    );
+                    LLVMBuildStore(llbuilder, NonNull::from(val).as_ptr(), dst);
+                }
+            }
+
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
+    /// Evaluates a noise table at `freq`. The power is interpolated linearly between the
+    /// table entries (between their logarithms for `noise_table_log`) and is constant
+    /// outside of the tabulated frequency range.
+    unsafe fn interpolate_noise_table(
+        &self,
+        log: bool,
+        vals: &[(Ieee64, Ieee64)],
+        freq: &'ll llvm_sys::LLVMValue,
+        llbuilder: LLVMBuilderRef,
+    ) -> &'ll llvm_sys::LLVMValue {
+        let cx = self.cx;
+        let real = |val: f64| NonNull::from(cx.const_real(val)).as_ptr();
+        let call_intrinsic = |name: &'static str, args: &mut [LLVMValueRef]| {
+            let (ty, fun) =
+                cx.intrinsic(name).unwrap_or_else(|| unreachable!("intrinsic {} not found", name));
+            LLVMBuildCall2(
+                llbuilder,
+                NonNull::from(ty).as_ptr(),
+                NonNull::from(fun).as_ptr(),
+                args.as_mut_ptr(),
+                args.len() as c_uint,
+                UNNAMED,
+            )
+        };
+
+        let mut x = NonNull::from(freq).as_ptr();
+        if log {
+            x = call_intrinsic("llvm.log10.f64", &mut [x]);
+        }
+
+        // below the first frequency the table is constant
+        let mut res = real(vals[0].1.into());
+        for segment in vals.windows(2) {
+            let (x_lo, y_lo): (f64, f64) = (segment[0].0.into(), segment[0].1.into());
+            let (x_hi, y_hi): (f64, f64) = (segment[1].0.into(), segment[1].1.into());
+            let slope = (y_hi - y_lo) / (x_hi - x_lo);
+
+            // clamping to the end of the segment makes the last segment constant
+            // beyond the last frequency
+            let above_hi = LLVMBuildFCmp(llbuilder, LLVMRealOGT, x, real(x_hi), UNNAMED);
+            let x_seg = LLVMBuildSelect(llbuilder, above_hi, real(x_hi), x, UNNAMED);
+            let dx = LLVMBuildFSub(llbuilder, x_seg, real(x_lo), UNNAMED);
+            let dy = LLVMBuildFMul(llbuilder, dx, real(slope), UNNAMED);
+            let y = LLVMBuildFAdd(llbuilder, real(y_lo), dy, UNNAMED);
+
+            let above_lo = LLVMBuildFCmp(llbuilder, LLVMRealOGT, x, real(x_lo), UNNAMED);
+            res = LLVMBuildSelect(llbuilder, above_lo, y, res, UNNAMED);
+        }
+
+        if log {
+            res = call_intrinsic("llvm.pow.f64", &mut [real(10.0), res]);
+        }
+        &*res
+    }
+
     pub fn load_residual(&self, reactive: bool) -> &'ll llvm_sys::LLVMValue {
         let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
         let ptr_ty = cx.ty_ptr();
@@ -1,14 +1,16 @@
 use core::ptr::NonNull;
+use std::fmt::Write;
 use std::iter::once;
 
-use hir::{CompilationDB, ParamSysFun, Type};
-use hir_lower::CurrentKind;
+use ahash::AHashMap;
+use hir::{CompilationDB, ParamSysFun, Parameter, Type};
+use hir_lower::{is_unnamed_noise_src, HirInterner};
 use lasso::{Rodeo, Spur};
 use llvm_sys::target::{LLVMABISizeOfType, LLVMOffsetOfElement, LLVMTargetDataRef};
-use mir::{ValueDef, F_ZERO};
+use mir::{Const, ValueDef, F_ZERO};
 use mir_llvm::CodegenCx;
-use sim_back::dae::MatrixEntry;
-use sim_back::SimUnknownKind;
+use sim_back::dae::{MatrixEntry, NoiseSource, SimUnknown};
+use sim_back::{const_param_default, nature_abstol, Abstol, SimUnknownKind};
 use smol_str::SmolStr;
 
 use crate::compilation_unit::{OsdiCompilationUnit, OsdiModule};
@@ -16,16 +18,17 @@ use crate::inst_data::{
     OsdiInstanceParam, COLLAPSED, JACOBIAN_PTR_REACT, JACOBIAN_PTR_RESIST, NODE_MAPPING, STATE_IDX,
 };
 use crate::load::JacobianLoadType;
-use crate::metadata::osdi_0_4::{
-    OsdiDescriptor, OsdiJacobianEntry, OsdiNode, OsdiNodePair, OsdiNoiseSource, OsdiParamOpvar,
-    OsdiTys, JACOBIAN_ENTRY_REACT, JACOBIAN_ENTRY_REACT_CONST, JACOBIAN_ENTRY_RESIST,
-    JACOBIAN_ENTRY_RESIST_CONST, PARA_KIND_INST, PARA_KIND_MODEL, PARA_KIND_OPVAR, PARA_TY_INT,
-    PARA_TY_REAL, PARA_TY_STR,
+use crate::metadata::osdi_0_5::{
+    OsdiDescriptor, OsdiJacobianEntry, OsdiNode, OsdiNodeAbstol, OsdiNodePair, OsdiNoiseSource,
+    OsdiParamDefault, OsdiParamOpvar, OsdiTys, JACOBIAN_ENTRY_REACT, JACOBIAN_ENTRY_REACT_CONST,
+    JACOBIAN_ENTRY_RESIST, JACOBIAN_ENTRY_RESIST_CONST, NODE_ABSTOL_COMPUTED, NODE_ABSTOL_NONE,
+    NODE_ABSTOL_STATIC, PARA_DEFAULT_COMPUTED, PARA_DEFAULT_STATIC, PARA_KIND_INST,
+    PARA_KIND_MODEL, PARA_KIND_OPVAR, PARA_TY_INT, PARA_TY_REAL, PARA_TY_STR,
 };
-use crate::ty_len;
+use crate::{ty_len, OsdiParamSlot};
 
 #[allow(unused_parens, dead_code)]
-pub mod osdi_0_4;
+pub mod osdi_0_5;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct OsdiLimFunction {
@@ -39,7 +42,7 @@ impl OsdiLimFunction {
         ctx: &CodegenCx<'_, 'll>,
         tys: &'ll OsdiTys,
     ) -> &'ll llvm_sys::LLVMValue {
-        osdi_0_4::OsdiLimFunction {
+        osdi_0_5::OsdiLimFunction {
             name: ctx.literals.resolve(&self.name).to_owned(),
             num_args: self.num_args,
             func_ptr: ctx.const_null_ptr(),
@@ -48,6 +51,14 @@ impl OsdiLimFunction {
     }
 }
 
+fn builtin_param_units(builtin: ParamSysFun) -> &'static str {
+    match builtin {
+        ParamSysFun::yposition | ParamSysFun::xposition => "m",
+        ParamSysFun::angle => "deg",
+        _ => "",
+    }
+}
+
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
     pub fn param_opvar(&self) -> Vec<OsdiParamOpvar> {
         let OsdiCompilationUnit { inst_data, model_data, module, .. } = self;
@@ -73,11 +84,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         ParamSysFun::mfactor => "Multiplier (Verilog-A $mfactor)".to_owned(),
                         _ => "".to_owned(),
                     },
-                    units: match builtin {
-                        ParamSysFun::yposition | ParamSysFun::xposition => "m".to_owned(),
-                        ParamSysFun::angle => "deg".to_owned(),
-                        _ => "".to_owned(),
-                    },
+                    units: builtin_param_units(*builtin).to_owned(),
                     flags: PARA_TY_REAL | PARA_KIND_INST,
                     len: 0,
                 }
@@ -140,6 +147,28 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         inst_params.chain(model_params).chain(opvars).collect()
     }
 
+    /// The default values of all parameters in the same order as the
+    /// parameters in [`param_opvar`](Self::param_opvar).
+    pub fn param_defaults(&self) -> Vec<OsdiParamDefault> {
+        let OsdiCompilationUnit { inst_data, model_data, module, cx, .. } = self;
+        let inst_params = inst_data.params.keys().map(|param| match *param {
+            OsdiInstanceParam::Builtin(builtin) => OsdiParamDefault {
+                flags: PARA_DEFAULT_STATIC,
+                int_val: 0,
+                real_val: builtin.default_value(),
+                str_val: String::new(),
+            },
+            OsdiInstanceParam::User(param) => module.param_default(param, cx.literals),
+        });
+        let model_params = model_data.params.keys().filter_map(|&param| {
+            if module.info.params[&param].is_instance {
+                return None;
+            }
+            Some(module.param_default(param, cx.literals))
+        });
+        inst_params.chain(model_params).collect()
+    }
+
     pub fn nodes(&self, target_data: &LLVMTargetDataRef, db: &CompilationDB) -> Vec<OsdiNode> {
         let OsdiCompilationUnit { inst_data, module, .. } = self;
         module
@@ -147,7 +176,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             .unknowns
             .iter_enumerated()
             .map(|(id, unknown)| {
-                let (name, units, is_flow) = sim_unknown_info(*unknown, db);
+                let (name, units) = sim_unknown_info(*unknown, module.intern, db);
                 let resist_residual_off =
                     inst_data.residual_off(id, false, target_data).unwrap_or(u32::MAX);
                 let react_residual_off =
@@ -163,7 +192,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     residual_units: String::new(),
                     resist_residual_off,
                     react_residual_off,
-                    is_flow,
+                    is_flow: unknown.is_flow(),
                     resist_limit_rhs_off,
                     react_limit_rhs_off,
                 }
@@ -241,6 +270,67 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             .collect()
     }
 
+    /// The absolute tolerance of each node (the `abstol` of its nature). Tolerances that are
+    /// not constant are computed by `setup_model` and stored in the model data.
+    pub fn node_abstol(
+        &self,
+        target_data: &LLVMTargetDataRef,
+        db: &CompilationDB,
+    ) -> Vec<OsdiNodeAbstol> {
+        let OsdiCompilationUnit { inst_data, model_data, module, .. } = self;
+        module
+            .dae_system
+            .unknowns
+            .iter()
+            .map(|unknown| {
+                let abstol = unknown.nature(db).and_then(|nature| {
+                    nature_abstol(module.model_param_setup, module.model_param_intern, nature)
+                        .map(|abstol| (nature, abstol))
+                });
+                match abstol {
+                    Some((_, Abstol::Const(val))) => {
+                        OsdiNodeAbstol { flags: NODE_ABSTOL_STATIC, model_off: u32::MAX, val }
+                    }
+                    Some((nature, Abstol::Setup(_))) => OsdiNodeAbstol {
+                        flags: NODE_ABSTOL_COMPUTED,
+                        model_off: model_data.abstol_off(nature, inst_data, target_data).unwrap(),
+                        val: 0.0,
+                    },
+                    None => {
+                        OsdiNodeAbstol { flags: NODE_ABSTOL_NONE, model_off: u32::MAX, val: 0.0 }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn gmin_nodes(&self) -> Vec<u32> {
+        self.module.dae_system.gmin_nodes.iter().map(|&node| node.into()).collect()
+    }
+
+    /// The index of each parameter in [`param_opvar`](Self::param_opvar) whose
+    /// sensitivities are written by `load_sensitivities`.
+    pub fn sensitivity_params(&self) -> Vec<u32> {
+        let OsdiCompilationUnit { inst_data, model_data, module, .. } = self;
+        module
+            .info
+            .sensitivity_params
+            .iter()
+            .map(|&param| {
+                if let Some(pos) = inst_data.params.get_index_of(&OsdiInstanceParam::User(param)) {
+                    return pos as u32;
+                }
+                let pos = model_data
+                    .params
+                    .keys()
+                    .filter(|param| !module.info.params[*param].is_instance)
+                    .position(|&model_param| model_param == param)
+                    .unwrap();
+                (inst_data.params.len() + pos) as u32
+            })
+            .collect()
+    }
+
     pub fn inputs(&self) -> Vec<OsdiNodePair> {
         self.module
             .dae_system
@@ -257,6 +347,8 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
     ) -> OsdiDescriptor<'ll> {
         let collapsible = self.collapsible();
         let inputs = self.inputs();
+        let gmin_nodes = self.gmin_nodes();
+        let sensitivity_params = self.sensitivity_params();
         let OsdiCompilationUnit { ref inst_data, ref model_data, module, cx, .. } = *self;
 
         unsafe {
@@ -287,6 +379,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             let model_size =
                 LLVMABISizeOfType(*target_data, NonNull::from(model_data.ty).as_ptr()) as u32;
 
+            let mut unnamed = AHashMap::new();
             let noise_sources: Vec<_> = module
                 .dae_system
                 .noise_sources
@@ -294,7 +387,10 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 .map(|source| {
                     let node_1: u32 = source.hi.into();
                     let node_2: u32 = source.lo.map_or(u32::MAX, u32::from);
-                    let name = cx.literals.resolve(&source.name).to_owned();
+                    let mut name = cx.literals.resolve(&source.name).to_owned();
+                    if is_unnamed_noise_src(&name) {
+                        name = module.unnamed_noise_src_name(source, &mut unnamed, db);
+                    }
                     OsdiNoiseSource { name, nodes: OsdiNodePair { node_1, node_2 } }
                 })
                 .collect();
@@ -351,71 +447,107 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 load_jacobian_with_offset_resist: self
                     .load_jacobian(JacobianLoadType::Resist, true),
                 load_jacobian_with_offset_react: self.load_jacobian(JacobianLoadType::React, true),
+                param_defaults: self.param_defaults(),
+                eval_residual: self.eval_residual_prototype(),
+                num_gmin_nodes: gmin_nodes.len() as u32,
+                gmin_nodes,
+                num_sensitivity_params: sensitivity_params.len() as u32,
+                sensitivity_params,
+                load_sensitivities: self.load_sensitivities(),
+                node_abstol: self.node_abstol(target_data, db),
             }
         }
     }
 }
 
 impl OsdiModule<'_> {
+    /// The source of each parameter in the same order as the `param_opvar`
+    /// array of the descriptor.
+    pub fn param_slots(&self, db: &CompilationDB) -> Vec<OsdiParamSlot> {
+        let user_slot = |param: Parameter| {
+            let info = &self.info.params[&param];
+            OsdiParamSlot {
+                name: info.name.to_string(),
+                units: info.unit.clone(),
+                decl: Some(param.decl_location(db)),
+            }
+        };
+        let inst_params = self.inst_params().into_iter().map(|param| match param {
+            OsdiInstanceParam::Builtin(builtin) => OsdiParamSlot {
+                name: format!("${builtin:?}"),
+                units: builtin_param_units(builtin).to_owned(),
+                decl: None,
+            },
+            OsdiInstanceParam::User(param) => user_slot(param),
+        });
+        let model_params = self
+            .info
+            .params
+            .iter()
+            .filter(|(_, info)| !info.is_instance)
+            .map(|(&param, _)| user_slot(param));
+        inst_params.chain(model_params).collect()
+    }
+
+    /// Constant defaults are emitted directly. All other defaults depend on other
+    /// parameters and are only computed by `setup_model`.
+    pub fn param_default(&self, param: Parameter, literals: &Rodeo) -> OsdiParamDefault {
+        let mut res = OsdiParamDefault {
+            flags: PARA_DEFAULT_STATIC,
+            int_val: 0,
+            real_val: 0.0,
+            str_val: String::new(),
+        };
+        match const_param_default(self.model_param_setup, self.model_param_intern, param) {
+            Some(Const::Float(val)) => res.real_val = val.into(),
+            Some(Const::Int(val)) => res.int_val = val,
+            Some(Const::Str(val)) => res.str_val = literals.resolve(&val).to_owned(),
+            Some(Const::Bool(_)) | None => res.flags = PARA_DEFAULT_COMPUTED,
+        }
+        res
+    }
+
+    /// Noise sources without a name are named after the nodes they are connected to
+    /// (`noise(a,c)` or `noise(a)` if connected to ground). Further unnamed sources between
+    /// the same nodes are numbered (`noise(a,c)_1`), so the names are unique and only depend
+    /// on the order of the sources between these nodes.
+    fn unnamed_noise_src_name(
+        &self,
+        source: &NoiseSource,
+        unnamed: &mut AHashMap<(SimUnknown, Option<SimUnknown>), u32>,
+        db: &CompilationDB,
+    ) -> String {
+        let unknowns = &self.dae_system.unknowns;
+        let (hi, _, _, _) = sim_unknown_info(unknowns[source.hi], self.intern, db);
+        let mut name = match source.lo {
+            Some(lo) => {
+                format!("noise({hi},{})", sim_unknown_info(unknowns[lo], self.intern, db).0)
+            }
+            None => format!("noise({hi})"),
+        };
+        let cnt = unnamed.entry((source.hi, source.lo)).or_insert(0);
+        if *cnt != 0 {
+            write!(name, "_{cnt}").unwrap();
+        }
+        *cnt += 1;
+        name
+    }
+
     pub fn intern_node_strs(&self, intern: &mut Rodeo, db: &CompilationDB) {
         for &unknown in self.dae_system.unknowns.iter() {
-            let (name, units, _) = sim_unknown_info(unknown, db);
+            let (name, units) = sim_unknown_info(unknown, self.intern, db);
             intern.get_or_intern(&name);
             intern.get_or_intern(&units);
         }
     }
 }
 
-fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, String, bool) {
-    let name;
-    let discipline;
-    let is_flow;
-
-    match unknown {
-        SimUnknownKind::KirchoffLaw(node) => {
-            name = node.name(db).to_string();
-            discipline = Some(node.discipline(db));
-            is_flow = false;
-        }
-
-        SimUnknownKind::Current(CurrentKind::Unnamed { hi, lo }) => {
-            name = if let Some(lo) = lo {
-                format!("flow({},{})", &hi.name(db), &lo.name(db))
-            } else {
-                format!("flow({})", &hi.name(db))
-            };
-            discipline = Some(hi.discipline(db));
-            is_flow = true;
-        }
-        SimUnknownKind::Current(CurrentKind::Branch(br)) => {
-            name = format!("flow({})", &br.name(db));
-            discipline = Some(br.discipline(db));
-            is_flow = true;
-        }
-        SimUnknownKind::Current(CurrentKind::Port(node)) => {
-            name = format!("flow(<{}>)", &node.name(db));
-            discipline = Some(node.discipline(db));
-            is_flow = true;
-        }
-        SimUnknownKind::Implicit(equ) => {
-            name = format!("implicit_equation_{}", u32::from(equ));
-            discipline = None;
-            is_flow = false;
-        }
-    };
-
-    // its valid to have disciplines without pot/flow nature but then we can't
-    // have branches for those so its ok to unwrap here
-    let units = discipline
-        .map(|discipline| {
-            let nature = if is_flow {
-                discipline.flow(db).unwrap()
-            } else {
-                discipline.potential(db).unwrap()
-            };
-            nature.units(db)
-        })
-        .unwrap_or_default();
-
-    (name, units, is_flow)
+fn sim_unknown_info(
+    unknown: SimUnknownKind,
+    intern: &HirInterner,
+    db: &CompilationDB,
+) -> (String, String) {
+    let name = unknown.name(db, intern);
+    let units = unknown.nature(db).map(|nature| nature.units(db)).unwrap_or_default();
+    (name, units)
 }
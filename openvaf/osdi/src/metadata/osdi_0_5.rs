@@ -3,19 +3,19 @@
 use mir_llvm::CodegenCx;
 
 const STDLIB_BITCODE_X86_64_UNKNOWN_LINUX_GNU: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_x86_64-unknown-linux-gnu.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_x86_64-unknown-linux-gnu.bc"));
 const STDLIB_BITCODE_X86_64_PC_WINDOWS_MSVC: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_x86_64-pc-windows-msvc.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_x86_64-pc-windows-msvc.bc"));
 const STDLIB_BITCODE_X86_64_APPLE_MACOSX10_15_0: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_x86_64-apple-macosx10.15.0.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_x86_64-apple-macosx10.15.0.bc"));
 const STDLIB_BITCODE_AARCH64_UNKNOWN_LINUX_GNU: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_aarch64-unknown-linux-gnu.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_aarch64-unknown-linux-gnu.bc"));
 const STDLIB_BITCODE_AARCH64_PC_WINDOWS_MSVC: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_aarch64-pc-windows-msvc.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_aarch64-pc-windows-msvc.bc"));
 const STDLIB_BITCODE_ARM64_APPLE_MACOSX11_0_0: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_arm64-apple-macosx11.0.0.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_arm64-apple-macosx11.0.0.bc"));
 const STDLIB_BITCODE_X86_64_PC_WINDOWS_GNU: &[u8] =
-    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_4_x86_64-pc-windows-gnu.bc"));
+    include_bytes!(concat!(env!("OUT_DIR"), "/stdlib_0_5_x86_64-pc-windows-gnu.bc"));
 pub fn stdlib_bitcode(target: &target::spec::Target) -> &'static [u8] {
     match &*target.llvm_target {
         "x86_64-unknown-linux-gnu" => STDLIB_BITCODE_X86_64_UNKNOWN_LINUX_GNU,
@@ -29,7 +29,7 @@ pub fn stdlib_bitcode(target: &target::spec::Target) -> &'static [u8] {
     }
 }
 pub const OSDI_VERSION_MAJOR_CURR: u32 = 0;
-pub const OSDI_VERSION_MINOR_CURR: u32 = 4;
+pub const OSDI_VERSION_MINOR_CURR: u32 = 5;
 pub const PARA_TY_MASK: u32 = 3;
 pub const PARA_TY_REAL: u32 = 0;
 pub const PARA_TY_INT: u32 = 1;
@@ -38,6 +38,11 @@ pub const PARA_KIND_MASK: u32 = (3 << 30);
 pub const PARA_KIND_MODEL: u32 = (0 << 30);
 pub const PARA_KIND_INST: u32 = (1 << 30);
 pub const PARA_KIND_OPVAR: u32 = (2 << 30);
+pub const PARA_DEFAULT_STATIC: u32 = 0;
+pub const PARA_DEFAULT_COMPUTED: u32 = 1;
+pub const NODE_ABSTOL_NONE: u32 = 0;
+pub const NODE_ABSTOL_STATIC: u32 = 1;
+pub const NODE_ABSTOL_COMPUTED: u32 = 2;
 pub const ACCESS_FLAG_READ: u32 = 0;
 pub const ACCESS_FLAG_SET: u32 = 1;
 pub const ACCESS_FLAG_INSTANCE: u32 = 4;
@@ -266,6 +271,34 @@ impl OsdiTyBuilder<'_, '_, '_> {
         self.osdi_node = Some(ty);
     }
 }
+pub struct OsdiNodeAbstol {
+    pub flags: u32,
+    pub model_off: u32,
+    pub val: f64,
+}
+impl OsdiNodeAbstol {
+    pub fn to_ll_val<'ll>(
+        &self,
+        ctx: &CodegenCx<'_, 'll>,
+        tys: &'ll OsdiTys,
+    ) -> &'ll llvm_sys::LLVMValue {
+        let fields = [
+            ctx.const_unsigned_int(self.flags),
+            ctx.const_unsigned_int(self.model_off),
+            ctx.const_real(self.val),
+        ];
+        let ty = tys.osdi_node_abstol;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_node_abstol(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_int(), ctx.ty_int(), ctx.ty_double()];
+        let ty = ctx.ty_struct("OsdiNodeAbstol", &fields);
+        self.osdi_node_abstol = Some(ty);
+    }
+}
 pub struct OsdiParamOpvar {
     pub name: Vec<String>,
     pub num_alias: u32,
@@ -302,6 +335,36 @@ impl OsdiTyBuilder<'_, '_, '_> {
         self.osdi_param_opvar = Some(ty);
     }
 }
+pub struct OsdiParamDefault {
+    pub flags: u32,
+    pub int_val: i32,
+    pub real_val: f64,
+    pub str_val: String,
+}
+impl OsdiParamDefault {
+    pub fn to_ll_val<'ll>(
+        &self,
+        ctx: &CodegenCx<'_, 'll>,
+        tys: &'ll OsdiTys,
+    ) -> &'ll llvm_sys::LLVMValue {
+        let fields = [
+            ctx.const_unsigned_int(self.flags),
+            ctx.const_int(self.int_val),
+            ctx.const_real(self.real_val),
+            ctx.const_str_uninterned(&self.str_val),
+        ];
+        let ty = tys.osdi_param_default;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_param_default(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_int(), ctx.ty_int(), ctx.ty_double(), ctx.ty_ptr()];
+        let ty = ctx.ty_struct("OsdiParamDefault", &fields);
+        self.osdi_param_default = Some(ty);
+    }
+}
 pub struct OsdiNoiseSource {
     pub name: String,
     pub nodes: OsdiNodePair,
@@ -372,6 +435,14 @@ pub struct OsdiDescriptor<'ll> {
     pub inputs: Vec<OsdiNodePair>,
     pub load_jacobian_with_offset_resist: &'ll llvm_sys::LLVMValue,
     pub load_jacobian_with_offset_react: &'ll llvm_sys::LLVMValue,
+    pub param_defaults: Vec<OsdiParamDefault>,
+    pub eval_residual: &'ll llvm_sys::LLVMValue,
+    pub num_gmin_nodes: u32,
+    pub gmin_nodes: Vec<u32>,
+    pub num_sensitivity_params: u32,
+    pub sensitivity_params: Vec<u32>,
+    pub load_sensitivities: &'ll llvm_sys::LLVMValue,
+    pub node_abstol: Vec<OsdiNodeAbstol>,
 }
 impl<'ll> OsdiDescriptor<'ll> {
     pub fn to_ll_val(
@@ -385,6 +456,11 @@ impl<'ll> OsdiDescriptor<'ll> {
         let arr_9: Vec<_> = self.noise_sources.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
         let arr_14: Vec<_> = self.param_opvar.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
         let arr_43: Vec<_> = self.inputs.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let arr_46: Vec<_> = self.param_defaults.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let arr_49: Vec<_> = self.gmin_nodes.iter().map(|&it| ctx.const_unsigned_int(it)).collect();
+        let arr_51: Vec<_> =
+            self.sensitivity_params.iter().map(|&it| ctx.const_unsigned_int(it)).collect();
+        let arr_53: Vec<_> = self.node_abstol.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
         let fields = [
             ctx.const_str_uninterned(&self.name),
             ctx.const_unsigned_int(self.num_nodes),
@@ -432,6 +508,14 @@ impl<'ll> OsdiDescriptor<'ll> {
             ctx.const_arr_ptr(tys.osdi_node_pair, &arr_43),
             self.load_jacobian_with_offset_resist,
             self.load_jacobian_with_offset_react,
+            ctx.const_arr_ptr(tys.osdi_param_default, &arr_46),
+            self.eval_residual,
+            ctx.const_unsigned_int(self.num_gmin_nodes),
+            ctx.const_arr_ptr(ctx.ty_int(), &arr_49),
+            ctx.const_unsigned_int(self.num_sensitivity_params),
+            ctx.const_arr_ptr(ctx.ty_int(), &arr_51),
+            self.load_sensitivities,
+            ctx.const_arr_ptr(tys.osdi_node_abstol, &arr_53),
         ];
         let ty = tys.osdi_descriptor;
         ctx.const_struct(ty, &fields)
@@ -487,6 +571,14 @@ impl OsdiTyBuilder<'_, '_, '_> {
             ctx.ty_ptr(),
             ctx.ty_ptr(),
             ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
         ];
         let ty = ctx.ty_struct("OsdiDescriptor", &fields);
         self.osdi_descriptor = Some(ty);
@@ -503,7 +595,9 @@ pub struct OsdiTys<'ll> {
     pub osdi_node_pair: &'ll llvm_sys::LLVMType,
     pub osdi_jacobian_entry: &'ll llvm_sys::LLVMType,
     pub osdi_node: &'ll llvm_sys::LLVMType,
+    pub osdi_node_abstol: &'ll llvm_sys::LLVMType,
     pub osdi_param_opvar: &'ll llvm_sys::LLVMType,
+    pub osdi_param_default: &'ll llvm_sys::LLVMType,
     pub osdi_noise_source: &'ll llvm_sys::LLVMType,
     pub osdi_descriptor: &'ll llvm_sys::LLVMType,
 }
@@ -521,7 +615,9 @@ impl<'ll> OsdiTys<'ll> {
             osdi_node_pair: None,
             osdi_jacobian_entry: None,
             osdi_node: None,
+            osdi_node_abstol: None,
             osdi_param_opvar: None,
+            osdi_param_default: None,
             osdi_noise_source: None,
             osdi_descriptor: None,
         };
@@ -534,7 +630,9 @@ impl<'ll> OsdiTys<'ll> {
         builder.osdi_node_pair();
         builder.osdi_jacobian_entry();
         builder.osdi_node();
+        builder.osdi_node_abstol();
         builder.osdi_param_opvar();
+        builder.osdi_param_default();
         builder.osdi_noise_source();
         builder.osdi_descriptor();
         builder.finish()
@@ -552,7 +650,9 @@ struct OsdiTyBuilder<'a, 'b, 'll> {
     osdi_node_pair: Option<&'ll llvm_sys::LLVMType>,
     osdi_jacobian_entry: Option<&'ll llvm_sys::LLVMType>,
     osdi_node: Option<&'ll llvm_sys::LLVMType>,
+    osdi_node_abstol: Option<&'ll llvm_sys::LLVMType>,
     osdi_param_opvar: Option<&'ll llvm_sys::LLVMType>,
+    osdi_param_default: Option<&'ll llvm_sys::LLVMType>,
     osdi_noise_source: Option<&'ll llvm_sys::LLVMType>,
     osdi_descriptor: Option<&'ll llvm_sys::LLVMType>,
 }
@@ -568,7 +668,9 @@ impl<'ll> OsdiTyBuilder<'_, '_, 'll> {
             osdi_node_pair: self.osdi_node_pair.unwrap(),
             osdi_jacobian_entry: self.osdi_jacobian_entry.unwrap(),
             osdi_node: self.osdi_node.unwrap(),
+            osdi_node_abstol: self.osdi_node_abstol.unwrap(),
             osdi_param_opvar: self.osdi_param_opvar.unwrap(),
+            osdi_param_default: self.osdi_param_default.unwrap(),
             osdi_noise_source: self.osdi_noise_source.unwrap(),
             osdi_descriptor: self.osdi_descriptor.unwrap(),
         }
@@ -1,11 +1,14 @@
+use core::mem::size_of;
 use core::ptr::NonNull;
 
 use ahash::RandomState;
-use hir::{CompilationDB, Parameter};
-use indexmap::IndexMap;
+use hir::{CompilationDB, Nature, Parameter};
+use indexmap::{IndexMap, IndexSet};
 use llvm_sys::core::{LLVMBuildLoad2, LLVMBuildStore, LLVMBuildStructGEP2};
+use llvm_sys::target::{LLVMOffsetOfElement, LLVMTargetDataRef};
 use llvm_sys::LLVMValue as Value;
 use mir_llvm::{CodegenCx, MemLoc, UNNAMED};
+use sim_back::{nature_abstol, Abstol};
 
 use crate::compilation_unit::OsdiModule;
 use crate::inst_data::{OsdiInstanceData, OsdiInstanceParam};
@@ -16,6 +19,8 @@ const NUM_CONST_FIELDS: u32 = 1;
 pub struct OsdiModelData<'ll> {
     pub param_given: &'ll llvm_sys::LLVMType,
     pub params: IndexMap<Parameter, &'ll llvm_sys::LLVMType, RandomState>,
+    /// The natures whose abstol is computed by `setup_model`.
+    pub abstols: IndexSet<Nature, RandomState>,
     pub ty: &'ll llvm_sys::LLVMType,
 }
 
@@ -40,17 +45,30 @@ impl<'ll> OsdiModelData<'ll> {
             })
             .collect();
 
+        let abstols: IndexSet<_, _> = cgunit
+            .dae_system
+            .unknowns
+            .iter()
+            .filter_map(|unknown| {
+                let nature = unknown.nature(db)?;
+                let abstol =
+                    nature_abstol(cgunit.model_param_setup, cgunit.model_param_intern, nature)?;
+                matches!(abstol, Abstol::Setup(_)).then_some(nature)
+            })
+            .collect();
+
         let param_given = bitfield::arr_ty((inst_params.len() + params.len()) as u32, cx);
 
         let mut fields: Vec<_> = vec![param_given];
         fields.extend(params.values().copied());
         fields.extend(inst_params.values());
+        fields.push(cx.ty_array(cx.ty_double(), abstols.len() as u32));
 
         let name = &cgunit.sym;
         let name = format!("osdi_model_data_{name}");
         let ty = cx.ty_struct(&name, &fields);
 
-        OsdiModelData { param_given, params, ty }
+        OsdiModelData { param_given, params, abstols, ty }
     }
 
     pub fn nth_param_loc(
@@ -295,4 +313,42 @@ impl<'ll> OsdiModelData<'ll> {
     //         false
     //     }
     // }
+
+    fn abstol_elem(&self, inst_data: &OsdiInstanceData<'ll>) -> u32 {
+        NUM_CONST_FIELDS + self.params.len() as u32 + inst_data.params.len() as u32
+    }
+
+    /// The offset of the abstol of `nature` within the model data
+    /// (only for natures whose abstol is computed by `setup_model`).
+    pub fn abstol_off(
+        &self,
+        nature: Nature,
+        inst_data: &OsdiInstanceData<'ll>,
+        target_data: &LLVMTargetDataRef,
+    ) -> Option<u32> {
+        let pos = self.abstols.get_index_of(&nature)? as u32;
+        let elem = self.abstol_elem(inst_data);
+        let off =
+            unsafe { LLVMOffsetOfElement(*target_data, NonNull::from(self.ty).as_ptr(), elem) }
+                as u32;
+        Some(off + pos * size_of::<f64>() as u32)
+    }
+
+    pub unsafe fn store_nth_abstol(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        inst_data: &OsdiInstanceData<'ll>,
+        pos: u32,
+        ptr: &'ll Value,
+        val: &'ll Value,
+        llbuilder: &llvm_sys::LLVMBuilder,
+    ) {
+        let elem = self.abstol_elem(inst_data);
+        let indices =
+            vec![cx.const_unsigned_int(0), cx.const_unsigned_int(elem), cx.const_unsigned_int(pos)];
+        let loc = MemLoc { ptr, ptr_ty: self.ty, ty: cx.ty_double(), indices: indices.into() };
+        let llbuilder = NonNull::from(llbuilder).as_ptr();
+        let ptr = loc.to_ptr(llbuilder);
+        LLVMBuildStore(llbuilder, NonNull::from(val).as_ptr(), ptr);
+    }
 }
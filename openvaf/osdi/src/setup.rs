@@ -1,5 +1,6 @@
 use core::ptr::NonNull;
 
+use hir::Parameter;
 use hir_lower::{CallBackKind, ParamInfoKind, ParamKind, PlaceKind};
 use llvm_sys::core::{
     LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCondBr, LLVMBuildRetVoid,
@@ -10,7 +11,7 @@ use mir::ControlFlowGraph;
 use mir_llvm::{Builder, BuilderVal, CallbackFun, CodegenCx, UNNAMED};
 use sim_back::SimUnknownKind;
 
-use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
+use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit, OsdiModule};
 use crate::inst_data::OsdiInstanceParam;
 
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
@@ -56,6 +57,33 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         (ty, val)
     }
 
+    /// Logs a message that names the parameter and the bound it violated
+    /// (like `Parameter l=1e-07 below minimum 1`).
+    fn param_bound_err(
+        cx: &CodegenCx<'_, 'll>,
+        module: &OsdiModule,
+        handle: &'ll llvm_sys::LLVMValue,
+        param: Parameter,
+        upper: bool,
+        inclusive: bool,
+    ) -> CallbackFun<'ll> {
+        let fun = cx
+            .get_func_by_name("log_param_bound_err")
+            .expect("stdlib function log_param_bound_err is missing");
+        let fun_ty = cx.ty_func(
+            &[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_int(), cx.ty_double(), cx.ty_double()],
+            cx.ty_void(),
+        );
+        let name = cx.const_str_uninterned(&module.info.params[&param].name);
+        let state = vec![
+            handle,
+            name,
+            cx.const_unsigned_int(upper as u32),
+            cx.const_unsigned_int(!inclusive as u32),
+        ];
+        CallbackFun { fun_ty, fun, state: state.into_boxed_slice(), num_state: 0 }
+    }
+
     pub fn setup_model_prototype(&self) -> &'ll llvm_sys::LLVMValue {
         let cx = &self.cx;
         let name = &format!("setup_model_{}", &self.module.sym);
@@ -154,10 +182,12 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 &*simparam
             });
         for (call_id, call) in intern.callbacks.iter_enumerated() {
-            if let CallBackKind::ParamInfo(ParamInfoKind::Invalid, param) = call {
-                if !self.module.info.params[param].is_instance {
+            match *call {
+                CallBackKind::ParamInfo(ParamInfoKind::Invalid, param)
+                    if !self.module.info.params[&param].is_instance =>
+                {
                     let id =
-                        model_data.params.get_index_of(param).unwrap() + inst_data.params.len();
+                        model_data.params.get_index_of(&param).unwrap() + inst_data.params.len();
                     let err_param = cx.const_unsigned_int(id as u32);
                     let cb = CallbackFun {
                         fun_ty: invalid_param_err.0,
@@ -168,6 +198,15 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
                     builder.callbacks[call_id] = Some(cb);
                 }
+                CallBackKind::ParamBoundViolated { param, upper, inclusive }
+                    if !self.module.info.params[&param].is_instance =>
+                {
+                    let handle = unsafe { &*handle };
+                    let cb =
+                        Self::param_bound_err(cx, self.module, handle, param, upper, inclusive);
+                    builder.callbacks[call_id] = Some(cb);
+                }
+                _ => (),
             }
         }
 
@@ -197,6 +236,25 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             }
         }
 
+        // store the abstols that are not constant (see `node_abstol` in the descriptor)
+        for (i, nature) in model_data.abstols.iter().enumerate() {
+            let val = intern.outputs[&PlaceKind::NatureAbstol(*nature)].unwrap_unchecked();
+            let inst = func.dfg.value_def(val).unwrap_inst();
+            let bb = func.layout.inst_block(inst).unwrap();
+            builder.select_bb_before_terminator(bb);
+            unsafe {
+                let val = builder.values[val].get(&builder);
+                model_data.store_nth_abstol(
+                    cx,
+                    inst_data,
+                    i as u32,
+                    &*model,
+                    val,
+                    builder.llbuilder,
+                );
+            }
+        }
+
         builder.select_bb(exit_bb);
         // Debug: Selected exit_bb
         unsafe { builder.ret_void() }
@@ -388,6 +446,14 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         trivial_cb.clone()
                     }
                 }
+                &CallBackKind::ParamBoundViolated { param, upper, inclusive } => {
+                    if inst_data.params.contains_key(&OsdiInstanceParam::User(param)) {
+                        let handle = unsafe { &*handle };
+                        Self::param_bound_err(cx, module, handle, param, upper, inclusive)
+                    } else {
+                        trivial_cb.clone()
+                    }
+                }
                 CallBackKind::CollapseHint(node1, node2) => {
                     let node1 = module
                         .dae_system
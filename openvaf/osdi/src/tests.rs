@@ -16,7 +16,7 @@ fn test_compile(root_file: &Path) {
     let db = CompilationDB::new(root_file, &[], &[], &[]).unwrap();
     let modules = db.collect_modules().unwrap();
     let target = Target::host_target().unwrap();
-    let back = LLVMBackend::new(&[], &target, "native".to_owned(), &[]);
+    let back = LLVMBackend::new(&[], &target, "native".to_owned(), &[]).unwrap();
     let emit = !stdx::IS_CI;
     crate::compile(
         &db,
@@ -25,7 +25,7 @@ fn test_compile(root_file: &Path) {
         &target,
         &back,
         emit,
-        LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
+        &LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive.into(),
     );
 }
 
@@ -9,6 +9,7 @@ extern void *malloc (size_t __size);
 extern void *realloc (void *__ptr, size_t __size);
 extern double log(double);
 extern int strcmp(const char*, const char*);
+extern int snprintf(char *__s, size_t __maxlen, const char *__format, ...);
 #define NULL ((void*)0)
 #else
 #include <math.h>
@@ -17,13 +18,13 @@ extern int strcmp(const char*, const char*);
 #include "string.h"
 #endif
 
-#ifndef OSDI_0_4
-#include "header/osdi_0_4.h"
+#ifndef OSDI_0_5
+#include "header/osdi_0_5.h"
 #endif
 
 // no header was included explicitly so just use the newest version
 #ifndef OSDI_VERSION_MAJOR_CURR
-#include "header/osdi_0_4.h"
+#include "header/osdi_0_5.h"
 #endif
 
 
@@ -42,6 +43,37 @@ char *concat(const char *s1, const char *s2) {
 typedef void (*osdi_log_ptr)(void *handle, char *msg, uint32_t lvl);
 extern osdi_log_ptr osdi_log;
 
+// File IO ($fopen/$fwrite/$fclose) is handled by the simulator. These hooks
+// are optional, file operations are ignored (with a warning) if they are not set.
+typedef int (*osdi_fopen_ptr)(void *handle, char *name, char *mode);
+extern osdi_fopen_ptr osdi_fopen;
+typedef void (*osdi_fwrite_ptr)(void *handle, int fd, char *msg);
+extern osdi_fwrite_ptr osdi_fwrite;
+typedef void (*osdi_fclose_ptr)(void *handle, int fd);
+extern osdi_fclose_ptr osdi_fclose;
+
+int file_open(void *handle, char *name, char *mode) {
+  if (osdi_fopen == NULL) {
+    osdi_log(handle, "$fopen is not supported by the simulator", LOG_LVL_WARN);
+    return 0;
+  }
+  return osdi_fopen(handle, name, mode);
+}
+
+void file_write(void *handle, int fd, char *msg, uint32_t flags) {
+  if (flags & LOG_FMT_ERR) {
+    osdi_log(handle, msg, LOG_LVL_ERR | LOG_FMT_ERR);
+  } else if (osdi_fwrite != NULL) {
+    osdi_fwrite(handle, fd, msg);
+  }
+}
+
+void file_close(void *handle, int fd) {
+  if (osdi_fclose != NULL) {
+    osdi_fclose(handle, fd);
+  }
+}
+
 double simparam(void *params_, void *handle, uint32_t *flags, char *name) {
   OsdiSimParas *params = params_;
   for (int i = 0; params->names[i]; i++) {
@@ -117,6 +149,27 @@ void push_invalid_param_err(void **dst, uint32_t *len, uint32_t *cap,
   push_error((OsdiInitError **)dst, len, cap, err);
 }
 
+void log_param_bound_err(void *handle, char *name, uint32_t upper,
+                         uint32_t exclusive, double val, double bound) {
+  const char *fmt;
+  if (upper) {
+    fmt = exclusive ? "Parameter %s=%g not below exclusive maximum %g"
+                    : "Parameter %s=%g above maximum %g";
+  } else {
+    fmt = exclusive ? "Parameter %s=%g not above exclusive minimum %g"
+                    : "Parameter %s=%g below minimum %g";
+  }
+
+  int len = snprintf(NULL, 0, fmt, name, val, bound);
+  char *msg = len < 0 ? NULL : malloc(len + 1);
+  if (msg == NULL) {
+    osdi_log(handle, "Parameter %s is out of bounds", LOG_LVL_ERR | LOG_FMT_ERR);
+    return;
+  }
+  snprintf(msg, len + 1, fmt, name, val, bound);
+  osdi_log(handle, msg, LOG_LVL_ERR);
+}
+
 void bound_step(double *dst, double val) { *dst = val; }
 
 #define FMT_OFF 6
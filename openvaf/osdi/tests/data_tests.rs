@@ -1,3 +1,4 @@
+use std::fs;
 use std::path::Path;
 
 use camino::Utf8Path;
@@ -6,9 +7,10 @@ use hir::CompilationDB;
 use llvm_sys::target_machine::LLVMCodeGenOptLevel;
 use mini_harness::{harness, Result};
 use mir_llvm::LLVMBackend;
+use osdi::{CodegenParts, OptLevel};
 use paths::AbsPathBuf;
 use sim_back::collect_modules;
-use stdx::{ignore_slow_tests, project_root};
+use stdx::{ignore_slow_tests, openvaf_test_data, project_root};
 use target::spec::Target;
 
 fn test_compile(root_file: &Path) {
@@ -16,7 +18,7 @@ fn test_compile(root_file: &Path) {
     let db = CompilationDB::new_fs(root_file, &[], &[], &[]).unwrap();
     let modules = collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap();
     let target = Target::host_target().unwrap();
-    let back = LLVMBackend::new(&[], &target, "native".to_owned(), &[]);
+    let back = LLVMBackend::new(&[], &target, "native".to_owned(), &[]).unwrap();
     let emit = !stdx::IS_CI;
     osdi::compile(
         &db,
@@ -25,7 +27,10 @@ fn test_compile(root_file: &Path) {
         &target,
         &back,
         emit,
-        LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
+        CodegenParts::All,
+        &LLVMCodeGenOptLevel::LLVMCodeGenLevelNone.into(),
+        &mut ConsoleSink::new(&db),
+        |_, _| (),
     );
 }
 
@@ -37,6 +42,95 @@ fn integration_test(dir: &Path) -> Result {
     Ok(())
 }
 
+fn module_opt_lvl() -> Result {
+    // object files are not emitted in CI
+    if stdx::IS_CI {
+        return Ok(());
+    }
+
+    let root_file = openvaf_test_data("osdi").join("opt_lvl_for.va");
+    let root_file = AbsPathBuf::assert(root_file.canonicalize().unwrap());
+    let db = CompilationDB::new_fs(root_file, &[], &[], &[]).unwrap();
+    let modules = collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap();
+    let target = Target::host_target().unwrap();
+    let back = LLVMBackend::new(&[], &target, "native".to_owned(), &[]).unwrap();
+    let opt_lvl = OptLevel {
+        default: LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
+        modules: vec![("diode_cold".to_owned(), LLVMCodeGenOptLevel::LLVMCodeGenLevelNone)],
+    };
+    let dst = std::env::temp_dir().join("openvaf_opt_lvl_for.o");
+    let objects = osdi::compile(
+        &db,
+        &modules,
+        Utf8Path::from_path(&dst).unwrap(),
+        &target,
+        &back,
+        true,
+        CodegenParts::All,
+        &opt_lvl,
+        &mut ConsoleSink::new(&db),
+        |_, _| (),
+    );
+
+    // both modules are identical so the (unoptimized) eval function of
+    // diode_cold must be larger than the (optimized) one of diode_hot
+    let eval_size = |module: &str| {
+        let i = modules.iter().position(|info| info.module.name(&db) == module).unwrap();
+        fs::metadata(&objects.structural[i * 2 + 1]).unwrap().len()
+    };
+    let hot = eval_size("diode_hot");
+    let cold = eval_size("diode_cold");
+    for path in objects.iter() {
+        fs::remove_file(path).unwrap();
+    }
+    assert!(
+        cold > hot,
+        "expected the -O0 module ({cold} bytes) to be larger than the -O3 module ({hot} bytes)"
+    );
+
+    Ok(())
+}
+
+fn cross_compile() -> Result {
+    // object files are not emitted in CI
+    if stdx::IS_CI {
+        return Ok(());
+    }
+
+    let root_file = project_root().join("integration_tests").join("DIODE").join("diode.va");
+    let root_file = AbsPathBuf::assert(root_file.canonicalize().unwrap());
+    let db = CompilationDB::new_fs(root_file, &[], &[], &[]).unwrap();
+    let modules = collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap();
+    let target = Target::parse("aarch64-unknown-linux-gnu").unwrap();
+    assert_eq!(target, Target::search("aarch64-unknown-linux").unwrap());
+    let back = LLVMBackend::new(&[], &target, "generic".to_owned(), &[]).unwrap();
+    let dst = std::env::temp_dir().join("openvaf_cross_compile.o");
+    let objects = osdi::compile(
+        &db,
+        &modules,
+        Utf8Path::from_path(&dst).unwrap(),
+        &target,
+        &back,
+        true,
+        CodegenParts::All,
+        &LLVMCodeGenOptLevel::LLVMCodeGenLevelNone.into(),
+        &mut ConsoleSink::new(&db),
+        |_, _| (),
+    );
+
+    for path in objects.iter() {
+        let obj = fs::read(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(&obj[..4], b"\x7fELF", "{path} is not an ELF object");
+        // e_machine (little endian) of the ELF header, 183 is EM_AARCH64
+        let machine = u16::from_le_bytes([obj[18], obj[19]]);
+        assert_eq!(machine, 183, "{path} was not compiled for aarch64");
+    }
+
+    Ok(())
+}
+
 harness! {
-    Test::from_dir("integration", &integration_test, &ignore_slow_tests, &project_root().join("integration_tests"))
+    Test::from_dir("integration", &integration_test, &ignore_slow_tests, &project_root().join("integration_tests")),
+    [Test::new("module_opt_lvl", &module_opt_lvl), Test::new("cross_compile", &cross_compile)]
 }
@@ -0,0 +1,68 @@
+use hir::{CompilationDB, Nature};
+use hir_lower::{CallBackKind, HirInterner, PlaceKind};
+use lasso::Rodeo;
+use mir::{strip_optbarrier, Const, Function, Value, ValueDef};
+
+use crate::dae::DaeSystem;
+
+#[cfg(test)]
+mod tests;
+
+/// The absolute tolerance of the unknowns of a nature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Abstol {
+    /// The `abstol` of the nature folds to a constant.
+    Const(f64),
+    /// The `abstol` of the nature is computed by `model_param_setup`,
+    /// for example because it depends on `$simparam`.
+    Setup(Value),
+}
+
+/// Returns the absolute tolerance of the unknowns of `nature` (`None` if the nature has
+/// no `abstol` or if it can not be computed during model setup).
+/// `model_param_setup` is constant folded so this includes constant expressions like `2 * 1e-6`.
+pub fn nature_abstol(
+    model_param_setup: &Function,
+    model_param_intern: &HirInterner,
+    nature: Nature,
+) -> Option<Abstol> {
+    let val = model_param_intern.outputs.get(&PlaceKind::NatureAbstol(nature))?.expand()?;
+    match model_param_setup.dfg.value_def(strip_optbarrier(model_param_setup, val)) {
+        ValueDef::Const(Const::Float(abstol)) => Some(Abstol::Const(abstol.into())),
+        _ => Some(Abstol::Setup(val)),
+    }
+}
+
+/// Collects the natures of the unknowns in `dae_system` that have an `abstol`.
+/// The first list contains the natures whose abstol can be computed during model setup.
+/// The second list contains the natures whose abstol depends on values that are only
+/// known later (like `$temperature`).
+pub(crate) fn abstol_natures(
+    db: &CompilationDB,
+    dae_system: &DaeSystem,
+    literals: &mut Rodeo,
+) -> (Vec<Nature>, Vec<Nature>) {
+    let mut natures = Vec::new();
+    for unknown in dae_system.unknowns.iter() {
+        if let Some(nature) = unknown.nature(db) {
+            if nature.abstol_attr(db).is_some() && !natures.contains(&nature) {
+                natures.push(nature)
+            }
+        }
+    }
+
+    natures.into_iter().partition(|&nature| {
+        let mut func = Function::default();
+        let mut intern = HirInterner::default();
+        intern.insert_nature_abstol(db, &mut func, literals, &[nature]);
+        // natures are declared outside of modules, so the only values
+        // known during model setup are simulator parameters
+        intern.params.is_empty()
+            && intern.callbacks.iter().all(|kind| {
+                matches!(
+                    kind,
+                    CallBackKind::SimParam | CallBackKind::SimParamOpt | CallBackKind::SimParamStr
+                )
+            })
+    })
+}
@@ -0,0 +1,116 @@
+use hir::diagnostics::ConsoleSink;
+use hir::CompilationDB;
+use hir_lower::CallBackKind;
+use lasso::Rodeo;
+
+use crate::{nature_abstol, Abstol, CompiledModule, SimUnknownKind};
+
+const NATURES: &str = r#"
+    `include "disciplines.vams"
+    nature ScaledVoltage
+        units = "V";
+        access = VS;
+        abstol = 2 * 1e-6;
+    endnature
+    nature SimParamVoltage
+        units = "V";
+        access = VP;
+        abstol = $simparam("vntol", 1e-6);
+    endnature
+    nature TempDependent
+        units = "V";
+        access = VT;
+        abstol = $temperature * 1e-9;
+    endnature
+    discipline scaled
+        potential ScaledVoltage;
+        flow Current;
+    enddiscipline
+    discipline simparam
+        potential SimParamVoltage;
+        flow Current;
+    enddiscipline
+    discipline temperature
+        potential TempDependent;
+        flow Current;
+    enddiscipline
+"#;
+
+fn compile(src: &str, check: impl FnOnce(&CompilationDB, &CompiledModule)) {
+    let src = format!("{NATURES}{src}");
+    let db = CompilationDB::new_virtual(&src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    check(&db, &module)
+}
+
+fn abstol(db: &CompilationDB, module: &CompiledModule, node: &str) -> Option<Abstol> {
+    let nature = module
+        .dae_system
+        .unknowns
+        .iter()
+        .find(|unknown| matches!(unknown, SimUnknownKind::KirchoffLaw(hi) if hi.name(db) == node))
+        .unwrap()
+        .nature(db)
+        .unwrap();
+    nature_abstol(&module.model_param_setup, &module.model_param_intern, nature)
+}
+
+#[test]
+fn const_abstol() {
+    let src = r#"
+        module consts(inout a, inout c, inout s);
+            electrical a, c;
+            scaled s;
+            analog begin
+                I(a, c) <+ V(a, c);
+                I(s) <+ VS(s);
+            end
+        endmodule
+    "#;
+    compile(src, |db, module| {
+        assert_eq!(abstol(db, module, "a"), Some(Abstol::Const(1e-6)));
+        // constant expressions are folded
+        assert_eq!(abstol(db, module, "s"), Some(Abstol::Const(2.0 * 1e-6)));
+        assert!(module.ignored_abstols.is_empty());
+    });
+}
+
+#[test]
+fn setup_abstol() {
+    let src = r#"
+        module setup(inout p);
+            simparam p;
+            analog I(p) <+ VP(p);
+        endmodule
+    "#;
+    compile(src, |db, module| {
+        let val = match abstol(db, module, "p") {
+            Some(Abstol::Setup(val)) => val,
+            abstol => panic!("abstol is {abstol:?}"),
+        };
+        // the abstol is computed by the model setup function
+        let func = &module.model_param_setup;
+        let inst = func.dfg.value_def(val).unwrap_inst();
+        assert!(func.layout.inst_block(inst).is_some());
+        assert!(module.model_param_intern.callbacks.contains(&CallBackKind::SimParamOpt));
+        assert!(module.ignored_abstols.is_empty());
+    });
+}
+
+#[test]
+fn ignored_abstol() {
+    let src = r#"
+        module ignored(inout t);
+            temperature t;
+            analog I(t) <+ VT(t);
+        endmodule
+    "#;
+    compile(src, |db, module| {
+        assert_eq!(abstol(db, module, "t"), None);
+        assert_eq!(module.ignored_abstols.len(), 1);
+        assert_eq!(module.ignored_abstols[0].name(db), "TempDependent");
+    });
+}
@@ -4,13 +4,13 @@ use hir_lower::{HirInterner, MirBuilder, PlaceKind};
 use lasso::Rodeo;
 use mir::{Block, ControlFlowGraph, DominatorTree, Function, Inst, Value};
 use mir_opt::{
-    aggressive_dead_code_elimination, dead_code_elimination, inst_combine, propagate_direct_taint,
-    propagate_taint, simplify_cfg, simplify_cfg_no_phi_merge,
-    sparse_conditional_constant_propagation, GVN,
+    aggressive_dead_code_elimination, dead_code_elimination, inst_combine, merge_identical_blocks,
+    propagate_conditions, propagate_direct_taint, propagate_taint, reassociate, simplify_cfg,
+    simplify_cfg_no_phi_merge, sink_instructions, sparse_conditional_constant_propagation, GVN,
 };
 use stdx::packed_option::PackedOption;
 
-use crate::ModuleInfo;
+use crate::{ModuleInfo, Scratch};
 
 pub(crate) struct Context<'a> {
     pub(crate) func: Function,
@@ -22,6 +22,7 @@ pub(crate) struct Context<'a> {
     pub(crate) output_values: BitSet<Value>,
     pub(crate) op_dependent_insts: BitSet<Inst>,
     pub(crate) op_dependent_vals: Vec<Value>,
+    pub(crate) scratch: Scratch,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -48,6 +49,7 @@ impl<'a> Context<'a> {
         )
         .with_equations()
         .with_tagged_writes()
+        .with_collapse_resistance(module.collapse_resistance)
         .build(literals);
         // TODO hidden state
         intern.insert_var_init(db, &mut func, literals);
@@ -62,6 +64,7 @@ impl<'a> Context<'a> {
             module,
             op_dependent_insts: BitSet::new_empty(0),
             op_dependent_vals: Vec::new(),
+            scratch: Scratch::default(),
         }
     }
 
@@ -71,17 +74,20 @@ impl<'a> Context<'a> {
         }
         sparse_conditional_constant_propagation(&mut self.func, &self.cfg);
         inst_combine(&mut self.func);
-        if stage == OptimiziationStage::Final {
-            simplify_cfg(&mut self.func, &mut self.cfg);
-        } else {
-            simplify_cfg_no_phi_merge(&mut self.func, &mut self.cfg);
-        }
+        self.simplify_cfg(&stage);
+        merge_identical_blocks(&mut self.func, &mut self.cfg);
         self.compute_domtree(true, true, false);
+        if propagate_conditions(&mut self.func, &self.cfg, &self.dom_tree) {
+            self.simplify_cfg(&stage);
+            self.compute_domtree(true, true, false);
+        }
 
         let mut gvn = GVN::default();
         gvn.init(&self.func, &self.dom_tree, self.intern.params.len() as u32);
         gvn.solve(&mut self.func);
         gvn.remove_unnecessary_insts(&mut self.func, &self.dom_tree);
+        // only changes the (fast-math) instructions generated by autodiff
+        reassociate(&mut self.func);
 
         if stage == OptimiziationStage::Final {
             let mut control_dep = SparseBitMatrix::new_square(0);
@@ -98,6 +104,23 @@ impl<'a> Context<'a> {
         gvn
     }
 
+    fn simplify_cfg(&mut self, stage: &OptimiziationStage) {
+        if *stage == OptimiziationStage::Final {
+            simplify_cfg(&mut self.func, &mut self.cfg);
+        } else {
+            simplify_cfg_no_phi_merge(&mut self.func, &mut self.cfg);
+        }
+    }
+
+    /// Moves instructions into the branches that actually use them. This must only run after
+    /// the initialization was split off: an operating point independent instruction that is
+    /// sunk below an operating point dependent branch could no longer be cached.
+    pub fn sink_instructions(&mut self) {
+        self.compute_cfg();
+        self.compute_domtree(true, false, false);
+        sink_instructions(&mut self.func, &self.cfg, &self.dom_tree);
+    }
+
     pub fn compute_cfg(&mut self) {
         self.cfg.compute(&self.func);
     }
@@ -155,6 +178,15 @@ impl<'a> Context<'a> {
         )
     }
 
+    /// Removes `inst` from `op_dependent_insts` after it was removed from the function.
+    /// Removing an instruction whose results are unused doesn't change whether any other
+    /// instruction is operating point dependent, so the set remains identical to the result
+    /// of [`Context::refresh_op_dependent_insts`] without recomputing it.
+    pub fn remove_op_dependent_inst(&mut self, inst: Inst) {
+        debug_assert!(self.func.layout.inst_block(inst).is_none());
+        self.op_dependent_insts.remove(inst);
+    }
+
     pub fn refresh_op_dependent_insts(&mut self) {
         let dfg = &mut self.func.dfg;
         self.op_dependent_vals.clear();
@@ -0,0 +1,70 @@
+//! Tracks what is currently being compiled on each thread so that a crash
+//! report can name the model (and the compilation phase) that triggered a panic.
+
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    static CURRENT: RefCell<CrashContext> = RefCell::new(CrashContext::default());
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrashContext {
+    pub file: Option<String>,
+    pub module: Option<String>,
+    pub phase: Option<&'static str>,
+}
+
+impl CrashContext {
+    /// Returns the compilation context of the current thread.
+    /// This is called from a panic hook and therefore never panics.
+    pub fn current() -> CrashContext {
+        CURRENT
+            .try_with(|cx| cx.try_borrow().map(|cx| cx.clone()).unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    pub fn set_file(file: impl Into<String>) {
+        Self::update(|cx| cx.file = Some(file.into()))
+    }
+
+    pub fn set_module(module: impl Into<String>) {
+        Self::update(|cx| cx.module = Some(module.into()))
+    }
+
+    pub fn set_phase(phase: &'static str) {
+        Self::update(|cx| cx.phase = Some(phase))
+    }
+
+    /// Makes `self` the context of the current thread until the returned guard is dropped.
+    /// This is used to forward the context to worker threads.
+    pub fn enter(self) -> CrashContextGuard {
+        let prev = CURRENT.with(|cx| cx.replace(self));
+        CrashContextGuard { prev }
+    }
+
+    fn update(f: impl FnOnce(&mut CrashContext)) {
+        CURRENT.with(|cx| f(&mut cx.borrow_mut()))
+    }
+}
+
+impl fmt::Display for CrashContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = "<unknown>";
+        writeln!(f, "file: {}", self.file.as_deref().unwrap_or(unknown))?;
+        writeln!(f, "module: {}", self.module.as_deref().unwrap_or(unknown))?;
+        writeln!(f, "phase: {}", self.phase.unwrap_or(unknown))
+    }
+}
+
+#[must_use]
+pub struct CrashContextGuard {
+    prev: CrashContext,
+}
+
+impl Drop for CrashContextGuard {
+    fn drop(&mut self) {
+        let prev = std::mem::take(&mut self.prev);
+        let _ = CURRENT.try_with(|cx| cx.replace(prev));
+    }
+}
@@ -1,15 +1,23 @@
-use indexmap::IndexSet;
-use mir::{strip_optbarrier, Value, F_ZERO};
+use bitset::SparseBitMatrix;
+use hir::{Node, Parameter};
+use hir_lower::CurrentKind;
+use indexmap::{IndexMap, IndexSet};
+use mir::{strip_optbarrier, ControlFlowGraph, DominatorTree, Function, Value, F_ZERO};
+use mir_opt::aggressive_dead_code_elimination;
 use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiVec;
 use typed_indexmap::TiSet;
 
 use crate::context::Context;
 use crate::dae::builder::Builder;
+use crate::dae::series_voltage_srcs::series_voltage_srcs;
 pub use crate::noise::{NoiseSource, NoiseSourceKind};
+use crate::util::is_op_dependent;
 use crate::{topology, SimUnknownKind};
 
 mod builder;
+mod latex;
+mod series_voltage_srcs;
 #[cfg(test)]
 mod tests;
 
@@ -45,18 +53,57 @@ pub struct DaeSystem {
     pub noise_sources: Vec<NoiseSource>,
     /// model inputs (node pairs)
     pub model_inputs: Vec<(u32, u32)>,
-    /// Jacobian entry counts
+    /// The number of jacobian entries with a resistive/reactive part. Determined after
+    /// optimization so that entries whose reactive part folds to zero don't receive a
+    /// reactive matrix pointer.
     pub num_resistive: u32,
     pub num_reactive: u32,
+    /// Whether the jacobian is independent of the operating point, i.e. every
+    /// entry only depends on parameters. Simulators may skip reloading the
+    /// matrix for such (linear) modules.
+    pub is_linear: bool,
+    /// Derivatives of the residual with respect to the parameters listed in
+    /// [`ModuleInfo::sensitivity_params`](crate::ModuleInfo::sensitivity_params).
+    /// Entries are ordered by row and then by the order in which the parameters
+    /// were requested. Entries where both parts are zero are omitted.
+    pub param_sensitivities: Vec<ParamSensitivity>,
+    /// Branch currents that are probed but not defined by any branch (neither by
+    /// a source equation nor by a contribution). These probes always read zero.
+    pub orphaned_current_probes: Vec<CurrentKind>,
+    /// Internal nodes that only receive reactive (`ddt`) contributions. These nodes
+    /// have no conductance during DC analysis which often prevents convergence.
+    pub reactive_only_nodes: Vec<Node>,
+    /// Internal nodes that receive contributions which cancel out (like
+    /// `I(a, m) <+ x; I(a, m) <+ -x;`). Their residual is zero so the node floats,
+    /// unlike unused nodes these nodes do have contributions which is usually a mistake.
+    pub cancelled_nodes: Vec<Node>,
+    /// Branches whose contribution only depends on the probed quantity of the branch
+    /// itself (like `I(a, c) <+ I(a, c)`). A branch that probes its own current or
+    /// voltage is usually solved with an additional equation, but for these branches
+    /// the equation cancels out which leaves a singular jacobian.
+    pub algebraic_loops: Vec<CurrentKind>,
+    /// Nodes whose Kirchhoff law has a resistive jacobian entry that depends on the
+    /// operating point (for example because a diode is connected to the node). Adding
+    /// a small conductance (gmin) from these nodes to ground and gradually removing it
+    /// (gmin stepping) helps the simulator to find the DC operating point.
+    pub gmin_nodes: Vec<SimUnknown>,
 }
 
 impl DaeSystem {
     pub(crate) fn new(ctx: &mut Context, contributions: topology::Topology) -> DaeSystem {
-        let mut builder =
-            Builder::new(ctx).with_small_signal_network(contributions.small_signal_vals);
+        let series_srcs = series_voltage_srcs(ctx, &contributions);
+        let mut builder = Builder::new(ctx, &series_srcs)
+            .with_small_signal_network(contributions.small_signal_vals);
 
-        for (branch, contributions) in contributions.branches.raw {
-            builder.build_branch(branch, &contributions)
+        for (&branch, info) in contributions.branches.iter() {
+            let is_merged =
+                series_srcs.iter().any(|series| series.srcs.iter().any(|&(src, _)| src == branch));
+            if !is_merged {
+                builder.build_branch(branch, info)
+            }
+        }
+        for series in &series_srcs {
+            builder.build_series_voltage_srcs(series, &contributions.branches)
         }
         for (eq, contributions) in contributions.implicit_equations.iter_enumerated() {
             builder.build_implicit_equation(eq, contributions)
@@ -65,6 +112,9 @@ impl DaeSystem {
     }
 
     pub(super) fn sparsify(&mut self, ctx: &mut Context) {
+        // the removed instructions are dropped from the op dependent instructions
+        // one by one so the set doesn't need to be recomputed afterwards
+        ctx.refresh_op_dependent_insts();
         let mut sparsify = |val| {
             let stripped = strip_optbarrier(&ctx.func, val);
             if ctx.func.dfg.value_def(stripped).inst().is_some() {
@@ -75,13 +125,19 @@ impl DaeSystem {
                     if ctx.func.dfg.instr_safe_to_remove(inst) {
                         ctx.func.dfg.zap_inst(inst);
                         ctx.func.layout.remove_inst(inst);
+                        ctx.remove_op_dependent_inst(inst);
                     }
                 }
                 stripped
             }
         };
-        for residual in &mut self.residual {
-            residual.map_vals(&mut sparsify)
+        let mut cancelled = Vec::new();
+        for (unknown, residual) in self.residual.iter_mut_enumerated() {
+            let has_contributions = !residual.is_small_signal();
+            residual.map_vals(&mut sparsify);
+            if has_contributions && residual.is_small_signal() {
+                cancelled.push(unknown);
+            }
         }
 
         self.noise_sources.retain_mut(|noise_src| {
@@ -101,10 +157,179 @@ impl DaeSystem {
             matrix_entry.resist = sparsify(matrix_entry.resist);
             matrix_entry.react = sparsify(matrix_entry.react);
             matrix_entry.resist != F_ZERO || matrix_entry.react != F_ZERO
-        })
+        });
+        self.num_resistive =
+            self.jacobian.iter().filter(|entry| entry.resist != F_ZERO).count() as u32;
+        self.num_reactive =
+            self.jacobian.iter().filter(|entry| entry.react != F_ZERO).count() as u32;
+
+        self.param_sensitivities.retain_mut(|sensitivity| {
+            sensitivity.resist = sparsify(sensitivity.resist);
+            sensitivity.react = sparsify(sensitivity.react);
+            sensitivity.resist != F_ZERO || sensitivity.react != F_ZERO
+        });
+
+        self.find_reactive_only_nodes(ctx);
+        self.find_cancelled_nodes(ctx, &cancelled);
+        self.find_algebraic_loops();
+
+        let op_dependent =
+            |val| is_op_dependent(&ctx.func, val, &ctx.op_dependent_insts, &ctx.intern);
+        self.is_linear = self
+            .jacobian
+            .iter()
+            .all(|entry| !op_dependent(entry.resist) && !op_dependent(entry.react));
+        self.gmin_nodes = self
+            .unknowns
+            .iter_enumerated()
+            .filter(|(unknown, kind)| {
+                matches!(kind, SimUnknownKind::KirchoffLaw(_))
+                    && self
+                        .jacobian
+                        .iter()
+                        .any(|entry| entry.row == *unknown && op_dependent(entry.resist))
+            })
+            .map(|(unknown, _)| unknown)
+            .collect();
+    }
+
+    /// Finds internal nodes whose row of the jacobian only has reactive entries.
+    /// Must be called after zero entries are removed from the jacobian. Ports are
+    /// ignored since the circuit they are connected to may provide a DC path.
+    fn find_reactive_only_nodes(&mut self, ctx: &Context) {
+        self.reactive_only_nodes.clear();
+        for (unknown, kind) in self.unknowns.iter_enumerated() {
+            let node = match *kind {
+                SimUnknownKind::KirchoffLaw(node) if !node.is_port(ctx.db) => node,
+                _ => continue,
+            };
+            let mut row = self.jacobian.iter().filter(|entry| entry.row == unknown).peekable();
+            // nodes without any entries are floating and not reactive
+            if row.peek().is_none() {
+                continue;
+            }
+            if row.all(|entry| entry.resist == F_ZERO) {
+                self.reactive_only_nodes.push(node);
+            }
+        }
+    }
+
+    /// Records the internal nodes among `cancelled` (unknowns whose residual only folded
+    /// to zero during the final optimization). Nodes whose contributions already cancelled
+    /// out before the system was built are recorded when unused nodes are removed.
+    fn find_cancelled_nodes(&mut self, ctx: &Context, cancelled: &[SimUnknown]) {
+        for &unknown in cancelled {
+            if let SimUnknownKind::KirchoffLaw(node) = self.unknowns[unknown] {
+                if !node.is_port(ctx.db) {
+                    self.cancelled_nodes.push(node);
+                }
+            }
+        }
+    }
+
+    /// Finds the rows of the jacobian that provide no conductance during DC analysis
+    /// because they are empty or only have reactive entries. Unlike
+    /// [`reactive_only_nodes`](DaeSystem::reactive_only_nodes) all unknowns (including
+    /// ports, branch currents and implicit equations) are considered, since this is meant
+    /// to help finding the cause of a singular matrix. Must be called after zero entries
+    /// are removed from the jacobian.
+    pub fn weak_rows(&self) -> Vec<(SimUnknown, WeakRow)> {
+        let mut res = Vec::new();
+        for (unknown, _) in self.unknowns.iter_enumerated() {
+            let mut row = self.jacobian.iter().filter(|entry| entry.row == unknown).peekable();
+            if row.peek().is_none() {
+                res.push((unknown, WeakRow::Empty))
+            } else if row.all(|entry| entry.resist == F_ZERO) {
+                res.push((unknown, WeakRow::ReactiveOnly))
+            }
+        }
+        res
+    }
+
+    /// Finds branch equations without any jacobian entries. The equation of a branch
+    /// is `contribution - probe = 0` so the row is only empty if the contribution is
+    /// the probe itself. Must be called after zero entries are removed from the jacobian.
+    fn find_algebraic_loops(&mut self) {
+        self.algebraic_loops.clear();
+        for (unknown, kind) in self.unknowns.iter_enumerated() {
+            if let SimUnknownKind::Current(current) = *kind {
+                if self.jacobian.iter().all(|entry| entry.row != unknown) {
+                    self.algebraic_loops.push(current);
+                }
+            }
+        }
+    }
+
+    /// Groups the noise sources by the pair of unknowns (`hi`, `lo`) they are stamped into,
+    /// so the spectral densities of each matrix location can be accumulated in one pass.
+    /// Every group contains the indices of its sources in [`noise_sources`](Self::noise_sources)
+    /// in ascending order and the groups are ordered by their first source. Correlated noise is
+    /// represented by separate unknowns, so correlated sources always remain distinguishable.
+    pub fn noise_sources_by_location(
+        &self,
+    ) -> IndexMap<(SimUnknown, Option<SimUnknown>), Vec<usize>, ahash::RandomState> {
+        let mut groups: IndexMap<_, Vec<_>, ahash::RandomState> = IndexMap::default();
+        for (i, src) in self.noise_sources.iter().enumerate() {
+            groups.entry((src.hi, src.lo)).or_default().push(i);
+        }
+        groups
+    }
+
+    /// Creates a copy of the eval function that only computes the residual.
+    /// All instructions that are only required to compute the jacobian (or the
+    /// limit rhs and parameter sensitivities, which are derived from derivatives
+    /// too) are removed. Value numbers are identical in both functions.
+    pub(crate) fn residual_eval(&self, ctx: &Context) -> Function {
+        let mut live = ctx.output_values.clone();
+        let jacobian = self.jacobian.iter().flat_map(|entry| [entry.resist, entry.react]);
+        let sensitivities =
+            self.param_sensitivities.iter().flat_map(|entry| [entry.resist, entry.react]);
+        let lim_rhs = self
+            .residual
+            .iter()
+            .flat_map(|residual| [residual.resist_lim_rhs, residual.react_lim_rhs]);
+        for val in jacobian.chain(sensitivities).chain(lim_rhs) {
+            live.remove(val);
+        }
+        // a value may be both a residual and a jacobian entry (for example for linear equations)
+        for residual in &self.residual {
+            let mut residual = *residual;
+            residual.resist_lim_rhs = F_ZERO;
+            residual.react_lim_rhs = F_ZERO;
+            residual.map_vals(|val| {
+                live.insert(val);
+                val
+            });
+        }
+
+        let mut func = ctx.func.clone();
+        let mut cfg = ControlFlowGraph::new();
+        cfg.compute(&func);
+        let mut dom_tree = DominatorTree::default();
+        dom_tree.compute(&func, &cfg, true, true, false);
+        let mut control_dep = SparseBitMatrix::new_square(0);
+        dom_tree.compute_postdom_frontiers(&cfg, &mut control_dep);
+        // the cfg is not simplified so that the exit block remains the last block
+        aggressive_dead_code_elimination(
+            &mut func,
+            &mut cfg,
+            &|val, _| live.contains(val),
+            &control_dep,
+        );
+        func
     }
 }
 
+/// A row of the jacobian without any conductance during DC analysis,
+/// see [`DaeSystem::weak_rows`].
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum WeakRow {
+    /// The row has no entries, the residual is constant.
+    Empty,
+    /// The row only has reactive entries.
+    ReactiveOnly,
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub struct Residual {
     /// The resistive part (I) of the DAE cost function
@@ -215,6 +440,16 @@ pub struct MatrixEntry {
     pub react: Value,
 }
 
+/// The derivative of a residual with respect to a parameter:
+/// (ddx(I_row, param), ddx(Q_row, param))
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct ParamSensitivity {
+    pub row: SimUnknown,
+    pub param: Parameter,
+    pub resist: Value,
+    pub react: Value,
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct MatrixEntryId(u32);
 impl_idx_from!(MatrixEntryId(u32));
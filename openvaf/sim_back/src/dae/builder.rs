@@ -1,10 +1,9 @@
-use std::mem::replace;
-use std::vec;
+use std::mem::{replace, take};
 
 use ahash::AHashMap;
 use bitset::BitSet;
-use hir::{BranchWrite, CompilationDB, Node, ParamSysFun};
-use hir_lower::{CurrentKind, HirInterner, ImplicitEquation, ParamKind};
+use hir::{BranchWrite, CompilationDB, Node, ParamSysFun, Parameter};
+use hir_lower::{CallBackKind, CurrentKind, HirInterner, ImplicitEquation, ParamKind};
 use indexmap::IndexSet;
 use mir::builder::InstBuilder;
 use mir::cursor::{Cursor, FuncCursor};
@@ -14,13 +13,15 @@ use mir::{
 };
 use mir_autodiff::auto_diff;
 use typed_index_collections::TiVec;
+use typed_indexmap::TiMap;
 
 use crate::context::Context;
-use crate::dae::{DaeSystem, MatrixEntry, Residual, SimUnknown};
+use crate::dae::series_voltage_srcs::SeriesVoltageSrcs;
+use crate::dae::{DaeSystem, MatrixEntry, ParamSensitivity, Residual, SimUnknown};
 use crate::noise::NoiseSource;
-use crate::topology::{BranchInfo, Contribution};
+use crate::topology::{BranchId, BranchInfo, Contribution};
 use crate::util::{add, is_op_dependent, update_optbarrier};
-use crate::SimUnknownKind;
+use crate::{ReactiveFormulation, Scratch, SimUnknownKind};
 
 impl Residual {
     fn add(&mut self, cursor: &mut FuncCursor, negate: bool, mut val: Value) {
@@ -62,10 +63,16 @@ pub(super) struct Builder<'a> {
     pub(super) dom_tree: &'a mut DominatorTree,
     pub(super) op_dependent_insts: &'a BitSet<Inst>,
     pub(super) output_values: &'a mut BitSet<Value>,
+    pub(super) scratch: &'a mut Scratch,
+    pub(super) sensitivity_params: &'a [Parameter],
+    pub(super) reference_conductance: Option<f64>,
+    pub(super) reactive_formulation: ReactiveFormulation,
+    /// Nodes that are connected to a branch something is contributed to.
+    pub(super) contributed_nodes: Vec<Node>,
 }
 
 impl<'a> Builder<'a> {
-    pub(super) fn new(ctx: &'a mut Context) -> Self {
+    pub(super) fn new(ctx: &'a mut Context, series_srcs: &[SeriesVoltageSrcs]) -> Self {
         ctx.compute_outputs(false);
         let mut builder = Self {
             system: DaeSystem::default(),
@@ -76,6 +83,11 @@ impl<'a> Builder<'a> {
             dom_tree: &mut ctx.dom_tree,
             op_dependent_insts: &ctx.op_dependent_insts,
             output_values: &mut ctx.output_values,
+            scratch: &mut ctx.scratch,
+            sensitivity_params: &ctx.module.sensitivity_params,
+            reference_conductance: ctx.module.reference_conductance,
+            reactive_formulation: ctx.module.reactive_formulation,
+            contributed_nodes: Vec::new(),
         };
 
         // ensure ports are the first unknowns and always have an unknown
@@ -84,7 +96,14 @@ impl<'a> Builder<'a> {
         }
 
         for node in ctx.module.module.internal_nodes(builder.db) {
-            builder.build_node(node)
+            // aliases share the unknown of the node they refer to
+            if node.alias_target(builder.db).is_some() {
+                continue;
+            }
+            // nodes between two voltage sources in series are eliminated
+            if series_srcs.iter().all(|series| series.node != node) {
+                builder.build_node(node)
+            }
         }
 
         builder
@@ -92,9 +111,13 @@ impl<'a> Builder<'a> {
 
     pub(super) fn finish(mut self) -> DaeSystem {
         let sim_unknown_reads = self.sim_unknown_reads();
-        let derivative_info = self.intern.unknowns(&self.cursor, true);
-        let extra_derivatives = self
+        self.check_current_probes(&sim_unknown_reads);
+        self.remove_unused_nodes(&sim_unknown_reads);
+        let mut derivative_info = self.intern.unknowns(&self.cursor, true);
+        let sensitivity_unknowns = self.sensitivity_unknowns(&mut derivative_info);
+        let mut extra_derivatives = self
             .jacobian_derivatives(sim_unknown_reads.iter().map(|&(_, val)| val), &derivative_info);
+        extra_derivatives.extend(self.sensitivity_derivatives(&sensitivity_unknowns));
         // TODO(pref): incrementially update dom_tree (for switch branches) instead
         self.dom_tree.compute(self.cursor.func, self.cfg, true, false, true);
         let derivatives =
@@ -104,20 +127,37 @@ impl<'a> Builder<'a> {
         self.cursor.goto_exit();
 
         self.build_jacobian(&sim_unknown_reads, &derivative_info, &derivatives);
+        self.build_sensitivities(&sensitivity_unknowns, &derivatives);
+        let capacitance_residual = match self.reactive_formulation {
+            ReactiveFormulation::Charge => None,
+            ReactiveFormulation::Capacitance => {
+                Some(self.capacitance_residual(&sim_unknown_reads, &derivative_info, &derivatives))
+            }
+        };
+        // the limit rhs is computed from the derivatives of the original residual
         self.build_lim_rhs(&derivative_info, derivatives);
+        if let Some(capacitance_residual) = capacitance_residual {
+            for (residual, react) in self.system.residual.iter_mut().zip(capacitance_residual) {
+                residual.react = react;
+            }
+        }
         self.ensure_optbarriers();
 
         self.build_input_unknown_pairs();
 
-        let (nres, nreact) = self.count_jacobian_entries();
-        self.system.num_resistive = nres;
-        self.system.num_reactive = nreact;
-
         self.system
     }
 
     pub(super) fn build_node(&mut self, node: Node) {
-        self.ensure_unknown(SimUnknownKind::KirchoffLaw(node));
+        if let Some(unknown) = self.kirchoff_law(node) {
+            self.ensure_unknown(unknown);
+        }
+    }
+
+    /// The unknown of the Kirchhoff law of `node`. Ground is the reference node
+    /// and has no unknown, contributions to ground are absorbed by the reference.
+    fn kirchoff_law(&self, node: Node) -> Option<SimUnknownKind> {
+        (!node.is_gnd(self.db)).then_some(SimUnknownKind::KirchoffLaw(node))
     }
 
     pub(super) fn with_small_signal_network(
@@ -154,6 +194,90 @@ impl<'a> Builder<'a> {
             .collect()
     }
 
+    /// Every probed branch current must be an unknown of the system (created by
+    /// [`add_source_equation`](Self::add_source_equation)). A probe of a branch that
+    /// no branch defines would otherwise only appear as a column of the matrix
+    /// without a corresponding row. These probes are recorded so they can be
+    /// reported to the user.
+    fn check_current_probes(&mut self, sim_unknown_reads: &[(ParamKind, Value)]) {
+        for &(kind, _) in sim_unknown_reads {
+            let current = match kind {
+                ParamKind::Current(CurrentKind::Port(_)) => continue,
+                ParamKind::Current(current) => current,
+                _ => continue,
+            };
+            if self.system.unknowns.index(&SimUnknownKind::Current(current)).is_none() {
+                self.system.orphaned_current_probes.push(current);
+            }
+        }
+    }
+
+    /// Removes the unknowns of internal nodes that nothing is contributed to, their
+    /// residual is zero so they would only add a row of zeros to the jacobian.
+    /// Nodes that are still referenced (by a voltage probe, a noise source or a
+    /// collapse hint) are kept. Ports are always kept since they connect the module
+    /// to the circuit. Nodes that are connected to a branch with contributions only
+    /// end up here if the contributions cancel out, these are recorded in
+    /// [`DaeSystem::cancelled_nodes`]. Must be called before the jacobian is built
+    /// because the remaining unknowns are renumbered.
+    fn remove_unused_nodes(&mut self, sim_unknown_reads: &[(ParamKind, Value)]) {
+        let is_used = |unknown: SimUnknown, node: Node| {
+            let probed = sim_unknown_reads.iter().any(|&(kind, _)| {
+                matches!(kind, ParamKind::Voltage { hi, lo } if hi == node || lo == Some(node))
+            });
+            let noisy = self
+                .system
+                .noise_sources
+                .iter()
+                .any(|src| src.hi == unknown || src.lo == Some(unknown));
+            let collapsed = self.intern.callbacks.iter().any(|kind| match *kind {
+                CallBackKind::CollapseHint(hi, lo) => hi == node || lo == Some(node),
+                _ => false,
+            });
+            probed || noisy || collapsed
+        };
+        let unused: Vec<SimUnknown> = self
+            .system
+            .unknowns
+            .iter_enumerated()
+            .filter_map(|(unknown, kind)| match *kind {
+                SimUnknownKind::KirchoffLaw(node)
+                    if !node.is_port(self.db)
+                        && self.system.residual[unknown].is_trivial()
+                        && !is_used(unknown, node) =>
+                {
+                    Some(unknown)
+                }
+                _ => None,
+            })
+            .collect();
+        if unused.is_empty() {
+            return;
+        }
+
+        let kinds: TiVec<SimUnknown, SimUnknownKind> =
+            self.system.unknowns.iter().copied().collect();
+        for &unknown in &unused {
+            if let SimUnknownKind::KirchoffLaw(node) = kinds[unknown] {
+                if self.contributed_nodes.contains(&node) {
+                    self.system.cancelled_nodes.push(node);
+                }
+            }
+        }
+        self.system.unknowns.retain(|unknown, _| !unused.contains(&unknown));
+        self.system.residual = take(&mut self.system.residual)
+            .into_iter_enumerated()
+            .filter(|(unknown, _)| !unused.contains(unknown))
+            .map(|(_, residual)| residual)
+            .collect();
+        let unknowns = &self.system.unknowns;
+        let remap = |unknown: SimUnknown| unknowns.unwrap_index(&kinds[unknown]);
+        for src in &mut self.system.noise_sources {
+            src.hi = remap(src.hi);
+            src.lo = src.lo.map(remap);
+        }
+    }
+
     // Create a list of input node pairs corresponding to all model inputs
     fn build_input_unknown_pairs(&mut self) {
         self.system.model_inputs.clear();
@@ -200,20 +324,45 @@ impl<'a> Builder<'a> {
         }
     }
 
-    fn count_jacobian_entries(&mut self) -> (u32, u32) {
-        // Count resistive and reactive Jacobian entries
-        let mut nres: u32 = 0;
-        let mut nreact: u32 = 0;
-        for key in self.system.jacobian.keys() {
-            if self.system.jacobian[key].resist != F_ZERO {
-                nres = nres + 1;
-            }
+    /// Computes the reactive residual of the capacitance formulation
+    /// (see [`ReactiveFormulation::Capacitance`]): `sum_j C_ij x_j` with `C_ij = ddx(Q_i, x_j)`.
+    /// Every probe is a linear combination of the unknowns (`V(hi, lo) = x_hi - x_lo`),
+    /// so the sum is equivalent to summing the derivative with respect to each probe
+    /// multiplied by the value of the probe.
+    fn capacitance_residual(
+        &mut self,
+        sim_unknown_reads: &[(ParamKind, Value)],
+        derivative_info: &KnownDerivatives,
+        derivatives: &AHashMap<(Value, Unknown), Value>,
+    ) -> Vec<Value> {
+        let mut res = Vec::with_capacity(self.system.residual.len());
+        for residual in &self.system.residual {
+            let mut react = F_ZERO;
+            for &(_, probe) in sim_unknown_reads {
+                // same as the reactive jacobian: limited probes are derived by the limited value
+                let mut capacitance = F_ZERO;
+                let mut add_ddx = |val, negate| {
+                    if let Some(unknown) = derivative_info.unknowns.index(&val) {
+                        if let Some(&ddx) = derivatives.get(&(residual.react, unknown)) {
+                            add(&mut self.cursor, &mut capacitance, ddx, negate)
+                        }
+                    }
+                };
+                if let Some(lim_vals) = self.intern.lim_state.raw.get(&probe) {
+                    for &(val, negate) in lim_vals {
+                        add_ddx(val, negate)
+                    }
+                }
+                add_ddx(probe, false);
 
-            if self.system.jacobian[key].react != F_ZERO {
-                nreact = nreact + 1;
+                if capacitance != F_ZERO {
+                    let charge = self.cursor.ins().fmul(capacitance, probe);
+                    add(&mut self.cursor, &mut react, charge, false);
+                }
             }
+            res.push(react);
         }
-        (nres, nreact)
+        res
     }
 
     fn build_lim_rhs(
@@ -278,7 +427,7 @@ impl<'a> Builder<'a> {
             TiVec::with_capacity(self.system.unknowns.len() * self.system.unknowns.len());
 
         //  construct the matrix by creating a dense row and then sparsifying
-        let mut dense_row = TiVec::from(vec![(F_ZERO, F_ZERO); self.system.unknowns.len()]);
+        let mut dense_row = self.scratch.take_dense_row(self.system.unknowns.len());
         let mut add = |matrix_entry: &mut Value, residual, unknown, negate| {
             if let Some(ddx) = derivatives.get(&(residual, unknown)).copied() {
                 add(&mut self.cursor, matrix_entry, ddx, negate)
@@ -348,6 +497,64 @@ impl<'a> Builder<'a> {
                 });
             }
         }
+        self.scratch.return_dense_row(dense_row);
+    }
+
+    /// Registers the parameters whose sensitivities were requested as unknowns
+    /// so that `auto_diff` can compute derivatives with respect to them.
+    fn sensitivity_unknowns(
+        &self,
+        derivative_info: &mut KnownDerivatives,
+    ) -> Vec<(Parameter, Unknown)> {
+        self.sensitivity_params
+            .iter()
+            .filter_map(|&param| {
+                let val = *self.intern.params.raw.get(&ParamKind::Param(param))?;
+                if self.cursor.func.dfg.value_dead(val) {
+                    return None;
+                }
+                let (unknown, _) = derivative_info.unknowns.ensure(val);
+                Some((param, unknown))
+            })
+            .collect()
+    }
+
+    fn sensitivity_derivatives(
+        &self,
+        sensitivity_unknowns: &[(Parameter, Unknown)],
+    ) -> Vec<(Value, Unknown)> {
+        let mut res = Vec::new();
+        for residual in &self.system.residual {
+            for val in [residual.resist, residual.react] {
+                if self.cursor.func.dfg.value_def(val).as_const().is_none() {
+                    res.extend(sensitivity_unknowns.iter().map(|&(_, unknown)| (val, unknown)))
+                }
+            }
+        }
+        res
+    }
+
+    fn build_sensitivities(
+        &mut self,
+        sensitivity_unknowns: &[(Parameter, Unknown)],
+        derivatives: &AHashMap<(Value, Unknown), Value>,
+    ) {
+        let ddx = |val, unknown| derivatives.get(&(val, unknown)).copied().unwrap_or(F_ZERO);
+        for (row, residual) in self.system.residual.iter_enumerated() {
+            for &(param, unknown) in sensitivity_unknowns {
+                let resist = ddx(residual.resist, unknown);
+                let react = ddx(residual.react, unknown);
+                if resist == F_ZERO && react == F_ZERO {
+                    continue;
+                }
+                self.system.param_sensitivities.push(ParamSensitivity {
+                    row,
+                    param,
+                    resist,
+                    react,
+                });
+            }
+        }
     }
 
     pub fn jacobian_derivatives(
@@ -401,6 +608,9 @@ impl<'a> Builder<'a> {
     }
 
     pub(super) fn build_branch(&mut self, branch: BranchWrite, contributions: &BranchInfo) {
+        let (hi, lo) = branch.nodes(self.db);
+        self.contributed_nodes.push(hi);
+        self.contributed_nodes.extend(lo);
         let current = branch.into();
         // contributions.is_voltage_src is a Value that is used for choosing the branch type (voltage, current)
         match contributions.is_voltage_src {
@@ -502,6 +712,39 @@ impl<'a> Builder<'a> {
         };
     }
 
+    /// Builds a single voltage source that replaces two voltage sources in series.
+    /// The source value is the (signed) sum of both source values and the
+    /// current of both sources is the current of the merged branch.
+    pub(super) fn build_series_voltage_srcs(
+        &mut self,
+        series: &SeriesVoltageSrcs,
+        branches: &TiMap<BranchId, BranchWrite, BranchInfo>,
+    ) {
+        let current =
+            self.intern.ensure_param(&mut self.cursor, ParamKind::Current(series.merged.into()));
+        let mut contrib = Contribution::default();
+        for &(src, negate) in &series.srcs {
+            let info = branches.get(&src).unwrap();
+            let src_contrib = self.voltage_branch(info);
+            for (dst, val) in [
+                (&mut contrib.resist, src_contrib.resist),
+                (&mut contrib.react, src_contrib.react),
+                (&mut contrib.resist_small_signal, src_contrib.resist_small_signal),
+                (&mut contrib.react_small_signal, src_contrib.react_small_signal),
+            ] {
+                let val = strip_optbarrier(&self.cursor, val);
+                add(&mut self.cursor, dst, val, negate);
+            }
+            contrib.noise.extend(src_contrib.noise);
+            self.cursor.func.dfg.replace_uses(info.current_src.unknown.unwrap(), current);
+        }
+
+        let (hi, lo) = series.merged.nodes(self.db);
+        contrib.unknown =
+            Some(self.intern.ensure_param(&mut self.cursor, ParamKind::Voltage { hi, lo }));
+        self.add_source_equation(&contrib, current, series.merged);
+    }
+
     pub(super) fn build_implicit_equation(&mut self, eq: ImplicitEquation, contrib: &Contribution) {
         get_residual!(self, SimUnknownKind::Implicit(eq)).add_contribution(
             contrib,
@@ -685,14 +928,21 @@ impl<'a> Builder<'a> {
 
     fn add_kirchoff_law(&mut self, contrib: &Contribution, dst: BranchWrite) {
         let (hi, lo) = dst.nodes(self.db);
-        let hi = SimUnknownKind::KirchoffLaw(hi);
-        let lo = lo.map(SimUnknownKind::KirchoffLaw);
-        get_residual!(self, hi).add_contribution(contrib, &mut self.cursor, false);
+        let hi = self.kirchoff_law(hi);
+        let lo = lo.and_then(|lo| self.kirchoff_law(lo));
+        if let Some(hi) = hi {
+            get_residual!(self, hi).add_contribution(contrib, &mut self.cursor, false);
+        }
         if let Some(lo) = lo {
             get_residual!(self, lo).add_contribution(contrib, &mut self.cursor, true);
         }
         // self.add_noise(contrib, hi, lo, true);
-        self.add_noise(contrib, hi, lo);
+        match (hi, lo) {
+            (Some(hi), lo) => self.add_noise(contrib, hi, lo),
+            // the power of a noise source does not depend on its direction
+            (None, Some(lo)) => self.add_noise(contrib, lo, None),
+            (None, None) => (),
+        }
     }
 
     fn add_source_equation(&mut self, contrib: &Contribution, eq_val: Value, dst: BranchWrite) {
@@ -703,25 +953,34 @@ impl<'a> Builder<'a> {
         self.add_noise(contrib, SimUnknownKind::Current(dst.into()), None);
 
         let (hi, lo) = dst.nodes(self.db);
-        let hi = SimUnknownKind::KirchoffLaw(hi);
-        let lo = lo.map(SimUnknownKind::KirchoffLaw);
-        get_residual!(self, hi).add(&mut self.cursor, false, eq_val);
-        if let Some(lo) = lo {
+        if let Some(hi) = self.kirchoff_law(hi) {
+            get_residual!(self, hi).add(&mut self.cursor, false, eq_val);
+        }
+        if let Some(lo) = lo.and_then(|lo| self.kirchoff_law(lo)) {
             get_residual!(self, lo).add(&mut self.cursor, true, eq_val);
         }
     }
 
-    /// multiply each residual and matrix entry with mfactor and ensure it has
+    /// multiply each residual and matrix entry with mfactor (and the inverse of the
+    /// reference conductance, see [`ModuleInfo::reference_conductance`]) and ensure it has
     /// a optbarrier
+    ///
+    /// [`ModuleInfo::reference_conductance`]: crate::ModuleInfo::reference_conductance
     pub(super) fn ensure_optbarriers(&mut self) {
         let mfactor = self
             .intern
             .ensure_param(&mut self.cursor, ParamKind::ParamSysFun(ParamSysFun::mfactor));
+        let scale =
+            self.reference_conductance.map(|g| self.cursor.func.dfg.fconst((1.0 / g).into()));
         let mut ensure_optbarrier = |mut val, is_kirchoff_law| {
             val = self.cursor.ins().ensure_optbarrier(val);
             if is_kirchoff_law && val != F_ZERO {
                 update_optbarrier(self.cursor.func, &mut val, |val, cursor| {
-                    cursor.ins().fmul(mfactor, val)
+                    let val = cursor.ins().fmul(mfactor, val);
+                    match scale {
+                        Some(scale) => cursor.ins().fmul(scale, val),
+                        None => val,
+                    }
                 })
             }
             self.output_values.ensure(self.cursor.func.dfg.num_values());
@@ -748,5 +1007,12 @@ impl<'a> Builder<'a> {
             entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
             entry.react = ensure_optbarrier(entry.react, is_kirchoff);
         }
+
+        for entry in &mut self.system.param_sensitivities {
+            let is_kirchoff =
+                matches!(self.system.unknowns[entry.row], SimUnknownKind::KirchoffLaw(_));
+            entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
+            entry.react = ensure_optbarrier(entry.react, is_kirchoff);
+        }
     }
 }
@@ -0,0 +1,277 @@
+use std::fmt::Write;
+
+use hir::CompilationDB;
+use hir_lower::{CurrentKind, HirInterner, ParamKind};
+use mir::{Const, Function, InstructionData, Opcode, Param, Value, ValueDef, F_ZERO};
+
+use crate::dae::DaeSystem;
+use crate::init::{CacheSlot, Initialization};
+
+/// Expressions nested deeper than this are abbreviated with the name of the value
+/// to keep the output readable (and to avoid exponential growth of shared subexpressions).
+const MAX_DEPTH: u32 = 12;
+
+impl DaeSystem {
+    /// Renders the residual and the jacobian as LaTeX (`aligned` environment) for
+    /// documentation. The expressions are reconstructed from `eval` on a best effort basis:
+    /// parameters, node voltages and branch currents are named after their declarations
+    /// and values computed by `init` are inlined, while control flow (phis) and
+    /// callbacks are printed as opaque values (like `v42`).
+    pub fn to_latex(
+        &self,
+        db: &CompilationDB,
+        eval: &Function,
+        intern: &HirInterner,
+        init: &Initialization,
+    ) -> String {
+        let printer = LatexPrinter { db, func: eval, intern, init: Some(init) };
+        let unknown_name = |unknown| latex_ident(&self.unknowns[unknown].name(db, intern));
+        let mut dst = "\\begin{aligned}\n".to_owned();
+        for (unknown, residual) in self.residual.iter_enumerated() {
+            let name = unknown_name(unknown);
+            writeln!(dst, "I_{{{name}}} &= {} \\\\", printer.print(residual.resist)).unwrap();
+            if residual.react != F_ZERO {
+                writeln!(dst, "Q_{{{name}}} &= {} \\\\", printer.print(residual.react)).unwrap();
+            }
+        }
+        for entry in &self.jacobian {
+            let (row, col) = (unknown_name(entry.row), unknown_name(entry.col));
+            for (part, val) in [('I', entry.resist), ('Q', entry.react)] {
+                if val != F_ZERO {
+                    writeln!(
+                        dst,
+                        "\\frac{{\\partial {part}_{{{row}}}}}{{\\partial {col}}} &= {} \\\\",
+                        printer.print(val)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        dst.push_str("\\end{aligned}");
+        dst
+    }
+}
+
+/// Binding strength of a rendered expression, used to decide where parentheses are needed.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Prec {
+    Sum,
+    Product,
+    Unary,
+    Atom,
+}
+
+struct LatexPrinter<'a> {
+    db: &'a CompilationDB,
+    func: &'a Function,
+    intern: &'a HirInterner,
+    /// The initialization that computes the cached parameters of `func`,
+    /// `None` when printing the initialization itself.
+    init: Option<&'a Initialization>,
+}
+
+impl LatexPrinter<'_> {
+    fn print(&self, val: Value) -> String {
+        self.expr(val, 0).0
+    }
+
+    fn operand(&self, val: Value, depth: u32, prec: Prec) -> String {
+        let (expr, expr_prec) = self.expr(val, depth);
+        if expr_prec < prec {
+            format!("\\left({expr}\\right)")
+        } else {
+            expr
+        }
+    }
+
+    fn expr(&self, val: Value, depth: u32) -> (String, Prec) {
+        let inst = match self.func.dfg.value_def(val) {
+            ValueDef::Result(inst, _) => inst,
+            ValueDef::Param(param) => return self.param(param, depth),
+            ValueDef::Const(Const::Float(val)) => return float(val.into()),
+            ValueDef::Const(Const::Int(val)) => return float(val.into()),
+            ValueDef::Const(Const::Bool(val)) => return (format!("\\mathrm{{{val}}}"), Prec::Atom),
+            ValueDef::Const(Const::Str(_)) | ValueDef::Invalid => return self.opaque(val),
+        };
+        if depth > MAX_DEPTH {
+            return self.opaque(val);
+        }
+        let depth = depth + 1;
+
+        match self.func.dfg.insts[inst] {
+            InstructionData::Unary { opcode, arg } => match opcode {
+                Opcode::OptBarrier | Opcode::IFcast | Opcode::BFcast => self.expr(arg, depth),
+                Opcode::Fneg | Opcode::Ineg => {
+                    (format!("-{}", self.operand(arg, depth, Prec::Unary)), Prec::Unary)
+                }
+                Opcode::Sqrt => {
+                    (format!("\\sqrt{{{}}}", self.print_nested(arg, depth)), Prec::Atom)
+                }
+                Opcode::Fabs => {
+                    (format!("\\left|{}\\right|", self.print_nested(arg, depth)), Prec::Atom)
+                }
+                Opcode::Exp
+                | Opcode::Ln
+                | Opcode::Log
+                | Opcode::Sin
+                | Opcode::Cos
+                | Opcode::Tan
+                | Opcode::Sinh
+                | Opcode::Cosh
+                | Opcode::Tanh => {
+                    let fun = if opcode == Opcode::Log { "log_{10}" } else { opcode.name() };
+                    let arg = self.print_nested(arg, depth);
+                    (format!("\\{fun}\\left({arg}\\right)"), Prec::Atom)
+                }
+                _ => {
+                    let arg = self.print_nested(arg, depth);
+                    (format!("\\operatorname{{{opcode}}}\\left({arg}\\right)"), Prec::Atom)
+                }
+            },
+            InstructionData::Binary { opcode, args: [lhs, rhs] } => match opcode {
+                Opcode::Fadd | Opcode::Iadd => (
+                    format!(
+                        "{} + {}",
+                        self.operand(lhs, depth, Prec::Sum),
+                        self.operand(rhs, depth, Prec::Sum)
+                    ),
+                    Prec::Sum,
+                ),
+                Opcode::Fsub | Opcode::Isub => (
+                    format!(
+                        "{} - {}",
+                        self.operand(lhs, depth, Prec::Sum),
+                        self.operand(rhs, depth, Prec::Product)
+                    ),
+                    Prec::Sum,
+                ),
+                Opcode::Fmul | Opcode::Imul => (
+                    format!(
+                        "{} \\cdot {}",
+                        self.operand(lhs, depth, Prec::Product),
+                        self.operand(rhs, depth, Prec::Unary)
+                    ),
+                    Prec::Product,
+                ),
+                Opcode::Fdiv | Opcode::Idiv => (
+                    format!(
+                        "\\frac{{{}}}{{{}}}",
+                        self.print_nested(lhs, depth),
+                        self.print_nested(rhs, depth)
+                    ),
+                    Prec::Atom,
+                ),
+                Opcode::Pow => (
+                    format!(
+                        "{{{}}}^{{{}}}",
+                        self.operand(lhs, depth, Prec::Atom),
+                        self.print_nested(rhs, depth)
+                    ),
+                    Prec::Atom,
+                ),
+                _ => {
+                    let (lhs, rhs) = (self.print_nested(lhs, depth), self.print_nested(rhs, depth));
+                    (format!("\\operatorname{{{opcode}}}\\left({lhs}, {rhs}\\right)"), Prec::Atom)
+                }
+            },
+            _ => self.opaque(val),
+        }
+    }
+
+    fn print_nested(&self, val: Value, depth: u32) -> String {
+        self.expr(val, depth).0
+    }
+
+    fn param(&self, param: Param, depth: u32) -> (String, Prec) {
+        let kind = if let Some((kind, _)) = self.intern.params.get_index(param) {
+            kind
+        } else {
+            return self.cached_val(param, depth);
+        };
+        let res = match *kind {
+            ParamKind::Param(param) => latex_ident(&param.name(self.db)),
+            ParamKind::Voltage { hi, lo: Some(lo) } => {
+                format!(
+                    "V_{{{},{}}}",
+                    latex_ident(&hi.name(self.db)),
+                    latex_ident(&lo.name(self.db))
+                )
+            }
+            ParamKind::Voltage { hi, lo: None } => {
+                format!("V_{{{}}}", latex_ident(&hi.name(self.db)))
+            }
+            ParamKind::Current(CurrentKind::Branch(br)) => {
+                format!("I_{{{}}}", latex_ident(&br.name(self.db)))
+            }
+            ParamKind::Current(CurrentKind::Unnamed { hi, lo: Some(lo) }) => {
+                format!(
+                    "I_{{{},{}}}",
+                    latex_ident(&hi.name(self.db)),
+                    latex_ident(&lo.name(self.db))
+                )
+            }
+            ParamKind::Current(CurrentKind::Unnamed { hi, lo: None }) => {
+                format!("I_{{{}}}", latex_ident(&hi.name(self.db)))
+            }
+            ParamKind::Current(CurrentKind::Port(node)) => {
+                format!("I_{{<{}>}}", latex_ident(&node.name(self.db)))
+            }
+            ParamKind::Temperature => "T".to_owned(),
+            ParamKind::Abstime => "t".to_owned(),
+            ParamKind::ParamSysFun(fun) => format!("\\mathrm{{\\${fun:?}}}"),
+            ParamKind::ImplicitUnknown(eq) => {
+                format!("x_{{{}}}", latex_ident(&self.intern.implicit_equation_name(eq)))
+            }
+            _ => return self.opaque(self.intern.params[param]),
+        };
+        (res, Prec::Atom)
+    }
+
+    /// Parameters of `eval` beyond the interned parameters are cache slots
+    /// that are computed by the initialization.
+    fn cached_val(&self, param: Param, depth: u32) -> (String, Prec) {
+        let init = self.init.unwrap();
+        let slot = CacheSlot::from(usize::from(param) - self.intern.params.len());
+        let val = init.cached_vals.iter().find_map(|(&val, &it)| (it == slot).then_some(val));
+        let printer =
+            LatexPrinter { db: self.db, func: &init.func, intern: &init.intern, init: None };
+        match val {
+            Some(val) => printer.expr(val, depth),
+            None => (format!("\\mathrm{{{slot}}}"), Prec::Atom),
+        }
+    }
+
+    fn opaque(&self, val: Value) -> (String, Prec) {
+        (format!("\\mathrm{{{val}}}"), Prec::Atom)
+    }
+}
+
+fn float(val: f64) -> (String, Prec) {
+    let res = format!("{val:?}");
+    let (res, prec) = match res.split_once('e') {
+        Some((mantissa, exp)) => (format!("{mantissa} \\cdot 10^{{{exp}}}"), Prec::Product),
+        None => (res.strip_suffix(".0").unwrap_or(&res).to_owned(), Prec::Atom),
+    };
+    if val < 0.0 {
+        (res, Prec::Unary)
+    } else {
+        (res, prec)
+    }
+}
+
+/// Names with more than one character are set upright, characters that have
+/// a special meaning in LaTeX are escaped.
+fn latex_ident(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '_' | '$' | '%' | '&' | '#' | '{' | '}') {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    if name.chars().count() > 1 {
+        format!("\\mathrm{{{res}}}")
+    } else {
+        res
+    }
+}
@@ -0,0 +1,133 @@
+//! Ideal voltage sources that are connected in series introduce a node
+//! (and a branch current) that carry no information:
+//!
+//! ```verilog
+//! V(a, m) <+ v1;
+//! V(m, c) <+ v2;
+//! ```
+//!
+//! As long as `m` is not used anywhere else, this is equivalent to a single
+//! source `V(a, c) <+ v1 + v2`. This module finds such nodes so that the
+//! DAE builder can merge both sources into a single branch, which removes
+//! two unknowns from the system.
+
+use ahash::AHashSet;
+use hir::{BranchWrite, Node};
+use hir_lower::{CallBackKind, ParamKind};
+use mir::TRUE;
+
+use crate::context::Context;
+use crate::topology::{BranchInfo, Topology};
+
+pub(super) struct SeriesVoltageSrcs {
+    /// The node between both sources which is eliminated.
+    pub(super) node: Node,
+    /// The branch that replaces both sources.
+    pub(super) merged: BranchWrite,
+    /// Both sources and whether they are oriented against `merged`.
+    pub(super) srcs: [(BranchWrite, bool); 2],
+}
+
+pub(super) fn series_voltage_srcs(ctx: &Context, topology: &Topology) -> Vec<SeriesVoltageSrcs> {
+    let ports = ctx.module.module.ports(ctx.db);
+    // voltage probes and collapse hints require the node to be part of the system
+    let mut used_nodes = AHashSet::new();
+    for (_, &kind, _) in ctx.intern.live_params(&ctx.func.dfg) {
+        if let ParamKind::Voltage { hi, lo } = kind {
+            used_nodes.insert(hi);
+            used_nodes.extend(lo);
+        }
+    }
+    for kind in ctx.intern.callbacks.iter() {
+        if let CallBackKind::CollapseHint(hi, lo) = *kind {
+            used_nodes.insert(hi);
+            used_nodes.extend(lo);
+        }
+    }
+
+    let mut res: Vec<SeriesVoltageSrcs> = Vec::new();
+    for node in ctx.module.module.internal_nodes(ctx.db) {
+        if ports.contains(&node) || used_nodes.contains(&node) {
+            continue;
+        }
+        let series = if let Some(series) = find_series_srcs(ctx, topology, node) {
+            series
+        } else {
+            continue;
+        };
+        // a source can only be merged once and the merged branch must
+        // not be confused with an existing branch
+        let conflict = res.iter().any(|other| {
+            other.merged == series.merged
+                || other.srcs.iter().any(|(src, _)| series.srcs.iter().any(|(it, _)| it == src))
+        });
+        if !conflict {
+            res.push(series)
+        }
+    }
+    res
+}
+
+fn find_series_srcs(ctx: &Context, topology: &Topology, node: Node) -> Option<SeriesVoltageSrcs> {
+    let mut srcs = topology.branches.iter().filter(|(branch, _)| {
+        let (hi, lo) = branch.nodes(ctx.db);
+        hi == node || lo == Some(node)
+    });
+    let (&src1, info1) = srcs.next()?;
+    let (&src2, info2) = srcs.next()?;
+    if srcs.next().is_some() {
+        return None;
+    }
+
+    for info in [info1, info2] {
+        if !is_mergeable_voltage_src(ctx, info) {
+            return None;
+        }
+    }
+
+    let other_end = |src: BranchWrite| match src.nodes(ctx.db) {
+        (hi, lo) if hi == node => lo,
+        (hi, _) => Some(hi),
+    };
+    let (end1, end2) = (other_end(src1), other_end(src2));
+    if end1 == end2 || end1 == Some(node) || end2 == Some(node) {
+        return None;
+    }
+
+    // the merged branch starts at a node (not ground). If possible it is oriented
+    // so that the currents of probed sources can be reused as is
+    let candidates = [(end1, end2, src1, src2), (end2, end1, src2, src1)];
+    candidates.into_iter().find_map(|(hi, lo, hi_src, lo_src)| {
+        let merged = BranchWrite::Unnamed { hi: hi?, lo };
+        if topology.branches.contains_key(&merged)
+            || ctx.intern.is_param_live(&ctx.func, &ParamKind::Current(merged.into()))
+        {
+            return None;
+        }
+        // the source at the `hi` side of the merged branch flows into the node
+        // while the source at the `lo` side flows out of the node
+        let srcs =
+            [(hi_src, hi_src.nodes(ctx.db).0 == node), (lo_src, lo_src.nodes(ctx.db).0 != node)];
+        let probed_against = srcs.iter().any(|&(src, negate)| {
+            negate && ctx.intern.is_param_live(&ctx.func, &ParamKind::Current(src.into()))
+        });
+        if probed_against {
+            None
+        } else {
+            Some(SeriesVoltageSrcs { node, merged, srcs })
+        }
+    })
+}
+
+/// Checks that `info` is an ideal voltage source whose current can be
+/// replaced by the current of a merged branch.
+fn is_mergeable_voltage_src(ctx: &Context, info: &BranchInfo) -> bool {
+    if info.is_voltage_src != TRUE || info.voltage_src.is_trivial() {
+        return false;
+    }
+    // limiting the branch current would require the original unknown
+    match info.current_src.unknown {
+        Some(current) => !ctx.intern.lim_state.raw.contains_key(&current),
+        None => false,
+    }
+}
@@ -1,17 +1,57 @@
-use std::fs;
+use std::ffi::c_void;
+use std::{fs, ptr};
 
 use expect_test::expect_file;
+use float_cmp::assert_approx_eq;
+use hir::diagnostics::sink::Buffer;
 use hir::diagnostics::ConsoleSink;
-use hir::CompilationDB;
+use hir::{CompilationDB, ParamSysFun};
+use hir_lower::{CurrentKind, HirInterner, ParamKind};
 use indoc::indoc;
 use lasso::Rodeo;
+use mir::{FuncRef, Function, InstructionData, Param, Value, F_ZERO};
+use mir_interpret::{Data, Func, Interpreter, InterpreterState};
+use stdx::iter::zip;
 use stdx::{integration_test_dir, openvaf_test_data};
+use typed_index_collections::{TiSlice, TiVec};
 
 use crate::context::{Context, OptimiziationStage};
-use crate::dae::DaeSystem;
-use crate::topology;
+use crate::dae::{DaeSystem, NoiseSourceKind, WeakRow};
+use crate::{topology, CompiledModule, ReactiveFormulation, Scratch, SimUnknownKind};
 
-fn run_test(src: &str) {
+/// Runs the init function of `module` (to compute the cached values) followed by the eval
+/// function. `param` returns the value of each parameter (`None` is treated as zero) and all
+/// callbacks are ignored.
+fn eval(module: &CompiledModule, param: impl Fn(&ParamKind) -> Option<f64>) -> InterpreterState {
+    fn ignore(_: &mut InterpreterState, _: &[Value], _: &[Value], _: *mut c_void) {}
+    let args = |intern: &HirInterner, len: usize| {
+        let mut args: TiVec<Param, Data> = vec![Data::from(0f64); len].into();
+        for (param_, (kind, _)) in intern.params.iter_enumerated() {
+            if let Some(val) = param(kind) {
+                args[param_] = val.into();
+            }
+        }
+        args
+    };
+    let run = |func: &Function, args: &TiSlice<Param, Data>| {
+        let calls: TiVec<FuncRef, (Func, *mut c_void)> =
+            vec![(ignore as Func, ptr::null_mut()); func.dfg.signatures.len()].into();
+        let mut interpreter = Interpreter::new(func, &calls, args);
+        interpreter.run();
+        interpreter.state
+    };
+
+    let init = &module.init;
+    let init_state = run(&init.func, &args(&init.intern, init.intern.params.len()));
+    let num_params = module.intern.params.len();
+    let mut args = args(&module.intern, num_params + init.cache_slots.len());
+    for (&val, &slot) in init.cached_vals.iter() {
+        args[Param::from(num_params + usize::from(slot))] = init_state.read(val);
+    }
+    run(&module.eval, &args)
+}
+
+fn run_test(src: &str) -> DaeSystem {
     let db = CompilationDB::new_virtual(src).unwrap();
     let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
     let mut literals = Rodeo::new();
@@ -24,25 +64,34 @@ fn run_test(src: &str) {
     context.compute_cfg();
     context.optimize(OptimiziationStage::Final);
     dae_system.sparsify(&mut context);
+    // sparsify updates the op dependent instructions incrementally
+    let op_dependent_insts: Vec<_> = context.op_dependent_insts.iter().collect();
+    context.refresh_op_dependent_insts();
+    assert_eq!(op_dependent_insts, context.op_dependent_insts.iter().collect::<Vec<_>>());
     let name = module.module.name(&db);
     let test_dir = openvaf_test_data("dae");
     let topology = format!("{dae_system:#?}");
     assert!(context.func.validate());
     expect_file![test_dir.join(format!("{name}_system.snap"))].assert_eq(&topology);
     let func = format!("{:#?}", context.func);
-    expect_file![test_dir.join(format!("{name}_mir.snap"))].assert_eq(&func)
+    expect_file![test_dir.join(format!("{name}_mir.snap"))].assert_eq(&func);
+    dae_system
 }
 
 #[test]
 fn diode() {
     let src = fs::read_to_string(integration_test_dir("DIODE").join("diode.va")).unwrap();
-    run_test(&src);
+    assert!(!run_test(&src).is_linear);
 }
 
 #[test]
 fn resistor() {
     let src = fs::read_to_string(integration_test_dir("RESISTOR").join("resistor.va")).unwrap();
-    run_test(&src);
+    let system = run_test(&src);
+    assert!(system.is_linear);
+    // purely resistive, no reactive matrix pointers are required
+    assert_eq!(system.num_reactive, 0);
+    assert!(system.jacobian.iter().all(|entry| entry.react == F_ZERO));
 }
 
 #[test]
@@ -146,3 +195,866 @@ fn dyn_switch_branch() {
     "#};
     run_test(src);
 }
+
+#[test]
+fn param_sensitivities() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module sensitivity(inout a, inout c);
+            electrical a, c;
+            parameter real r=1.0, unused=2.0;
+            analog begin
+                I(a, c) <+ V(a, c) / r;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let param = |name: &str| *module.params.keys().find(|param| param.name(&db) == name).unwrap();
+    let (r, unused) = (param("r"), param("unused"));
+    module.sensitivity_params = vec![r, unused];
+
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let sensitivities = &module.dae_system.param_sensitivities;
+    // ddx(I(a, c), r) = -V(a, c) / r^2 contributes to both nodes
+    assert_eq!(sensitivities.len(), 2);
+    for sensitivity in sensitivities {
+        assert_eq!(sensitivity.param, r);
+        assert_ne!(sensitivity.resist, F_ZERO);
+        assert_eq!(sensitivity.react, F_ZERO);
+    }
+}
+
+#[test]
+fn series_voltage_src() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module series_voltage_src(inout a, inout c);
+            electrical a, c, m;
+            parameter real v1=1.0, v2=2.0;
+            analog begin
+                V(a, m) <+ v1;
+                V(c, m) <+ v2;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let unknowns = &module.dae_system.unknowns;
+
+    // the node between both sources and one of the branch currents are eliminated
+    let m = module.info.module.internal_nodes(&db)[0];
+    assert!(!unknowns.contains(&SimUnknownKind::KirchoffLaw(m)));
+    assert_eq!(unknowns.len(), 3);
+    let a = module.info.module.ports(&db)[0];
+    let (current, hi) = unknowns
+        .iter_enumerated()
+        .find_map(|(unknown, kind)| match *kind {
+            SimUnknownKind::Current(CurrentKind::Unnamed { hi, .. }) => Some((unknown, hi)),
+            _ => None,
+        })
+        .unwrap();
+
+    // V(a, c) = V(a, m) - V(c, m) = v1 - v2, the merged branch may be oriented either way
+    let sign = if hi == a { 1.0 } else { -1.0 };
+    let state = eval(&module, |kind| match *kind {
+        ParamKind::Param(it) if it.name(&db) == "v1" => Some(1.0),
+        ParamKind::Param(it) if it.name(&db) == "v2" => Some(2.0),
+        ParamKind::Voltage { .. } => Some(sign * 0.25),
+        ParamKind::ParamSysFun(ParamSysFun::mfactor) => Some(1.0),
+        _ => None,
+    });
+    let residual: f64 = state.read(module.dae_system.residual[current].resist);
+    assert_eq!(residual, sign * (1.0 - 2.0 - 0.25));
+}
+
+#[test]
+fn residual_eval() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module residual_eval(inout a, inout c);
+            electrical a, c;
+            parameter real is=1e-14, cj=1e-12;
+            analog begin
+                I(a, c) <+ is * (exp(V(a, c) / 0.025) - 1) + ddt(cj * V(a, c) * V(a, c));
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    module.residual_eval = true;
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let residual_eval = module.residual_eval.as_ref().unwrap();
+    assert!(residual_eval.validate());
+
+    let num_params = module.intern.params.len() + module.init.cache_slots.len();
+    let mut args: TiVec<Param, Data> = vec![Data::from(0f64); num_params].into();
+    for (param, (kind, _)) in module.intern.params.iter_enumerated() {
+        args[param] = match *kind {
+            ParamKind::Param(it) if it.name(&db) == "is" => 1e-14,
+            ParamKind::Param(it) if it.name(&db) == "cj" => 1e-12,
+            ParamKind::Voltage { .. } => 0.6,
+            ParamKind::ParamSysFun(ParamSysFun::mfactor) => 1.0,
+            _ => continue,
+        }
+        .into();
+    }
+    let mut full = Interpreter::new(&module.eval, TiSlice::from_ref(&[]), &args);
+    full.run();
+    let mut residual_only = Interpreter::new(residual_eval, TiSlice::from_ref(&[]), &args);
+    residual_only.run();
+    let mut nonzero = false;
+    for residual in &module.dae_system.residual {
+        for val in [residual.resist, residual.react] {
+            let expected: f64 = full.state.read(val);
+            let found: f64 = residual_only.state.read(val);
+            assert_eq!(expected, found);
+            nonzero |= expected != 0.0;
+        }
+    }
+    assert!(nonzero);
+
+    // none of the jacobian entries are computed
+    assert!(!module.dae_system.jacobian.is_empty());
+    for entry in &module.dae_system.jacobian {
+        for val in [entry.resist, entry.react] {
+            if let Some(inst) = module.eval.dfg.value_def(val).inst() {
+                assert!(module.eval.layout.inst_block(inst).is_some());
+                assert_eq!(residual_eval.layout.inst_block(inst), None);
+            }
+        }
+    }
+    let num_insts = |func: &Function| {
+        func.layout.blocks().map(|bb| func.layout.block_insts(bb).count()).sum::<usize>()
+    };
+    assert!(num_insts(residual_eval) < num_insts(&module.eval));
+}
+
+#[test]
+fn node_alias() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module node_alias(inout a, inout c);
+            electrical a, c, m, x;
+            parameter real r=1.0;
+            analog begin
+                $analog_node_alias(x, "m");
+                I(a, x) <+ V(a, x) / r;
+                I(m, c) <+ V(m, c) / r;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let unknowns = &module.dae_system.unknowns;
+
+    let internal_nodes = module.info.module.internal_nodes(&db);
+    let (m, x) = (internal_nodes[0], internal_nodes[1]);
+    assert_eq!(x.alias_target(&db), Some(m));
+    assert!(unknowns.contains(&SimUnknownKind::KirchoffLaw(m)));
+    assert!(!unknowns.contains(&SimUnknownKind::KirchoffLaw(x)));
+    assert_eq!(unknowns.len(), 3);
+
+    // both contributions are part of the Kirchhoff law of the same node
+    let row = unknowns.index(&SimUnknownKind::KirchoffLaw(m)).unwrap();
+    let cols = module.dae_system.jacobian.iter().filter(|entry| entry.row == row).count();
+    assert_eq!(cols, 3);
+}
+
+#[test]
+fn orphaned_current_probe() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module orphaned_probe(inout a, inout c);
+            electrical a, c;
+            branch (a, c) br;
+            analog begin
+                I(a, c) <+ V(a, c) + I(br);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    assert!(matches!(
+        module.dae_system.orphaned_current_probes[..],
+        [CurrentKind::Branch(branch)] if branch.name(&db) == "br"
+    ));
+
+    let mut buf = Buffer::no_color();
+    module.diagnostics(&db, &mut ConsoleSink::buffer(&db, &mut buf));
+    let diagnostics = String::from_utf8(buf.into_inner()).unwrap();
+    assert!(diagnostics.contains(
+        "warning: current of branch br is probed in module 'orphaned_probe' but never defined"
+    ));
+}
+
+#[test]
+fn reference_conductance() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module divider(inout a, inout c);
+            electrical a, c, m;
+            analog begin
+                I(a, m) <+ V(a, m) / 1k;
+                I(m, c) <+ V(m, c) / 3k;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let (a, m) = (module.module.ports(&db)[0], module.module.internal_nodes(&db)[0]);
+    let node_voltage = |node| if node == a { 1.0 } else { 0.0 };
+
+    // performs a single newton step for V(m) (the system is linear so the step is exact)
+    // with V(a) = 1, V(c) = 0 and an initial guess of V(m) = 0
+    let mut solve = |reference_conductance| {
+        module.reference_conductance = reference_conductance;
+        let mut literals = Rodeo::new();
+        let module = CompiledModule::new(&db, &module, &mut literals);
+        let state = eval(&module, |kind| match *kind {
+            ParamKind::Voltage { hi, lo } => Some(node_voltage(hi) - lo.map_or(0.0, node_voltage)),
+            ParamKind::ParamSysFun(ParamSysFun::mfactor) => Some(1.0),
+            _ => None,
+        });
+        let dae_system = &module.dae_system;
+        let row = dae_system.unknowns.index(&SimUnknownKind::KirchoffLaw(m)).unwrap();
+        let residual: f64 = state.read(dae_system.residual[row].resist);
+        let entry = dae_system.jacobian.iter().find(|it| it.row == row && it.col == row).unwrap();
+        let conductance: f64 = state.read(entry.resist);
+        (residual, -residual / conductance)
+    };
+
+    let (residual, solution) = solve(None);
+    let (scaled_residual, scaled_solution) = solve(Some(1e-3));
+    assert!((solution - 0.75).abs() < 1e-12, "V(m) = {solution}");
+    assert!((scaled_solution - solution).abs() < 1e-12, "V(m) = {scaled_solution}");
+    assert!((scaled_residual - residual / 1e-3).abs() < 1e-12);
+}
+
+#[test]
+fn resistor_latex() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module resistor(inout a, inout c);
+            electrical a, c;
+            parameter real R=1k;
+            analog I(a, c) <+ V(a, c) / R;
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let latex = module.dae_system.to_latex(&db, &module.eval, &module.intern, &module.init);
+    assert!(latex.starts_with("\\begin{aligned}\n"), "{latex}");
+    assert!(latex.ends_with("\\end{aligned}"), "{latex}");
+    let residual = latex.lines().find(|line| line.starts_with("I_{a} &= ")).unwrap();
+    assert!(residual.contains("V_{a,c}") && residual.contains('R'), "{latex}");
+    assert!(latex.contains("\\frac{\\partial I_{a}}{\\partial c} &= "), "{latex}");
+}
+
+#[test]
+fn collapse_resistance() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module series_resistor(inout a, inout c);
+            electrical a, c, m;
+            parameter real rs = 1m;
+            analog begin
+                I(a, m) <+ V(a, m) / rs;
+                I(m, c) <+ 1m * V(m, c);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let (a, c) = (module.module.ports(&db)[0], module.module.ports(&db)[1]);
+    let m = module.module.internal_nodes(&db)[0];
+    let mut collapse_pairs = |collapse_resistance| {
+        module.collapse_resistance = collapse_resistance;
+        let mut literals = Rodeo::new();
+        let module = CompiledModule::new(&db, &module, &mut literals);
+        let unknowns = &module.dae_system.unknowns;
+        module
+            .node_collapse
+            .pairs()
+            .map(|(_, hi, lo)| (unknowns[hi], lo.map(|lo| unknowns[lo])))
+            .collect::<Vec<_>>()
+    };
+    let pair = |hi, lo| (SimUnknownKind::KirchoffLaw(hi), Some(SimUnknownKind::KirchoffLaw(lo)));
+
+    // by default only the collapse hints of the model are used
+    assert_eq!(collapse_pairs(None), []);
+    // rs is collapsed (during the simulation) if it is below 1 Ohm, the 1 kOhm resistor is kept
+    assert_eq!(collapse_pairs(Some(1.0)), [pair(a, m)]);
+    assert_eq!(collapse_pairs(Some(10e3)), [pair(a, m), pair(m, c)]);
+}
+
+#[test]
+fn ground_branch() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module gnd_branch(inout a, inout c);
+            electrical a, c, gnd;
+            ground gnd;
+            branch (a, gnd) br;
+            analog begin
+                I(a, c) <+ V(a, c) / 1k;
+                I(br) <+ V(br) / 1k;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let a = module.module.ports(&db)[0];
+    let gnd = module.module.internal_nodes(&db).into_iter().find(|node| node.is_gnd(&db)).unwrap();
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let dae_system = &module.dae_system;
+
+    // only the ports have an unknown, the contribution to ground is absorbed by the reference
+    assert!(dae_system.unknowns.index(&SimUnknownKind::KirchoffLaw(gnd)).is_none());
+    assert_eq!(dae_system.unknowns.len(), 2);
+
+    let state = eval(&module, |kind| match *kind {
+        ParamKind::Voltage { hi, .. } if hi == a => Some(1.0),
+        ParamKind::ParamSysFun(ParamSysFun::mfactor) => Some(1.0),
+        _ => None,
+    });
+    let row = dae_system.unknowns.index(&SimUnknownKind::KirchoffLaw(a)).unwrap();
+    let residual: f64 = state.read(dae_system.residual[row].resist);
+    assert_approx_eq!(f64, residual, 2e-3, epsilon = 1e-15);
+}
+
+#[test]
+fn reactive_formulation() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module varactor(inout a, inout c);
+            electrical a, c;
+            parameter real c0=1p, c1=0;
+            analog begin
+                I(a, c) <+ ddt(c0 * V(a, c) + c1 * V(a, c) * V(a, c));
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let a = module.module.ports(&db)[0];
+
+    // returns the reactive residual and the reactive jacobian entry of node a for V(a, c) = 0.5
+    let mut eval = |formulation, c1| {
+        module.reactive_formulation = formulation;
+        let mut literals = Rodeo::new();
+        let module = CompiledModule::new(&db, &module, &mut literals);
+        let mut args: TiVec<Param, Data> =
+            vec![Data::from(0f64); module.intern.params.len()].into();
+        for (param, (kind, _)) in module.intern.params.iter_enumerated() {
+            args[param] = match *kind {
+                ParamKind::Param(it) if it.name(&db) == "c0" => 1e-12,
+                ParamKind::Param(it) if it.name(&db) == "c1" => c1,
+                ParamKind::Voltage { .. } => 0.5,
+                ParamKind::ParamSysFun(ParamSysFun::mfactor) => 1.0,
+                _ => continue,
+            }
+            .into();
+        }
+        let mut interpreter = Interpreter::new(&module.eval, TiSlice::from_ref(&[]), &args);
+        interpreter.run();
+        let dae_system = &module.dae_system;
+        let row = dae_system.unknowns.index(&SimUnknownKind::KirchoffLaw(a)).unwrap();
+        let residual: f64 = interpreter.state.read(dae_system.residual[row].react);
+        let entry = dae_system.jacobian.iter().find(|it| it.row == row && it.col == row).unwrap();
+        let capacitance: f64 = interpreter.state.read(entry.react);
+        (residual, capacitance)
+    };
+
+    // both formulations are identical for a linear capacitor
+    let (charge, capacitance) = eval(ReactiveFormulation::Charge, 0.0);
+    assert_approx_eq!(f64, charge, 0.5e-12);
+    assert_approx_eq!(f64, capacitance, 1e-12);
+    let (charge, capacitance) = eval(ReactiveFormulation::Capacitance, 0.0);
+    assert_approx_eq!(f64, charge, 0.5e-12);
+    assert_approx_eq!(f64, capacitance, 1e-12);
+
+    // Q = c0 V + c1 V^2 and C V = c0 V + 2 c1 V^2
+    let (charge, capacitance) = eval(ReactiveFormulation::Charge, 1e-12);
+    assert_approx_eq!(f64, charge, 0.75e-12);
+    assert_approx_eq!(f64, capacitance, 2e-12);
+    let (charge, capacitance) = eval(ReactiveFormulation::Capacitance, 1e-12);
+    assert_approx_eq!(f64, charge, 1e-12);
+    assert_approx_eq!(f64, capacitance, 2e-12);
+}
+
+#[test]
+fn analog_function_inlined() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module analog_function(inout a, inout c);
+            electrical a, c;
+            parameter real g=2.0;
+
+            analog function real clipped;
+                input x, k;
+                output twice;
+                real x, k, twice;
+                begin
+                    twice = 2 * x;
+                    if (x > 0)
+                        clipped = k * x;
+                    else
+                        clipped = 0;
+                end
+            endfunction
+
+            real doubled;
+            analog begin
+                I(a, c) <+ clipped(V(a, c), g, doubled) + doubled;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    assert_eq!(db.compilation_unit().test_diagnostics(&db), "");
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+
+    // the function body is lowered directly into the caller
+    for bb in module.eval.layout.blocks() {
+        for inst in module.eval.layout.block_insts(bb) {
+            assert!(!matches!(module.eval.dfg.insts[inst], InstructionData::Call { .. }));
+        }
+    }
+
+    let a = module.info.module.ports(&db)[0];
+    let row = module.dae_system.unknowns.index(&SimUnknownKind::KirchoffLaw(a)).unwrap();
+    let residual = |voltage: f64| {
+        let mut args: TiVec<Param, Data> =
+            vec![Data::from(0f64); module.intern.params.len()].into();
+        for (param, (kind, _)) in module.intern.params.iter_enumerated() {
+            args[param] = match *kind {
+                ParamKind::Param(it) if it.name(&db) == "g" => 2.0,
+                ParamKind::Voltage { .. } => voltage,
+                ParamKind::ParamSysFun(ParamSysFun::mfactor) => 1.0,
+                _ => continue,
+            }
+            .into();
+        }
+        let mut interpreter = Interpreter::new(&module.eval, TiSlice::from_ref(&[]), &args);
+        interpreter.run();
+        interpreter.state.read::<f64>(module.dae_system.residual[row].resist)
+    };
+    // 2 * 0.5 + 2 * 0.5 and 0 + 2 * -0.5
+    assert_eq!(residual(0.5), 2.0);
+    assert_eq!(residual(-0.5), -1.0);
+}
+
+#[test]
+fn reactive_only_node() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module reactive_only(inout a, inout c);
+            electrical a, c, m;
+            parameter real cj=1e-12, r=1k;
+            analog begin
+                I(a, m) <+ ddt(cj * V(a, m));
+                I(m, c) <+ ddt(cj * V(m, c));
+                I(a, c) <+ V(a, c) / r;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    // the resistor provides a DC path between the ports
+    let m = module.info.module.internal_nodes(&db)[0];
+    assert_eq!(module.dae_system.reactive_only_nodes, [m]);
+
+    let mut buf = Buffer::no_color();
+    module.diagnostics(&db, &mut ConsoleSink::buffer(&db, &mut buf));
+    let diagnostics = String::from_utf8(buf.into_inner()).unwrap();
+    assert!(
+        diagnostics.contains(
+            "warning: node m of module 'reactive_only' only has reactive (ddt) contributions"
+        ),
+        "{diagnostics}"
+    );
+}
+
+#[test]
+fn cancelled_contributions() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module cancelled(inout a, inout c);
+            electrical a, c, m, n;
+            parameter real r=1k;
+            real i;
+            analog begin
+                i = V(a, c) / r;
+                I(a, c) <+ i;
+                I(a, m) <+ i;
+                I(a, m) <+ -i;
+                I(a, n) <+ i;
+                I(n, a) <+ i;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    // the contributions to m cancel out within a single branch, the contributions to n
+    // are made by two branches with opposite directions
+    let internal_nodes = module.info.module.internal_nodes(&db);
+    let (m, n) = (internal_nodes[0], internal_nodes[1]);
+    let cancelled_nodes = &module.dae_system.cancelled_nodes;
+    assert!(cancelled_nodes.contains(&m) && cancelled_nodes.contains(&n), "{cancelled_nodes:?}");
+    assert_eq!(cancelled_nodes.len(), 2);
+
+    let mut buf = Buffer::no_color();
+    module.diagnostics(&db, &mut ConsoleSink::buffer(&db, &mut buf));
+    let diagnostics = String::from_utf8(buf.into_inner()).unwrap();
+    assert!(
+        diagnostics.contains("warning: contributions to node m of module 'cancelled' cancel out"),
+        "{diagnostics}"
+    );
+}
+
+#[test]
+fn weak_rows() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module weak_rows(inout a, inout c, inout b);
+            electrical a, c, b, m;
+            parameter real cj=1e-12, r=1k;
+            analog begin
+                I(a, c) <+ V(a, c) / r;
+                I(a, m) <+ ddt(cj * V(a, m));
+                I(m, c) <+ ddt(cj * V(m, c));
+                I(b) <+ ddt(cj * V(b));
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let dae_system = &module.dae_system;
+    let mut weak_rows: Vec<_> = dae_system
+        .weak_rows()
+        .into_iter()
+        .map(|(unknown, row)| (dae_system.unknowns[unknown].name(&db, &module.intern), row))
+        .collect();
+    weak_rows.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+    // unlike reactive_only_nodes the port b is reported as well
+    assert_eq!(
+        weak_rows,
+        [("b".to_owned(), WeakRow::ReactiveOnly), ("m".to_owned(), WeakRow::ReactiveOnly)]
+    );
+
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module empty_row(inout a, inout c);
+            electrical a, c;
+            analog I(a, c) <+ I(a, c);
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let dae_system = &module.dae_system;
+    let weak_rows: Vec<_> = dae_system
+        .weak_rows()
+        .into_iter()
+        .map(|(unknown, row)| (dae_system.unknowns[unknown].name(&db, &module.intern), row))
+        .collect();
+    assert!(weak_rows.contains(&("flow(a,c)".to_owned(), WeakRow::Empty)), "{weak_rows:?}");
+}
+
+#[test]
+fn algebraic_loop() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module algebraic_loop(inout a, inout c);
+            electrical a, c;
+            parameter real r=1k;
+            analog I(a, c) <+ V(a, c) / r + 0.5 * I(a, c);
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    // the current of the branch becomes an unknown with its own equation
+    let ports = module.info.module.ports(&db);
+    let current = CurrentKind::Unnamed { hi: ports[0], lo: Some(ports[1]) };
+    let unknown = module.dae_system.unknowns.index(&SimUnknownKind::Current(current));
+    let unknown = unknown.expect("no equation was created for the algebraic loop");
+    assert!(module.dae_system.jacobian.iter().any(|entry| entry.row == unknown));
+    assert!(module.dae_system.algebraic_loops.is_empty());
+}
+
+#[test]
+fn unsolvable_algebraic_loop() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module unsolvable_loop(inout a, inout c);
+            electrical a, c;
+            analog I(a, c) <+ I(a, c);
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let ports = module.info.module.ports(&db);
+    let current = CurrentKind::Unnamed { hi: ports[0], lo: Some(ports[1]) };
+    assert_eq!(module.dae_system.algebraic_loops, [current]);
+
+    let mut buf = Buffer::no_color();
+    module.diagnostics(&db, &mut ConsoleSink::buffer(&db, &mut buf));
+    let diagnostics = String::from_utf8(buf.into_inner()).unwrap();
+    assert!(
+        diagnostics.contains(
+            "warning: contribution to branch (a, c) in module 'unsolvable_loop' only depends \
+             on the branch itself"
+        ),
+        "{diagnostics}"
+    );
+}
+
+#[test]
+fn gmin_nodes() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module gmin_nodes(inout a, inout c);
+            electrical a, c, m;
+            parameter real is=1e-14, r=1k;
+            analog begin
+                I(a, m) <+ V(a, m) / r;
+                I(m, c) <+ is * (limexp(V(m, c) / $vt) - 1);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let ports = module.info.module.ports(&db);
+    let m = module.info.module.internal_nodes(&db)[0];
+    let unknown =
+        |node| module.dae_system.unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(node));
+    // only the nodes of the diode receive a gmin hint, a is only connected to the resistor
+    let gmin_nodes = &module.dae_system.gmin_nodes;
+    assert_eq!(gmin_nodes.len(), 2, "{gmin_nodes:?}");
+    assert!(gmin_nodes.contains(&unknown(m)), "{gmin_nodes:?}");
+    assert!(gmin_nodes.contains(&unknown(ports[1])), "{gmin_nodes:?}");
+    assert!(!gmin_nodes.contains(&unknown(ports[0])), "{gmin_nodes:?}");
+}
+
+#[test]
+fn unused_nodes() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module unused_nodes(inout a, inout c, inout b);
+            electrical a, c, b, m, unused;
+            parameter real r=1k;
+            analog begin
+                I(a, m) <+ V(a, m) / r;
+                I(m, c) <+ V(m, c) / r;
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let unknowns = &module.dae_system.unknowns;
+    let has_unknown = |node| unknowns.contains(&SimUnknownKind::KirchoffLaw(node));
+    // ports are kept even if nothing is contributed to them
+    let ports = module.info.module.ports(&db);
+    assert!(ports.iter().all(|&port| has_unknown(port)));
+    let internal_nodes = module.info.module.internal_nodes(&db);
+    assert_eq!(internal_nodes[0].name(&db), "m");
+    assert!(has_unknown(internal_nodes[0]));
+    assert_eq!(internal_nodes[1].name(&db), "unused");
+    assert!(!has_unknown(internal_nodes[1]));
+    assert_eq!(unknowns.len(), 4);
+    assert_eq!(module.dae_system.residual.len(), 4);
+}
+
+#[test]
+fn noise_table() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module table_noise(inout a, inout c);
+            electrical a, c;
+            parameter real r=1k;
+            analog begin
+                I(a, c) <+ V(a, c) / r;
+                I(a, c) <+ noise_table_log("noise.tbl", "table");
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    db.add_virtual_file("/noise.tbl", "# f pwr\n1 1e-20\n1e3 1e-22 # corner\n\n1e6 1e-22\n");
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+
+    let noise_sources = &module.dae_system.noise_sources;
+    assert_eq!(noise_sources.len(), 1);
+    assert_eq!(literals.resolve(&noise_sources[0].name), "table");
+    if let NoiseSourceKind::NoiseTable { log, ref vals } = noise_sources[0].kind {
+        assert!(log);
+        // the table is interpolated between the logarithms of its entries
+        assert_eq!(vals.len(), 3);
+        for (&(f, pwr), (expected_f, expected_pwr)) in
+            zip(&**vals, [(0.0, -20.0), (3.0, -22.0), (6.0, -22.0)])
+        {
+            assert_approx_eq!(f64, f64::from(f), expected_f);
+            assert_approx_eq!(f64, f64::from(pwr), expected_pwr);
+        }
+    } else {
+        unreachable!("expected a noise table but found {:?}", noise_sources[0].kind)
+    }
+}
+
+#[test]
+fn noise_sources_by_location() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module grouped_noise(inout a, inout c);
+            electrical a, c;
+            parameter real r=1k;
+            analog begin
+                I(a, c) <+ V(a, c) / r;
+                I(a, c) <+ white_noise(4e-21 / r, "thermal");
+                I(a) <+ white_noise(1e-20, "shunt");
+                I(a, c) <+ flicker_noise(1e-20, 1.0, "flicker");
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+
+    let dae_system = &module.dae_system;
+    let ports = module.info.module.ports(&db);
+    let a = dae_system.unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(ports[0]));
+    let c = dae_system.unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(ports[1]));
+    let groups = dae_system.noise_sources_by_location();
+    let groups: Vec<_> = groups
+        .iter()
+        .map(|(&location, srcs)| {
+            let names: Vec<_> = srcs
+                .iter()
+                .map(|&src| literals.resolve(&dae_system.noise_sources[src].name))
+                .collect();
+            (location, names)
+        })
+        .collect();
+    assert_eq!(groups, [((a, Some(c)), vec!["thermal", "flicker"]), ((a, None), vec!["shunt"])]);
+}
+
+#[test]
+fn identical_case_arms() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module identical_case_arms(inout a, inout c);
+            electrical a, c;
+            parameter integer mode = 0;
+            parameter real r = 1.0;
+            real g;
+            analog begin
+                case (mode)
+                    0: g = V(a, c) / r;
+                    1: g = V(a, c) * r;
+                    2: g = V(a, c) * r;
+                    default: g = 0.0;
+                endcase
+                I(a, c) <+ g;
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn redundant_nested_condition() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module redundant_nested_condition(inout a, inout c);
+            electrical a, c;
+            parameter real r = 1.0;
+            real g;
+            analog begin
+                g = 0.0;
+                if (V(a, c) > 0) begin
+                    g = V(a, c) / r;
+                    if (V(a, c) > 0)
+                        g = g * r;
+                end
+                I(a, c) <+ g;
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn reassociated_derivative() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module reassociated_derivative(inout a, inout c);
+            electrical a, c;
+            analog begin
+                I(a, c) <+ exp(V(a, c)) + exp(2 * V(a, c)) + exp(3 * V(a, c))
+                    + exp(4 * V(a, c)) + exp(5 * V(a, c));
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn shared_scratch() {
+    let compile = |src: &str, scratch: &mut Scratch| {
+        let db = CompilationDB::new_virtual(src).unwrap();
+        let module =
+            crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+        let mut literals = Rodeo::new();
+        let module = CompiledModule::new_with_scratch(&db, &module, &mut literals, scratch);
+        format!("{:#?}\n{:?}", module.dae_system, module.eval)
+    };
+    let diode = fs::read_to_string(integration_test_dir("DIODE").join("diode.va")).unwrap();
+    let resistor =
+        fs::read_to_string(integration_test_dir("RESISTOR").join("resistor.va")).unwrap();
+
+    // the diode has more unknowns and instructions than the resistor so the reused
+    // buffers are larger than required and contain stale values
+    let mut scratch = Scratch::default();
+    compile(&diode, &mut scratch);
+    let shared = compile(&resistor, &mut scratch);
+    let fresh = compile(&resistor, &mut Scratch::default());
+    assert_eq!(shared, fresh);
+    // the buffers are handed back so the next module can reuse them
+    assert!(scratch.dense_row.capacity() >= 3);
+}
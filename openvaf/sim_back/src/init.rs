@@ -9,14 +9,16 @@ use mir::{
     strip_optbarrier, Block, ControlFlowGraph, DominatorTree, FuncRef, Function, Inst,
     InstructionData, Opcode, Value, FALSE,
 };
-use mir_opt::{aggressive_dead_code_elimination, simplify_cfg, ClassId, GVN};
+use mir_opt::{aggressive_dead_code_elimination, simplify_cfg, sink_instructions, ClassId, GVN};
 use stdx::packed_option::PackedOption;
 use stdx::{impl_debug_display, impl_idx_from};
 use typed_indexmap::TiMap;
 
 use crate::context::Context;
+pub use crate::init::model::ModelInitialization;
 use crate::util::strip_optbarrier_if_const;
 
+mod model;
 #[cfg(test)]
 mod tests;
 
@@ -296,6 +298,8 @@ impl<'a> Builder<'a> {
             &self.control_dep,
         );
         simplify_cfg(&mut self.init.func, self.cfg);
+        self.dom_tree.compute(&self.init.func, self.cfg, true, false, false);
+        sink_instructions(&mut self.init.func, self.cfg, self.dom_tree);
     }
 
     fn build_init_itern(&mut self) -> AHashSet<Value> {
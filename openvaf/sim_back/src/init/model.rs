@@ -0,0 +1,155 @@
+use ahash::RandomState;
+use bitset::{BitSet, SparseBitMatrix};
+use hir_lower::{HirInterner, ParamKind};
+use indexmap::IndexMap;
+use mir::builder::InstBuilder;
+use mir::cursor::{Cursor, FuncCursor};
+use mir::{Function, Inst, InstructionData, Value};
+use mir_opt::{aggressive_dead_code_elimination, propagate_taint, simplify_cfg};
+
+use crate::context::Context;
+use crate::init::Initialization;
+
+/// The part of the [`Initialization`] that only depends on model parameters.
+/// These values are identical for all instances of a model and can be computed
+/// once per model (when the model is setup) instead of once per instance.
+/// Only generated if [`ModuleInfo::model_init`](crate::ModuleInfo::model_init) is set.
+#[derive(Debug)]
+pub struct ModelInitialization {
+    pub func: Function,
+    pub intern: HirInterner,
+    /// Maps values of the instance initialization to the values of `func` that
+    /// compute them. Only values that are required by the instance dependent part
+    /// of the initialization (or that are cached/returned by it) are listed here.
+    pub outputs: IndexMap<Value, Value, RandomState>,
+}
+
+impl ModelInitialization {
+    pub(crate) fn new(init: &Initialization, cx: &mut Context) -> ModelInitialization {
+        let instance_dependent = instance_dependent_insts(init, cx);
+
+        // the model initialization starts out as a copy of the instance initialization
+        // so values (and their uses) are the same in both functions
+        let mut func = init.func.clone();
+        func.name = format!("{}_model", init.func.name);
+        let mut intern = init.intern.clone();
+        intern.outputs.clear();
+
+        let mut outputs = IndexMap::with_capacity_and_hasher(64, RandomState::new());
+        for bb in init.func.layout.blocks() {
+            for inst in init.func.layout.block_insts(bb) {
+                if instance_dependent.contains(inst) {
+                    continue;
+                }
+                for &val in init.func.dfg.inst_results(inst) {
+                    if is_model_output(init, &instance_dependent, val) {
+                        let new_val = FuncCursor::new(&mut func)
+                            .after_inst_no_phi(inst)
+                            .ins()
+                            .ensure_optbarrier(val);
+                        outputs.insert(val, new_val);
+                    }
+                }
+            }
+        }
+
+        for bb in init.func.layout.blocks() {
+            for inst in init.func.layout.block_insts(bb) {
+                if !instance_dependent.contains(inst) {
+                    continue;
+                }
+                match init.func.dfg.insts[inst] {
+                    // keep the control structure intact, all blocks that are only
+                    // reachable depending on an instance parameter were removed
+                    InstructionData::Branch { then_dst, else_dst, .. } => {
+                        func.dfg.zap_inst(inst);
+                        func.layout.remove_inst(inst);
+                        FuncCursor::new(&mut func).at_bottom(bb).ins().jump(else_dst);
+                        // bb is no longer a predecessor of then_dst
+                        if then_dst != else_dst {
+                            for phi in func.layout.block_insts(then_dst) {
+                                if func.dfg.try_remove_phi_edge_at(phi, bb).is_none() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    InstructionData::Jump { .. } => (),
+                    _ => {
+                        func.dfg.zap_inst(inst);
+                        func.layout.remove_inst(inst);
+                    }
+                }
+            }
+        }
+
+        let mut live = BitSet::new_empty(func.dfg.num_values());
+        live.extend(outputs.values().copied());
+        cx.cfg.compute(&func);
+        cx.dom_tree.compute(&func, &cx.cfg, true, true, false);
+        let mut control_dep = SparseBitMatrix::new_square(0);
+        cx.dom_tree.compute_postdom_frontiers(&cx.cfg, &mut control_dep);
+        aggressive_dead_code_elimination(
+            &mut func,
+            &mut cx.cfg,
+            &|val, _| live.contains(val),
+            &control_dep,
+        );
+        simplify_cfg(&mut func, &mut cx.cfg);
+
+        ModelInitialization { func, intern, outputs }
+    }
+}
+
+/// Finds all instructions of the initialization that depend on instance
+/// parameters (or the simulator) either directly or through control flow.
+fn instance_dependent_insts(init: &Initialization, cx: &mut Context) -> BitSet<Inst> {
+    let mut tainted = Vec::new();
+    for (kind, &val) in init.intern.params.iter() {
+        let model_param = match *kind {
+            ParamKind::Param(param) | ParamKind::ParamGiven { param } => {
+                cx.module.params.get(&param).map_or(false, |info| !info.is_instance)
+            }
+            _ => false,
+        };
+        if !model_param {
+            tainted.push(val)
+        }
+    }
+
+    // callbacks may have side effects or be evaluated by the simulator
+    // so they are always evaluated per instance
+    let mut calls = Vec::new();
+    for bb in init.func.layout.blocks() {
+        for inst in init.func.layout.block_insts(bb) {
+            if let InstructionData::Call { .. } = init.func.dfg.insts[inst] {
+                tainted.extend_from_slice(init.func.dfg.inst_results(inst));
+                calls.push(inst);
+            }
+        }
+    }
+
+    cx.cfg.compute(&init.func);
+    cx.dom_tree.compute(&init.func, &cx.cfg, true, true, false);
+    let mut instance_dependent = BitSet::new_empty(init.func.dfg.num_insts());
+    propagate_taint(
+        &init.func,
+        &cx.dom_tree,
+        &cx.cfg,
+        tainted.into_iter(),
+        &mut instance_dependent,
+    );
+    for inst in calls {
+        instance_dependent.insert(inst);
+    }
+    instance_dependent
+}
+
+fn is_model_output(init: &Initialization, instance_dependent: &BitSet<Inst>, val: Value) -> bool {
+    init.cached_vals.contains_key(&val)
+        || init.intern.outputs.values().any(|out| out.expand() == Some(val))
+        || init.func.dfg.uses(val).any(|use_| {
+            let user = init.func.dfg.use_to_operand(use_).0;
+            instance_dependent.contains(user)
+        })
+}
@@ -3,14 +3,17 @@ use std::fs;
 use expect_test::expect_file;
 use hir::diagnostics::ConsoleSink;
 use hir::CompilationDB;
+use hir_lower::{CallBackKind, ParamKind};
 use indoc::indoc;
 use lasso::Rodeo;
+use mir::{Const, Function, Opcode};
 use stdx::{integration_test_dir, openvaf_test_data};
 
 use crate::context::{Context, OptimiziationStage};
 use crate::dae::DaeSystem;
 use crate::init::Initialization;
 use crate::topology::Topology;
+use crate::{const_param_default, CompiledModule};
 
 fn run_test(src: &str) {
     let db = CompilationDB::new_virtual(src).unwrap();
@@ -30,6 +33,7 @@ fn run_test(src: &str) {
 
     cx.refresh_op_dependent_insts();
     let init = Initialization::new(&mut cx, gvn);
+    cx.sink_instructions();
     let name = module.module.name(&db);
     let test_dir = openvaf_test_data("init");
     let topology = format!("{:#?}\n{:#?}", init.cached_vals, init.cache_slots);
@@ -93,3 +97,177 @@ fn analysis() {
     "#};
     run_test(src);
 }
+
+fn contains_opcode(func: &Function, opcode: Opcode) -> bool {
+    func.layout
+        .blocks()
+        .flat_map(|bb| func.layout.block_insts(bb))
+        .any(|inst| func.dfg.insts[inst].opcode() == opcode)
+}
+
+#[test]
+fn events() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module events(inout a, inout c);
+            electrical a, c;
+            parameter real r=1.0;
+            real g;
+            analog begin
+                @(initial_step) g = 1.0 / r;
+                I(a, c) <+ g * V(a, c);
+                @(final_step) $strobe("g=%g", g);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+
+    // the initial_step assignment is only computed during setup
+    assert!(contains_opcode(&module.init.func, Opcode::Fdiv));
+    assert!(!contains_opcode(&module.eval, Opcode::Fdiv));
+    // final_step is never executed during an iteration
+    assert!(!module.intern.callbacks.iter().any(|cb| matches!(cb, CallBackKind::Print { .. })));
+}
+
+#[test]
+fn param_given_alias() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module param_given_alias(inout a, inout c);
+            electrical a, c;
+            aliasparam rr = r;
+            parameter real r=1.0;
+            real g;
+            analog begin
+                if ($param_given(rr))
+                    g = 1.0 / r;
+                else
+                    g = 0.0;
+                I(a, c) <+ g * V(a, c);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let (&param, info) = module.params.first().unwrap();
+    assert_eq!(param.name(&db), "r");
+    // the alias is registered even though it is declared before the parameter
+    assert_eq!(info.alias, ["rr"]);
+
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    // $param_given(rr) reports whether r was given
+    assert!(module.init.intern.is_param_live(&module.init.func, &ParamKind::ParamGiven { param }));
+}
+
+#[test]
+fn param_defaults() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module param_defaults(inout a, inout c);
+            electrical a, c;
+            parameter real r=2.5;
+            parameter integer n=3;
+            parameter real g=n / r;
+            parameter integer m=2 + 3;
+            parameter real s=sqrt(4);
+            analog I(a, c) <+ g * V(a, c);
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let params: Vec<_> = module.params.keys().copied().collect();
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let default =
+        |param| const_param_default(&module.model_param_setup, &module.model_param_intern, param);
+
+    assert_eq!(default(params[0]), Some(Const::Float(2.5.into())));
+    assert_eq!(default(params[1]), Some(Const::Int(3)));
+    // depends on other parameters and is only known after model setup
+    assert_eq!(default(params[2]), None);
+    // parameter independent expressions are folded
+    assert_eq!(default(params[3]), Some(Const::Int(5)));
+    assert_eq!(default(params[4]), Some(Const::Float(2.0.into())));
+}
+
+fn compile_model_init<'a>(
+    db: &CompilationDB,
+    module: &'a mut crate::ModuleInfo,
+    literals: &mut Rodeo,
+) -> CompiledModule<'a> {
+    module.model_init = true;
+    CompiledModule::new(db, module, literals)
+}
+
+#[test]
+fn model_init() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module model_init(inout a, inout c);
+            electrical a, c;
+            parameter real r=1.0;
+            (* type="instance" *) parameter real w=1.0;
+            real g, h;
+            analog begin
+                g = exp(1.0 / r);
+                h = sqrt(g * w);
+                I(a, c) <+ h * V(a, c);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = compile_model_init(&db, &mut module, &mut literals);
+    let model_init = module.model_init.as_ref().unwrap();
+
+    // the instance initialization still computes everything
+    assert!(contains_opcode(&module.init.func, Opcode::Exp));
+    assert!(contains_opcode(&module.init.func, Opcode::Sqrt));
+    // g only depends on model parameters
+    assert!(contains_opcode(&model_init.func, Opcode::Exp));
+    assert!(!model_init.outputs.is_empty());
+    // h depends on the instance parameter w
+    assert!(!contains_opcode(&model_init.func, Opcode::Sqrt));
+    assert!(!contains_opcode(&model_init.func, Opcode::Fmul));
+}
+
+#[test]
+fn model_init_instance_branch() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module model_init_instance_branch(inout a, inout c);
+            electrical a, c;
+            parameter real r=1.0;
+            (* type="instance" *) parameter real w=1.0;
+            real g, h;
+            analog begin
+                g = exp(1.0 / r);
+                h = g;
+                if (w > 1.0)
+                    h = sqrt(g);
+                if (w < 0.5) begin
+                end else
+                    h = ln(h);
+                I(a, c) <+ g * h * V(a, c);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = compile_model_init(&db, &mut module, &mut literals);
+    let model_init = module.model_init.as_ref().unwrap();
+
+    // the branches on w are removed without leaving phi edges of removed predecessors behind
+    assert!(model_init.func.validate());
+    assert!(contains_opcode(&model_init.func, Opcode::Exp));
+    assert!(!contains_opcode(&model_init.func, Opcode::Sqrt));
+    assert!(!contains_opcode(&model_init.func, Opcode::Ln));
+}
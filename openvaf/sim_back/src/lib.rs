@@ -1,23 +1,37 @@
-use hir::{BranchWrite, CompilationDB, Node};
-use hir_lower::{CurrentKind, HirInterner, ImplicitEquation, ParamKind};
+use std::mem::take;
+
+use ahash::AHashMap;
+use hir::diagnostics::{BaseDB, Diagnostic, FileId, Report};
+use hir::{BranchWrite, CompilationDB, DiagnosticSink, Nature, Node, Parameter};
+use hir_lower::{CurrentKind, HirInterner, ImplicitEquation, ParamKind, PlaceKind};
 use lasso::Rodeo;
-use mir::Function;
-use mir_opt::{simplify_cfg, sparse_conditional_constant_propagation};
-pub use module_info::{collect_modules, ModuleInfo};
+use mir::{strip_optbarrier, Const, ControlFlowGraph, Function};
+use mir_opt::{register_pressure, simplify_cfg, sparse_conditional_constant_propagation};
+pub use module_info::{collect_modules, ModuleInfo, ReactiveFormulation};
 use stdx::impl_debug_display;
 
+use crate::abstol::abstol_natures;
+pub use crate::abstol::{nature_abstol, Abstol};
 use crate::context::{Context, OptimiziationStage};
+pub use crate::crash_context::{CrashContext, CrashContextGuard};
 use crate::dae::DaeSystem;
-use crate::init::Initialization;
+use crate::init::{Initialization, ModelInitialization};
 use crate::node_collapse::NodeCollapse;
+use crate::param_fmt::param_ranges;
+pub use crate::param_fmt::{fmt_param, ParamRange};
+pub use crate::scratch::Scratch;
 use crate::topology::Topology;
 
+mod abstol;
 mod context;
+mod crash_context;
 pub mod dae;
 pub mod init;
 mod module_info;
 pub mod node_collapse;
 mod noise;
+mod param_fmt;
+mod scratch;
 mod topology;
 
 mod util;
@@ -40,14 +54,69 @@ impl_debug_display! {
     }
 }
 
+impl SimUnknownKind {
+    /// The name of the unknown as shown to users (like `a` for the Kirchhoff law
+    /// of node `a` or `flow(br)` for the current of branch `br`).
+    pub fn name(self, db: &CompilationDB, intern: &HirInterner) -> String {
+        match self {
+            SimUnknownKind::KirchoffLaw(node) => node.name(db).to_string(),
+            SimUnknownKind::Current(CurrentKind::Unnamed { hi, lo: Some(lo) }) => {
+                format!("flow({},{})", &hi.name(db), &lo.name(db))
+            }
+            SimUnknownKind::Current(CurrentKind::Unnamed { hi, lo: None }) => {
+                format!("flow({})", &hi.name(db))
+            }
+            SimUnknownKind::Current(CurrentKind::Branch(br)) => format!("flow({})", &br.name(db)),
+            SimUnknownKind::Current(CurrentKind::Port(node)) => {
+                format!("flow(<{}>)", &node.name(db))
+            }
+            SimUnknownKind::Implicit(equ) => intern.implicit_equation_name(equ),
+        }
+    }
+
+    /// Whether the unknown is a current (flow) instead of a potential.
+    pub fn is_flow(self) -> bool {
+        matches!(self, SimUnknownKind::Current(_))
+    }
+
+    /// The nature of the unknown (`None` for implicit equations).
+    pub fn nature(self, db: &CompilationDB) -> Option<Nature> {
+        let discipline = match self {
+            SimUnknownKind::KirchoffLaw(node)
+            | SimUnknownKind::Current(CurrentKind::Port(node)) => node.discipline(db),
+            SimUnknownKind::Current(CurrentKind::Unnamed { hi, .. }) => hi.discipline(db),
+            SimUnknownKind::Current(CurrentKind::Branch(br)) => br.discipline(db),
+            SimUnknownKind::Implicit(_) => return None,
+        };
+        // its valid to have disciplines without pot/flow nature but then we can't
+        // have branches for those so its ok to unwrap here
+        let nature = if self.is_flow() { discipline.flow(db) } else { discipline.potential(db) };
+        Some(nature.unwrap())
+    }
+}
+
 pub struct CompiledModule<'a> {
     pub info: &'a ModuleInfo,
     pub dae_system: DaeSystem,
     pub eval: Function,
+    /// A variant of `eval` that only computes the residual (no jacobian entries).
+    /// Only generated if [`ModuleInfo::residual_eval`] is set.
+    pub residual_eval: Option<Function>,
     pub intern: HirInterner,
     pub init: Initialization,
+    /// The part of `init` that only depends on model parameters.
+    /// Only generated if [`ModuleInfo::model_init`] is set.
+    pub model_init: Option<ModelInitialization>,
     pub model_param_setup: Function,
     pub model_param_intern: HirInterner,
+    /// Natures whose `abstol` depends on values that are not known during model setup.
+    /// Their abstol is ignored.
+    pub ignored_abstols: Vec<Nature>,
+    /// The ranges allowed by the `from` constraints of the parameters (see [`fmt_param`]).
+    pub param_ranges: AHashMap<Parameter, ParamRange>,
+    /// Parameters whose constant default value is outside of their range
+    /// with their declaration formatted by [`fmt_param`].
+    pub invalid_param_defaults: Vec<(Parameter, String)>,
     pub node_collapse: NodeCollapse,
 }
 
@@ -57,12 +126,28 @@ impl<'a> CompiledModule<'a> {
         module: &'a ModuleInfo,
         literals: &mut Rodeo,
     ) -> CompiledModule<'a> {
+        Self::new_with_scratch(db, module, literals, &mut Scratch::default())
+    }
+
+    /// Same as [`CompiledModule::new`] but reuses the temporary buffers in `scratch`
+    /// instead of allocating them again. This avoids repeated allocations when
+    /// compiling multiple modules.
+    pub fn new_with_scratch(
+        db: &CompilationDB,
+        module: &'a ModuleInfo,
+        literals: &mut Rodeo,
+        scratch: &mut Scratch,
+    ) -> CompiledModule<'a> {
+        CrashContext::set_module(module.module.name(db));
+        CrashContext::set_phase("lowering");
         let mut cx = Context::new(db, literals, module);
+        cx.scratch = take(scratch);
         cx.compute_outputs(true);
         cx.compute_cfg();
         cx.optimize(OptimiziationStage::Initial);
         debug_assert!(cx.func.validate());
 
+        CrashContext::set_phase("dae system");
         let topology = Topology::new(&mut cx);
         debug_assert!(cx.func.validate());
         let mut dae_system = DaeSystem::new(&mut cx, topology);
@@ -164,10 +249,13 @@ impl<'a> CompiledModule<'a> {
 
         debug_assert!(cx.func.validate());
 
-        cx.refresh_op_dependent_insts();
+        CrashContext::set_phase("initialization");
+        // the op dependent instructions were kept up to date by `DaeSystem::sparsify`
         let mut init = Initialization::new(&mut cx, gvn);
         let node_collapse = NodeCollapse::new(&init, &dae_system, &cx);
+        cx.sink_instructions();
         debug_assert!(cx.func.validate());
+        let residual_eval = module.residual_eval.then(|| dae_system.residual_eval(&cx));
 
         // For debugging purposes - print MIR
         if debugging {
@@ -178,6 +266,7 @@ impl<'a> CompiledModule<'a> {
 
         debug_assert!(init.func.validate());
 
+        CrashContext::set_phase("parameter initialization");
         // TODO: refactor param intilization to use tables
         let inst_params: Vec<_> = module
             .params
@@ -185,10 +274,20 @@ impl<'a> CompiledModule<'a> {
             .filter_map(|(param, info)| info.is_instance.then_some(*param))
             .collect();
         init.intern.insert_param_init(db, &mut init.func, literals, false, true, &inst_params);
+        let model_init = module.model_init.then(|| ModelInitialization::new(&init, &mut cx));
+        debug_assert!(model_init.as_ref().map_or(true, |init| init.func.validate()));
 
         let mut model_param_setup = Function::default();
         let model_params: Vec<_> = module.params.keys().copied().collect();
         let mut model_param_intern = HirInterner::default();
+        // the abstols are lowered first so that the parameter initialization is placed before them
+        let (setup_abstols, ignored_abstols) = abstol_natures(db, &dae_system, literals);
+        model_param_intern.insert_nature_abstol(
+            db,
+            &mut model_param_setup,
+            literals,
+            &setup_abstols,
+        );
         model_param_intern.insert_param_init(
             db,
             &mut model_param_setup,
@@ -201,16 +300,243 @@ impl<'a> CompiledModule<'a> {
         simplify_cfg(&mut model_param_setup, &mut cx.cfg);
         sparse_conditional_constant_propagation(&mut model_param_setup, &cx.cfg);
         simplify_cfg(&mut model_param_setup, &mut cx.cfg);
+        let param_ranges = param_ranges(db, literals, &model_params);
+        *scratch = take(&mut cx.scratch);
 
-        CompiledModule {
+        let mut res = CompiledModule {
             eval: cx.func,
+            residual_eval,
             intern: cx.intern,
             info: module,
             dae_system,
             init,
+            model_init,
             model_param_intern,
             model_param_setup,
+            ignored_abstols,
+            param_ranges,
+            invalid_param_defaults: Vec::new(),
             node_collapse,
+        };
+        res.invalid_param_defaults = model_params
+            .iter()
+            .filter(|param| {
+                let range = if let Some(range) = res.param_ranges.get(param) {
+                    range
+                } else {
+                    return false;
+                };
+                const_param_default(&res.model_param_setup, &res.model_param_intern, **param)
+                    .map_or(false, |default| range.excludes(default))
+            })
+            .map(|&param| (param, fmt_param(db, &res, literals, param)))
+            .collect();
+        res
+    }
+}
+
+impl CompiledModule<'_> {
+    /// Estimates the register pressure of the eval function (the maximum number of values
+    /// that are live at the same time, see [`mir_opt::register_pressure`]).
+    pub fn eval_register_pressure(&self) -> usize {
+        let mut cfg = ControlFlowGraph::new();
+        cfg.compute(&self.eval);
+        register_pressure(&self.eval, &cfg)
+    }
+
+    /// Reports problems that are only detected while building the DAE system
+    /// (like current probes of branches that are never defined, nodes that
+    /// only have reactive contributions, contributions that cancel out or
+    /// unsolvable algebraic loops) and parameter defaults outside of their range.
+    pub fn diagnostics(&self, db: &CompilationDB, sink: &mut impl DiagnosticSink) {
+        let root_file = db.compilation_unit().root_file();
+        for &current in &self.dae_system.orphaned_current_probes {
+            let branch = match branch_name(current, db) {
+                Some(branch) => branch,
+                None => continue,
+            };
+            let diag = OrphanedCurrentProbe { module: self.info.module.name(db), branch };
+            sink.add_diagnostic(&diag, root_file, db);
+        }
+        for &current in &self.dae_system.algebraic_loops {
+            let branch = match branch_name(current, db) {
+                Some(branch) => branch,
+                None => continue,
+            };
+            let diag = AlgebraicLoop { module: self.info.module.name(db), branch };
+            sink.add_diagnostic(&diag, root_file, db);
+        }
+        for &node in &self.dae_system.reactive_only_nodes {
+            let diag = ReactiveOnlyNode {
+                module: self.info.module.name(db),
+                node: node.name(db).to_string(),
+            };
+            sink.add_diagnostic(&diag, root_file, db);
+        }
+        for &node in &self.dae_system.cancelled_nodes {
+            let diag = CancelledNode {
+                module: self.info.module.name(db),
+                node: node.name(db).to_string(),
+            };
+            sink.add_diagnostic(&diag, root_file, db);
+        }
+        for &nature in &self.ignored_abstols {
+            let diag = IgnoredAbstol { module: self.info.module.name(db), nature: nature.name(db) };
+            sink.add_diagnostic(&diag, root_file, db);
+        }
+        for (param, declaration) in &self.invalid_param_defaults {
+            let diag = InvalidParamDefault {
+                module: self.info.module.name(db),
+                param: self.info.params[param].name.to_string(),
+                declaration: declaration.clone(),
+            };
+            sink.add_diagnostic(&diag, root_file, db);
         }
     }
 }
+
+fn branch_name(current: CurrentKind, db: &CompilationDB) -> Option<String> {
+    let name = match current {
+        CurrentKind::Branch(branch) => branch.name(db),
+        CurrentKind::Unnamed { hi, lo: Some(lo) } => format!("({}, {})", hi.name(db), lo.name(db)),
+        CurrentKind::Unnamed { hi, lo: None } => format!("({})", hi.name(db)),
+        CurrentKind::Port(_) => return None,
+    };
+    Some(name)
+}
+
+struct OrphanedCurrentProbe {
+    module: String,
+    branch: String,
+}
+
+impl Diagnostic for OrphanedCurrentProbe {
+    fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
+        Report::warning()
+            .with_message(format!(
+                "current of branch {} is probed in module '{}' but never defined",
+                self.branch, self.module
+            ))
+            .with_notes(vec![
+                format!("help: there are no contributions to branch {}", self.branch),
+                "info: the probe always returns zero".to_owned(),
+            ])
+    }
+}
+
+struct AlgebraicLoop {
+    module: String,
+    branch: String,
+}
+
+impl Diagnostic for AlgebraicLoop {
+    fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
+        Report::warning()
+            .with_message(format!(
+                "contribution to branch {} in module '{}' only depends on the branch itself",
+                self.branch, self.module
+            ))
+            .with_notes(vec![
+                "info: the branch equation cancels out and can not be solved".to_owned(),
+                "info: this makes the jacobian matrix singular".to_owned(),
+                format!(
+                    "help: remove the probe of branch {} from its own contribution",
+                    self.branch
+                ),
+            ])
+    }
+}
+
+struct ReactiveOnlyNode {
+    module: String,
+    node: String,
+}
+
+impl Diagnostic for ReactiveOnlyNode {
+    fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
+        Report::warning()
+            .with_message(format!(
+                "node {} of module '{}' only has reactive (ddt) contributions",
+                self.node, self.module
+            ))
+            .with_notes(vec![
+                format!("info: node {} has no conductance during DC analysis", self.node),
+                "info: this is a common cause of DC convergence failures".to_owned(),
+                "help: add a resistive path from the node (for example a large resistor)"
+                    .to_owned(),
+            ])
+    }
+}
+
+struct CancelledNode {
+    module: String,
+    node: String,
+}
+
+impl Diagnostic for CancelledNode {
+    fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
+        Report::warning()
+            .with_message(format!(
+                "contributions to node {} of module '{}' cancel out",
+                self.node, self.module
+            ))
+            .with_notes(vec![
+                format!("info: the currents contributed to node {} sum up to zero", self.node),
+                "info: the node is floating which makes the jacobian matrix singular".to_owned(),
+                "help: check the signs and directions of the contributions".to_owned(),
+            ])
+    }
+}
+
+struct IgnoredAbstol {
+    module: String,
+    nature: String,
+}
+
+impl Diagnostic for IgnoredAbstol {
+    fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
+        Report::warning()
+            .with_message(format!(
+                "abstol of nature {} can not be computed during the model setup of module '{}'",
+                self.nature, self.module
+            ))
+            .with_notes(vec![
+                "info: the abstol may only depend on constants and $simparam".to_owned(),
+                "info: the abstol is ignored and the simulator default is used instead".to_owned(),
+            ])
+    }
+}
+
+struct InvalidParamDefault {
+    module: String,
+    param: String,
+    declaration: String,
+}
+
+impl Diagnostic for InvalidParamDefault {
+    fn build_report(&self, _root_file: FileId, _db: &dyn BaseDB) -> Report {
+        Report::warning()
+            .with_message(format!(
+                "default value of parameter {} of module '{}' is outside of its range",
+                self.param, self.module
+            ))
+            .with_notes(vec![
+                format!("info: the parameter is declared as {}", self.declaration),
+                format!("info: the model setup fails unless {} is given", self.param),
+            ])
+    }
+}
+
+/// Returns the default value of `param` if it is a compile time constant.
+/// `model_param_setup` is constant folded so this includes defaults like
+/// `2 + 3` or `sqrt(4)`. Defaults that depend on other parameters are only
+/// known once the model has been setup, `None` is returned for those.
+pub fn const_param_default(
+    model_param_setup: &Function,
+    model_param_intern: &HirInterner,
+    param: Parameter,
+) -> Option<Const> {
+    // the default value stored by `model_param_setup` is wrapped in an optbarrier
+    let val = model_param_intern.outputs.get(&PlaceKind::Param(param))?.expand()?;
+    model_param_setup.dfg.value_def(strip_optbarrier(model_param_setup, val)).as_const()
+}
@@ -1,3 +1,5 @@
+use std::mem::take;
+
 use ahash::AHashSet;
 use hir::diagnostics::{BaseDB, ConsoleSink, Diagnostic, FileId, Label, LabelStyle, Report};
 use hir::{
@@ -45,6 +47,53 @@ pub struct ModuleInfo {
     pub params: IndexMap<Parameter, ParamInfo, ahash::RandomState>,
     pub sys_fun_alias: IndexMap<ParamSysFun, Vec<SmolStr>, ahash::RandomState>,
     pub op_vars: IndexMap<Variable, OpVar, ahash::RandomState>,
+    /// Parameters for which the derivatives of the residual are generated
+    /// (see [`DaeSystem::param_sensitivities`](crate::dae::DaeSystem::param_sensitivities)).
+    /// Empty by default.
+    pub sensitivity_params: Vec<Parameter>,
+    /// Whether a variant of the eval function that only computes the residual
+    /// is generated (see [`CompiledModule::residual_eval`](crate::CompiledModule::residual_eval)).
+    /// Disabled by default.
+    pub residual_eval: bool,
+    /// Whether the part of the initialization that only depends on model parameters
+    /// is split out (see [`CompiledModule::model_init`](crate::CompiledModule::model_init)).
+    /// Disabled by default.
+    pub model_init: bool,
+    /// Reference conductance `g` used to normalize the Kirchhoff laws. If set, the
+    /// residual (including the limiting rhs) of every node and the corresponding row
+    /// of the jacobian and of the parameter sensitivities are divided by `g` during code
+    /// generation. Scaling a row of both the residual and the jacobian does not change
+    /// the solution of the newton iteration but the residual of a node is no longer a
+    /// current (it has the unit of a voltage instead). Equations of branch currents and
+    /// implicit equations as well as noise sources are not scaled.
+    /// Disabled by default.
+    pub reference_conductance: Option<f64>,
+    /// How the reactive residual is formulated, see [`ReactiveFormulation`].
+    /// Defaults to [`ReactiveFormulation::Charge`].
+    pub reactive_formulation: ReactiveFormulation,
+    /// Branches that are linear resistors (`I(a, b) <+ V(a, b) / R` or `I(a, b) <+ G * V(a, b)`)
+    /// whose resistance only depends on parameters are collapsed if the resistance is below
+    /// this threshold (in ohm). Collapsing removes a node from the system which makes the
+    /// simulation faster, but the voltage drop across the resistor (and its noise) is lost.
+    /// Disabled by default, so only the collapse hints of the model (`V(a, b) <+ 0`) are used.
+    pub collapse_resistance: Option<f64>,
+}
+
+/// The formulation of the reactive part of the DAE system (`ddt(Q(x))`).
+/// The reactive jacobian always contains the capacitances `C = ddx(Q, x)`,
+/// the formulations only differ in the reactive residual.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ReactiveFormulation {
+    /// The reactive residual is the charge `Q(x)` exactly as contributed by the model.
+    /// The simulator integrates the charge (`ddt` is applied by the simulator),
+    /// which conserves charge for nonlinear capacitances.
+    #[default]
+    Charge,
+    /// The reactive residual is the charge of the linearized capacitances `C(x) x`
+    /// (the product of the reactive jacobian and the unknowns). This matches simulators
+    /// that integrate `C dx/dt` instead of the charge. Both formulations are identical
+    /// for linear capacitances. Parameter sensitivities still refer to the charge.
+    Capacitance,
 }
 
 impl ModuleInfo {
@@ -167,17 +216,16 @@ impl ModuleInfo {
                         }
                     };
 
-                    params.insert(
-                        param,
-                        ParamInfo {
-                            name: declarations.to_path(name),
-                            alias: Vec::new(),
-                            unit: units,
-                            description: desc,
-                            group,
-                            is_instance,
-                        },
-                    );
+                    // aliases may be declared before the parameter they refer to
+                    let info = params.entry(param).or_default();
+                    *info = ParamInfo {
+                        name: declarations.to_path(name),
+                        alias: take(&mut info.alias),
+                        unit: units,
+                        description: desc,
+                        group,
+                        is_instance,
+                    };
                 }
 
                 ScopeDef::AliasParameter(alias) => match alias.resolve(db).unwrap() {
@@ -193,7 +241,18 @@ impl ModuleInfo {
             }
         }
 
-        ModuleInfo { module, params, op_vars, sys_fun_alias }
+        ModuleInfo {
+            module,
+            params,
+            op_vars,
+            sys_fun_alias,
+            sensitivity_params: Vec::new(),
+            residual_eval: false,
+            model_init: false,
+            reference_conductance: None,
+            reactive_formulation: ReactiveFormulation::Charge,
+            collapse_resistance: None,
+        }
     }
 }
 
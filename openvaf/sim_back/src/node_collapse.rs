@@ -1,5 +1,5 @@
 use bitset::HybridBitSet;
-use hir::BranchWrite;
+use hir::{BranchWrite, Node};
 use hir_lower::{CallBackKind, PlaceKind};
 use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiVec;
@@ -50,9 +50,14 @@ impl NodeCollapse {
                 } else {
                     continue;
                 };
-                let lo =
-                    lo.map(|lo| dae_system.unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(lo)));
-                let hi = dae_system.unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(hi));
+                // ground has no unknown
+                let node =
+                    |node: Node| dae_system.unknowns.index(&SimUnknownKind::KirchoffLaw(node));
+                let (hi, lo) = match (node(hi), lo.and_then(node)) {
+                    (Some(hi), lo) => (hi, lo),
+                    (None, Some(lo)) => (lo, None),
+                    (None, None) => continue,
+                };
                 let source_pair: Option<CollapsePair> = pairs.index(&(hi, lo)).or_else(|| {
                     let lo = lo?;
                     pairs.index(&(lo, Some(hi)))
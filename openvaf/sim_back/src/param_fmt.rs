@@ -0,0 +1,191 @@
+use std::fmt::Write;
+
+use ahash::AHashMap;
+use hir::{CompilationDB, ConstraintValue, Expr, Literal, Parameter, Type};
+use hir_lower::{CallBackKind, HirInterner, ParamInfoKind, PlaceKind};
+use lasso::Rodeo;
+use mir::{
+    strip_optbarrier, Const, ControlFlowGraph, DisplayStr, Function, InstructionData, Value,
+};
+use mir_opt::{simplify_cfg, sparse_conditional_constant_propagation};
+use syntax::ast::{ConstraintKind, UnaryOp};
+
+use crate::{const_param_default, CompiledModule};
+
+#[cfg(test)]
+mod tests;
+
+/// The range of values allowed by the `from` constraints of a numeric parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamRange {
+    /// The bounds depend on other parameters.
+    Computed,
+    /// Constant bounds, `None` if the range is unbounded (`-inf` or `inf`).
+    Const { min: Option<Const>, min_inclusive: bool, max: Option<Const>, max_inclusive: bool },
+}
+
+impl ParamRange {
+    /// Returns whether `val` is outside of the range. Always `false` for computed ranges.
+    pub fn excludes(&self, val: Const) -> bool {
+        let (min, min_inclusive, max, max_inclusive) = match *self {
+            ParamRange::Computed => return false,
+            ParamRange::Const { min, min_inclusive, max, max_inclusive } => {
+                (min, min_inclusive, max, max_inclusive)
+            }
+        };
+        let val = if let Some(val) = as_f64(val) { val } else { return false };
+        let below = match min.and_then(as_f64) {
+            Some(min) if min_inclusive => val < min,
+            Some(min) => val <= min,
+            None => false,
+        };
+        let above = match max.and_then(as_f64) {
+            Some(max) if max_inclusive => val > max,
+            Some(max) => val >= max,
+            None => false,
+        };
+        below || above
+    }
+}
+
+fn as_f64(val: Const) -> Option<f64> {
+    match val {
+        Const::Float(val) => Some(val.into()),
+        Const::Int(val) => Some(val.into()),
+        _ => None,
+    }
+}
+
+/// Computes the ranges allowed by the `from` constraints of `params`. Parameters
+/// without a range are not included.
+/// `literals` must be the interner that is used to build the module.
+pub(crate) fn param_ranges(
+    db: &CompilationDB,
+    literals: &mut Rodeo,
+    params: &[Parameter],
+) -> AHashMap<Parameter, ParamRange> {
+    let params: Vec<_> = params
+        .iter()
+        .copied()
+        .filter(|param| {
+            matches!(param.ty(db), Type::Real | Type::Integer)
+                && param.bounds(db).iter().any(|bound| {
+                    bound.kind == ConstraintKind::From
+                        && matches!(bound.val, ConstraintValue::Range(_))
+                })
+        })
+        .collect();
+    if params.is_empty() {
+        return AHashMap::default();
+    }
+
+    // the bounds are computed by the parameter initialization so that
+    // constant expressions (like `1/2`) are folded
+    let mut func = Function::default();
+    let mut intern = HirInterner::default();
+    intern.insert_param_init(db, &mut func, literals, true, false, &params);
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    simplify_cfg(&mut func, &mut cfg);
+    sparse_conditional_constant_propagation(&mut func, &cfg);
+    simplify_cfg(&mut func, &mut cfg);
+
+    // whether a bound is inclusive is reported with a callback
+    let mut called = Vec::new();
+    for bb in func.layout.blocks() {
+        for inst in func.layout.block_insts(bb) {
+            if let InstructionData::Call { func_ref, .. } = func.dfg.insts[inst] {
+                called.push(func_ref)
+            }
+        }
+    }
+
+    let is_called =
+        |kind| intern.callbacks.index(&kind).map_or(false, |func_ref| called.contains(&func_ref));
+    let bound = |place| -> Option<Const> {
+        let val: Value = intern.outputs.get(&place)?.expand()?;
+        func.dfg.value_def(strip_optbarrier(&func, val)).as_const()
+    };
+
+    params
+        .into_iter()
+        .map(|param| {
+            let range = match (bound(PlaceKind::ParamMin(param)), bound(PlaceKind::ParamMax(param)))
+            {
+                (Some(min), Some(max)) => {
+                    // an integer `inf` is lowered to `i32::MAX` so unbounded ranges
+                    // are detected with the source code of the bounds
+                    let (neg_inf, inf) = inf_bounds(db, param);
+                    ParamRange::Const {
+                        min: (!neg_inf).then_some(min),
+                        min_inclusive: !is_called(CallBackKind::ParamInfo(
+                            ParamInfoKind::MinExclusive,
+                            param,
+                        )),
+                        max: (!inf).then_some(max),
+                        max_inclusive: !is_called(CallBackKind::ParamInfo(
+                            ParamInfoKind::MaxExclusive,
+                            param,
+                        )),
+                    }
+                }
+                _ => ParamRange::Computed,
+            };
+            (param, range)
+        })
+        .collect()
+}
+
+/// Returns whether any `from` range of `param` starts at `-inf` or ends at `inf`.
+fn inf_bounds(db: &CompilationDB, param: Parameter) -> (bool, bool) {
+    let body = param.init(db);
+    let body = body.borrow();
+    let is_inf = |expr| body.as_literal(expr) == Some(&Literal::Inf);
+    let mut res = (false, false);
+    for bound in param.bounds(db).iter() {
+        if let (ConstraintKind::From, ConstraintValue::Range(range)) = (bound.kind, bound.val) {
+            if let Expr::UnaryOp { expr, op: UnaryOp::Neg } = body.get_expr(range.start) {
+                res.0 |= is_inf(expr);
+            }
+            res.1 |= is_inf(range.end);
+        }
+    }
+    res
+}
+
+/// Renders `param` of `module` in a single line of Verilog-A with its type, default value,
+/// the range allowed by its `from` constraints, units and description (like
+/// `real r = 1k from (0.0:inf) units="Ohm" desc="resistance"`). Defaults and bounds
+/// that depend on other parameters are printed as `(computed)`. `literals` must be the
+/// interner that was used to build `module`.
+pub fn fmt_param(
+    db: &CompilationDB,
+    module: &CompiledModule,
+    literals: &Rodeo,
+    param: Parameter,
+) -> String {
+    let info = &module.info.params[&param];
+    let mut dst = format!("{} {} = ", param.ty(db), info.name);
+    match const_param_default(&module.model_param_setup, &module.model_param_intern, param) {
+        Some(val) => write!(dst, "{}", val.display(literals)).unwrap(),
+        None => dst.push_str("(computed)"),
+    }
+    match module.param_ranges.get(&param) {
+        Some(ParamRange::Computed) => dst.push_str(" from (computed)"),
+        Some(&ParamRange::Const { min, min_inclusive, max, max_inclusive }) => {
+            let open = if min_inclusive { '[' } else { '(' };
+            let close = if max_inclusive { ']' } else { ')' };
+            let min = min.map_or("-inf".to_owned(), |min| min.display(literals).to_string());
+            let max = max.map_or("inf".to_owned(), |max| max.display(literals).to_string());
+            write!(dst, " from {open}{min}:{max}{close}").unwrap();
+        }
+        None => (),
+    }
+    if !info.unit.is_empty() {
+        write!(dst, " units={}", DisplayStr(&info.unit)).unwrap();
+    }
+    if !info.description.is_empty() {
+        write!(dst, " desc={}", DisplayStr(&info.description)).unwrap();
+    }
+    dst
+}
@@ -0,0 +1,74 @@
+use hir::diagnostics::sink::Buffer;
+use hir::diagnostics::ConsoleSink;
+use hir::CompilationDB;
+use indoc::indoc;
+use lasso::Rodeo;
+
+use crate::{fmt_param, CompiledModule};
+
+#[test]
+fn params() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module fmt_params(inout a, inout c);
+            electrical a, c;
+            (* units="Ohm", desc="resistance" *) parameter real r = 1k from (0:inf);
+            parameter integer n = 2 from [1:10];
+            parameter integer m = 0 from (-inf:2147483647];
+            parameter real g = 1 / r;
+            parameter real x = 0.5 from [0:r);
+            parameter string mode = "fast \"and\" safe";
+            analog I(a, c) <+ n * m * g * x * V(a, c);
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let params: Vec<_> = module.params.keys().copied().collect();
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let lines: Vec<_> =
+        params.iter().map(|&param| fmt_param(&db, &module, &literals, param)).collect();
+    assert_eq!(
+        lines,
+        [
+            r#"real r = 1k from (0.0:inf) units="Ohm" desc="resistance""#,
+            "integer n = 2 from [1:10]",
+            "integer m = 0 from (-inf:2147483647]",
+            "real g = (computed)",
+            "real x = 0.5 from (computed)",
+            r#"string mode = "fast \"and\" safe""#,
+        ]
+    );
+    assert!(module.invalid_param_defaults.is_empty());
+}
+
+#[test]
+fn invalid_default() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module invalid_default(inout a, inout c);
+            electrical a, c;
+            parameter real r = 0 from (0:inf);
+            parameter integer n = 10 from [1:10];
+            analog I(a, c) <+ n * V(a, c) / r;
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let mut buf = Buffer::no_color();
+    module.diagnostics(&db, &mut ConsoleSink::buffer(&db, &mut buf));
+    let diagnostics = String::from_utf8(buf.into_inner()).unwrap();
+    assert!(
+        diagnostics.contains(
+            "warning: default value of parameter r of module 'invalid_default' is outside of its range"
+        ),
+        "{diagnostics}"
+    );
+    assert!(
+        diagnostics.contains("info: the parameter is declared as real r = 0.0 from (0.0:inf)"),
+        "{diagnostics}"
+    );
+    assert!(!diagnostics.contains("parameter n"), "{diagnostics}");
+}
@@ -0,0 +1,37 @@
+use ahash::AHashMap;
+use bitset::BitSet;
+use mir::{Block, Inst, Value, F_ZERO};
+use typed_index_collections::TiVec;
+
+use crate::dae::SimUnknown;
+
+/// Temporary buffers that are required by the passes in this crate.
+///
+/// Passes take the buffers they need, clear them before every use and put them back once
+/// they are done. The buffers are never deallocated so a single `Scratch` can be reused to
+/// compile many modules without reallocating them for every pass.
+#[derive(Default)]
+pub struct Scratch {
+    /// Dense jacobian row that is reused for every row of the matrix.
+    pub(crate) dense_row: Vec<(Value, Value)>,
+    pub(crate) visited: BitSet<Inst>,
+    pub(crate) postorder: Vec<Inst>,
+    pub(crate) val_map: AHashMap<Value, Value>,
+    pub(crate) edges: Vec<(Block, Value)>,
+    pub(crate) phis: Vec<Inst>,
+}
+
+impl Scratch {
+    /// Takes a dense jacobian row with `len` zeroed entries out of the scratch
+    /// buffer. The row should be returned with [`Scratch::return_dense_row`].
+    pub(crate) fn take_dense_row(&mut self, len: usize) -> TiVec<SimUnknown, (Value, Value)> {
+        let mut row = std::mem::take(&mut self.dense_row);
+        row.clear();
+        row.resize(len, (F_ZERO, F_ZERO));
+        row.into()
+    }
+
+    pub(crate) fn return_dense_row(&mut self, row: TiVec<SimUnknown, (Value, Value)>) {
+        self.dense_row = row.raw;
+    }
+}
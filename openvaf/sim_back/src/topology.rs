@@ -17,8 +17,10 @@
 //!   generation of unnecessary derivatives.
 //!
 
+use std::mem::take;
+
 use ahash::AHashMap;
-use bitset::{BitSet, SparseBitMatrix};
+use bitset::SparseBitMatrix;
 use hir_lower::{CallBackKind, HirInterner, ImplicitEquation, ParamKind, PlaceKind};
 use indexmap::IndexSet;
 use lasso::Spur;
@@ -287,18 +289,19 @@ impl Topology {
                 ahash::RandomState::default(),
             ),
         };
-        let num_insts = ctx.func.dfg.num_insts();
+        ctx.scratch.visited.clear();
+        ctx.scratch.visited.ensure(ctx.func.dfg.num_insts());
         let mut builder = Builder {
             topology: &mut topology,
             db: ctx.db,
             func: &mut ctx.func,
             output_values: &ctx.output_values,
             cfg: &mut ctx.cfg,
-            scratch_buf: BitSet::new_empty(num_insts),
-            postorder: Vec::with_capacity(128),
-            val_map: AHashMap::with_capacity(128),
-            edges: Vec::with_capacity(128),
-            phis: Vec::with_capacity(128),
+            scratch_buf: take(&mut ctx.scratch.visited),
+            postorder: take(&mut ctx.scratch.postorder),
+            val_map: take(&mut ctx.scratch.val_map),
+            edges: take(&mut ctx.scratch.edges),
+            phis: take(&mut ctx.scratch.phis),
             op_dependent_insts: &ctx.op_dependent_insts,
             op_dependent_vals: &ctx.op_dependent_vals,
         };
@@ -307,6 +310,11 @@ impl Topology {
         builder.builid_analog_operators(operators, &mut ctx.intern);
         simplify_cfg_no_phi_merge(builder.func, builder.cfg);
         builder.prune_small_signal();
+        ctx.scratch.visited = builder.scratch_buf;
+        ctx.scratch.postorder = builder.postorder;
+        ctx.scratch.val_map = builder.val_map;
+        ctx.scratch.edges = builder.edges;
+        ctx.scratch.phis = builder.phis;
         topology.contributes = AHashMap::new();
         topology
     }
@@ -42,7 +42,6 @@ impl<'a> super::Builder<'a> {
     ) {
         let mut ssa_builder = mir_build::SSAVariableBuilder::new(self.cfg);
         for (operator_inst, evaluation) in analog_operators {
-            let arg0 = self.func.dfg.instr_args(operator_inst)[0];
             let cb = self.func.dfg.func_ref(operator_inst).unwrap();
             let is_noise = intern.callbacks[cb].is_noise();
             match evaluation {
@@ -121,6 +120,7 @@ impl<'a> super::Builder<'a> {
                             ..Contribution::default()
                         }
                     } else {
+                        let arg0 = self.func.dfg.instr_args(operator_inst)[0];
                         let arg0 =
                             ssa_builder.define_at_exit(self.func, F_ZERO, arg0, operator_inst);
                         Contribution {
@@ -480,6 +480,7 @@ pub mod sysfun {
         error,
         info,
         abstime,
+        realtime,
 
         bitstoreal,
         realtobits,
@@ -145,6 +145,7 @@ const SYSFUNS: [&str; 81] = [
     "$error",
     "$info",
     "$abstime",
+    "$realtime",
     "$dist_chi_square",
     "$dist_exponential",
     "$dist_poisson",
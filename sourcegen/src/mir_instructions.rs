@@ -94,6 +94,7 @@ opcodes! {
         Asinh
         Acosh
         Atanh
+        Fabs
     }
 
     Binary(2) -> 1{
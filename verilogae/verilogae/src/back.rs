@@ -64,7 +64,13 @@ pub fn stub_callbacks<'ll>(
                 | CallBackKind::NoiseTable(_) => {
                     cx.const_callback(&[cx.ty_double()], cx.const_real(0.0))
                 }
+                // file descriptor 0 signals that the file could not be opened
+                CallBackKind::FileOpen => {
+                    cx.const_callback(&[cx.ty_ptr(), cx.ty_ptr()], cx.const_int(0))
+                }
                 CallBackKind::Print { .. }
+                | CallBackKind::FileWrite { .. }
+                | CallBackKind::FileClose
                 | CallBackKind::ParamInfo(_, _)
                 | CallBackKind::BuiltinLimit { .. }
                 | CallBackKind::StoreLimit(_)
@@ -6,7 +6,7 @@ use mir::{ControlFlowGraph, DominatorTree, Function, ValueDef};
 use mir_autodiff::auto_diff;
 use mir_opt::{
     aggressive_dead_code_elimination, dead_code_elimination, inst_combine, simplify_cfg,
-    sparse_conditional_constant_propagation,
+    sink_instructions, sparse_conditional_constant_propagation,
 };
 
 use crate::compiler_db::{CompilationDB, FuncSpec, ModelInfo};
@@ -55,6 +55,8 @@ impl FuncSpec {
             &control_dep,
         );
         simplify_cfg(&mut func, &mut cfg);
+        dom_tree.compute(&func, &cfg, true, false, false);
+        sink_instructions(&mut func, &cfg, &dom_tree);
 
         (func, cfg)
     }